@@ -0,0 +1,18 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/dolphin.proto");
+
+        // Compile the protocol in Rust, so protoc doesn't need to be installed
+        let descriptors = protox::compile(["proto/dolphin.proto"], ["proto"])
+            .expect("unable to compile the gRPC protocol");
+        let service_generator = tonic_build::configure()
+            .build_client(false)
+            .service_generator();
+
+        prost_build::Config::new()
+            .service_generator(service_generator)
+            .compile_fds(descriptors)
+            .expect("unable to generate the gRPC service");
+    }
+}
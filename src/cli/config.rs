@@ -0,0 +1,7 @@
+use crate::config::{schema, Error};
+
+/// Print a fully commented example config to stdout.
+pub fn schema() -> Result<(), Error> {
+    print!("{}", schema::generate()?);
+    Ok(())
+}
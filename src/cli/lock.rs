@@ -0,0 +1,160 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use thiserror::Error;
+use tracing::warn;
+
+/// A lock file that keeps two instances of Dolphin from running with
+/// the same config, which would post every message twice. The lock is
+/// released when this is dropped.
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Take the lock at the given path. If another running instance
+    /// holds it, an error is returned unless `force` is set. Locks left
+    /// behind by an instance that is no longer running are taken over.
+    pub fn acquire(path: PathBuf, force: bool) -> Result<Self, Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        match create(&path) {
+            Ok(()) => return Ok(Self { path }),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        // Someone has the lock, so see if they're still around. A lock
+        // without a PID in it could be one that's still being written.
+        match read_pid(&path) {
+            Some(pid) if pid == process::id() || !is_running(pid) => {}
+            Some(pid) if !force => return Err(Error::AlreadyRunning(pid, path)),
+            None if !force => return Err(Error::Unreadable(path)),
+            _ => warn!("Another instance holds the lock, but --force was given"),
+        }
+
+        // Take it over, unless another instance just beat us to it
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        match create(&path) {
+            Ok(()) => Ok(Self { path }),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => match read_pid(&path) {
+                Some(pid) => Err(Error::AlreadyRunning(pid, path)),
+                None => Err(Error::Unreadable(path)),
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Create the lock file with our PID in it, failing if it already
+/// exists, so two instances starting at once can't both get the lock.
+fn create(path: &Path) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(process::id().to_string().as_bytes())
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        // Only clean up the lock if it's still ours
+        if read_pid(&self.path) == Some(process::id()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Check if a process with the given PID is running. Where we can't
+/// tell, assume that it is.
+fn is_running(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        Path::new("/proc").join(pid.to_string()).exists()
+    } else {
+        true
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("another instance (PID {0}) is already running with this config; remove '{}' or use --force if it isn't", .1.display())]
+    AlreadyRunning(u32, PathBuf),
+
+    #[error("another instance may be running with this config; remove '{}' or use --force if it isn't", .0.display())]
+    Unreadable(PathBuf),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::InstanceLock;
+
+    #[test]
+    fn take_over_stale_lock() {
+        // Given
+        let path = env::temp_dir().join(format!("dolphin-stale-{}.lock", process::id()));
+        fs::write(&path, u32::MAX.to_string()).unwrap();
+
+        // When
+        let lock = InstanceLock::acquire(path.clone(), false);
+
+        // Then
+        assert!(lock.is_ok());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            process::id().to_string()
+        );
+        drop(lock);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn refuse_a_lock_still_being_written() {
+        // Given
+        let path = env::temp_dir().join(format!("dolphin-fresh-{}.lock", process::id()));
+        let _ = fs::remove_file(&path);
+
+        // When
+        let first = InstanceLock::acquire(path.clone(), false);
+        fs::write(&path, "").unwrap();
+        let second = InstanceLock::acquire(path.clone(), false);
+
+        // Then
+        assert!(first.is_ok());
+        assert!(second.is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn refuse_running_instance() {
+        // Given
+        let path = env::temp_dir().join(format!("dolphin-running-{}.lock", process::id()));
+        fs::write(&path, "1").unwrap();
+
+        // When
+        let refused = InstanceLock::acquire(path.clone(), false);
+        let forced = InstanceLock::acquire(path.clone(), true);
+
+        // Then
+        assert!(refused.is_err());
+        assert!(forced.is_ok());
+        drop(forced);
+        assert!(!path.exists());
+    }
+}
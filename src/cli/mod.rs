@@ -1,8 +1,10 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use thiserror::Error;
 
+mod config;
+mod lock;
 mod start;
 
 #[derive(Parser)]
@@ -29,7 +31,43 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Connect to Discord and start reading the Minecraft log file
-    Start {},
+    Start {
+        /// Start even if another instance seems to be running with this config
+        #[arg(long = "force")]
+        force: bool,
+
+        /// Where to get messages from Minecraft, instead of what the config says
+        #[arg(long = "listener", value_enum)]
+        listener: Option<ListenerMode>,
+
+        /// Read this Minecraft log file instead of the configured one
+        #[arg(long = "log-path", value_name = "FILE")]
+        log_path: Option<String>,
+
+        /// Listen on this port for the webserver instead of the configured one
+        #[arg(long = "port")]
+        port: Option<u16>,
+    },
+
+    /// Work with the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ListenerMode {
+    /// Read the Minecraft log file
+    Log,
+    /// Listen for messages sent to the webserver
+    Webserver,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print an example config with every option, its default value, and what it does
+    Schema {},
 }
 
 pub async fn process() -> Result<(), Error> {
@@ -43,9 +81,24 @@ pub async fn process() -> Result<(), Error> {
 
     // Handle the proper subcommand
     match cli.command {
-        Some(Commands::Start {}) => start::handle(config_path.clone(), cli.debug)
-            .await
-            .map_err(Error::Start),
+        Some(Commands::Start {
+            force,
+            listener,
+            log_path,
+            port,
+        }) => {
+            let overrides = start::Overrides {
+                listener,
+                log_path,
+                port,
+            };
+            start::handle(config_path.clone(), cli.debug, force, overrides)
+                .await
+                .map_err(Error::Start)
+        }
+        Some(Commands::Config {
+            command: ConfigCommands::Schema {},
+        }) => config::schema().map_err(Error::Schema),
         _ => unreachable!(),
     }
 }
@@ -55,6 +108,9 @@ pub enum Error {
     #[error("config")]
     Config(#[from] confy::ConfyError),
 
+    #[error("schema")]
+    Schema(#[from] crate::config::Error),
+
     #[error("start")]
     Start(#[from] start::Error),
 }
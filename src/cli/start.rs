@@ -1,19 +1,80 @@
-use std::{env, num::ParseIntError, path::PathBuf, sync::Arc};
+use std::{env, num::ParseIntError, path::PathBuf, sync::Arc, time::Duration};
 
-use serenity::{all::ApplicationId, prelude::GatewayIntents, Client};
+use serenity::{all::ApplicationId, Client};
 use thiserror::Error;
 use tokio::sync::RwLock;
-use tracing::{info, Level};
+use tracing::{info, warn, Level};
+use tracing_subscriber::{filter::LevelFilter, prelude::*};
 
+use super::{
+    lock::{self, InstanceLock},
+    ListenerMode,
+};
 use crate::{
     config::{
-        container::{ConfigContainer, ConfigPathContainer},
-        RootConfig,
+        container::{ConfigContainer, ConfigPathContainer, ServerConfigsContainer},
+        migrate, writer, RootConfig,
+    },
+    discord::{
+        banner::{self, ServerInfoContainer},
+        bridged::BridgedMessages,
+        cooldown::CooldownsContainer,
+        deletion::{DeletionQueue, DeletionQueueContainer},
+        echo::{EchoGuardContainer, Echoes},
+        mentions::{MentionCache, MentionCacheContainer},
+        preflight::PreflightContainer,
+        retry::RetryQueue,
+        BridgedServer, Handler,
+    },
+    links::{LinkStore, LinkStoreContainer},
+    listener::{
+        ansi::{self, AnsiBatchContainer},
+        digest::DigestContainer,
+        history::ChatHistoryContainer,
+        relog::RelogFilterContainer,
+        sessions::SessionsContainer,
+        spam::SpamFilterContainer,
     },
-    discord::Handler,
+    logbuffer::{LogBuffer, LogBufferContainer},
+    metrics::{self, Metrics, MetricsContainer},
+    pause::PauseContainer,
+    per_server::PerServer,
+    server::{ManagedServer, Settings},
+    shutdown,
+    usage::UsageContainer,
 };
 
-pub async fn handle(config_path: PathBuf, debug: bool) -> Result<(), Error> {
+/// Config values given on the command line for this run, which aren't
+/// saved to the config file.
+pub struct Overrides {
+    pub listener: Option<ListenerMode>,
+    pub log_path: Option<String>,
+    pub port: Option<u16>,
+}
+
+impl Overrides {
+    fn apply(self, config: &mut RootConfig) {
+        if let Some(listener) = self.listener {
+            config.set_listener(matches!(listener, ListenerMode::Webserver));
+        }
+        if let Some(log_path) = self.log_path {
+            config.set_log_file(log_path);
+        }
+        if let Some(port) = self.port {
+            config.set_webserver_port(port);
+        }
+    }
+}
+
+/// How long to wait for listeners to finish up when shutting down.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub async fn handle(
+    config_path: PathBuf,
+    debug: bool,
+    force: bool,
+    overrides: Overrides,
+) -> Result<(), Error> {
     let log_level = match debug {
         true => Level::DEBUG,
         false => Level::INFO,
@@ -25,15 +86,46 @@ pub async fn handle(config_path: PathBuf, debug: bool) -> Result<(), Error> {
         .compact()
         .with_target(false);
 
-    tracing_subscriber::fmt()
-        .event_format(format)
-        .with_max_level(log_level)
+    // Keep recent errors around for `/bridge status`
+    let logs = LogBuffer::default();
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .event_format(format)
+                .with_filter(LevelFilter::from_level(log_level)),
+        )
+        .with(logs.layer())
         .init();
 
+    // Make sure we're the only instance using this config
+    let _lock = InstanceLock::acquire(config_path.with_extension("lock"), force)?;
+
+    // Bring configs from older versions up to date before loading
+    migrate::migrate_file(&config_path)?;
+
     // Load the configuration file
-    let config: RootConfig = confy::load_path(&config_path)?;
-    confy::store_path(&config_path, &config)?;
+    let mut config: RootConfig = confy::load_path(&config_path)?;
+    writer::save(&config_path, &config)?;
+    overrides.apply(&mut config);
+    config.check_transport()?;
+    config.check_webserver_auth()?;
+
+    // Each extra server gets a config of its own
+    let servers: Vec<BridgedServer> = config
+        .get_servers()
+        .into_iter()
+        .map(|(name, webserver_token, config)| BridgedServer {
+            name,
+            webserver_token,
+            config_lock: Arc::new(RwLock::new(config)),
+        })
+        .collect();
     let config_lock = Arc::new(RwLock::new(config));
+    let server_configs: Vec<_> = servers
+        .iter()
+        .map(|server| server.config_lock.clone())
+        .collect();
 
     info!("Config loaded successfully");
 
@@ -50,17 +142,59 @@ pub async fn handle(config_path: PathBuf, debug: bool) -> Result<(), Error> {
         _ => return Err(Error::NoApplicationID),
     };
 
+    // Start the Minecraft server if we're running it ourselves
+    if config_lock.read().await.managed_server_enabled() {
+        ManagedServer::start(Settings::from_config(&*config_lock.read().await))?;
+        info!("Managing the Minecraft server process");
+    }
+
+    // Start every server's metrics now, so their uptimes are right
+    let metrics = Arc::new(PerServer::<Metrics>::default());
+    metrics.get(config_lock.read().await.get_channel_id());
+    for config in &server_configs {
+        metrics.get(config.read().await.get_channel_id());
+    }
+    let links = Arc::new(RwLock::new(LinkStore::load(
+        config_path.with_file_name("links.json"),
+    )?));
+
+    let echo_window = Duration::from_secs(config_lock.read().await.get_echo_window_secs());
+    let echoes = Arc::new(PerServer::new(move || Echoes::new(echo_window)));
+
+    let mentions = Arc::new(MentionCache::default());
+
+    let size_summary_interval = config_lock.read().await.get_size_summary_interval_mins();
+    if size_summary_interval > 0 {
+        tokio::spawn(metrics::log_sizes_periodically(
+            metrics.clone(),
+            Duration::from_secs(size_summary_interval * 60),
+        ));
+    }
+
     // Create our Discord handler
-    let handler = Handler::new(config_lock.clone());
+    let handler = Handler::new(
+        config_lock.clone(),
+        servers,
+        metrics.clone(),
+        echoes.clone(),
+        mentions.clone(),
+        BridgedMessages::new(Duration::from_secs(
+            config_lock.read().await.get_edit_window_secs(),
+        )),
+        RetryQueue::new(
+            config_lock.read().await.get_retry_queue_size(),
+            Duration::from_secs(config_lock.read().await.get_retry_max_age_secs()),
+        ),
+    );
 
     // Create our Discord client
-    let intents = GatewayIntents::GUILDS
-        | GatewayIntents::GUILD_MEMBERS
-        | GatewayIntents::GUILD_MESSAGES
-        | GatewayIntents::GUILD_PRESENCES
-        | GatewayIntents::MESSAGE_CONTENT;
+    let (intents, cache_settings) = {
+        let config = config_lock.read().await;
+        (config.gateway_intents()?, config.cache_settings())
+    };
     let mut client = Client::builder(bot_token, intents)
         .application_id(application_id)
+        .cache_settings(cache_settings)
         .event_handler(handler)
         .await?;
 
@@ -69,13 +203,89 @@ pub async fn handle(config_path: PathBuf, debug: bool) -> Result<(), Error> {
         let mut data = client.data.write().await;
         data.insert::<ConfigContainer>(config_lock.clone());
         data.insert::<ConfigPathContainer>(Arc::new(config_path));
+        data.insert::<ServerConfigsContainer>(Arc::new(server_configs.clone()));
+        data.insert::<MetricsContainer>(metrics);
+        data.insert::<LogBufferContainer>(logs);
+        data.insert::<LinkStoreContainer>(links);
+        data.insert::<EchoGuardContainer>(echoes);
+        data.insert::<ServerInfoContainer>(Default::default());
+        data.insert::<PauseContainer>(Default::default());
+        data.insert::<MentionCacheContainer>(mentions);
+        data.insert::<DigestContainer>(Default::default());
+        data.insert::<SessionsContainer>(Default::default());
+        data.insert::<RelogFilterContainer>(Default::default());
+        data.insert::<SpamFilterContainer>(Default::default());
+        data.insert::<AnsiBatchContainer>(Default::default());
+        data.insert::<ChatHistoryContainer>(Default::default());
+        data.insert::<UsageContainer>(Default::default());
+        data.insert::<CooldownsContainer>(Default::default());
+        data.insert::<PreflightContainer>(Default::default());
+        data.insert::<DeletionQueueContainer>(DeletionQueue::start(client.http.clone()));
     }
 
+    // Stop cleanly when asked to, so the next run doesn't race our
+    // sockets
+    let shards = client.shard_manager.clone();
+    let (http, data) = (client.http.clone(), client.data.clone());
+    let configs: Vec<_> = std::iter::once(config_lock.clone())
+        .chain(server_configs)
+        .collect();
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        info!("Shutting down");
+
+        // Let the listeners finish what they're sending before anything
+        // else goes out
+        shutdown::global().begin();
+        if !shutdown::global().drained(SHUTDOWN_TIMEOUT).await {
+            warn!("Some listeners didn't stop in time");
+        }
+
+        if let Some(batches) = data.read().await.get::<AnsiBatchContainer>().cloned() {
+            ansi::flush(&http, &batches).await;
+        }
+
+        for config in &configs {
+            let config = config.read().await;
+            banner::announce(&http, &config, &config.get_offline_notice()).await;
+        }
+
+        shards.shutdown_all().await;
+    });
+
     // Connect to Discord and wait for events
     info!("Starting Discord client");
-    match client.start().await {
-        Ok(()) => Ok(()),
-        Err(e) => Err(Error::Discord(e)),
+    let result = client.start().await;
+
+    shutdown::global().begin();
+    if !shutdown::global().drained(SHUTDOWN_TIMEOUT).await {
+        warn!("Some listeners didn't stop in time");
+    }
+
+    result.map_err(Error::Discord)
+}
+
+/// Wait for Ctrl+C, or for SIGTERM from a service manager.
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => warn!("Unable to listen for SIGTERM: {}", e),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Unable to listen for Ctrl+C: {}", e);
+        std::future::pending::<()>().await;
     }
 }
 
@@ -87,6 +297,18 @@ pub enum Error {
     #[error("Discord error")]
     Discord(#[from] serenity::Error),
 
+    #[error("invalid configuration")]
+    Settings(#[from] crate::config::Error),
+
+    #[error("{0}")]
+    Lock(#[from] lock::Error),
+
+    #[error("unable to load links")]
+    Links(#[from] crate::links::Error),
+
+    #[error("unable to start the Minecraft server: {0}")]
+    Server(#[from] crate::server::Error),
+
     #[error("no Discord Application ID given")]
     NoApplicationID,
 
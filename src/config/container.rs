@@ -1,6 +1,9 @@
 use std::{path::PathBuf, sync::Arc};
 
-use serenity::prelude::TypeMapKey;
+use serenity::{
+    all::ChannelId,
+    prelude::{Context, TypeMapKey},
+};
 use tokio::sync::RwLock;
 
 use super::RootConfig;
@@ -16,3 +19,27 @@ pub struct ConfigPathContainer;
 impl TypeMapKey for ConfigPathContainer {
     type Value = Arc<PathBuf>;
 }
+
+/// The configs of the extra servers from `[[servers]]`.
+pub struct ServerConfigsContainer;
+
+impl TypeMapKey for ServerConfigsContainer {
+    type Value = Arc<Vec<Arc<RwLock<RootConfig>>>>;
+}
+
+/// The config of the server bridged with a channel. Channels that aren't
+/// one of the extra servers' belong to the main server.
+pub async fn config_for(ctx: &Context, channel_id: ChannelId) -> Arc<RwLock<RootConfig>> {
+    let data = ctx.data.read().await;
+    if let Some(servers) = data.get::<ServerConfigsContainer>() {
+        for config in servers.iter() {
+            if config.read().await.get_channel_id() == channel_id.get() {
+                return config.clone();
+            }
+        }
+    }
+
+    data.get::<ConfigContainer>()
+        .cloned()
+        .expect("expected config container in TypeMap")
+}
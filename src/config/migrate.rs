@@ -0,0 +1,183 @@
+use std::{fs, path::Path};
+
+use toml_edit::{value, DocumentMut, Item, Table};
+use tracing::info;
+
+use super::Error;
+
+/// Check the config file at the given path for keys and sections from
+/// older versions of Dolphin, and rewrite them to the current layout.
+///
+/// If anything had to be changed, the original file is backed up next
+/// to it with a `.bak` extension before the migrated config is written.
+/// A description of every change is returned.
+pub fn migrate_file(path: &Path) -> Result<Vec<String>, Error> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut doc: DocumentMut = fs::read_to_string(path)?.parse()?;
+
+    let changes = migrate(doc.as_table_mut());
+    if changes.is_empty() {
+        return Ok(changes);
+    }
+
+    let backup = path.with_extension("toml.bak");
+    fs::copy(path, &backup)?;
+    info!("Backed up old config to '{}'", backup.display());
+
+    fs::write(path, doc.to_string())?;
+
+    for change in &changes {
+        info!("Config migration: {}", change);
+    }
+
+    Ok(changes)
+}
+
+/// Rewrite any legacy keys in a parsed config to the current layout.
+fn migrate(table: &mut Table) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    // The webhook used to have its own section, with a flag to enable it.
+    // Now, webhooks are used whenever a URL is set.
+    if let Some(Item::Table(webhook)) = table.remove("webhook_config") {
+        let enabled = webhook
+            .get("enabled")
+            .and_then(Item::as_bool)
+            .unwrap_or(true);
+        let url = webhook.get("url").and_then(Item::as_str).unwrap_or("");
+
+        if enabled && !url.is_empty() {
+            discord_section(table).insert("webhook_url", value(url));
+            changes.push(String::from(
+                "moved 'webhook_config.url' to 'discord_config.webhook_url'",
+            ));
+        } else {
+            changes.push(String::from(
+                "removed disabled 'webhook_config' section; set 'discord_config.webhook_url' to use a webhook",
+            ));
+        }
+    }
+
+    let discord = discord_section(table);
+
+    // Same as above, but for when the flag lived in the Discord section
+    if let Some(enabled) = discord.remove("webhook_enabled") {
+        if enabled.as_bool() == Some(false)
+            && discord
+                .get("webhook_url")
+                .and_then(Item::as_str)
+                .is_some_and(|url| !url.is_empty())
+        {
+            discord.insert("webhook_url", value(""));
+            changes.push(String::from(
+                "cleared 'discord_config.webhook_url' because webhooks were disabled",
+            ));
+        }
+
+        changes.push(String::from("removed 'discord_config.webhook_enabled'"));
+    }
+
+    // The bot token is read from the environment now
+    if discord.remove("bot_token").is_some() {
+        changes.push(String::from(
+            "removed 'discord_config.bot_token'; set the DISCORD_TOKEN environment variable instead",
+        ));
+    }
+
+    if discord.is_empty() {
+        table.remove("discord_config");
+    }
+
+    changes
+}
+
+/// Get the `discord_config` section, creating it if it doesn't exist.
+fn discord_section(table: &mut Table) -> &mut Table {
+    let section = table
+        .entry("discord_config")
+        .or_insert_with(|| Item::Table(Table::new()));
+
+    if !section.is_table() {
+        *section = Item::Table(Table::new());
+    }
+
+    section.as_table_mut().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use toml_edit::DocumentMut;
+
+    use super::migrate;
+
+    #[test]
+    fn moves_webhook_section() {
+        // Given
+        let mut doc: DocumentMut = r#"
+            [discord_config]
+            channel_id = 1234
+
+            [webhook_config]
+            enabled = true
+            url = "https://discord.com/api/webhooks/12345/67890"
+            "#
+        .parse()
+        .unwrap();
+
+        // When
+        let changes = migrate(doc.as_table_mut());
+
+        // Then
+        assert_eq!(changes.len(), 1);
+        assert!(doc.get("webhook_config").is_none());
+        assert_eq!(
+            doc["discord_config"]["webhook_url"].as_str(),
+            Some("https://discord.com/api/webhooks/12345/67890")
+        );
+    }
+
+    #[test]
+    fn drops_disabled_webhook() {
+        // Given
+        let mut doc: DocumentMut = r#"
+            [discord_config]
+            webhook_enabled = false
+            webhook_url = "https://discord.com/api/webhooks/12345/67890"
+            bot_token = "hunter2"
+            "#
+        .parse()
+        .unwrap();
+
+        // When
+        let changes = migrate(doc.as_table_mut());
+
+        // Then
+        assert_eq!(changes.len(), 3);
+        assert_eq!(doc["discord_config"]["webhook_url"].as_str(), Some(""));
+        assert!(doc["discord_config"].get("webhook_enabled").is_none());
+        assert!(doc["discord_config"].get("bot_token").is_none());
+    }
+
+    #[test]
+    fn leaves_current_config_alone() {
+        // Given
+        let mut doc: DocumentMut = r#"
+            [discord_config]
+            channel_id = 1234
+            webhook_url = ""
+            "#
+        .parse()
+        .unwrap();
+        let expected = doc.to_string();
+
+        // When
+        let changes = migrate(doc.as_table_mut());
+
+        // Then
+        assert!(changes.is_empty());
+        assert_eq!(doc.to_string(), expected);
+    }
+}
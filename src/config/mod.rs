@@ -1,8 +1,18 @@
 extern crate confy;
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use serenity::{cache::Settings as CacheSettings, prelude::GatewayIntents};
+use thiserror::Error;
 
 pub mod container;
+pub mod migrate;
+pub mod schema;
+pub mod writer;
+
+const DISCORD_MAX_MESSAGE_LENGTH: usize = 2000;
+const MINECRAFT_MAX_LINE_LENGTH: usize = 256;
 
 #[derive(Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
@@ -10,27 +20,227 @@ pub struct RootConfig {
     discord_config: DiscordConfig,
     minecraft_config: MinecraftConfig,
     webserver_config: WebserverConfig,
+    grpc_config: GrpcConfig,
+    mqtt_config: MqttConfig,
+    server_config: ServerConfig,
+    event_webhooks: HashMap<String, EventWebhookConfig>,
+    translation_config: TranslationConfig,
+    usage_config: UsageConfig,
+    update_config: UpdateConfig,
+    // Left out when empty, since it would come before every table
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    servers: Vec<BridgedServerConfig>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct DiscordConfig {
     channel_id: u64,
+    ops_channel_id: u64,
+    advancement_channel_id: u64,
+    death_channel_id: u64,
+    startup_banner: bool,
+    lag_alert_threshold: usize,
+    lag_alert_window_secs: u64,
+    ignored_bot_ids: Vec<u64>,
+    echo_window_secs: u64,
+    relog_window_secs: u64,
+    relog_note: bool,
+    spam_window_secs: u64,
+    spam_mute_threshold: u32,
+    spam_mute_secs: u64,
     allow_mentions: bool,
+    mention_matching: String,
+    allow_everyone_mentions: bool,
+    everyone_mention_players: Vec<String>,
+    event_players: Vec<String>,
     use_member_nicks: bool,
     webhook_url: String,
+    guild_tag: String,
+    language: String,
+    mirrors: HashMap<String, MirrorConfig>,
+    channel_events: Vec<String>,
+    command_prefix: String,
+    max_message_length: usize,
+    continuation_marker: String,
+    size_summary_interval_mins: u64,
+    escape_event_markdown: bool,
+    escape_chat_markdown: bool,
+    corrections: bool,
+    normalize_unicode: bool,
+    transliterate: bool,
+    transliterate_exceptions: Vec<String>,
+    ascii_emoji: bool,
+    emoji_shortcodes: bool,
+    timestamps: bool,
+    timestamp_style: String,
+    mention_dms: bool,
+    whispers: bool,
+    whisper_players: Vec<String>,
+    outage_notices: bool,
+    online_notice: String,
+    offline_notice: String,
+    reserved_names: Vec<String>,
+    reserved_name_suffix: String,
+    player_name_template: String,
+    name_swatches: bool,
+    chat_style: String,
+    ansi_batch_window_secs: u64,
+    server_display_name: String,
+    server_avatar_url: String,
+    avatar_provider: String,
+    avatar_fallbacks: Vec<String>,
+    avatar_refresh_mins: u64,
+    cmd_roles: Vec<u64>,
+    name_replacements: HashMap<String, String>,
+    avatar_overrides: HashMap<String, String>,
+    command_responses: HashMap<String, CommandResponseConfig>,
+    command_cooldowns: HashMap<String, CommandCooldownConfig>,
+    role_prefixes: HashMap<String, RolePrefixConfig>,
+    macros: HashMap<String, MacroConfig>,
+    gateway: GatewayConfig,
+    publish: PublishConfig,
+    sound_cues: SoundCueConfig,
+    voice: VoiceConfig,
+    digest: DigestConfig,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GatewayConfig {
+    members_intent: bool,
+    presences_intent: bool,
+    max_cached_messages: usize,
+    cache_users: bool,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PublishConfig {
+    server_start: bool,
+    server_stop: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundCueConfig {
+    enabled: bool,
+    mention_sound: String,
+    announcement_roles: Vec<u64>,
+    announcement_sound: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceConfig {
+    channel_id: u64,
+    display: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    enabled: bool,
+    interval_mins: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandResponseConfig {
+    visibility: String,
+    delete_after_secs: u64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandCooldownConfig {
+    user_secs: u64,
+    channel_secs: u64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RolePrefixConfig {
+    label: String,
+    color: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MirrorConfig {
+    channel_id: u64,
+    webhook_url: String,
+    language: String,
+    events: Vec<String>,
+    message_template: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BridgedServerConfig {
+    name: String,
+    channel_id: u64,
+    webhook_url: String,
+    rcon_ip: String,
+    rcon_port: i32,
+    rcon_password: String,
+    log_file_path: String,
+    webserver_token: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MacroConfig {
+    description: String,
+    commands: Vec<String>,
+    roles: Vec<u64>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventWebhookConfig {
+    url: String,
+    secret: String,
+    events: Vec<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct MinecraftConfig {
+    advancements_file: String,
     rcon_ip: String,
     rcon_port: i32,
     rcon_password: String,
+    rcon_transport: String,
+    rcon_socket_path: String,
+    rcon_command: Vec<String>,
+    console_type: String,
+    console_target: String,
     custom_death_keywords: Vec<String>,
     death_ignore_keywords: Vec<String>,
+    death_stats: bool,
+    death_embeds: bool,
+    death_translations: HashMap<String, String>,
+    announce_joins: bool,
+    announce_leaves: bool,
+    announce_deaths: bool,
+    announce_advancements: bool,
+    announce_server_status: bool,
+    events_only: bool,
     log_file_path: String,
+    language: String,
+    startup_suppression_secs: u64,
+    startup_suppression_mode: String,
     chat_regex: String,
+    unmatched_log_path: String,
+    max_line_length: usize,
+    server_start_template: String,
+    reply_template: String,
+    startup_commands: Vec<String>,
+    send_delay_secs: u64,
+    edit_window_secs: u64,
+    retry_queue_size: usize,
+    retry_max_age_secs: u64,
     templates: TellrawTemplates,
 }
 
@@ -39,6 +249,66 @@ pub struct MinecraftConfig {
 pub struct WebserverConfig {
     enabled: bool,
     port: u16,
+    strip_legacy_colors: bool,
+    max_pending: usize,
+    retry_after_secs: u64,
+    api_token: String,
+    auth_token: String,
+    allow_unauthenticated: bool,
+    allowed_ips: Vec<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslationConfig {
+    endpoint: String,
+    api_key: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageConfig {
+    endpoint: String,
+    tps_command: String,
+    poll_interval_secs: u64,
+    report_interval_mins: u64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateConfig {
+    enabled: bool,
+    notify_ops: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcConfig {
+    enabled: bool,
+    port: u16,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    enabled: bool,
+    broker_url: String,
+    client_id: String,
+    username: String,
+    password: String,
+    inbound_topic: String,
+    outbound_topic: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    enabled: bool,
+    command: Vec<String>,
+    working_dir: String,
+    restart_on_crash: bool,
+    restart_delay_secs: u64,
+    stop_timeout_secs: u64,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -47,15 +317,139 @@ pub struct TellrawTemplates {
     username_template: String,
     attachment_template: String,
     message_template: String,
+    edited_template: String,
+    deleted_template: String,
 }
 
 impl Default for DiscordConfig {
     fn default() -> Self {
         DiscordConfig {
             channel_id: 0,
+            ops_channel_id: 0,
+            advancement_channel_id: 0,
+            death_channel_id: 0,
+            startup_banner: false,
+            lag_alert_threshold: 0,
+            lag_alert_window_secs: 300,
+            ignored_bot_ids: Vec::new(),
+            echo_window_secs: 30,
+            relog_window_secs: 0,
+            relog_note: false,
+            spam_window_secs: 0,
+            spam_mute_threshold: 0,
+            spam_mute_secs: 300,
             allow_mentions: true,
+            mention_matching: String::from("ignore_case"),
+            allow_everyone_mentions: false,
+            everyone_mention_players: Vec::new(),
+            event_players: Vec::new(),
             use_member_nicks: false,
             webhook_url: String::new(),
+            guild_tag: String::new(),
+            language: String::new(),
+            mirrors: HashMap::new(),
+            channel_events: Vec::new(),
+            command_prefix: String::new(),
+            max_message_length: 2000,
+            continuation_marker: String::from("…"),
+            size_summary_interval_mins: 60,
+            escape_event_markdown: true,
+            escape_chat_markdown: false,
+            corrections: true,
+            normalize_unicode: true,
+            transliterate: false,
+            transliterate_exceptions: Vec::new(),
+            ascii_emoji: false,
+            emoji_shortcodes: true,
+            timestamps: false,
+            timestamp_style: String::from("t"),
+            mention_dms: false,
+            whispers: false,
+            whisper_players: Vec::new(),
+            outage_notices: true,
+            online_notice: String::new(),
+            offline_notice: String::new(),
+            reserved_names: vec![
+                String::from("admin"),
+                String::from("moderator"),
+                String::from("owner"),
+                String::from("staff"),
+            ],
+            reserved_name_suffix: String::from("[MC]"),
+            player_name_template: String::from("%if rank%[%rank%] %end%%name%"),
+            name_swatches: false,
+            chat_style: String::from("plain"),
+            ansi_batch_window_secs: 2,
+            server_display_name: String::new(),
+            server_avatar_url: String::new(),
+            avatar_provider: String::from("https://crafatar.com/avatars/%uuid%?size=256"),
+            avatar_fallbacks: vec![String::from("https://mc-heads.net/avatar/%username%/256")],
+            avatar_refresh_mins: 60,
+            cmd_roles: Vec::new(),
+            name_replacements: HashMap::from([
+                (String::from("\""), String::from("'")),
+                (String::from("\\"), String::from("/")),
+                (String::from("§"), String::new()),
+            ]),
+            avatar_overrides: HashMap::new(),
+            command_responses: HashMap::new(),
+            command_cooldowns: HashMap::new(),
+            role_prefixes: HashMap::new(),
+            macros: HashMap::new(),
+            gateway: GatewayConfig::default(),
+            publish: PublishConfig::default(),
+            sound_cues: SoundCueConfig::default(),
+            voice: VoiceConfig::default(),
+            digest: DigestConfig::default(),
+        }
+    }
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        GatewayConfig {
+            members_intent: true,
+            presences_intent: true,
+            max_cached_messages: 0,
+            cache_users: true,
+        }
+    }
+}
+
+impl Default for SoundCueConfig {
+    fn default() -> Self {
+        SoundCueConfig {
+            enabled: false,
+            mention_sound: String::from("minecraft:entity.experience_orb.pickup"),
+            announcement_roles: Vec::new(),
+            announcement_sound: String::from("minecraft:block.note_block.bell"),
+        }
+    }
+}
+
+impl Default for VoiceConfig {
+    fn default() -> Self {
+        VoiceConfig {
+            channel_id: 0,
+            display: String::from("actionbar"),
+        }
+    }
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        DigestConfig {
+            enabled: false,
+            interval_mins: 60,
+        }
+    }
+}
+
+impl Default for CommandResponseConfig {
+    fn default() -> Self {
+        CommandResponseConfig {
+            visibility: String::from("auto_delete"),
+            delete_after_secs: 30,
         }
     }
 }
@@ -63,24 +457,102 @@ impl Default for DiscordConfig {
 impl Default for MinecraftConfig {
     fn default() -> Self {
         MinecraftConfig {
+            advancements_file: String::new(),
             rcon_ip: String::from("localhost"),
             rcon_port: 25575,
             rcon_password: String::new(),
+            rcon_transport: String::from("tcp"),
+            rcon_socket_path: String::new(),
+            rcon_command: Vec::new(),
+            console_type: String::from("pipe"),
+            console_target: String::new(),
             custom_death_keywords: Vec::new(),
             death_ignore_keywords: Vec::new(),
+            death_stats: true,
+            death_embeds: false,
+            death_translations: HashMap::new(),
+            announce_joins: true,
+            announce_leaves: true,
+            announce_deaths: true,
+            announce_advancements: true,
+            announce_server_status: true,
+            events_only: false,
             log_file_path: String::new(),
+            language: String::new(),
+            startup_suppression_secs: 0,
+            startup_suppression_mode: String::from("events"),
             chat_regex: String::from(r"^<(?P<username>\w+)> (?P<content>.+)"),
+            unmatched_log_path: String::new(),
+            max_line_length: MINECRAFT_MAX_LINE_LENGTH,
+            server_start_template: String::from(":white_check_mark: Server has started"),
+            reply_template: String::from("[Reply to %username%: %snippet%]"),
+            startup_commands: Vec::new(),
+            send_delay_secs: 0,
+            edit_window_secs: 300,
+            retry_queue_size: 100,
+            retry_max_age_secs: 300,
             templates: TellrawTemplates::default(),
         }
     }
 }
 
+impl Default for BridgedServerConfig {
+    fn default() -> Self {
+        BridgedServerConfig {
+            name: String::new(),
+            channel_id: 0,
+            webhook_url: String::new(),
+            rcon_ip: String::from("localhost"),
+            rcon_port: 25575,
+            rcon_password: String::new(),
+            log_file_path: String::new(),
+            webserver_token: String::new(),
+        }
+    }
+}
+
 impl Default for TellrawTemplates {
     fn default() -> Self {
         TellrawTemplates {
             username_template: String::from("{\"color\": \"white\", \"text\": \"<%username%> \", \"clickEvent\":{\"action\":\"suggest_command\", \"value\":\"%mention% \"}}",),
             attachment_template: String::from("{\"color\":\"gray\",\"text\":\"[%num% attachment(s) sent]\", \"clickEvent\":{\"action\":\"open_url\",\"value\":\"%url%\"},\"hoverEvent\":{\"action\":\"show_text\",\"value\":{\"text\":\"Click to open\"}}}"),
             message_template: String::from("{\"color\":\"white\", \"text\":\"%content%\"}"),
+            edited_template: String::from("{\"color\":\"gray\", \"text\":\"(edited) \"}"),
+            deleted_template: String::from("{\"color\":\"gray\", \"italic\":true, \"text\":\"(message deleted)\"}"),
+        }
+    }
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        GrpcConfig {
+            enabled: false,
+            port: 25586,
+        }
+    }
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        UsageConfig {
+            endpoint: String::new(),
+            tps_command: String::new(),
+            poll_interval_secs: 60,
+            report_interval_mins: 0,
+        }
+    }
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        MqttConfig {
+            enabled: false,
+            broker_url: String::from("mqtt://localhost:1883"),
+            client_id: String::from("dolphin"),
+            username: String::new(),
+            password: String::new(),
+            inbound_topic: String::from("dolphin/minecraft"),
+            outbound_topic: String::from("dolphin/discord"),
         }
     }
 }
@@ -90,6 +562,32 @@ impl Default for WebserverConfig {
         WebserverConfig {
             enabled: false,
             port: 25585,
+            strip_legacy_colors: false,
+            max_pending: 64,
+            retry_after_secs: 2,
+            api_token: String::new(),
+            auth_token: String::new(),
+            allow_unauthenticated: false,
+            allowed_ips: Vec::new(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            enabled: false,
+            command: vec![
+                String::from("java"),
+                String::from("-Xmx2G"),
+                String::from("-jar"),
+                String::from("server.jar"),
+                String::from("nogui"),
+            ],
+            working_dir: String::new(),
+            restart_on_crash: true,
+            restart_delay_secs: 10,
+            stop_timeout_secs: 60,
         }
     }
 }
@@ -99,10 +597,88 @@ impl RootConfig {
         self.discord_config.channel_id
     }
 
+    /// Get the channel for messages meant for server admins. If no ops
+    /// channel is set, the bridge channel is used.
+    pub fn get_ops_channel_id(&self) -> u64 {
+        match self.discord_config.ops_channel_id {
+            0 => self.discord_config.channel_id,
+            id => id,
+        }
+    }
+
+    /// Get the channel advancements are announced in, if they have one
+    /// of their own. 0 means they're posted with the rest of the chat.
+    pub fn get_advancement_channel_id(&self) -> u64 {
+        self.discord_config.advancement_channel_id
+    }
+
+    /// Get the channel for death messages, so a busy server's deaths
+    /// don't drown out chat. 0 keeps them in the bridge channel.
+    pub fn get_death_channel_id(&self) -> u64 {
+        self.discord_config.death_channel_id
+    }
+
+    pub fn startup_banner_enabled(&self) -> bool {
+        self.discord_config.startup_banner
+    }
+
+    pub fn get_lag_alert_threshold(&self) -> usize {
+        self.discord_config.lag_alert_threshold
+    }
+
+    pub fn get_lag_alert_window_secs(&self) -> u64 {
+        self.discord_config.lag_alert_window_secs
+    }
+
+    pub fn get_ignored_bot_ids(&self) -> Vec<u64> {
+        self.discord_config.ignored_bot_ids.clone()
+    }
+
+    pub fn get_echo_window_secs(&self) -> u64 {
+        self.discord_config.echo_window_secs
+    }
+
+    pub fn get_relog_window_secs(&self) -> u64 {
+        self.discord_config.relog_window_secs
+    }
+
+    pub fn relog_note(&self) -> bool {
+        self.discord_config.relog_note
+    }
+
+    pub fn get_spam_window_secs(&self) -> u64 {
+        self.discord_config.spam_window_secs
+    }
+
+    pub fn get_spam_mute_threshold(&self) -> u32 {
+        self.discord_config.spam_mute_threshold
+    }
+
+    pub fn get_spam_mute_secs(&self) -> u64 {
+        self.discord_config.spam_mute_secs
+    }
+
     pub fn mentions_allowed(&self) -> bool {
         self.discord_config.allow_mentions
     }
 
+    pub fn get_mention_matching(&self) -> String {
+        self.discord_config.mention_matching.clone()
+    }
+
+    pub fn everyone_mentions_allowed(&self) -> bool {
+        self.discord_config.allow_everyone_mentions
+    }
+
+    pub fn get_everyone_mention_players(&self) -> Vec<String> {
+        self.discord_config.everyone_mention_players.clone()
+    }
+
+    /// Minecraft players who can schedule Discord events from game.
+    pub fn get_event_players(&self) -> Vec<String> {
+        self.discord_config.event_players.clone()
+    }
+
     pub fn use_member_nicks(&self) -> bool {
         self.discord_config.use_member_nicks
     }
@@ -111,6 +687,382 @@ impl RootConfig {
         self.discord_config.webhook_url.clone()
     }
 
+    pub fn get_guild_tag(&self) -> String {
+        self.discord_config.guild_tag.clone()
+    }
+
+    /// The language people write in the bridge channel, for machine
+    /// translation.
+    pub fn get_discord_language(&self) -> String {
+        self.discord_config.language.clone()
+    }
+
+    /// Get every mirror as `(tag, channel_id, webhook_url, language)`,
+    /// sorted by tag. Mirrors without a channel or webhook are left out.
+    pub fn get_mirrors(&self) -> Vec<(String, u64, String, String)> {
+        let mut mirrors: Vec<_> = self
+            .discord_config
+            .mirrors
+            .iter()
+            .filter(|(_, mirror)| mirror.channel_id != 0 || !mirror.webhook_url.is_empty())
+            .map(|(tag, mirror)| {
+                (
+                    tag.clone(),
+                    mirror.channel_id,
+                    mirror.webhook_url.clone(),
+                    mirror.language.clone(),
+                )
+            })
+            .collect();
+        mirrors.sort_by(|a, b| a.0.cmp(&b.0));
+        mirrors
+    }
+
+    /// Get every extra server in `[[servers]]` as `(name, webserver_token,
+    /// config)`. Each config is a copy of this one with the server's own
+    /// channel, webhook, RCON connection, and log file, so the rest of
+    /// the settings are shared. Mirrors belong to the main server, and
+    /// the extra servers are always reached over RCON.
+    pub fn get_servers(&self) -> Vec<(String, String, RootConfig)> {
+        self.servers
+            .iter()
+            .filter(|server| server.channel_id != 0)
+            .map(|server| {
+                let mut config = self.clone();
+                config.servers.clear();
+                config.discord_config.channel_id = server.channel_id;
+                config.discord_config.webhook_url = server.webhook_url.clone();
+                config.discord_config.mirrors.clear();
+                config.minecraft_config.rcon_ip = server.rcon_ip.clone();
+                config.minecraft_config.rcon_port = server.rcon_port;
+                config.minecraft_config.rcon_password = server.rcon_password.clone();
+                config.minecraft_config.rcon_transport = String::from("tcp");
+                config.minecraft_config.log_file_path = server.log_file_path.clone();
+                config.webserver_config.enabled = false;
+                config.grpc_config.enabled = false;
+                config.mqtt_config.enabled = false;
+                config.server_config.enabled = false;
+
+                (server.name.clone(), server.webserver_token.clone(), config)
+            })
+            .collect()
+    }
+
+    /// The kinds of messages from Minecraft to post in the bridge
+    /// channel, like `chat` or `death`. Empty means all of them.
+    pub fn get_channel_events(&self) -> Vec<String> {
+        self.discord_config.channel_events.clone()
+    }
+
+    /// The kinds of messages from Minecraft to post in a mirror. Empty
+    /// means all of them.
+    pub fn get_mirror_events(&self, tag: &str) -> Vec<String> {
+        self.discord_config
+            .mirrors
+            .get(tag)
+            .map(|mirror| mirror.events.clone())
+            .unwrap_or_default()
+    }
+
+    /// The template for chat sent to Minecraft from a channel. Mirrors
+    /// can have their own, and everything else uses `message_template`.
+    pub fn get_message_template_for(&self, channel_id: u64) -> String {
+        self.discord_config
+            .mirrors
+            .values()
+            .find(|mirror| mirror.channel_id == channel_id && !mirror.message_template.is_empty())
+            .map(|mirror| mirror.message_template.clone())
+            .unwrap_or_else(|| self.get_message_template())
+    }
+
+    pub fn get_reserved_names(&self) -> Vec<String> {
+        self.discord_config.reserved_names.clone()
+    }
+
+    pub fn get_reserved_name_suffix(&self) -> String {
+        self.discord_config.reserved_name_suffix.clone()
+    }
+
+    pub fn get_player_name_template(&self) -> String {
+        self.discord_config.player_name_template.clone()
+    }
+
+    pub fn name_swatches(&self) -> bool {
+        self.discord_config.name_swatches
+    }
+
+    /// Whether chat is posted in batches of `ansi` code blocks instead
+    /// of one message each.
+    pub fn ansi_chat_enabled(&self) -> bool {
+        self.discord_config.chat_style.eq_ignore_ascii_case("ansi")
+    }
+
+    pub fn get_ansi_batch_window_secs(&self) -> u64 {
+        self.discord_config.ansi_batch_window_secs
+    }
+
+    pub fn get_server_display_name(&self) -> String {
+        self.discord_config.server_display_name.clone()
+    }
+
+    pub fn get_server_avatar_url(&self) -> String {
+        self.discord_config.server_avatar_url.clone()
+    }
+
+    /// Get the avatar templates to try for players, in order: the
+    /// provider first, then its fallbacks.
+    pub fn get_avatar_providers(&self) -> Vec<String> {
+        let mut providers = vec![self.discord_config.avatar_provider.clone()];
+        providers.extend(self.discord_config.avatar_fallbacks.iter().cloned());
+        providers
+    }
+
+    pub fn get_avatar_refresh_mins(&self) -> u64 {
+        self.discord_config.avatar_refresh_mins
+    }
+
+    pub fn get_name_replacements(&self) -> HashMap<String, String> {
+        self.discord_config.name_replacements.clone()
+    }
+
+    pub fn get_avatar_overrides(&self) -> HashMap<String, String> {
+        self.discord_config.avatar_overrides.clone()
+    }
+
+    /// Get how the given command's responses should be shown, as
+    /// `(visibility, delete_after_secs)`, if one has been configured.
+    pub fn get_command_response(&self, command: &str) -> Option<(String, u64)> {
+        self.discord_config
+            .command_responses
+            .get(command)
+            .map(|response| (response.visibility.clone(), response.delete_after_secs))
+    }
+
+    /// Get how long a user has to wait between uses of the given
+    /// command, and how long it can't be used again in the same channel,
+    /// as `(user_secs, channel_secs)`. Commands without a cooldown get
+    /// zeros.
+    pub fn get_command_cooldown(&self, command: &str) -> (u64, u64) {
+        self.discord_config
+            .command_cooldowns
+            .get(command)
+            .map_or((0, 0), |cooldown| {
+                (cooldown.user_secs, cooldown.channel_secs)
+            })
+    }
+
+    /// Get the in-game prefix for each role that has one, by role ID, as
+    /// `(label, color)`. Entries that aren't keyed by a role ID are left
+    /// out.
+    pub fn get_role_prefixes(&self) -> HashMap<u64, (String, String)> {
+        self.discord_config
+            .role_prefixes
+            .iter()
+            .filter_map(|(role, prefix)| {
+                let role = role.parse().ok()?;
+                Some((role, (prefix.label.clone(), prefix.color.clone())))
+            })
+            .collect()
+    }
+
+    /// Get the IDs of the roles allowed to run server commands with
+    /// `/cmd`.
+    pub fn get_cmd_roles(&self) -> Vec<u64> {
+        self.discord_config.cmd_roles.clone()
+    }
+
+    /// Get every command macro as `(name, description, commands, roles)`,
+    /// sorted by name.
+    pub fn get_macros(&self) -> Vec<(String, String, Vec<String>, Vec<u64>)> {
+        let mut macros: Vec<_> = self
+            .discord_config
+            .macros
+            .iter()
+            .map(|(name, command_macro)| {
+                (
+                    name.clone(),
+                    command_macro.description.clone(),
+                    command_macro.commands.clone(),
+                    command_macro.roles.clone(),
+                )
+            })
+            .collect();
+        macros.sort_by(|a, b| a.0.cmp(&b.0));
+        macros
+    }
+
+    pub fn get_command_prefix(&self) -> String {
+        self.discord_config.command_prefix.clone()
+    }
+
+    /// The longest message to post to Discord before splitting it up.
+    /// This can't be more than Discord's own limit of 2000 characters.
+    pub fn get_max_message_length(&self) -> usize {
+        match self.discord_config.max_message_length {
+            0 => DISCORD_MAX_MESSAGE_LENGTH,
+            length => length.min(DISCORD_MAX_MESSAGE_LENGTH),
+        }
+    }
+
+    pub fn get_continuation_marker(&self) -> String {
+        self.discord_config.continuation_marker.clone()
+    }
+
+    pub fn get_size_summary_interval_mins(&self) -> u64 {
+        self.discord_config.size_summary_interval_mins
+    }
+
+    pub fn escape_event_markdown(&self) -> bool {
+        self.discord_config.escape_event_markdown
+    }
+
+    pub fn escape_chat_markdown(&self) -> bool {
+        self.discord_config.escape_chat_markdown
+    }
+
+    pub fn corrections_enabled(&self) -> bool {
+        self.discord_config.corrections
+    }
+
+    pub fn normalize_unicode(&self) -> bool {
+        self.discord_config.normalize_unicode
+    }
+
+    pub fn transliterate_enabled(&self) -> bool {
+        self.discord_config.transliterate
+    }
+
+    pub fn ascii_emoji_enabled(&self) -> bool {
+        self.discord_config.ascii_emoji
+    }
+
+    pub fn emoji_shortcodes_enabled(&self) -> bool {
+        self.discord_config.emoji_shortcodes
+    }
+
+    /// Characters that are never transliterated, because the server can
+    /// show them.
+    pub fn get_transliterate_exceptions(&self) -> Vec<char> {
+        self.discord_config
+            .transliterate_exceptions
+            .iter()
+            .flat_map(|exception| exception.chars())
+            .collect()
+    }
+
+    pub fn timestamps_enabled(&self) -> bool {
+        self.discord_config.timestamps
+    }
+
+    pub fn get_timestamp_style(&self) -> String {
+        self.discord_config.timestamp_style.clone()
+    }
+
+    pub fn mention_dms_enabled(&self) -> bool {
+        self.discord_config.mention_dms
+    }
+
+    pub fn whispers_enabled(&self) -> bool {
+        self.discord_config.whispers
+    }
+
+    /// The players allowed to whisper to Discord. Empty means everyone.
+    pub fn get_whisper_players(&self) -> Vec<String> {
+        self.discord_config.whisper_players.clone()
+    }
+
+    pub fn outage_notices_enabled(&self) -> bool {
+        self.discord_config.outage_notices
+    }
+
+    /// Get the notice sent to Discord and Minecraft when the bridge comes
+    /// up. Nothing is sent if this is empty.
+    pub fn get_online_notice(&self) -> String {
+        self.discord_config.online_notice.clone()
+    }
+
+    /// Get the notice sent to Discord and Minecraft when Dolphin is
+    /// stopped. Nothing is sent if this is empty.
+    pub fn get_offline_notice(&self) -> String {
+        self.discord_config.offline_notice.clone()
+    }
+
+    /// Build the gateway intents to connect to Discord with. Returns an
+    /// error if an enabled feature needs an intent that is turned off.
+    pub fn gateway_intents(&self) -> Result<GatewayIntents, Error> {
+        let gateway = &self.discord_config.gateway;
+
+        if self.discord_config.allow_mentions && !gateway.members_intent {
+            return Err(Error::MissingIntent("allow_mentions", "members_intent"));
+        }
+
+        let mut intents = GatewayIntents::GUILDS
+            | GatewayIntents::GUILD_MESSAGES
+            | GatewayIntents::MESSAGE_CONTENT;
+
+        if gateway.members_intent {
+            intents |= GatewayIntents::GUILD_MEMBERS;
+        }
+
+        if gateway.presences_intent {
+            intents |= GatewayIntents::GUILD_PRESENCES;
+        }
+
+        if self.discord_config.voice.channel_id != 0 {
+            intents |= GatewayIntents::GUILD_VOICE_STATES;
+        }
+
+        Ok(intents)
+    }
+
+    pub fn cache_settings(&self) -> CacheSettings {
+        let mut settings = CacheSettings::default();
+        settings.max_messages = self.discord_config.gateway.max_cached_messages;
+        settings.cache_users = self.discord_config.gateway.cache_users;
+        settings
+    }
+
+    pub fn publish_server_start(&self) -> bool {
+        self.discord_config.publish.server_start
+    }
+
+    pub fn publish_server_stop(&self) -> bool {
+        self.discord_config.publish.server_stop
+    }
+
+    pub fn sound_cues_enabled(&self) -> bool {
+        self.discord_config.sound_cues.enabled
+    }
+
+    pub fn get_mention_sound(&self) -> String {
+        self.discord_config.sound_cues.mention_sound.clone()
+    }
+
+    pub fn get_announcement_roles(&self) -> Vec<u64> {
+        self.discord_config.sound_cues.announcement_roles.clone()
+    }
+
+    pub fn get_announcement_sound(&self) -> String {
+        self.discord_config.sound_cues.announcement_sound.clone()
+    }
+
+    pub fn get_voice_channel_id(&self) -> u64 {
+        self.discord_config.voice.channel_id
+    }
+
+    pub fn get_voice_display(&self) -> String {
+        self.discord_config.voice.display.clone()
+    }
+
+    pub fn digest_enabled(&self) -> bool {
+        self.discord_config.digest.enabled
+    }
+
+    /// How often to post the digest, at least once a minute.
+    pub fn get_digest_interval_mins(&self) -> u64 {
+        self.discord_config.digest.interval_mins.max(1)
+    }
+
     pub fn get_rcon_addr(&self) -> String {
         format!(
             "{}:{}",
@@ -122,6 +1074,37 @@ impl RootConfig {
         self.minecraft_config.rcon_password.clone()
     }
 
+    pub fn get_rcon_transport(&self) -> String {
+        self.minecraft_config.rcon_transport.clone()
+    }
+
+    /// Make sure the configured RCON transport can be used on this
+    /// platform. Unix domain sockets only exist on Unix.
+    pub fn check_transport(&self) -> Result<(), Error> {
+        let transport = self.get_rcon_transport().to_lowercase();
+        if cfg!(not(unix)) && transport == "unix" {
+            return Err(Error::UnsupportedTransport(transport));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_rcon_socket_path(&self) -> String {
+        self.minecraft_config.rcon_socket_path.clone()
+    }
+
+    pub fn get_rcon_command(&self) -> Vec<String> {
+        self.minecraft_config.rcon_command.clone()
+    }
+
+    pub fn get_console_type(&self) -> String {
+        self.minecraft_config.console_type.clone()
+    }
+
+    pub fn get_console_target(&self) -> String {
+        self.minecraft_config.console_target.clone()
+    }
+
     pub fn get_death_keywords(&self) -> Vec<String> {
         self.minecraft_config.custom_death_keywords.clone()
     }
@@ -130,14 +1113,162 @@ impl RootConfig {
         self.minecraft_config.death_ignore_keywords.clone()
     }
 
+    pub fn death_stats_enabled(&self) -> bool {
+        self.minecraft_config.death_stats
+    }
+
+    pub fn death_embeds_enabled(&self) -> bool {
+        self.minecraft_config.death_embeds
+    }
+
+    pub fn get_death_translations(&self) -> HashMap<String, String> {
+        self.minecraft_config.death_translations.clone()
+    }
+
+    pub fn announce_joins(&self) -> bool {
+        self.minecraft_config.announce_joins
+    }
+
+    pub fn announce_leaves(&self) -> bool {
+        self.minecraft_config.announce_leaves
+    }
+
+    pub fn announce_deaths(&self) -> bool {
+        self.minecraft_config.announce_deaths
+    }
+
+    pub fn announce_advancements(&self) -> bool {
+        self.minecraft_config.announce_advancements
+    }
+
+    /// Whether to post when the server starts and stops.
+    pub fn announce_server_status(&self) -> bool {
+        self.minecraft_config.announce_server_status
+    }
+
+    /// Whether only events are bridged to Discord, and never what
+    /// players say.
+    pub fn events_only(&self) -> bool {
+        self.minecraft_config.events_only
+    }
+
+    pub fn get_advancements_file(&self) -> String {
+        self.minecraft_config.advancements_file.clone()
+    }
+
     pub fn get_log_path(&self) -> String {
         self.minecraft_config.log_file_path.clone()
     }
 
+    /// The log file path of the server bridged to `channel_id`: one of
+    /// `servers` if it's theirs, or else the main server's.
+    pub fn get_log_path_for(&self, channel_id: u64) -> String {
+        self.servers
+            .iter()
+            .find(|server| server.channel_id != 0 && server.channel_id == channel_id)
+            .map(|server| server.log_file_path.clone())
+            .unwrap_or_else(|| self.get_log_path())
+    }
+
+    /// The language players write in, for machine translation.
+    pub fn get_minecraft_language(&self) -> String {
+        self.minecraft_config.language.clone()
+    }
+
+    pub fn get_translation_endpoint(&self) -> String {
+        self.translation_config.endpoint.clone()
+    }
+
+    pub fn get_translation_api_key(&self) -> String {
+        self.translation_config.api_key.clone()
+    }
+
+    /// Whether there's somewhere to get the server's resource usage from.
+    pub fn usage_enabled(&self) -> bool {
+        !self.usage_config.endpoint.is_empty() || !self.usage_config.tps_command.is_empty()
+    }
+
+    pub fn get_usage_endpoint(&self) -> String {
+        self.usage_config.endpoint.clone()
+    }
+
+    pub fn get_usage_tps_command(&self) -> String {
+        self.usage_config.tps_command.clone()
+    }
+
+    pub fn get_usage_poll_interval_secs(&self) -> u64 {
+        self.usage_config.poll_interval_secs
+    }
+
+    pub fn get_usage_report_interval_mins(&self) -> u64 {
+        self.usage_config.report_interval_mins
+    }
+
+    pub fn update_check_enabled(&self) -> bool {
+        self.update_config.enabled
+    }
+
+    pub fn update_notify_ops(&self) -> bool {
+        self.update_config.notify_ops
+    }
+
+    pub fn get_startup_suppression_secs(&self) -> u64 {
+        self.minecraft_config.startup_suppression_secs
+    }
+
+    pub fn get_startup_suppression_mode(&self) -> String {
+        self.minecraft_config.startup_suppression_mode.clone()
+    }
+
     pub fn get_chat_regex(&self) -> String {
         self.minecraft_config.chat_regex.clone()
     }
 
+    pub fn get_unmatched_log_path(&self) -> String {
+        self.minecraft_config.unmatched_log_path.clone()
+    }
+
+    /// The longest line to send to Minecraft before splitting it up.
+    /// Set to 0 for the default of 256, the chat limit since 1.11.
+    pub fn get_max_line_length(&self) -> usize {
+        match self.minecraft_config.max_line_length {
+            0 => MINECRAFT_MAX_LINE_LENGTH,
+            length => length,
+        }
+    }
+
+    pub fn get_send_delay_secs(&self) -> u64 {
+        self.minecraft_config.send_delay_secs
+    }
+
+    /// How long edits and deletes of Discord messages are passed on to
+    /// players after the message was sent. Set to 0 to disable.
+    pub fn get_edit_window_secs(&self) -> u64 {
+        self.minecraft_config.edit_window_secs
+    }
+
+    /// How many Discord messages to hold on to while the Minecraft
+    /// server can't be reached. Set to 0 to disable.
+    pub fn get_retry_queue_size(&self) -> usize {
+        self.minecraft_config.retry_queue_size
+    }
+
+    pub fn get_retry_max_age_secs(&self) -> u64 {
+        self.minecraft_config.retry_max_age_secs
+    }
+
+    pub fn get_server_start_template(&self) -> String {
+        self.minecraft_config.server_start_template.clone()
+    }
+
+    pub fn get_reply_template(&self) -> String {
+        self.minecraft_config.reply_template.clone()
+    }
+
+    pub fn get_startup_commands(&self) -> Vec<String> {
+        self.minecraft_config.startup_commands.clone()
+    }
+
     pub fn get_attachment_template(&self) -> String {
         self.minecraft_config.templates.attachment_template.clone()
     }
@@ -150,14 +1281,123 @@ impl RootConfig {
         self.minecraft_config.templates.username_template.clone()
     }
 
+    pub fn get_edited_template(&self) -> String {
+        self.minecraft_config.templates.edited_template.clone()
+    }
+
+    pub fn get_deleted_template(&self) -> String {
+        self.minecraft_config.templates.deleted_template.clone()
+    }
+
     pub fn enable_webserver(&self) -> bool {
         self.webserver_config.enabled
     }
 
+    /// Get the token needed to use the webserver's control API. The API
+    /// is off if this is empty.
+    pub fn get_webserver_api_token(&self) -> String {
+        self.webserver_config.api_token.clone()
+    }
+
+    /// Get the secret senders have to give as a bearer token to post
+    /// messages to the webserver.
+    pub fn get_webserver_auth_token(&self) -> String {
+        self.webserver_config.auth_token.clone()
+    }
+
+    /// Whether anyone can post to the webserver when no `auth_token` is
+    /// set. Off unless the config says so.
+    pub fn webserver_allows_unauthenticated(&self) -> bool {
+        self.webserver_config.allow_unauthenticated
+    }
+
+    /// Make sure the webserver isn't left open to anyone by accident. It
+    /// needs an `auth_token`, unless `allow_unauthenticated` is set.
+    pub fn check_webserver_auth(&self) -> Result<(), Error> {
+        if self.enable_webserver()
+            && self.webserver_config.auth_token.is_empty()
+            && !self.webserver_config.allow_unauthenticated
+        {
+            return Err(Error::NoAuthToken);
+        }
+
+        Ok(())
+    }
+
+    /// Get the addresses and networks the webserver takes requests from.
+    /// Every address is allowed if this is empty.
+    pub fn get_webserver_allowed_ips(&self) -> Vec<String> {
+        self.webserver_config.allowed_ips.clone()
+    }
+
     pub fn get_webserver_port(&self) -> u16 {
         self.webserver_config.port
     }
 
+    pub fn strip_legacy_colors(&self) -> bool {
+        self.webserver_config.strip_legacy_colors
+    }
+
+    pub fn get_webserver_max_pending(&self) -> usize {
+        self.webserver_config.max_pending
+    }
+
+    pub fn get_webserver_retry_after_secs(&self) -> u64 {
+        self.webserver_config.retry_after_secs
+    }
+
+    pub fn enable_grpc(&self) -> bool {
+        self.grpc_config.enabled
+    }
+
+    pub fn get_grpc_port(&self) -> u16 {
+        self.grpc_config.port
+    }
+
+    pub fn enable_mqtt(&self) -> bool {
+        self.mqtt_config.enabled
+    }
+
+    /// Get the external endpoints to post events to, as
+    /// `(url, secret, events)`. Endpoints without a URL are left out.
+    pub fn get_event_webhooks(&self) -> Vec<(String, String, Vec<String>)> {
+        self.event_webhooks
+            .values()
+            .filter(|webhook| !webhook.url.is_empty())
+            .map(|webhook| {
+                (
+                    webhook.url.clone(),
+                    webhook.secret.clone(),
+                    webhook.events.clone(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn managed_server_enabled(&self) -> bool {
+        self.server_config.enabled
+    }
+
+    pub fn get_server_command(&self) -> Vec<String> {
+        self.server_config.command.clone()
+    }
+
+    pub fn get_server_working_dir(&self) -> String {
+        self.server_config.working_dir.clone()
+    }
+
+    pub fn restart_server_on_crash(&self) -> bool {
+        self.server_config.restart_on_crash
+    }
+
+    pub fn get_server_restart_delay_secs(&self) -> u64 {
+        self.server_config.restart_delay_secs
+    }
+
+    pub fn get_server_stop_timeout_secs(&self) -> u64 {
+        self.server_config.stop_timeout_secs
+    }
+
     // pub fn set_discord_channel(&mut self, channel: u64) {
     //     self.discord_config.channel_id = channel;
     // }
@@ -182,9 +1422,24 @@ impl RootConfig {
     //     self.minecraft_config.rcon_password = value;
     // }
 
-    // pub fn set_log_file(&mut self, value: String) {
-    //     self.minecraft_config.log_file_path = value;
-    // }
+    pub fn set_log_file(&mut self, value: String) {
+        self.minecraft_config.log_file_path = value;
+    }
+
+    pub fn set_webserver_port(&mut self, value: u16) {
+        self.webserver_config.port = value;
+    }
+
+    /// Get messages from the webserver, or from the log file if
+    /// `webserver` is false, instead of any other listener. Dolphin
+    /// won't run the Minecraft server either, since its output would be
+    /// used instead.
+    pub fn set_listener(&mut self, webserver: bool) {
+        self.webserver_config.enabled = webserver;
+        self.grpc_config.enabled = false;
+        self.mqtt_config.enabled = false;
+        self.server_config.enabled = false;
+    }
 
     // pub fn set_chat_regex(&mut self, value: String) {
     //     self.minecraft_config.chat_regex = value;
@@ -194,3 +1449,230 @@ impl RootConfig {
     //     self.discord_config.webhook_url = value;
     // }
 }
+
+// The rest of the MQTT settings are only read by the MQTT listener
+#[cfg(feature = "mqtt")]
+impl RootConfig {
+    pub fn get_mqtt_broker_url(&self) -> String {
+        self.mqtt_config.broker_url.clone()
+    }
+
+    pub fn get_mqtt_client_id(&self) -> String {
+        self.mqtt_config.client_id.clone()
+    }
+
+    pub fn get_mqtt_username(&self) -> String {
+        self.mqtt_config.username.clone()
+    }
+
+    pub fn get_mqtt_password(&self) -> String {
+        self.mqtt_config.password.clone()
+    }
+
+    pub fn get_mqtt_inbound_topic(&self) -> String {
+        self.mqtt_config.inbound_topic.clone()
+    }
+
+    pub fn get_mqtt_outbound_topic(&self) -> String {
+        self.mqtt_config.outbound_topic.clone()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("'{0}' requires '{1}' to be enabled")]
+    MissingIntent(&'static str, &'static str),
+
+    #[error("rcon_transport '{0}' isn't supported on this platform")]
+    UnsupportedTransport(String),
+
+    #[error(
+        "the webserver needs an auth_token, or allow_unauthenticated to take requests from anyone"
+    )]
+    NoAuthToken,
+
+    #[error("unable to parse config: {0}")]
+    Parse(#[from] toml_edit::TomlError),
+
+    #[error("unable to write config: {0}")]
+    Write(#[from] toml::ser::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::prelude::GatewayIntents;
+
+    use super::{Error, RootConfig};
+
+    #[test]
+    fn override_the_listener() {
+        // Given
+        let mut config = RootConfig::default();
+        config.grpc_config.enabled = true;
+        config.server_config.enabled = true;
+
+        // When
+        config.set_listener(true);
+
+        // Then
+        assert!(config.enable_webserver());
+        assert!(!config.enable_grpc());
+        assert!(!config.managed_server_enabled());
+    }
+
+    #[test]
+    fn need_a_webserver_token() {
+        // Given
+        let mut config = RootConfig::default();
+        config.webserver_config.enabled = true;
+
+        // When
+        let missing = config.check_webserver_auth();
+        config.webserver_config.allow_unauthenticated = true;
+        let opted_out = config.check_webserver_auth();
+
+        // Then
+        assert!(matches!(missing, Err(Error::NoAuthToken)));
+        assert!(opted_out.is_ok());
+        assert!(RootConfig::default().check_webserver_auth().is_ok());
+    }
+
+    #[test]
+    fn configure_extra_servers() {
+        // Given
+        let mut config: RootConfig = toml::from_str(
+            r#"
+[discord_config]
+channel_id = 1
+
+[discord_config.mirrors.eu]
+channel_id = 9
+
+[[servers]]
+name = "creative"
+channel_id = 2
+rcon_port = 25576
+log_file_path = "/srv/creative/logs/latest.log"
+
+[[servers]]
+name = "unfinished"
+"#,
+        )
+        .unwrap();
+        config.webserver_config.enabled = true;
+
+        // When
+        let servers = config.get_servers();
+
+        // Then
+        assert_eq!(servers.len(), 1);
+        let (name, token, server) = &servers[0];
+        assert_eq!(name, "creative");
+        assert_eq!(token, "");
+        assert_eq!(server.get_channel_id(), 2);
+        assert_eq!(server.get_rcon_addr(), "localhost:25576");
+        assert_eq!(server.get_log_path(), "/srv/creative/logs/latest.log");
+        assert!(server.get_mirrors().is_empty());
+        assert!(!server.enable_webserver());
+        assert_eq!(config.get_channel_id(), 1);
+        assert_eq!(config.get_log_path_for(2), "/srv/creative/logs/latest.log");
+        assert_eq!(config.get_log_path_for(1), config.get_log_path());
+    }
+
+    #[test]
+    fn mirror_templates() {
+        // Given
+        let config: RootConfig = toml::from_str(
+            r#"
+[discord_config]
+channel_id = 1
+
+[discord_config.mirrors]
+staff = { channel_id = 2, message_template = "[\"\",{\"text\":\"%content%\",\"color\":\"red\"}]", events = ["death"] }
+public = { channel_id = 3 }
+"#,
+        )
+        .unwrap();
+
+        // Then
+        assert!(config.get_message_template_for(2).contains("red"));
+        assert_eq!(
+            config.get_message_template_for(3),
+            config.get_message_template()
+        );
+        assert_eq!(
+            config.get_message_template_for(1),
+            config.get_message_template()
+        );
+        assert_eq!(config.get_mirror_events("staff"), vec!["death"]);
+        assert!(config.get_mirror_events("public").is_empty());
+    }
+
+    #[test]
+    fn only_check_for_updates_when_asked() {
+        // Given
+        let config = RootConfig::default();
+        let opted_in: RootConfig = toml::from_str("[update_config]\nenabled = true\n").unwrap();
+
+        // Then
+        assert!(!config.update_check_enabled());
+        assert!(opted_in.update_check_enabled());
+    }
+
+    #[test]
+    fn default_intents() {
+        // Given
+        let config = RootConfig::default();
+
+        // When
+        let intents = config.gateway_intents().unwrap();
+
+        // Then
+        assert!(intents.contains(GatewayIntents::GUILD_MEMBERS));
+        assert!(intents.contains(GatewayIntents::GUILD_PRESENCES));
+        assert!(intents.contains(GatewayIntents::MESSAGE_CONTENT));
+        assert!(!intents.contains(GatewayIntents::GUILD_VOICE_STATES));
+    }
+
+    #[test]
+    fn voice_channel_needs_voice_states() {
+        // Given
+        let mut config = RootConfig::default();
+        config.discord_config.voice.channel_id = 1234;
+
+        // When
+        let intents = config.gateway_intents().unwrap();
+
+        // Then
+        assert!(intents.contains(GatewayIntents::GUILD_VOICE_STATES));
+    }
+
+    #[test]
+    fn disable_presences() {
+        // Given
+        let mut config = RootConfig::default();
+        config.discord_config.gateway.presences_intent = false;
+
+        // When
+        let intents = config.gateway_intents().unwrap();
+
+        // Then
+        assert!(!intents.contains(GatewayIntents::GUILD_PRESENCES));
+    }
+
+    #[test]
+    fn mentions_require_members_intent() {
+        // Given
+        let mut config = RootConfig::default();
+        config.discord_config.gateway.members_intent = false;
+
+        // When/Then
+        assert!(config.gateway_intents().is_err());
+
+        config.discord_config.allow_mentions = false;
+        assert!(config.gateway_intents().is_ok());
+    }
+}
@@ -0,0 +1,776 @@
+use toml_edit::{DocumentMut, Item, Table};
+
+use super::{Error, RootConfig};
+
+/// Documentation for every config option, keyed by its dotted path.
+///
+/// When adding a config option, add an entry here too so it shows up
+/// in `dolphin config schema`. A test makes sure nothing is missed.
+const DOCS: &[(&str, &str)] = &[
+    ("discord_config", "Settings for the Discord side of the bridge"),
+    (
+        "discord_config.channel_id",
+        "The ID of the Discord channel to bridge with Minecraft",
+    ),
+    (
+        "discord_config.ops_channel_id",
+        "The ID of a Discord channel for admin messages. Set to 0 to use `channel_id`",
+    ),
+    (
+        "discord_config.advancement_channel_id",
+        "The ID of a Discord channel to post advancements in instead of `channel_id`. Set to 0 to disable",
+    ),
+    (
+        "discord_config.death_channel_id",
+        "The ID of a Discord channel to post deaths in instead of `channel_id`. Set to 0 to disable",
+    ),
+    (
+        "discord_config.startup_banner",
+        "Post Dolphin's version, the bridge mode, and the Minecraft version to the ops channel on startup",
+    ),
+    (
+        "discord_config.lag_alert_threshold",
+        "Alert the ops channel when the server logs this many \"Can't keep up!\" warnings within `lag_alert_window_secs`. Set to 0 to disable",
+    ),
+    (
+        "discord_config.lag_alert_window_secs",
+        "How many seconds back lag warnings are counted. At most one lag alert is posted per window",
+    ),
+    (
+        "discord_config.ignored_bot_ids",
+        "IDs of other bots whose messages are never sent to Minecraft, e.g. other bridges",
+    ),
+    (
+        "discord_config.echo_window_secs",
+        "Drop messages that repeat something bridged within this many seconds, to stop bridge loops. Set to 0 to disable",
+    ),
+    (
+        "discord_config.relog_window_secs",
+        "Wait this many seconds before posting a leave message, and post neither it nor the join if the player comes back in time. Set to 0 to disable",
+    ),
+    (
+        "discord_config.relog_note",
+        "Post a short \"reconnected\" note instead when a player leaves and rejoins within `relog_window_secs`",
+    ),
+    (
+        "discord_config.spam_window_secs",
+        "Collapse a player's message into an \"(xN)\" count on their last post if they repeat it within this many seconds. Set to 0 to disable",
+    ),
+    (
+        "discord_config.spam_mute_threshold",
+        "Stop bridging a player's chat for `spam_mute_secs` once they repeat a message this many times in a row. Set to 0 to disable",
+    ),
+    (
+        "discord_config.spam_mute_secs",
+        "How many seconds players stay muted on Discord for spamming",
+    ),
+    (
+        "discord_config.allow_mentions",
+        "Allow Minecraft players to mention Discord users, roles, and channels",
+    ),
+    (
+        "discord_config.mention_matching",
+        "How names in mentions are matched: `exact`, `ignore_case`, or `prefix`, which also matches the start of a member's name if only one member's name starts with it",
+    ),
+    (
+        "discord_config.allow_everyone_mentions",
+        "Let every Minecraft player ping `@everyone` and `@here`",
+    ),
+    (
+        "discord_config.everyone_mention_players",
+        "Minecraft players who can always ping `@everyone` and `@here`",
+    ),
+    (
+        "discord_config.event_players",
+        "Minecraft players who can schedule Discord events from game with `!event \"name\" <start> <length>`",
+    ),
+    (
+        "discord_config.use_member_nicks",
+        "Use Discord server nicknames instead of usernames in Minecraft",
+    ),
+    (
+        "discord_config.webhook_url",
+        "Post messages from Minecraft through this webhook. Leave empty to post as the bot",
+    ),
+    (
+        "discord_config.guild_tag",
+        "Put this tag in front of the names of Discord users in Minecraft, e.g. `[EU] Steve`, to tell them apart from mirrors. Leave empty to add nothing",
+    ),
+    (
+        "discord_config.language",
+        "The language people write in the bridge channel, e.g. `en`, for machine translation",
+    ),
+    (
+        "discord_config.mirrors",
+        "Other channels, e.g. in other Discord servers, to copy messages from Minecraft to, e.g.\nUS = { channel_id = 0, webhook_url = \"\", language = \"\", events = [], message_template = \"\" }\nMessages from these channels are sent to Minecraft with the mirror's name as a tag, using `message_template` if it's set. `events` picks the kinds of messages posted there, like the bridge channel's `channel_events`",
+    ),
+    (
+        "discord_config.channel_events",
+        "The kinds of messages from Minecraft to post in the bridge channel: `chat`, `join`, `leave`, `death`, `advancement`, `server_start`, and `server_stop`. Leave empty to post everything",
+    ),
+    (
+        "discord_config.command_prefix",
+        "Prefix for old-style commands like `!list`. Leave empty to disable",
+    ),
+    (
+        "discord_config.max_message_length",
+        "Messages from Minecraft longer than this are split up. Can't be more than 2000",
+    ),
+    (
+        "discord_config.continuation_marker",
+        "Added to the end of each part of a split message, except the last",
+    ),
+    (
+        "discord_config.size_summary_interval_mins",
+        "How often to log how long bridged messages are, and how many were split up. Set to 0 to disable",
+    ),
+    (
+        "discord_config.escape_event_markdown",
+        "Escape markdown in join, leave, death, and Advancement messages, so names like `xX_Steve_Xx` show up as written",
+    ),
+    (
+        "discord_config.escape_chat_markdown",
+        "Escape markdown in chat messages too. Players won't be able to use markdown in chat",
+    ),
+    (
+        "discord_config.corrections",
+        "Let players fix their last message with `s/old/new/`, which edits it on Discord instead of posting the correction. Only works with `webhook_url`",
+    ),
+    (
+        "discord_config.normalize_unicode",
+        "Normalize text sent both ways and remove invisible and direction override characters that can be used to spoof names",
+    ),
+    (
+        "discord_config.transliterate",
+        "Replace characters in messages from Discord with ASCII lookalikes, like `é` with `e`, for Minecraft clients that show them as `?`",
+    ),
+    (
+        "discord_config.transliterate_exceptions",
+        "Characters to send to Minecraft as they are when transliterating, e.g. [\"é\", \"ñ\"]",
+    ),
+    (
+        "discord_config.ascii_emoji",
+        "Replace common emoji in messages from Discord with ASCII like `:)`, or their name like `:fire:`, since Minecraft shows them as boxes",
+    ),
+    (
+        "discord_config.emoji_shortcodes",
+        "Turn `:name:` in chat from Minecraft into the guild's custom emoji with that name, or common emoji like `:thumbsup:`",
+    ),
+    (
+        "discord_config.timestamps",
+        "Start messages from Minecraft with the time they happened, shown in each user's time zone",
+    ),
+    (
+        "discord_config.timestamp_style",
+        "Discord timestamp style: `t` (16:20), `T` (16:20:30), `f` (full date and time), or `R` (2 minutes ago)",
+    ),
+    (
+        "discord_config.mention_dms",
+        "Let users opt in with `/notify` to get a DM when they're mentioned in game while offline",
+    ),
+    (
+        "discord_config.whispers",
+        "Let players send a DM to a Discord user with `@discord Name: message` in chat",
+    ),
+    (
+        "discord_config.whisper_players",
+        "Names of the players allowed to whisper to Discord. Leave empty to let every player whisper to any member",
+    ),
+    (
+        "discord_config.outage_notices",
+        "Tell players in game when the connection to Discord drops, and when it's back",
+    ),
+    (
+        "discord_config.online_notice",
+        "Sent to the bridge channel and to players in game when Dolphin starts bridging. Leave empty to disable",
+    ),
+    (
+        "discord_config.offline_notice",
+        "Sent to the bridge channel and to players in game when Dolphin is stopped. Leave empty to disable",
+    ),
+    (
+        "discord_config.reserved_names",
+        "Player names containing any of these get `reserved_name_suffix` added when posted with the webhook",
+    ),
+    (
+        "discord_config.reserved_name_suffix",
+        "Suffix for player names that contain a reserved name. Leave empty to disable",
+    ),
+    (
+        "discord_config.player_name_template",
+        "How player names are shown on Discord. Placeholders: %name%, %rank% (from a `rank` or `prefix` group in the chat regex)",
+    ),
+    (
+        "discord_config.name_swatches",
+        "Put a coloured square before player names, the same colour for each player, when posting without a webhook",
+    ),
+    (
+        "discord_config.chat_style",
+        "How chat is posted: `plain` for a message each, or `ansi` to group it into code blocks with coloured names, posted by the bot",
+    ),
+    (
+        "discord_config.ansi_batch_window_secs",
+        "With the `ansi` chat style, how many seconds of chat to group into each post",
+    ),
+    (
+        "discord_config.server_display_name",
+        "The name to post server messages with. Leave empty to use the bot's name",
+    ),
+    (
+        "discord_config.server_avatar_url",
+        "The avatar to post server messages with through the webhook. Leave empty to use the bot's avatar, or Discord's default one if it has none",
+    ),
+    (
+        "discord_config.avatar_provider",
+        "Where to get player avatars for the webhook. `%uuid%` is replaced with the player's UUID and `%username%` with their name",
+    ),
+    (
+        "discord_config.avatar_fallbacks",
+        "Avatar templates to try in order if the provider is down, or uses `%uuid%` and the player's UUID isn't known",
+    ),
+    (
+        "discord_config.avatar_refresh_mins",
+        "How often, in minutes, to check if a player's avatar changed, e.g. after a skin change. Avatar URLs get a version that only changes when the avatar does, so Discord shows the new one. 0 turns this off",
+    ),
+    (
+        "discord_config.cmd_roles",
+        "IDs of the roles allowed to run any command on the Minecraft server with `/cmd`. `/cmd` isn't registered if this is empty",
+    ),
+    (
+        "discord_config.name_replacements",
+        "Characters to replace in Discord names before they are shown in Minecraft.\nAny quotes or backslashes left over are escaped, and `§` codes are always removed",
+    ),
+    (
+        "discord_config.avatar_overrides",
+        "Custom avatar URLs for players posted with the webhook, by player name or UUID, e.g.\nSteve = \"https://example.com/steve.png\"",
+    ),
+    (
+        "discord_config.command_responses",
+        "How to show each slash command's response, e.g.\nlist = { visibility = \"auto_delete\", delete_after_secs = 30 }\nVisibility is `ephemeral` (only the user who ran it), `public`, or `auto_delete`",
+    ),
+    (
+        "discord_config.command_cooldowns",
+        "How long to wait between uses of each slash command, per user and per channel, e.g.\nlist = { user_secs = 30, channel_secs = 10 }",
+    ),
+    (
+        "discord_config.role_prefixes",
+        "Show the highest of these roles a Discord user has in front of their name in game, keyed by role ID, e.g.\n\"123456789012345678\" = { label = \"Admin\", color = \"red\" }\nLeave `label` empty to use the role's name, and `color` empty for white",
+    ),
+    (
+        "discord_config.macros",
+        "Named lists of commands run with `/macro`, e.g.\npromote = { description = \"Promote a player\", commands = [\"lp user %player% parent add member\"], roles = [] }\n`%name%` placeholders become command options. Macros without `roles` need the Manage Server permission",
+    ),
+    ("discord_config.gateway", "Discord gateway and cache settings"),
+    (
+        "discord_config.gateway.members_intent",
+        "Request the Server Members intent. Required if `allow_mentions` is enabled",
+    ),
+    (
+        "discord_config.gateway.presences_intent",
+        "Request the Presence intent",
+    ),
+    (
+        "discord_config.gateway.max_cached_messages",
+        "The maximum number of messages to cache per channel",
+    ),
+    (
+        "discord_config.gateway.cache_users",
+        "Keep Discord users in the cache",
+    ),
+    (
+        "discord_config.publish",
+        "If the channel is an Announcement channel, publish these messages to following servers",
+    ),
+    (
+        "discord_config.publish.server_start",
+        "Publish server start messages",
+    ),
+    (
+        "discord_config.publish.server_stop",
+        "Publish server stop messages",
+    ),
+    (
+        "discord_config.sound_cues",
+        "Play sounds in game for some Discord messages",
+    ),
+    (
+        "discord_config.sound_cues.enabled",
+        "Play sounds for mentions and announcements",
+    ),
+    (
+        "discord_config.sound_cues.mention_sound",
+        "Sound a player hears when they are mentioned, e.g. `@Steve`. Leave empty to disable",
+    ),
+    (
+        "discord_config.sound_cues.announcement_roles",
+        "IDs of Discord roles whose messages count as announcements",
+    ),
+    (
+        "discord_config.sound_cues.announcement_sound",
+        "Sound everyone hears for announcements. Leave empty to disable",
+    ),
+    (
+        "discord_config.voice",
+        "Let players in game know when people join or leave a voice channel",
+    ),
+    (
+        "discord_config.voice.channel_id",
+        "The ID of the voice channel to watch. Set to 0 to disable",
+    ),
+    (
+        "discord_config.voice.display",
+        "Where to show voice updates in game: `actionbar` or `chat`",
+    ),
+    (
+        "discord_config.digest",
+        "Post joins, leaves, and deaths together in a regular summary instead of one at a time",
+    ),
+    (
+        "discord_config.digest.enabled",
+        "Collect joins, leaves, and deaths into a digest",
+    ),
+    (
+        "discord_config.digest.interval_mins",
+        "How often to post the digest, in minutes. `/digest` posts it right away",
+    ),
+    (
+        "minecraft_config",
+        "Settings for the Minecraft side of the bridge",
+    ),
+    (
+        "minecraft_config.advancements_file",
+        "Path to a JSON file with custom Advancement names and descriptions",
+    ),
+    (
+        "minecraft_config.rcon_ip",
+        "The address of the Minecraft server's RCON",
+    ),
+    (
+        "minecraft_config.rcon_port",
+        "The port of the Minecraft server's RCON",
+    ),
+    (
+        "minecraft_config.rcon_password",
+        "The password of the Minecraft server's RCON",
+    ),
+    (
+        "minecraft_config.rcon_transport",
+        "How to send commands to the server: `tcp` for RCON at `rcon_ip` and `rcon_port`, `unix` for RCON on `rcon_socket_path`,\n`command` to run `rcon_command`, or `console` to write to the server console. `/list` doesn't work with `console`",
+    ),
+    (
+        "minecraft_config.rcon_socket_path",
+        "Path to a Unix socket that speaks RCON, used with the `unix` transport",
+    ),
+    (
+        "minecraft_config.rcon_command",
+        "Program and arguments to run for each command, used with the `command` transport.\nThe Minecraft command is added as the last argument, and the program's output is used as the response",
+    ),
+    (
+        "minecraft_config.console_type",
+        "How to reach the console with the `console` transport: `pipe`, `tmux`, or `screen`",
+    ),
+    (
+        "minecraft_config.console_target",
+        "The named pipe to write to, or the tmux target or screen session the server runs in",
+    ),
+    (
+        "minecraft_config.custom_death_keywords",
+        "Extra keywords that mark a log line as a death message",
+    ),
+    (
+        "minecraft_config.death_ignore_keywords",
+        "Death messages containing any of these keywords are not sent to Discord",
+    ),
+    (
+        "minecraft_config.death_stats",
+        "Note how often a kind of death has happened this week on death messages",
+    ),
+    (
+        "minecraft_config.death_embeds",
+        "Post death messages as embeds with an icon for the cause and the player's head",
+    ),
+    (
+        "minecraft_config.announce_joins",
+        "Post in Discord when players join",
+    ),
+    (
+        "minecraft_config.announce_leaves",
+        "Post in Discord when players leave",
+    ),
+    (
+        "minecraft_config.announce_deaths",
+        "Post in Discord when players die",
+    ),
+    (
+        "minecraft_config.announce_advancements",
+        "Post in Discord when players make an advancement",
+    ),
+    (
+        "minecraft_config.announce_server_status",
+        "Post in Discord when the server starts and stops",
+    ),
+    (
+        "minecraft_config.events_only",
+        "Only bridge events like joins, leaves, deaths, and advancements to Discord. Chat, whispers, and other messages from Minecraft are never sent there",
+    ),
+    (
+        "minecraft_config.death_translations",
+        "Map localized death messages to the message to post instead, e.g.\n\"%1$s ertrank\" = \"%1$s drowned\"",
+    ),
+    (
+        "minecraft_config.log_file_path",
+        "Path to the Minecraft server's latest.log file",
+    ),
+    (
+        "minecraft_config.language",
+        "The language players chat in, e.g. `de`. Chat is machine translated if this and the channel's `language` are both set and different",
+    ),
+    (
+        "minecraft_config.startup_suppression_secs",
+        "For this many seconds after starting, hold back messages from the log file. Set to 0 to disable",
+    ),
+    (
+        "minecraft_config.startup_suppression_mode",
+        "What to hold back after starting: `events` to only bridge chat, or `all` to bridge nothing",
+    ),
+    (
+        "minecraft_config.chat_regex",
+        "Pattern to match chat messages. Must have `username` and `content` named groups",
+    ),
+    (
+        "minecraft_config.unmatched_log_path",
+        "Write log lines that didn't match chat or any event to this file, to help with writing the chat regex. Leave empty to disable",
+    ),
+    (
+        "minecraft_config.max_line_length",
+        "Lines from Discord longer than this are split up. Use 100 for Minecraft versions before 1.11",
+    ),
+    (
+        "minecraft_config.send_delay_secs",
+        "Wait this many seconds before sending Discord messages to Minecraft, and don't send ones deleted in the meantime. Set to 0 to disable",
+    ),
+    (
+        "minecraft_config.edit_window_secs",
+        "Show players edits and deletes of Discord messages made within this many seconds of sending them. Set to 0 to disable",
+    ),
+    (
+        "minecraft_config.retry_queue_size",
+        "How many Discord messages to hold on to while the Minecraft server can't be reached, to send once it's back. Set to 0 to disable",
+    ),
+    (
+        "minecraft_config.retry_max_age_secs",
+        "Held messages older than this many seconds are dropped instead of sent",
+    ),
+    (
+        "minecraft_config.server_start_template",
+        "Message posted in Discord when the server has started. Placeholders: %version%, %startup_time%",
+    ),
+    (
+        "minecraft_config.reply_template",
+        "Put in front of Discord replies in Minecraft, to show what they're replying to. Placeholders: %username%, %snippet%. Leave empty to disable",
+    ),
+    (
+        "minecraft_config.startup_commands",
+        "Commands to run on the server once its Done line is read from the log or console, in order. Placeholders: %version%, %startup_time%",
+    ),
+    (
+        "minecraft_config.templates",
+        "tellraw JSON templates for messages sent to Minecraft",
+    ),
+    (
+        "minecraft_config.templates.username_template",
+        "Template for the sender's name. Placeholders: %username%, %mention%",
+    ),
+    (
+        "minecraft_config.templates.attachment_template",
+        "Template for attachment notices. Placeholders: %num%, %url%",
+    ),
+    (
+        "minecraft_config.templates.message_template",
+        "Template for each line of a message. Placeholders: %content%, %original% (the message as it was written, when it was translated)",
+    ),
+    (
+        "minecraft_config.templates.edited_template",
+        "Template put before the new version of an edited message. Leave empty to not show edits",
+    ),
+    (
+        "minecraft_config.templates.deleted_template",
+        "Template shown after the sender's name when a message is deleted. Leave empty to not show deletes",
+    ),
+    (
+        "webserver_config",
+        "Listen for messages from a remote Minecraft server instead of reading a log file",
+    ),
+    (
+        "webserver_config.enabled",
+        "Use the webserver instead of reading the log file",
+    ),
+    (
+        "webserver_config.port",
+        "The port to listen for messages on",
+    ),
+    (
+        "webserver_config.strip_legacy_colors",
+        "Remove legacy `§` color and formatting codes from messages, as well as console color codes",
+    ),
+    (
+        "webserver_config.max_pending",
+        "How many messages can be waiting to go to Discord at once. More are turned away with a 429 response",
+    ),
+    (
+        "webserver_config.retry_after_secs",
+        "How many seconds senders are told to wait in the `Retry-After` header when they're turned away",
+    ),
+    (
+        "webserver_config.api_token",
+        "The token to send in the `X-Dolphin-Token` header to use the control API under `/api`. The API is off while this is empty",
+    ),
+    (
+        "webserver_config.auth_token",
+        "A secret senders have to give in an `Authorization: Bearer <token>` header to post messages. Dolphin won't start the webserver without one unless `allow_unauthenticated` is set",
+    ),
+    (
+        "webserver_config.allow_unauthenticated",
+        "Let anyone post to the webserver while `auth_token` is empty. Only turn this on if nothing else can reach the port",
+    ),
+    (
+        "webserver_config.allowed_ips",
+        "Addresses or networks, like `10.0.0.5` or `10.0.0.0/24`, that can send to the webserver. Leave empty to allow all of them",
+    ),
+    (
+        "grpc_config",
+        "Stream messages to and from a companion plugin over gRPC. Dolphin must be built with the `grpc` feature",
+    ),
+    (
+        "grpc_config.enabled",
+        "Use the gRPC listener instead of reading the log file",
+    ),
+    (
+        "grpc_config.port",
+        "The port to listen for plugins on",
+    ),
+    (
+        "mqtt_config",
+        "Exchange messages through an MQTT broker. Dolphin must be built with the `mqtt` feature",
+    ),
+    (
+        "mqtt_config.enabled",
+        "Use MQTT instead of reading the log file",
+    ),
+    (
+        "mqtt_config.broker_url",
+        "The broker to connect to, e.g. `mqtt://localhost:1883`",
+    ),
+    (
+        "mqtt_config.client_id",
+        "The client ID to connect with. This must be unique on the broker",
+    ),
+    (
+        "mqtt_config.username",
+        "The username to log in to the broker with, if it needs one",
+    ),
+    (
+        "mqtt_config.password",
+        "The password to log in to the broker with",
+    ),
+    (
+        "mqtt_config.inbound_topic",
+        "The topic to read messages from Minecraft from, using the webserver's JSON body",
+    ),
+    (
+        "mqtt_config.outbound_topic",
+        "The topic to publish chat from Discord to",
+    ),
+    (
+        "event_webhooks",
+        "External endpoints to post events from Minecraft to as JSON, e.g.\nstats = { url = \"https://example.com/hook\", secret = \"hunter2\", events = [\"join\", \"death\"] }\nLeave `events` empty to get every event. With a `secret`, each post is signed in the `X-Dolphin-Signature` header",
+    ),
+    (
+        "servers",
+        "Extra Minecraft servers to bridge, each with its own channel, e.g.\n[[servers]]\nname = \"creative\"\nchannel_id = 1234\nwebhook_url = \"\"\nrcon_ip = \"localhost\"\nrcon_port = 25576\nrcon_password = \"\"\nlog_file_path = \"/srv/creative/logs/latest.log\"\nwebserver_token = \"\"\nEverything else is shared with the main server. Servers without a log file can send to the webserver with their `webserver_token` in the `X-Dolphin-Token` header",
+    ),
+    (
+        "translation_config",
+        "Machine translate chat between Minecraft and Discord channels that use different languages",
+    ),
+    (
+        "translation_config.endpoint",
+        "The translate endpoint of a LibreTranslate compatible service, e.g. `http://localhost:5000/translate`. Leave empty to disable",
+    ),
+    (
+        "translation_config.api_key",
+        "The API key for the translation service, if it needs one",
+    ),
+    (
+        "usage_config",
+        "Check the server's TPS, memory, and entity counts for `/status` and ops channel reports",
+    ),
+    (
+        "usage_config.endpoint",
+        "URL of a companion plugin endpoint that returns the server's usage as JSON. Leave empty to use `tps_command` instead",
+    ),
+    (
+        "usage_config.tps_command",
+        "Command to get the TPS over RCON, e.g. `tps` on Paper or `spark tps`. Leave both this and `endpoint` empty to disable",
+    ),
+    (
+        "usage_config.poll_interval_secs",
+        "How often to check the server's usage",
+    ),
+    (
+        "usage_config.report_interval_mins",
+        "How often to post the server's usage to the ops channel. Set to 0 to disable",
+    ),
+    (
+        "update_config",
+        "Check GitHub for a newer version of Dolphin on startup",
+    ),
+    (
+        "update_config.enabled",
+        "Look for a newer release when Dolphin starts. Off by default, since it contacts GitHub",
+    ),
+    (
+        "update_config.notify_ops",
+        "Post a notice to the ops channel when a newer release is out, as well as logging it",
+    ),
+    (
+        "server_config",
+        "Run the Minecraft server as part of Dolphin instead of reading its log file and using RCON",
+    ),
+    (
+        "server_config.enabled",
+        "Start the server with Dolphin, read its output, and send commands to its console",
+    ),
+    (
+        "server_config.command",
+        "The command that starts the server",
+    ),
+    (
+        "server_config.working_dir",
+        "Directory to start the server in. Leave empty to use the current directory",
+    ),
+    (
+        "server_config.restart_on_crash",
+        "Start the server again if it exits with an error",
+    ),
+    (
+        "server_config.restart_delay_secs",
+        "How long to wait before restarting a crashed server",
+    ),
+    (
+        "server_config.stop_timeout_secs",
+        "How long to wait for the server to stop before killing it",
+    ),
+];
+
+/// Options that are maps with user-defined keys, so their entries
+/// don't need documentation of their own.
+const MAPS: &[&str] = &[
+    "discord_config.name_replacements",
+    "event_webhooks",
+    "discord_config.avatar_overrides",
+    "discord_config.command_responses",
+    "discord_config.command_cooldowns",
+    "discord_config.role_prefixes",
+    "discord_config.macros",
+    "discord_config.mirrors",
+    "minecraft_config.death_translations",
+];
+
+/// Generate an example config with every option set to its default
+/// value, and a comment describing each one.
+pub fn generate() -> Result<String, Error> {
+    let mut doc: DocumentMut = toml::to_string_pretty(&RootConfig::default())?.parse()?;
+    annotate(doc.as_table_mut(), "");
+    Ok(doc.to_string().trim_start().to_string())
+}
+
+fn annotate(table: &mut Table, path: &str) {
+    let keys: Vec<String> = table.iter().map(|(key, _)| key.to_string()).collect();
+
+    for key in keys {
+        let full_path = match path {
+            "" => key.clone(),
+            _ => format!("{}.{}", path, key),
+        };
+
+        let comment = match documentation(&full_path) {
+            Some(doc) => doc
+                .lines()
+                .map(|line| format!("# {}\n", line))
+                .collect::<String>(),
+            None => continue,
+        };
+
+        if let Some(Item::Table(sub)) = table.get_mut(&key) {
+            sub.decor_mut().set_prefix(format!("\n{}", comment));
+            if !MAPS.contains(&full_path.as_str()) {
+                annotate(sub, &full_path);
+            }
+        } else if let Some(mut key) = table.key_mut(&key) {
+            key.leaf_decor_mut().set_prefix(comment);
+        }
+    }
+}
+
+fn documentation(path: &str) -> Option<&'static str> {
+    DOCS.iter().find(|(p, _)| *p == path).map(|(_, doc)| *doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use toml_edit::{DocumentMut, Item, Table};
+
+    use super::{documentation, generate, MAPS};
+    use crate::config::RootConfig;
+
+    fn undocumented(table: &Table, path: &str, missing: &mut Vec<String>) {
+        for (key, item) in table.iter() {
+            let full_path = match path {
+                "" => key.to_string(),
+                _ => format!("{}.{}", path, key),
+            };
+
+            if documentation(&full_path).is_none() {
+                missing.push(full_path.clone());
+            }
+
+            if let Item::Table(sub) = item {
+                if MAPS.contains(&full_path.as_str()) {
+                    continue;
+                }
+                undocumented(sub, &full_path, missing);
+            }
+        }
+    }
+
+    #[test]
+    fn every_option_is_documented() {
+        // Given
+        let doc: DocumentMut = toml::to_string_pretty(&RootConfig::default())
+            .unwrap()
+            .parse()
+            .unwrap();
+        let mut missing = Vec::new();
+
+        // When
+        undocumented(doc.as_table(), "", &mut missing);
+
+        // Then
+        assert!(missing.is_empty(), "undocumented options: {:?}", missing);
+    }
+
+    #[test]
+    fn generated_schema_is_valid_config() {
+        // Given
+        let schema = generate().unwrap();
+
+        // When
+        let parsed: Result<RootConfig, _> = toml::from_str(&schema);
+
+        // Then
+        assert!(parsed.is_ok());
+        assert!(schema.starts_with("# Settings for the Discord side of the bridge\n"));
+        assert!(schema.contains("# The port to listen for messages on\nport = 25585"));
+    }
+}
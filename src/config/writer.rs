@@ -0,0 +1,144 @@
+use std::{fs, path::Path};
+
+use toml_edit::{DocumentMut, Item, Table};
+
+use super::{Error, RootConfig};
+
+/// Save the config to the given path.
+///
+/// Unlike saving with confy, this keeps any comments and formatting the
+/// user has in their config file. Only values that have changed are
+/// rewritten, new options are added, and options that no longer exist
+/// are removed.
+pub fn save(path: &Path, config: &RootConfig) -> Result<(), Error> {
+    let new: DocumentMut = toml::to_string_pretty(config)?.parse()?;
+
+    let mut doc = if path.exists() {
+        fs::read_to_string(path)?.parse()?
+    } else {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        DocumentMut::new()
+    };
+
+    merge(doc.as_table_mut(), new.as_table());
+    fs::write(path, doc.to_string())?;
+
+    Ok(())
+}
+
+/// Merge the values from `new` into `existing`, keeping the decoration
+/// (comments and whitespace) of anything that is already there.
+fn merge(existing: &mut Table, new: &Table) {
+    let stale: Vec<String> = existing
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect();
+
+    for key in stale {
+        existing.remove(&key);
+    }
+
+    for (key, new_item) in new.iter() {
+        match (existing.get_mut(key), new_item) {
+            (Some(Item::Table(old)), Item::Table(new_table)) => merge(old, new_table),
+            (Some(Item::Value(old)), Item::Table(new_table)) if old.is_inline_table() => {
+                let decor = old.decor().clone();
+                *old = new_table.clone().into_inline_table().into();
+                *old.decor_mut() = decor;
+            }
+            (Some(Item::Value(old)), Item::Value(new_value)) => {
+                if old.to_string().trim() == new_value.to_string().trim() {
+                    continue;
+                }
+
+                let decor = old.decor().clone();
+                *old = new_value.clone();
+                *old.decor_mut() = decor;
+            }
+            (Some(old), _) => *old = new_item.clone(),
+            (None, _) => {
+                existing.insert(key, new_item.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use toml_edit::DocumentMut;
+
+    use super::merge;
+
+    #[test]
+    fn keeps_comments() {
+        // Given
+        let mut existing: DocumentMut = r#"
+# My Discord settings
+[discord_config]
+# The channel to bridge
+channel_id = 1234 # general
+"#
+        .parse()
+        .unwrap();
+        let new: DocumentMut = "[discord_config]\nchannel_id = 5678\n".parse().unwrap();
+
+        // When
+        merge(existing.as_table_mut(), new.as_table());
+
+        // Then
+        assert_eq!(
+            existing.to_string(),
+            r#"
+# My Discord settings
+[discord_config]
+# The channel to bridge
+channel_id = 5678 # general
+"#
+        );
+    }
+
+    #[test]
+    fn adds_new_and_removes_old_options() {
+        // Given
+        let mut existing: DocumentMut =
+            "[discord_config]\n# Old option\nbot_token = \"abc\"\nchannel_id = 1234\n"
+                .parse()
+                .unwrap();
+        let new: DocumentMut = "[discord_config]\nchannel_id = 1234\nallow_mentions = true\n"
+            .parse()
+            .unwrap();
+
+        // When
+        merge(existing.as_table_mut(), new.as_table());
+
+        // Then
+        let result = existing.to_string();
+        assert!(!result.contains("bot_token"));
+        assert!(result.contains("channel_id = 1234"));
+        assert!(result.contains("allow_mentions = true"));
+    }
+
+    #[test]
+    fn keeps_inline_tables() {
+        // Given
+        let mut existing: DocumentMut =
+            "[minecraft_config]\ntemplates = { message_template = \"a\" }\n"
+                .parse()
+                .unwrap();
+        let new: DocumentMut = "[minecraft_config.templates]\nmessage_template = \"b\"\n"
+            .parse()
+            .unwrap();
+
+        // When
+        merge(existing.as_table_mut(), new.as_table());
+
+        // Then
+        assert_eq!(
+            existing.to_string(),
+            "[minecraft_config]\ntemplates = { message_template = \"b\" }\n"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use serde_json::json;
+use serenity::{
+    all::{ChannelId, MessageId},
+    builder::{CreateAllowedMentions, CreateEmbed, CreateMessage, EditMessage},
+    http::Http,
+    model::Colour,
+    prelude::{Context, RwLock, TypeMapKey},
+};
+use tracing::{error, info};
+
+use super::send_to_minecraft;
+use crate::config::{container::ConfigContainer, RootConfig};
+use crate::per_server::PerServer;
+use crate::transport::Transport;
+
+/// What we know about a Minecraft server we're bridging with. Only the
+/// main server has a banner.
+#[derive(Debug, Default)]
+pub struct ServerInfo {
+    version: Option<String>,
+    banner: Option<(ChannelId, MessageId)>,
+}
+
+pub struct ServerInfoContainer;
+
+impl TypeMapKey for ServerInfoContainer {
+    type Value = Arc<PerServer<RwLock<ServerInfo>>>;
+}
+
+/// Describe how the bridge gets messages from Minecraft.
+pub fn bridge_mode(config: &RootConfig) -> String {
+    if config.managed_server_enabled() {
+        String::from("Running the Minecraft server")
+    } else if config.enable_webserver() {
+        format!(
+            "Listening for messages on port {}",
+            config.get_webserver_port()
+        )
+    } else {
+        String::from("Reading the Minecraft log file")
+    }
+}
+
+fn banner_embed(mode: &str, server_version: Option<&str>) -> CreateEmbed {
+    CreateEmbed::new()
+        .title("Dolphin Started")
+        .field("Version", env!("CARGO_PKG_VERSION"), true)
+        .field(
+            "Minecraft Version",
+            server_version.unwrap_or("Not detected yet"),
+            true,
+        )
+        .field("Bridge Mode", mode, false)
+        .color(Colour::BLUE)
+}
+
+/// Post the startup banner to the ops channel.
+pub async fn post(ctx: &Context) {
+    let (config, info) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<ConfigContainer>()
+                .cloned()
+                .expect("expected config container in TypeMap"),
+            data.get::<ServerInfoContainer>()
+                .cloned()
+                .expect("expected server info container in TypeMap"),
+        )
+    };
+
+    let (channel_id, mode, info) = {
+        let config = config.read().await;
+        (
+            ChannelId::new(config.get_ops_channel_id()),
+            bridge_mode(&config),
+            info.get(config.get_channel_id()),
+        )
+    };
+
+    let mut info = info.write().await;
+    let embed = banner_embed(&mode, info.version.as_deref());
+
+    match channel_id
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await
+    {
+        Ok(message) => info.banner = Some((channel_id, message.id)),
+        Err(e) => error!("banner:post: unable to post the startup banner: {}", e),
+    }
+}
+
+/// Tell the bridge channel and players in game that the bridge is
+/// coming up or going down. Nothing is sent for an empty notice.
+pub async fn announce(http: &Http, config: &RootConfig, notice: &str) {
+    if notice.is_empty() {
+        return;
+    }
+
+    let message = CreateMessage::new()
+        .content(notice)
+        .allowed_mentions(CreateAllowedMentions::new());
+    if let Err(e) = ChannelId::new(config.get_channel_id())
+        .send_message(http, message)
+        .await
+    {
+        error!("banner:announce: unable to post a notice to Discord: {}", e);
+    }
+
+    let command = format!(
+        "tellraw @a {}",
+        json!({ "text": format!("[Discord] {}", notice), "color": "gray" })
+    );
+    if let Err(e) = send_to_minecraft(command, Transport::from_config(config)).await {
+        error!(
+            "banner:announce: unable to send a notice to Minecraft: {}",
+            e
+        );
+    }
+}
+
+/// Get the version the Minecraft server bridged to `channel_id` says
+/// it's running, if it has said yet.
+pub async fn server_version(ctx: &Context, channel_id: u64) -> Option<String> {
+    let info = ctx
+        .data
+        .read()
+        .await
+        .get::<ServerInfoContainer>()?
+        .get(channel_id);
+    let version = info.read().await.version.clone();
+    version
+}
+
+/// Remember the version the Minecraft server bridged to `channel_id`
+/// says it's running, and add it to the startup banner if that's the
+/// server the banner is for.
+pub async fn set_server_version(ctx: &Context, channel_id: u64, version: String) {
+    info!("Minecraft server version is {}", version);
+
+    let (config, info) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<ConfigContainer>()
+                .cloned()
+                .expect("expected config container in TypeMap"),
+            data.get::<ServerInfoContainer>()
+                .expect("expected server info container in TypeMap")
+                .get(channel_id),
+        )
+    };
+
+    let mut info = info.write().await;
+    info.version = Some(version);
+
+    if let Some((channel_id, message_id)) = info.banner {
+        let mode = bridge_mode(&*config.read().await);
+        let embed = banner_embed(&mode, info.version.as_deref());
+
+        if let Err(e) = channel_id
+            .edit_message(&ctx.http, message_id, EditMessage::new().embed(embed))
+            .await
+        {
+            error!(
+                "banner:set_server_version: unable to update the banner: {}",
+                e
+            );
+        }
+    }
+}
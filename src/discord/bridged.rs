@@ -0,0 +1,142 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serenity::all::{Message, MessageId};
+
+/// How many bridged Discord messages can be edited or deleted at once.
+/// Past this, the oldest can't be corrected in game anymore.
+const MAX_REMEMBERED: usize = 256;
+
+/// A Discord message that was sent to Minecraft, with the name it was
+/// shown under, so a correction can be shown the same way.
+#[derive(Clone, Debug)]
+pub struct Bridged {
+    pub message: Message,
+    pub name: String,
+    pub mention: String,
+    pub prefix: Option<(String, String)>,
+}
+
+/// Remembers recently bridged Discord messages for a while, so edits
+/// and deletes can be passed on to players.
+#[derive(Debug)]
+pub struct BridgedMessages {
+    window: Duration,
+    recent: Mutex<VecDeque<(Instant, Bridged)>>,
+}
+
+impl BridgedMessages {
+    /// Remember messages for the given window. A window of zero turns
+    /// passing on edits and deletes off.
+    pub fn new(window: Duration) -> Self {
+        BridgedMessages {
+            window,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Remember a message we just sent to Minecraft.
+    pub fn remember(&self, bridged: Bridged) {
+        if self.window.is_zero() {
+            return;
+        }
+
+        let mut recent = self.recent.lock().unwrap();
+        expire(&mut recent, self.window);
+        if recent.len() == MAX_REMEMBERED {
+            recent.pop_front();
+        }
+        recent.push_back((Instant::now(), bridged));
+    }
+
+    /// Get a message we recently bridged, to apply an edit to.
+    pub fn get(&self, id: MessageId) -> Option<Bridged> {
+        let mut recent = self.recent.lock().unwrap();
+        expire(&mut recent, self.window);
+        recent
+            .iter()
+            .find(|(_, bridged)| bridged.message.id == id)
+            .map(|(_, bridged)| bridged.clone())
+    }
+
+    /// Swap in the edited version of a message, keeping when it was
+    /// first bridged.
+    pub fn update(&self, bridged: Bridged) {
+        let mut recent = self.recent.lock().unwrap();
+        if let Some((_, old)) = recent
+            .iter_mut()
+            .find(|(_, old)| old.message.id == bridged.message.id)
+        {
+            *old = bridged;
+        }
+    }
+
+    /// Forget a message because it was deleted, returning it if it was
+    /// recently bridged.
+    pub fn forget(&self, id: MessageId) -> Option<Bridged> {
+        let mut recent = self.recent.lock().unwrap();
+        expire(&mut recent, self.window);
+        let index = recent
+            .iter()
+            .position(|(_, bridged)| bridged.message.id == id)?;
+        recent.remove(index).map(|(_, bridged)| bridged)
+    }
+}
+
+fn expire(recent: &mut VecDeque<(Instant, Bridged)>, window: Duration) {
+    while recent.front().is_some_and(|(at, _)| at.elapsed() > window) {
+        recent.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serenity::all::{Message, MessageId};
+
+    use super::{Bridged, BridgedMessages};
+
+    fn bridged(id: u64) -> Bridged {
+        let mut message = Message::default();
+        message.id = MessageId::new(id);
+        Bridged {
+            message,
+            name: String::from("Steve"),
+            mention: String::from("steve"),
+            prefix: None,
+        }
+    }
+
+    #[test]
+    fn forget_deleted_messages() {
+        // Given
+        let bridged_messages = BridgedMessages::new(Duration::from_secs(300));
+        bridged_messages.remember(bridged(1));
+        bridged_messages.remember(bridged(2));
+
+        // When
+        let deleted = bridged_messages.forget(MessageId::new(1));
+
+        // Then
+        assert!(deleted.is_some_and(|deleted| deleted.name == "Steve"));
+        assert!(bridged_messages.get(MessageId::new(1)).is_none());
+        assert!(bridged_messages.get(MessageId::new(2)).is_some());
+        assert!(bridged_messages.forget(MessageId::new(3)).is_none());
+    }
+
+    #[test]
+    fn disabled_with_zero_window() {
+        // Given
+        let bridged_messages = BridgedMessages::new(Duration::ZERO);
+
+        // When
+        bridged_messages.remember(bridged(1));
+
+        // Then
+        assert!(bridged_messages.get(MessageId::new(1)).is_none());
+    }
+}
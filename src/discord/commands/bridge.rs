@@ -0,0 +1,278 @@
+use std::time::{Duration, SystemTime};
+
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, Permissions},
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponseMessage},
+    model::Colour,
+    prelude::*,
+};
+use thiserror::Error;
+use tracing::Level;
+
+use super::{SlashCommand, Visibility};
+use crate::{
+    config::{container, RootConfig},
+    discord::{banner::bridge_mode, deletion::DeletionQueueContainer},
+    listener::relog::RelogFilterContainer,
+    logbuffer::{LogBufferContainer, Record},
+    metrics::{
+        format_duration, DirectionStats, IgnoredStats, MetricsContainer, SizeStats, Snapshot,
+    },
+    per_server,
+};
+
+/// How many of the latest errors to show.
+const MAX_ERRORS: usize = 10;
+
+/// How much of each error to show, so ten of them fit in one embed.
+const MAX_ERROR_LENGTH: usize = 200;
+
+pub struct BridgeStats;
+
+#[async_trait]
+impl SlashCommand for BridgeStats {
+    fn name(&self) -> &'static str {
+        "bridgestats"
+    }
+
+    fn description(&self) -> &'static str {
+        "Show how the bridge has been doing today"
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(stats(ctx, command, visibility).await?)
+    }
+}
+
+pub struct BridgeStatus;
+
+#[async_trait]
+impl SlashCommand for BridgeStatus {
+    fn name(&self) -> &'static str {
+        "bridge"
+    }
+
+    fn description(&self) -> &'static str {
+        "Check on the bridge itself"
+    }
+
+    fn register(&self, _config: &RootConfig) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description(self.description())
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "status",
+                "Show recent errors, what the listener is doing, and what's queued up",
+            ))
+    }
+
+    fn required_permissions(&self) -> Option<Permissions> {
+        Some(Permissions::MANAGE_GUILD)
+    }
+
+    fn default_visibility(&self) -> Visibility {
+        Visibility::Ephemeral
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(status(ctx, command, visibility).await?)
+    }
+}
+
+/// Shows the bridge's recent errors and what it's up to, so admins
+/// don't have to go through the logs to find out what went wrong.
+pub async fn status(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let config = container::config_for(&ctx, command.channel_id).await;
+    let embed = {
+        let config = config.read().await;
+        let data = ctx.data.read().await;
+        let snapshot = data
+            .get::<MetricsContainer>()
+            .expect("expected metrics container in TypeMap")
+            .get(config.get_channel_id())
+            .snapshot();
+        let hour_ago = SystemTime::now() - Duration::from_secs(60 * 60);
+        let (errors, warnings) = data
+            .get::<LogBufferContainer>()
+            .map(|logs| {
+                (
+                    logs.recent(Level::ERROR, MAX_ERRORS),
+                    logs.count_since(Level::WARN, hour_ago),
+                )
+            })
+            .unwrap_or_default();
+        let deletions = data
+            .get::<DeletionQueueContainer>()
+            .map_or(0, |queue| queue.pending());
+        let leaves = match data.get::<RelogFilterContainer>() {
+            Some(relogs) => relogs.get(config.get_channel_id()).lock().await.pending(),
+            None => 0,
+        };
+
+        status_embed(&config, &snapshot, (&errors, warnings), (deletions, leaves))
+    };
+
+    let response = CreateInteractionResponseMessage::new().add_embed(embed);
+    visibility.respond(&ctx, &command, response).await?;
+
+    Ok(())
+}
+
+fn status_embed(
+    config: &RootConfig,
+    snapshot: &Snapshot,
+    (errors, warnings): (&[Record], usize),
+    (deletions, leaves): (usize, usize),
+) -> CreateEmbed {
+    let heard = match snapshot.last_heard {
+        Some(when) => {
+            let ago = SystemTime::now().duration_since(when).unwrap_or_default();
+            format!("{} ago", format_duration(ago))
+        }
+        None => String::from("Nothing yet"),
+    };
+
+    let recent = match errors {
+        [] => String::from("No errors since Dolphin started."),
+        errors => errors
+            .iter()
+            .map(|record| {
+                let ago = SystemTime::now()
+                    .duration_since(record.at)
+                    .unwrap_or_default();
+                format!(
+                    "**{} ago:** {}",
+                    format_duration(ago),
+                    shorten(&record.message)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+
+    CreateEmbed::new()
+        .title("Bridge Status")
+        .description(recent)
+        .field("Listener", bridge_mode(config), false)
+        .field("Last Heard From Minecraft", heard, true)
+        .field(
+            "Queued",
+            format!(
+                "{} pending deletions\n{} held back leaves",
+                deletions, leaves
+            ),
+            true,
+        )
+        .field(
+            "Warnings and Errors (Last Hour)",
+            warnings.to_string(),
+            true,
+        )
+        .field("Uptime", format_duration(snapshot.uptime), true)
+        .color(match errors.is_empty() {
+            true => Colour::DARK_GREEN,
+            false => Colour::ORANGE,
+        })
+}
+
+fn shorten(message: &str) -> String {
+    match message.char_indices().nth(MAX_ERROR_LENGTH) {
+        Some((end, _)) => format!("{}…", &message[..end]),
+        None => message.to_string(),
+    }
+}
+
+/// Shows how the bridge has been doing today, so admins can check
+/// that it is healthy without needing access to the server.
+pub async fn stats(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let metrics = per_server::for_channel::<MetricsContainer, _>(&ctx, command.channel_id)
+        .await
+        .expect("expected metrics container in TypeMap");
+
+    let response =
+        CreateInteractionResponseMessage::new().add_embed(stats_embed(&metrics.snapshot()));
+
+    visibility.respond(&ctx, &command, response).await?;
+
+    Ok(())
+}
+
+fn stats_embed(snapshot: &Snapshot) -> CreateEmbed {
+    let last_error = match &snapshot.last_rcon_error {
+        Some((error, when)) => {
+            let ago = SystemTime::now().duration_since(*when).unwrap_or_default();
+            format!("{} ({} ago)", error, format_duration(ago))
+        }
+        None => String::from("None"),
+    };
+
+    CreateEmbed::new()
+        .title("Bridge Stats")
+        .description("Message counts are for today (UTC).")
+        .field(
+            "Minecraft → Discord",
+            format_direction(&snapshot.to_discord, &snapshot.sizes_to_discord),
+            true,
+        )
+        .field(
+            "Discord → Minecraft",
+            format_direction(&snapshot.to_minecraft, &snapshot.sizes_to_minecraft),
+            true,
+        )
+        .field("Ignored Lines", format_ignored(&snapshot.ignored), false)
+        .field("Last RCON Error", last_error, false)
+        .field("Uptime", format_duration(snapshot.uptime), false)
+        .color(Colour::BLUE)
+}
+
+fn format_ignored(ignored: &IgnoredStats) -> String {
+    format!(
+        "**{}** since startup\n{} without a log prefix\n{} villager deaths\n{} ignore phrases\n{} didn't match anything",
+        ignored.total(),
+        ignored.no_prefix,
+        ignored.villager_deaths,
+        ignored.ignore_phrases,
+        ignored.no_match
+    )
+}
+
+fn format_direction(stats: &DirectionStats, sizes: &SizeStats) -> String {
+    let latency = match stats.average_latency() {
+        Some(latency) => format!("{}ms", latency.as_millis()),
+        None => String::from("n/a"),
+    };
+    let length = match sizes.average() {
+        Some(length) => format!("{} chars", length),
+        None => String::from("n/a"),
+    };
+
+    format!(
+        "**{}** messages\n{} average latency\n{} average length\n{} split up since startup",
+        stats.messages_today, latency, length, sizes.split
+    )
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("command error: {0}")]
+    Discord(#[from] serenity::Error),
+}
@@ -0,0 +1,294 @@
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, RoleId},
+    async_trait,
+    builder::{
+        CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter,
+        CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+        EditInteractionResponse,
+    },
+    model::Colour,
+    prelude::*,
+};
+use thiserror::Error;
+use tokio::time::timeout;
+use tracing::info;
+
+use super::{is_one_line, minecraft::RCON_TIMEOUT, SlashCommand, Visibility};
+use crate::{
+    config::{container, RootConfig},
+    metrics::MetricsContainer,
+    per_server,
+    transport::Transport,
+};
+
+/// Discord won't show embed descriptions longer than this.
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+
+/// The most pages of output to post. Anything after is cut off.
+const MAX_PAGES: usize = 5;
+
+pub struct Cmd;
+
+#[async_trait]
+impl SlashCommand for Cmd {
+    fn name(&self) -> &'static str {
+        "cmd"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a command on the Minecraft server"
+    }
+
+    fn enabled(&self, config: &RootConfig) -> bool {
+        !config.get_cmd_roles().is_empty()
+    }
+
+    fn register(&self, _config: &RootConfig) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description(self.description())
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "command",
+                    "The command to run, without the /",
+                )
+                .required(true),
+            )
+    }
+
+    fn default_visibility(&self) -> Visibility {
+        Visibility::Ephemeral
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(run_command(ctx, command, visibility).await?)
+    }
+}
+
+/// Sends the given command to the Minecraft server, if the member has
+/// one of the allowed roles, and shows what the server said.
+pub async fn run_command(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let line = command
+        .data
+        .options
+        .first()
+        .and_then(|option| option.value.as_str())
+        .unwrap_or_default()
+        .trim()
+        .trim_start_matches('/')
+        .to_string();
+
+    let config = container::config_for(&ctx, command.channel_id).await;
+    let (allowed, transport) = {
+        let config = config.read().await;
+        (
+            config
+                .get_cmd_roles()
+                .into_iter()
+                .map(RoleId::new)
+                .collect::<Vec<RoleId>>(),
+            Transport::from_config(&config),
+        )
+    };
+
+    let member_roles = command
+        .member
+        .as_ref()
+        .map(|member| member.roles.clone())
+        .unwrap_or_default();
+    if !can_run(&allowed, &member_roles) {
+        let response = CreateInteractionResponseMessage::new()
+            .content("You aren't allowed to run server commands.");
+        visibility.respond(&ctx, &command, response).await?;
+        return Ok(());
+    }
+
+    // Line breaks could sneak extra commands in
+    if !is_one_line(&line) {
+        let response = CreateInteractionResponseMessage::new()
+            .content("Give one command to run, without line breaks.");
+        visibility.respond(&ctx, &command, response).await?;
+        return Ok(());
+    }
+
+    info!("{} ran a server command: {}", command.user.name, line);
+
+    // The server can take longer to answer than Discord waits
+    visibility.defer(&ctx, &command).await?;
+
+    let result = match timeout(RCON_TIMEOUT, transport.send(&line)).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(String::from("timed out waiting for the server to respond")),
+    };
+
+    let (output, color) = match result {
+        Ok(response) if response.trim().is_empty() => {
+            (String::from("(no output)"), Colour::DARK_GREEN)
+        }
+        Ok(response) => (response.trim().to_string(), Colour::DARK_GREEN),
+        Err(e) => {
+            if let Some(metrics) =
+                per_server::for_channel::<MetricsContainer, _>(&ctx, command.channel_id).await
+            {
+                metrics.record_rcon_error(&e);
+            }
+            (format!("Failed: {}", e), Colour::RED)
+        }
+    };
+
+    let pages = paginate(&output, MAX_DESCRIPTION_LENGTH - "```\n\n```".len());
+    let count = pages.len();
+    for (index, page) in pages.into_iter().enumerate() {
+        let mut embed = CreateEmbed::new()
+            .description(format!("```\n{}\n```", page))
+            .color(color);
+        if index == 0 {
+            embed = embed.title(format!("/{}", line.chars().take(250).collect::<String>()));
+        }
+        if count > 1 {
+            embed = embed.footer(CreateEmbedFooter::new(format!(
+                "Page {} of {}",
+                index + 1,
+                count
+            )));
+        }
+
+        if index == 0 {
+            command
+                .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+                .await?;
+        } else {
+            let followup = CreateInteractionResponseFollowup::new()
+                .embed(embed)
+                .ephemeral(visibility == Visibility::Ephemeral);
+            command.create_followup(&ctx.http, followup).await?;
+        }
+    }
+    visibility.finish(&ctx, &command).await?;
+
+    Ok(())
+}
+
+/// Only members with one of the allowed roles can run commands.
+fn can_run(allowed: &[RoleId], member_roles: &[RoleId]) -> bool {
+    allowed.iter().any(|role| member_roles.contains(role))
+}
+
+/// Split command output into pages of at most `max` characters, at line
+/// breaks where it can. Output that needs more than [MAX_PAGES] pages is
+/// cut off.
+fn paginate(output: &str, max: usize) -> Vec<String> {
+    // Keep the output from closing its code block
+    let output = output.replace("```", "'''");
+
+    let mut pages: Vec<String> = Vec::new();
+    let mut page = String::new();
+    for line in output.lines() {
+        let mut line = line;
+        loop {
+            let room = max.saturating_sub(page.chars().count() + usize::from(!page.is_empty()));
+            let length = line.chars().count();
+            if length <= room {
+                if !page.is_empty() {
+                    page.push('\n');
+                }
+                page.push_str(line);
+                break;
+            }
+
+            // Lines longer than a whole page are split wherever they have to be
+            if page.is_empty() {
+                let split = line
+                    .char_indices()
+                    .nth(max)
+                    .map(|(i, _)| i)
+                    .unwrap_or(line.len());
+                pages.push(line[..split].to_string());
+                line = &line[split..];
+                continue;
+            }
+
+            pages.push(std::mem::take(&mut page));
+        }
+    }
+    if !page.is_empty() {
+        pages.push(page);
+    }
+
+    if pages.len() > MAX_PAGES {
+        pages.truncate(MAX_PAGES);
+        if let Some(last) = pages.last_mut() {
+            let mut cut: String = last.chars().take(max - 1).collect();
+            cut.push('…');
+            *last = cut;
+        }
+    }
+
+    pages
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::RoleId;
+
+    use super::{can_run, paginate, MAX_PAGES};
+
+    #[test]
+    fn only_allowed_roles_can_run() {
+        // Given
+        let allowed = vec![RoleId::new(1)];
+
+        // Then
+        assert!(can_run(&allowed, &[RoleId::new(2), RoleId::new(1)]));
+        assert!(!can_run(&allowed, &[RoleId::new(2)]));
+        assert!(!can_run(&[], &[RoleId::new(1)]));
+    }
+
+    #[test]
+    fn split_output_at_line_breaks() {
+        // Given
+        let output = "aaaa\nbbbb\ncccc";
+
+        // When
+        let pages = paginate(output, 9);
+
+        // Then
+        assert_eq!(pages, vec!["aaaa\nbbbb", "cccc"]);
+    }
+
+    #[test]
+    fn split_long_lines_and_cut_off_extra_pages() {
+        // Given
+        let output = "x".repeat(25);
+
+        // When
+        let long = paginate(&output, 10);
+        let too_long = paginate(&"y".repeat(100), 10);
+
+        // Then
+        assert_eq!(long, vec!["x".repeat(10), "x".repeat(10), "x".repeat(5)]);
+        assert_eq!(too_long.len(), MAX_PAGES);
+        assert!(too_long[MAX_PAGES - 1].ends_with('…'));
+    }
+
+    #[test]
+    fn keep_output_in_its_code_block() {
+        // Then
+        assert_eq!(paginate("```", 10), vec!["'''"]);
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use serenity::{
+    all::{ButtonStyle, CommandInteraction},
+    builder::{
+        CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse,
+        CreateInteractionResponseMessage, EditInteractionResponse,
+    },
+    model::Colour,
+    prelude::Context,
+};
+
+use super::Visibility;
+
+/// How long to wait for someone to make up their mind.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+
+const CONFIRM_ID: &str = "confirm:yes";
+const CANCEL_ID: &str = "confirm:no";
+
+/// Ask the user who ran a command to confirm what it's about to do, with
+/// ✅ and ❌ buttons. This sends the command's response, so the caller
+/// should edit the response with the result if this returns `true`, and
+/// then call [Visibility::finish]. If the user cancels or doesn't answer
+/// in time, the question is replaced with a note saying so.
+pub async fn confirm(
+    ctx: &Context,
+    command: &CommandInteraction,
+    visibility: Visibility,
+    question: &str,
+) -> Result<bool, serenity::Error> {
+    let embed = CreateEmbed::new()
+        .title("Are you sure?")
+        .description(question)
+        .color(Colour::ORANGE);
+    let response = CreateInteractionResponseMessage::new()
+        .embed(embed)
+        .components(buttons())
+        .ephemeral(visibility == Visibility::Ephemeral);
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+
+    let message = command.get_response(&ctx.http).await?;
+    let pressed = message
+        .await_component_interaction(&ctx.shard)
+        .author_id(command.user.id)
+        .timeout(CONFIRM_TIMEOUT)
+        .await;
+
+    let (confirmed, note) = match pressed {
+        Some(interaction) => {
+            interaction
+                .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+                .await?;
+            (is_confirmation(&interaction.data.custom_id), "Cancelled.")
+        }
+        None => (false, "No answer, so nothing was done."),
+    };
+
+    if !confirmed {
+        command.edit_response(&ctx.http, resolved(note)).await?;
+        visibility.finish(ctx, command).await?;
+    }
+
+    Ok(confirmed)
+}
+
+/// Build the edit that replaces the question with the outcome.
+pub fn resolved(content: &str) -> EditInteractionResponse {
+    EditInteractionResponse::new()
+        .content(content)
+        .embeds(Vec::new())
+        .components(Vec::new())
+}
+
+fn buttons() -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(CONFIRM_ID)
+            .label("✅ Confirm")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(CANCEL_ID)
+            .label("❌ Cancel")
+            .style(ButtonStyle::Secondary),
+    ])]
+}
+
+fn is_confirmation(custom_id: &str) -> bool {
+    custom_id == CONFIRM_ID
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_confirmation, CANCEL_ID, CONFIRM_ID};
+
+    #[test]
+    fn only_the_confirm_button_confirms() {
+        assert!(is_confirmation(CONFIRM_ID));
+        assert!(!is_confirmation(CANCEL_ID));
+        assert!(!is_confirmation("list:1:server:"));
+    }
+}
@@ -0,0 +1,76 @@
+use serenity::{
+    all::CommandInteraction, async_trait, builder::CreateInteractionResponseMessage, prelude::*,
+};
+use thiserror::Error;
+
+use super::{SlashCommand, Visibility};
+use crate::{config::container, listener::digest::DigestContainer};
+
+pub struct Digest;
+
+#[async_trait]
+impl SlashCommand for Digest {
+    fn name(&self) -> &'static str {
+        "digest"
+    }
+
+    fn description(&self) -> &'static str {
+        "Post the digest of joins, leaves, and deaths now"
+    }
+
+    fn default_visibility(&self) -> Visibility {
+        Visibility::Public
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(digest(ctx, command, visibility).await?)
+    }
+}
+
+/// Posts everything collected for the digest so far, and starts a new
+/// one.
+pub async fn digest(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let config = container::config_for(&ctx, command.channel_id).await;
+    let (enabled, channel_id) = {
+        let config = config.read().await;
+        (config.digest_enabled(), config.get_channel_id())
+    };
+    let digest = ctx
+        .data
+        .read()
+        .await
+        .get::<DigestContainer>()
+        .expect("expected digest container in TypeMap")
+        .get(channel_id);
+
+    let response = if !enabled {
+        CreateInteractionResponseMessage::new().content("Digests are turned off for this server.")
+    } else {
+        let digest = std::mem::take(&mut *digest.lock().await);
+        if digest.is_empty() {
+            CreateInteractionResponseMessage::new()
+                .content("Nothing has happened since the last digest.")
+        } else {
+            CreateInteractionResponseMessage::new().add_embed(digest.embed())
+        }
+    };
+
+    visibility.respond(&ctx, &command, response).await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+}
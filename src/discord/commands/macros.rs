@@ -0,0 +1,402 @@
+use serenity::{
+    all::{CommandDataOptionValue, CommandInteraction, CommandOptionType, RoleId},
+    async_trait,
+    builder::{
+        CreateCommand, CreateCommandOption, CreateInteractionResponseMessage,
+        EditInteractionResponse,
+    },
+    prelude::*,
+};
+use thiserror::Error;
+use tokio::time::timeout;
+use tracing::warn;
+
+use super::{is_one_line, minecraft::RCON_TIMEOUT, SlashCommand, Visibility};
+use crate::{
+    config::{container, RootConfig},
+    metrics::MetricsContainer,
+    per_server, template,
+    transport::Transport,
+};
+
+/// Discord allows at most this many subcommands, or options per
+/// subcommand.
+const MAX_OPTIONS: usize = 25;
+
+/// Discord won't accept descriptions longer than this.
+const MAX_DESCRIPTION_LENGTH: usize = 100;
+
+/// Discord won't post messages longer than this.
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+pub struct Macros;
+
+#[async_trait]
+impl SlashCommand for Macros {
+    fn name(&self) -> &'static str {
+        "macro"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a command macro on the Minecraft server"
+    }
+
+    fn enabled(&self, config: &RootConfig) -> bool {
+        !Macro::from_config(config).is_empty()
+    }
+
+    fn register(&self, config: &RootConfig) -> CreateCommand {
+        Macro::from_config(config).into_iter().fold(
+            CreateCommand::new(self.name()).description(self.description()),
+            |command, command_macro| command.add_option(command_macro.register()),
+        )
+    }
+
+    fn default_visibility(&self) -> Visibility {
+        Visibility::Ephemeral
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(run_macro(ctx, command, visibility).await?)
+    }
+}
+
+/// A named list of commands from the config, with `%name%` placeholders
+/// that are filled in from the command's options.
+#[derive(Debug, PartialEq)]
+struct Macro {
+    name: String,
+    description: String,
+    commands: Vec<String>,
+    roles: Vec<RoleId>,
+}
+
+impl Macro {
+    /// Get the configured macros that can be registered with Discord.
+    /// Macros with names Discord won't accept are skipped.
+    fn from_config(config: &RootConfig) -> Vec<Self> {
+        config
+            .get_macros()
+            .into_iter()
+            .map(|(name, description, commands, roles)| Macro {
+                name,
+                description,
+                commands,
+                roles: roles.into_iter().map(RoleId::new).collect(),
+            })
+            .filter(|command_macro| {
+                let valid = command_macro.is_valid();
+                if !valid {
+                    warn!(
+                        "macros:from_config: skipping macro '{}', names must be lowercase and have no spaces, and it needs at least one command",
+                        command_macro.name
+                    );
+                }
+                valid
+            })
+            .take(MAX_OPTIONS)
+            .collect()
+    }
+
+    /// The names of the options to ask for, in the order they're used.
+    fn params(&self) -> Vec<&str> {
+        let mut params = Vec::new();
+        for command in &self.commands {
+            for name in template::placeholders(command) {
+                if !params.contains(&name) {
+                    params.push(name);
+                }
+            }
+        }
+        params
+    }
+
+    fn is_valid(&self) -> bool {
+        let params = self.params();
+
+        is_option_name(&self.name)
+            && !self.commands.is_empty()
+            && params.len() <= MAX_OPTIONS
+            && params.iter().all(|param| is_option_name(param))
+    }
+
+    fn register(&self) -> CreateCommandOption {
+        let description = match self.description.trim() {
+            "" => format!("Run the {} macro", self.name),
+            description => description.chars().take(MAX_DESCRIPTION_LENGTH).collect(),
+        };
+
+        self.params().into_iter().fold(
+            CreateCommandOption::new(CommandOptionType::SubCommand, &self.name, description),
+            |option, param| {
+                option.add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        param,
+                        format!("Fills in %{}%", param),
+                    )
+                    .required(true),
+                )
+            },
+        )
+    }
+
+    /// Members with one of the macro's roles can run it, and so can
+    /// anyone who can manage the server. Macros without any roles are
+    /// only for the latter.
+    fn can_run(&self, member_roles: &[RoleId], manages_server: bool) -> bool {
+        manages_server || self.roles.iter().any(|role| member_roles.contains(role))
+    }
+
+    /// Fill in the options to get the commands to send.
+    fn expand(&self, args: &[(&str, &str)]) -> Vec<String> {
+        self.commands
+            .iter()
+            .map(|command| template::render(command, args))
+            .collect()
+    }
+}
+
+/// Discord option names have to be lowercase, and can't have spaces.
+fn is_option_name(name: &str) -> bool {
+    (1..=32).contains(&name.chars().count())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+/// Option values go straight into the commands, so they're limited to a
+/// single word of letters, digits, `_`, `-`, and `.`, like a player name
+/// or a number. Anything else could add arguments to a command, or a
+/// selector like `@a`.
+fn is_option_value(value: &str) -> bool {
+    is_one_line(value)
+        && value.len() <= 32
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Runs the macro picked by the user, sending each of its commands to
+/// the Minecraft server in order and showing what the server said.
+pub async fn run_macro(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let Some(subcommand) = command.data.options.first() else {
+        return Ok(());
+    };
+
+    let config = container::config_for(&ctx, command.channel_id).await;
+    let (command_macro, transport) = {
+        let config = config.read().await;
+        (
+            Macro::from_config(&config)
+                .into_iter()
+                .find(|command_macro| command_macro.name == subcommand.name),
+            Transport::from_config(&config),
+        )
+    };
+
+    let Some(command_macro) = command_macro else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("That macro isn't in the config anymore.");
+        visibility.respond(&ctx, &command, response).await?;
+        return Ok(());
+    };
+
+    let (member_roles, manages_server) = match &command.member {
+        Some(member) => (
+            member.roles.clone(),
+            member
+                .permissions
+                .is_some_and(|permissions| permissions.manage_guild()),
+        ),
+        None => (Vec::new(), false),
+    };
+
+    if !command_macro.can_run(&member_roles, manages_server) {
+        let response = CreateInteractionResponseMessage::new()
+            .content("You aren't allowed to run this macro.");
+        visibility.respond(&ctx, &command, response).await?;
+        return Ok(());
+    }
+
+    let args: Vec<(&str, &str)> = match &subcommand.value {
+        CommandDataOptionValue::SubCommand(options) => options
+            .iter()
+            .filter_map(|option| Some((option.name.as_str(), option.value.as_str()?)))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if args.iter().any(|(_, value)| !is_option_value(value)) {
+        let response = CreateInteractionResponseMessage::new()
+            .content("Macro options have to be a single word, like a player name or a number.");
+        visibility.respond(&ctx, &command, response).await?;
+        return Ok(());
+    }
+
+    // Running several commands can take longer than Discord waits
+    visibility.defer(&ctx, &command).await?;
+
+    let mut output = Vec::new();
+    for line in command_macro.expand(&args) {
+        let result = match timeout(RCON_TIMEOUT, transport.send(&line)).await {
+            Ok(result) => result.map_err(|e| e.to_string()),
+            Err(_) => Err(String::from("timed out waiting for the server to respond")),
+        };
+
+        match result {
+            Ok(response) if response.trim().is_empty() => output.push(format!("> {}", line)),
+            Ok(response) => output.push(format!("> {}\n{}", line, response.trim())),
+            Err(e) => {
+                if let Some(metrics) =
+                    per_server::for_channel::<MetricsContainer, _>(&ctx, command.channel_id).await
+                {
+                    metrics.record_rcon_error(&e);
+                }
+
+                output.push(format!("> {}\nFailed: {}", line, e));
+                break;
+            }
+        }
+    }
+
+    let content = summary(&command_macro.name, &output);
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        .await?;
+    visibility.finish(&ctx, &command).await?;
+
+    Ok(())
+}
+
+/// Show the commands that were run and what the server said, cut short
+/// if it's too long to post.
+fn summary(name: &str, output: &[String]) -> String {
+    let header = format!("Ran the `{}` macro:\n```\n", name);
+    let footer = "\n```";
+    let body = output.join("\n").replace("```", "'''");
+
+    let room = MAX_MESSAGE_LENGTH - header.chars().count() - footer.chars().count();
+    let body = if body.chars().count() > room {
+        let mut body: String = body.chars().take(room - 1).collect();
+        body.push('…');
+        body
+    } else {
+        body
+    };
+
+    format!("{}{}{}", header, body, footer)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::RoleId;
+
+    use super::{is_option_name, is_option_value, summary, Macro, MAX_MESSAGE_LENGTH};
+
+    fn promote() -> Macro {
+        Macro {
+            name: String::from("promote"),
+            description: String::new(),
+            commands: vec![
+                String::from("lp user %player% parent add %group%"),
+                String::from("say %player% is now a %group%"),
+            ],
+            roles: vec![RoleId::new(1)],
+        }
+    }
+
+    #[test]
+    fn params_come_from_placeholders() {
+        // Given
+        let command_macro = promote();
+
+        // When
+        let params = command_macro.params();
+
+        // Then
+        assert_eq!(params, vec!["player", "group"]);
+        assert!(command_macro.is_valid());
+    }
+
+    #[test]
+    fn option_values_are_one_word() {
+        assert!(is_option_value("Steve_01"));
+        assert!(is_option_value("-64"));
+        assert!(is_option_value("1.5"));
+        assert!(!is_option_value("Steve group set admin"));
+        assert!(!is_option_value("@a"));
+        assert!(!is_option_value("Steve\nop Alex"));
+        assert!(!is_option_value("{\"text\":\"hi\"}"));
+        assert!(!is_option_value(""));
+    }
+
+    #[test]
+    fn expand_fills_in_every_command() {
+        // Given
+        let command_macro = promote();
+
+        // When
+        let commands = command_macro.expand(&[("player", "Steve"), ("group", "mod")]);
+
+        // Then
+        assert_eq!(
+            commands,
+            vec!["lp user Steve parent add mod", "say Steve is now a mod"]
+        );
+    }
+
+    #[test]
+    fn only_listed_roles_and_admins_can_run() {
+        // Given
+        let command_macro = promote();
+        let no_roles = Macro {
+            roles: Vec::new(),
+            ..promote()
+        };
+
+        // Then
+        assert!(command_macro.can_run(&[RoleId::new(2), RoleId::new(1)], false));
+        assert!(!command_macro.can_run(&[RoleId::new(2)], false));
+        assert!(command_macro.can_run(&[], true));
+        assert!(!no_roles.can_run(&[RoleId::new(1)], false));
+        assert!(no_roles.can_run(&[], true));
+    }
+
+    #[test]
+    fn reject_names_discord_wont_take() {
+        assert!(is_option_name("give-kit_2"));
+        assert!(!is_option_name("Promote"));
+        assert!(!is_option_name("two words"));
+        assert!(!is_option_name(""));
+        assert!(!is_option_name(&"a".repeat(33)));
+    }
+
+    #[test]
+    fn summary_fits_in_a_message() {
+        // Given
+        let output = vec!["x".repeat(3000)];
+
+        // When
+        let content = summary("promote", &output);
+
+        // Then
+        assert_eq!(content.chars().count(), MAX_MESSAGE_LENGTH);
+        assert!(content.ends_with("…\n```"));
+    }
+}
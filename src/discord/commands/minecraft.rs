@@ -1,42 +1,168 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use crate::config::container::ConfigContainer;
+use super::{
+    player_list::{self, Query, Sort},
+    response, SlashCommand, Visibility, COMMANDS,
+};
+use crate::{
+    config::{container, RootConfig},
+    discord::{banner::bridge_mode, deletion::DeletionQueueContainer},
+    listener::{is_player_name, sessions::SessionsContainer},
+    metrics::MetricsContainer,
+    per_server,
+    transport::Transport,
+};
 use fancy_regex::Regex;
-use rcon::Connection;
 use serenity::{
-    all::CommandInteraction,
+    all::{
+        ChannelId, CommandDataOptionValue, CommandInteraction, CommandOptionType,
+        ComponentInteraction, Message, Permissions,
+    },
+    async_trait,
     builder::{
-        CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+        CreateActionRow, CreateCommand, CreateCommandOption, CreateEmbed,
+        CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse,
     },
     model::Colour,
     prelude::*,
 };
 use thiserror::Error;
-use tokio::time::sleep;
+use tokio::time::timeout;
+use tracing::info;
 
-/// Prints out an embed listing the currently-supported commands.
-pub async fn help(ctx: Context, command: CommandInteraction) -> Result<(), Error> {
-    let embed = CreateEmbed::new()
-        .title("Dolphin Help")
-        .description("These are the commands for Dolphin. Commands must start with a `/`.")
-        .fields(vec![
-            ("help", "Show this help page", true),
-            (
+/// How long to wait for the Minecraft server to answer an RCON command.
+pub(super) const RCON_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Help;
+
+#[async_trait]
+impl SlashCommand for Help {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the help page"
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(help(ctx, command, visibility).await?)
+    }
+}
+
+pub struct List;
+
+#[async_trait]
+impl SlashCommand for List {
+    fn name(&self) -> &'static str {
+        "list"
+    }
+
+    fn description(&self) -> &'static str {
+        "List the current players on the Minecraft server"
+    }
+
+    fn register(&self, _config: &RootConfig) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description(self.description())
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "sort",
+                    "How to order the players",
+                )
+                .add_string_choice("Alphabetical", "alphabetical")
+                .add_string_choice("Time online", "playtime"),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "filter",
+                "Only show players whose names contain this",
+            ))
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(list(ctx, command, visibility).await?)
+    }
+}
+
+pub struct Whitelist;
+
+#[async_trait]
+impl SlashCommand for Whitelist {
+    fn name(&self) -> &'static str {
+        "whitelist"
+    }
+
+    fn description(&self) -> &'static str {
+        "Add, remove, or list whitelisted players"
+    }
+
+    fn register(&self, _config: &RootConfig) -> CreateCommand {
+        let player = || {
+            CreateCommandOption::new(CommandOptionType::String, "player", "The player's name")
+                .required(true)
+        };
+
+        CreateCommand::new(self.name())
+            .description(self.description())
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Let a player join the server",
+                )
+                .add_sub_option(player()),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Stop a player from joining the server",
+                )
+                .add_sub_option(player()),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
                 "list",
-                "List the current players on the Minecraft server",
-                true,
-            ),
-        ])
-        .color(Colour::BLUE);
+                "List the whitelisted players",
+            ))
+    }
 
-    let response = CreateInteractionResponseMessage::new().add_embed(embed);
+    fn required_permissions(&self) -> Option<Permissions> {
+        Some(Permissions::MANAGE_GUILD)
+    }
 
-    command
-        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
-        .await?;
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(whitelist(ctx, command, visibility).await?)
+    }
+}
+
+/// Prints out an embed listing the currently-supported commands.
+pub async fn help(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let embed = help_embed(&*get_config(&ctx, command.channel_id).await.read().await);
+    let response = CreateInteractionResponseMessage::new().add_embed(embed);
 
-    sleep(Duration::new(30, 0)).await;
-    command.delete_response(&ctx.http).await?;
+    visibility.respond(&ctx, &command, response).await?;
 
     Ok(())
 }
@@ -46,63 +172,358 @@ pub async fn help(ctx: Context, command: CommandInteraction) -> Result<(), Error
 /// This works by calling the Minecraft `list` command via RCON,
 /// and listening for the response. The response is parsed to get
 /// the online player count, as well as their names.
-pub async fn list(ctx: Context, command: CommandInteraction) -> Result<(), Error> {
-    let config = ctx
-        .data
-        .read()
-        .await
-        .get::<ConfigContainer>()
-        .cloned()
-        .expect("expected config container in TypeMap");
+pub async fn list(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    // Discord only waits 3 seconds for a response, and RCON can take
+    // longer than that when the server is lagging
+    visibility.defer(&ctx, &command).await?;
 
-    // Create RCON connection
-    let addr = config.read().await.get_rcon_addr();
-    let password = config.read().await.get_rcon_password();
+    let option = |name: &str| {
+        command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_str())
+            .unwrap_or_default()
+    };
+    let query = Query::new(Sort::from_option(option("sort")), option("filter"));
 
-    let mut conn = Connection::builder()
-        .enable_minecraft_quirks(true)
-        .connect(addr, password.as_str())
+    let (embed, components, result) = player_list_reply(&ctx, command.channel_id, &query).await;
+    command
+        .edit_response(
+            &ctx.http,
+            EditInteractionResponse::new()
+                .embed(embed)
+                .components(components),
+        )
         .await?;
+    result?;
 
-    // Send the `list` command to the Minecraft server
-    let mut resp = conn.cmd("minecraft:list").await?;
-    if resp.starts_with("Unknown or incomplete command") {
-        resp = conn.cmd("list").await?;
-    }
+    visibility.finish(&ctx, &command).await?;
 
-    send_reply(&ctx, command, resp).await
+    Ok(())
 }
 
-async fn send_reply(ctx: &Context, command: CommandInteraction, resp: String) -> Result<(), Error> {
-    // Parse the response
-    let mut parts = resp.split(':');
-    let count_line = parts.next().unwrap();
-    let player_list = parts.next().unwrap_or("");
+/// Shows another page of the player list when one of the page buttons
+/// on a `list` response is clicked.
+pub async fn list_page(
+    ctx: Context,
+    component: ComponentInteraction,
+    query: Query,
+) -> Result<(), Error> {
+    component.defer(&ctx.http).await?;
 
-    let (online, max) = get_player_counts(count_line);
+    let (embed, components, result) = player_list_reply(&ctx, component.channel_id, &query).await;
+    component
+        .edit_response(
+            &ctx.http,
+            EditInteractionResponse::new()
+                .embed(embed)
+                .components(components),
+        )
+        .await?;
 
-    // Respond to the interaction
-    let embed = CreateEmbed::new()
-        .title("Online Players")
-        .description(format!(
-            "There are **{}** out of **{}** players online.",
-            online, max
-        ))
-        .color(Colour::BLUE)
-        .footer(CreateEmbedFooter::new(player_list));
+    result
+}
 
-    let response = CreateInteractionResponseMessage::new().add_embed(embed);
+/// Adds a player to, or removes them from, the server's whitelist, or
+/// lists who's on it, and shows what the server said.
+pub async fn whitelist(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let Some(subcommand) = command.data.options.first() else {
+        return Ok(());
+    };
+    let player = match &subcommand.value {
+        CommandDataOptionValue::SubCommand(options) => options
+            .iter()
+            .find(|option| option.name == "player")
+            .and_then(|option| option.value.as_str()),
+        _ => None,
+    };
+
+    let Some(line) = whitelist_command(&subcommand.name, player) else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("That isn't a valid Minecraft player name.");
+        visibility.respond(&ctx, &command, response).await?;
+        return Ok(());
+    };
+
+    let transport =
+        Transport::from_config(&*get_config(&ctx, command.channel_id).await.read().await);
+    if subcommand.name == "list" && !transport.has_responses() {
+        let response = CreateInteractionResponseMessage::new().content(
+            "The whitelist can't be read when commands are sent through the server console.",
+        );
+        visibility.respond(&ctx, &command, response).await?;
+        return Ok(());
+    }
+
+    if subcommand.name != "list" {
+        info!("{} ran a whitelist command: {}", command.user.name, line);
+    }
 
+    // The server can take longer to answer than Discord waits
+    visibility.defer(&ctx, &command).await?;
+
+    let result = match timeout(RCON_TIMEOUT, transport.send(&line)).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(String::from("timed out waiting for the server to respond")),
+    };
+    if let Err(e) = &result {
+        if let Some(metrics) =
+            per_server::for_channel::<MetricsContainer, _>(&ctx, command.channel_id).await
+        {
+            metrics.record_rcon_error(e);
+        }
+    }
+
+    let embed = whitelist_embed(&subcommand.name, result);
     command
-        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
         .await?;
+    visibility.finish(&ctx, &command).await?;
 
-    sleep(Duration::new(30, 0)).await;
-    command.delete_response(&ctx.http).await?;
+    Ok(())
+}
+
+/// The Minecraft command for a `/whitelist` subcommand, or `None` if the
+/// player's name couldn't be a Minecraft name, so nothing else can be
+/// snuck into the command.
+fn whitelist_command(action: &str, player: Option<&str>) -> Option<String> {
+    match (action, player.map(str::trim)) {
+        ("list", _) => Some(String::from("whitelist list")),
+        ("add" | "remove", Some(player)) if is_player_name(player) => {
+            Some(format!("whitelist {} {}", action, player))
+        }
+        _ => None,
+    }
+}
+
+/// Show what the server said to a whitelist command. The list is shown
+/// one player per line.
+fn whitelist_embed(action: &str, result: Result<String, String>) -> CreateEmbed {
+    let embed = CreateEmbed::new().title("Whitelist");
+
+    match result {
+        Ok(response) if action == "list" => {
+            let (summary, players) = response.split_once(':').unwrap_or((&response, ""));
+            let names = player_list::parse_names(players);
+            let description = match names.is_empty() {
+                true => summary.trim().to_string(),
+                false => format!("{}:\n{}", summary.trim(), names.join("\n")),
+            };
+            embed.description(description).color(Colour::BLUE)
+        }
+        Ok(response) if response.trim().is_empty() => embed
+            .description("Sent to the server.")
+            .color(Colour::DARK_GREEN),
+        Ok(response) => embed
+            .description(response.trim().to_string())
+            .color(Colour::DARK_GREEN),
+        Err(e) => embed
+            .description(format!("Couldn't reach the Minecraft server: {}", e))
+            .color(Colour::RED),
+    }
+}
+
+/// Replies to a prefix-style `help` command sent as a regular message.
+pub async fn help_message(ctx: &Context, msg: &Message) -> Result<(), Error> {
+    let embed = help_embed(&*get_config(ctx, msg.channel_id).await.read().await);
+    send_message_reply(ctx, msg, embed, Vec::new()).await
+}
+
+/// Replies to a prefix-style `list` command sent as a regular message.
+pub async fn list_message(ctx: &Context, msg: &Message) -> Result<(), Error> {
+    let query = Query::new(Sort::Server, "");
+    match player_list_reply(ctx, msg.channel_id, &query).await {
+        (_, _, Err(Error::Transport(e))) => Err(Error::Transport(e)),
+        (embed, components, _) => send_message_reply(ctx, msg, embed, components).await,
+    }
+}
+
+async fn send_message_reply(
+    ctx: &Context,
+    msg: &Message,
+    embed: CreateEmbed,
+    components: Vec<CreateActionRow>,
+) -> Result<(), Error> {
+    let reply = msg
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .reference_message(msg)
+                .embed(embed)
+                .components(components),
+        )
+        .await?;
+
+    ctx.data
+        .read()
+        .await
+        .get::<DeletionQueueContainer>()
+        .expect("expected deletion queue in TypeMap")
+        .delete_message_after(&reply, response::DEFAULT_DELETE_AFTER);
 
     Ok(())
 }
 
+/// Build the help embed from the registered commands and the
+/// current bridge configuration.
+fn help_embed(config: &RootConfig) -> CreateEmbed {
+    let mut description =
+        String::from("These are the commands for Dolphin. Commands must start with a `/`.");
+
+    let prefix = config.get_command_prefix();
+    if !prefix.is_empty() {
+        description.push_str(&format!(
+            " The `help` and `list` commands can also be used with the `{}` prefix.",
+            prefix
+        ));
+    }
+
+    let mode = bridge_mode(config);
+
+    CreateEmbed::new()
+        .title("Dolphin Help")
+        .description(description)
+        .fields(
+            COMMANDS
+                .iter()
+                .filter(|command| command.enabled(config))
+                .map(|command| (format!("/{}", command.name()), command.description(), true)),
+        )
+        .field("Bridge Mode", mode, false)
+        .field(
+            "Documentation",
+            format!("{}#readme", env!("CARGO_PKG_HOMEPAGE")),
+            false,
+        )
+        .color(Colour::BLUE)
+}
+
+/// The config of the server bridged with the channel a command was run
+/// in.
+async fn get_config(ctx: &Context, channel_id: ChannelId) -> Arc<RwLock<RootConfig>> {
+    container::config_for(ctx, channel_id).await
+}
+
+/// Sends the `list` command to the Minecraft server via RCON and
+/// returns the raw response.
+async fn query_player_list(ctx: &Context, channel_id: ChannelId) -> Result<String, Error> {
+    let config = get_config(ctx, channel_id).await;
+
+    let transport = Transport::from_config(&*config.read().await);
+    if !transport.has_responses() {
+        return Err(Error::Unsupported);
+    }
+
+    let result = async {
+        // Send the `list` command to the Minecraft server
+        let mut resp = transport.send("minecraft:list").await?;
+        if resp.starts_with("Unknown or incomplete command") {
+            resp = transport.send("list").await?;
+        }
+
+        Ok(resp)
+    }
+    .await;
+
+    if let Err(Error::Transport(e)) = &result {
+        if let Some(metrics) = per_server::for_channel::<MetricsContainer, _>(ctx, channel_id).await
+        {
+            metrics.record_rcon_error(&e.to_string());
+        }
+    }
+
+    result
+}
+
+/// Query the player list, giving up if the Minecraft server doesn't
+/// answer in time.
+async fn query_player_list_with_timeout(
+    ctx: &Context,
+    channel_id: ChannelId,
+) -> Result<String, Error> {
+    match timeout(RCON_TIMEOUT, query_player_list(ctx, channel_id)).await {
+        Ok(result) => result,
+        Err(_) => {
+            if let Some(metrics) =
+                per_server::for_channel::<MetricsContainer, _>(ctx, channel_id).await
+            {
+                metrics.record_rcon_error("timed out waiting for the server to respond");
+            }
+
+            Err(Error::Timeout)
+        }
+    }
+}
+
+fn timeout_embed() -> CreateEmbed {
+    CreateEmbed::new()
+        .title("Server Not Responding")
+        .description("The Minecraft server took too long to answer. It may be lagging or restarting; try again in a bit.")
+        .color(Colour::ORANGE)
+}
+
+fn unsupported_embed() -> CreateEmbed {
+    CreateEmbed::new()
+        .title("Player List Unavailable")
+        .description(
+            "The player list can't be read when commands are sent through the server console.",
+        )
+        .color(Colour::ORANGE)
+}
+
+fn error_embed() -> CreateEmbed {
+    CreateEmbed::new()
+        .title("Server Unavailable")
+        .description("Couldn't reach the Minecraft server.")
+        .color(Colour::RED)
+}
+
+/// Query the player list and build the page of it to show. If the query
+/// fails, the embed explains what went wrong and the error is returned
+/// alongside it.
+async fn player_list_reply(
+    ctx: &Context,
+    channel_id: ChannelId,
+    query: &Query,
+) -> (CreateEmbed, Vec<CreateActionRow>, Result<(), Error>) {
+    let resp = match query_player_list_with_timeout(ctx, channel_id).await {
+        Ok(resp) => resp,
+        Err(Error::Timeout) => return (timeout_embed(), Vec::new(), Ok(())),
+        Err(Error::Unsupported) => return (unsupported_embed(), Vec::new(), Ok(())),
+        Err(e) => return (error_embed(), Vec::new(), Err(e)),
+    };
+
+    // Parse the response
+    let (count_line, player_list) = resp.split_once(':').unwrap_or((&resp, ""));
+    let (online, max) = get_player_counts(count_line);
+    let names = player_list::parse_names(player_list);
+
+    let sessions = per_server::for_channel::<SessionsContainer, _>(ctx, channel_id).await;
+    let sessions = match &sessions {
+        Some(sessions) => Some(sessions.lock().await),
+        None => None,
+    };
+
+    let (embed, components) = player_list::build_page(online, max, names, query, |name| {
+        sessions
+            .as_ref()
+            .and_then(|sessions| sessions.online_for(name))
+    });
+
+    (embed, components, Ok(()))
+}
+
 fn get_player_counts(text: &str) -> (i32, i32) {
     lazy_static! {
         static ref COUNT_REGEX: Regex = Regex::new(r"(?P<online>\d+)\D+(?P<max>\d+)").unwrap();
@@ -136,6 +557,45 @@ pub enum Error {
     #[error("command error: {0}")]
     Discord(#[from] serenity::Error),
 
-    #[error("rcon error: {0}")]
-    Rcon(#[from] rcon::Error),
+    #[error("{0}")]
+    Transport(#[from] crate::transport::Error),
+
+    #[error("timed out waiting for the Minecraft server")]
+    Timeout,
+
+    #[error("the player list can't be read through the server console")]
+    Unsupported,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::whitelist_command;
+
+    #[test]
+    fn build_whitelist_commands() {
+        assert_eq!(
+            whitelist_command("add", Some("Steve_01")),
+            Some(String::from("whitelist add Steve_01"))
+        );
+        assert_eq!(
+            whitelist_command("remove", Some(" Alex ")),
+            Some(String::from("whitelist remove Alex"))
+        );
+        assert_eq!(
+            whitelist_command("list", None),
+            Some(String::from("whitelist list"))
+        );
+    }
+
+    #[test]
+    fn refuse_bad_player_names() {
+        assert_eq!(whitelist_command("add", Some("Steve; stop")), None);
+        assert_eq!(whitelist_command("add", Some("")), None);
+        assert_eq!(
+            whitelist_command("add", Some("a_name_that_is_too_long")),
+            None
+        );
+        assert_eq!(whitelist_command("remove", None), None);
+        assert_eq!(whitelist_command("op", Some("Steve")), None);
+    }
 }
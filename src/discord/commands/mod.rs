@@ -1 +1,237 @@
+use std::time::{Duration, Instant};
+
+use serenity::{
+    all::{CommandInteraction, ComponentInteraction, Permissions},
+    async_trait,
+    builder::{CreateCommand, CreateInteractionResponseMessage},
+    prelude::Context,
+};
+use thiserror::Error;
+
+use crate::config::{container::ConfigContainer, RootConfig};
+use crate::discord::cooldown::CooldownsContainer;
+pub use response::Visibility;
+
+pub mod bridge;
+pub mod cmd;
+pub mod confirm;
+pub mod digest;
+pub mod macros;
 pub mod minecraft;
+pub mod notify;
+mod player_list;
+pub mod prefix;
+pub mod purge;
+mod response;
+pub mod server;
+pub mod status;
+
+/// A slash command that can be registered with Discord and run when a
+/// user invokes it.
+///
+/// To add a command, implement this trait and add it to [COMMANDS]. It
+/// will then be registered, dispatched, and listed on the help page.
+#[async_trait]
+pub trait SlashCommand: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// Whether the command should be registered at all. Override this
+    /// for commands that only make sense with some config set.
+    fn enabled(&self, _config: &RootConfig) -> bool {
+        true
+    }
+
+    /// Build the registration to send to Discord. Override this to add
+    /// options to the command.
+    fn register(&self, _config: &RootConfig) -> CreateCommand {
+        CreateCommand::new(self.name()).description(self.description())
+    }
+
+    /// The permissions a member needs to use this command. Discord hides
+    /// the command from members without them, though server admins can
+    /// change this in their integration settings.
+    fn required_permissions(&self) -> Option<Permissions> {
+        None
+    }
+
+    /// Who can see the response if it isn't set in the config.
+    fn default_visibility(&self) -> Visibility {
+        Visibility::AutoDelete(response::DEFAULT_DELETE_AFTER)
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), Error>;
+}
+
+lazy_static! {
+    /// Every slash command we register. This is used to register the
+    /// commands with Discord, to dispatch them, and to build the help
+    /// page, so these never get out of sync.
+    pub static ref COMMANDS: Vec<Box<dyn SlashCommand>> = vec![
+        Box::new(bridge::BridgeStats),
+        Box::new(bridge::BridgeStatus),
+        Box::new(cmd::Cmd),
+        Box::new(digest::Digest),
+        Box::new(minecraft::Help),
+        Box::new(minecraft::List),
+        Box::new(macros::Macros),
+        Box::new(notify::Notify),
+        Box::new(purge::Purge),
+        Box::new(server::Server),
+        Box::new(status::Status),
+        Box::new(minecraft::Whitelist),
+    ];
+}
+
+/// Find the registered command with the given name.
+pub fn find(name: &str) -> Option<&'static dyn SlashCommand> {
+    COMMANDS
+        .iter()
+        .find(|command| command.name() == name)
+        .map(|command| command.as_ref())
+}
+
+/// Run a command, showing its response the way the config says to.
+/// Users who run it again before its cooldown is over are told to
+/// wait instead.
+pub async fn run(
+    handler: &dyn SlashCommand,
+    ctx: Context,
+    command: CommandInteraction,
+) -> Result<(), Error> {
+    let (visibility, (user_secs, channel_secs)) = {
+        let data = ctx.data.read().await;
+        let config = data
+            .get::<ConfigContainer>()
+            .expect("expected config container in TypeMap")
+            .read()
+            .await;
+        (
+            Visibility::from_config(&config, handler.name()),
+            config.get_command_cooldown(handler.name()),
+        )
+    };
+
+    let cooldowns = ctx.data.read().await.get::<CooldownsContainer>().cloned();
+    if let Some(cooldowns) = cooldowns {
+        let result = cooldowns.lock().unwrap().try_use(
+            handler.name(),
+            command.user.id,
+            command.channel_id,
+            (
+                Duration::from_secs(user_secs),
+                Duration::from_secs(channel_secs),
+            ),
+            Instant::now(),
+        );
+
+        if let Err(left) = result {
+            let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+            let response = CreateInteractionResponseMessage::new()
+                .content(format!("Slow down! Try again in {}s.", secs));
+            Visibility::Ephemeral
+                .respond(&ctx, &command, response)
+                .await?;
+            return Ok(());
+        }
+    }
+
+    let visibility = visibility.unwrap_or_else(|| handler.default_visibility());
+    handler.execute(ctx, command, visibility).await
+}
+
+/// Handle a click on a button in one of our command responses.
+pub async fn handle_component(ctx: Context, component: ComponentInteraction) -> Result<(), Error> {
+    if let Some(query) = player_list::Query::from_button_id(&component.data.custom_id) {
+        minecraft::list_page(ctx, component, query).await?;
+    }
+
+    Ok(())
+}
+
+/// Whether text typed into a command option can be put in a server
+/// command: one non-empty line. A line break or other control character
+/// could sneak a second command in after the first.
+fn is_one_line(text: &str) -> bool {
+    !text.is_empty() && !text.chars().any(char::is_control)
+}
+
+/// Build the slash command registrations to send to Discord.
+pub fn create_commands(config: &RootConfig) -> Vec<CreateCommand> {
+    COMMANDS
+        .iter()
+        .filter(|command| command.enabled(config))
+        .map(|command| match command.required_permissions() {
+            Some(permissions) => command
+                .register(config)
+                .default_member_permissions(permissions),
+            None => command.register(config),
+        })
+        .collect()
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+
+    #[error("{0}")]
+    Bridge(#[from] bridge::Error),
+
+    #[error("{0}")]
+    Cmd(#[from] cmd::Error),
+
+    #[error("{0}")]
+    Digest(#[from] digest::Error),
+
+    #[error("{0}")]
+    Macro(#[from] macros::Error),
+
+    #[error("{0}")]
+    Minecraft(#[from] minecraft::Error),
+
+    #[error("{0}")]
+    Notify(#[from] notify::Error),
+
+    #[error("{0}")]
+    Purge(#[from] purge::Error),
+
+    #[error("{0}")]
+    Server(#[from] server::Error),
+
+    #[error("{0}")]
+    Status(#[from] status::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{find, is_one_line, COMMANDS};
+
+    #[test]
+    fn command_names_are_unique() {
+        let names: HashSet<&str> = COMMANDS.iter().map(|command| command.name()).collect();
+        assert_eq!(names.len(), COMMANDS.len());
+    }
+
+    #[test]
+    fn find_registered_commands() {
+        assert_eq!(find("list").map(|command| command.name()), Some("list"));
+        assert!(find("nope").is_none());
+    }
+
+    #[test]
+    fn option_text_is_one_line() {
+        assert!(is_one_line("give Steve minecraft:stone 64"));
+        assert!(!is_one_line("say hi\nop Steve"));
+        assert!(!is_one_line("say hi\rop Steve"));
+        assert!(!is_one_line(""));
+    }
+}
@@ -0,0 +1,108 @@
+use serenity::{
+    all::{CommandInteraction, CommandOptionType},
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, CreateInteractionResponseMessage},
+    prelude::*,
+};
+use thiserror::Error;
+
+use super::{SlashCommand, Visibility};
+use crate::{
+    config::{container::ConfigContainer, RootConfig},
+    links::LinkStoreContainer,
+};
+
+pub struct Notify;
+
+#[async_trait]
+impl SlashCommand for Notify {
+    fn name(&self) -> &'static str {
+        "notify"
+    }
+
+    fn description(&self) -> &'static str {
+        "Choose whether to get a DM when you're mentioned in Minecraft while offline"
+    }
+
+    fn register(&self, _config: &RootConfig) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description(self.description())
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "enabled",
+                    "Whether to get mention DMs",
+                )
+                .required(true),
+            )
+    }
+
+    fn default_visibility(&self) -> Visibility {
+        Visibility::Ephemeral
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(notify(ctx, command, visibility).await?)
+    }
+}
+
+/// Lets a user choose whether they get a DM when a Minecraft player
+/// mentions them while they aren't online in Discord.
+pub async fn notify(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let (config, links) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<ConfigContainer>()
+                .cloned()
+                .expect("expected config container in TypeMap"),
+            data.get::<LinkStoreContainer>()
+                .cloned()
+                .expect("expected link store container in TypeMap"),
+        )
+    };
+
+    let enabled = command
+        .data
+        .options
+        .first()
+        .and_then(|option| option.value.as_bool())
+        .unwrap_or(false);
+
+    let content = if !config.read().await.mention_dms_enabled() {
+        "Mention DMs are turned off for this server."
+    } else {
+        links
+            .write()
+            .await
+            .set_mention_dms(command.user.id.get(), enabled)?;
+
+        if enabled {
+            "You will now get a DM when you're mentioned in Minecraft while you're offline."
+        } else {
+            "You will no longer get DMs when you're mentioned in Minecraft."
+        }
+    };
+
+    let response = CreateInteractionResponseMessage::new().content(content);
+    visibility.respond(&ctx, &command, response).await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+
+    #[error("link store error: {0}")]
+    Links(#[from] crate::links::Error),
+}
@@ -0,0 +1,265 @@
+use std::{cmp::Reverse, time::Duration};
+
+use serenity::{
+    all::ButtonStyle,
+    builder::{CreateActionRow, CreateButton, CreateEmbed},
+    model::Colour,
+};
+
+/// How many players to show on each page.
+const PAGE_SIZE: usize = 25;
+
+/// The prefix for the IDs of the page buttons.
+pub const BUTTON_PREFIX: &str = "list";
+
+/// Custom IDs can be at most 100 characters long, so the filter is cut
+/// short to fit.
+const MAX_FILTER_LENGTH: usize = 64;
+
+/// How to order the player list.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sort {
+    /// The order the server lists players in.
+    Server,
+    Alphabetical,
+    /// Players who have been online the longest first.
+    Playtime,
+}
+
+impl Sort {
+    pub fn from_option(value: &str) -> Self {
+        match value {
+            "alphabetical" => Sort::Alphabetical,
+            "playtime" => Sort::Playtime,
+            _ => Sort::Server,
+        }
+    }
+
+    fn as_option(self) -> &'static str {
+        match self {
+            Sort::Server => "server",
+            Sort::Alphabetical => "alphabetical",
+            Sort::Playtime => "playtime",
+        }
+    }
+}
+
+/// Which part of the player list to show.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    pub page: usize,
+    pub sort: Sort,
+    pub filter: String,
+}
+
+impl Query {
+    pub fn new(sort: Sort, filter: &str) -> Self {
+        Query {
+            page: 0,
+            sort,
+            filter: filter.chars().take(MAX_FILTER_LENGTH).collect(),
+        }
+    }
+
+    /// Encode the query for a page button, e.g. `list:2:alphabetical:st`.
+    fn button_id(&self, page: usize) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            BUTTON_PREFIX,
+            page,
+            self.sort.as_option(),
+            self.filter
+        )
+    }
+
+    /// Decode the query from a page button's ID.
+    pub fn from_button_id(id: &str) -> Option<Self> {
+        let mut parts = id.splitn(4, ':');
+        if parts.next()? != BUTTON_PREFIX {
+            return None;
+        }
+
+        Some(Query {
+            page: parts.next()?.parse().ok()?,
+            sort: Sort::from_option(parts.next()?),
+            filter: parts.next().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// Split the names out of the player list part of the `list` response.
+pub fn parse_names(player_list: &str) -> Vec<String> {
+    player_list
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Filter and sort the players, and pick out the requested page.
+/// Returns the players on the page, the number of players that matched,
+/// and the number of pages.
+pub fn select(
+    mut names: Vec<String>,
+    query: &Query,
+    online_for: impl Fn(&str) -> Option<Duration>,
+) -> (Vec<String>, usize, usize) {
+    let filter = query.filter.to_lowercase();
+    names.retain(|name| name.to_lowercase().contains(&filter));
+
+    match query.sort {
+        Sort::Server => {}
+        Sort::Alphabetical => names.sort_by_key(|name| name.to_lowercase()),
+        // Players we didn't see join have been on since before we started
+        Sort::Playtime => {
+            names.sort_by_key(|name| Reverse(online_for(name).unwrap_or(Duration::MAX)))
+        }
+    }
+
+    let matched = names.len();
+    let pages = matched.div_ceil(PAGE_SIZE).max(1);
+    let page = query.page.min(pages - 1);
+    let players = names
+        .into_iter()
+        .skip(page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .collect();
+
+    (players, matched, pages)
+}
+
+/// Build the embed and page buttons for a page of the player list.
+pub fn build_page(
+    online: i32,
+    max: i32,
+    names: Vec<String>,
+    query: &Query,
+    online_for: impl Fn(&str) -> Option<Duration>,
+) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let (players, matched, pages) = select(names, query, online_for);
+    let page = query.page.min(pages - 1);
+
+    let mut description = format!(
+        "There are **{}** out of **{}** players online.",
+        online, max
+    );
+    if !query.filter.is_empty() {
+        description.push_str(&format!(
+            " **{}** match `{}`.",
+            matched,
+            query.filter.replace('`', "")
+        ));
+    }
+    if !players.is_empty() {
+        description.push_str("\n\n");
+        description.push_str(&players.join("\n"));
+    }
+
+    let embed = CreateEmbed::new()
+        .title("Online Players")
+        .description(description)
+        .color(Colour::BLUE);
+
+    if pages == 1 {
+        return (embed, Vec::new());
+    }
+
+    let buttons = vec![
+        CreateButton::new(query.button_id(page.saturating_sub(1)))
+            .label("Previous")
+            .style(ButtonStyle::Secondary)
+            .disabled(page == 0),
+        CreateButton::new(format!("{}:page", BUTTON_PREFIX))
+            .label(format!("Page {} of {}", page + 1, pages))
+            .style(ButtonStyle::Secondary)
+            .disabled(true),
+        CreateButton::new(query.button_id(page + 1))
+            .label("Next")
+            .style(ButtonStyle::Secondary)
+            .disabled(page + 1 >= pages),
+    ];
+
+    (embed, vec![CreateActionRow::Buttons(buttons)])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_names, select, Query, Sort, PAGE_SIZE};
+
+    fn players(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("Player{:03}", i)).collect()
+    }
+
+    #[test]
+    fn parse_player_names() {
+        assert_eq!(
+            parse_names(" Steve, Alex,Notch"),
+            vec!["Steve", "Alex", "Notch"]
+        );
+        assert!(parse_names(" ").is_empty());
+    }
+
+    #[test]
+    fn button_ids_round_trip() {
+        let query = Query::new(Sort::Alphabetical, "st:eve");
+        let id = query.button_id(3);
+
+        assert_eq!(id, "list:3:alphabetical:st:eve");
+        assert_eq!(
+            Query::from_button_id(&id),
+            Some(Query {
+                page: 3,
+                sort: Sort::Alphabetical,
+                filter: String::from("st:eve"),
+            })
+        );
+        assert_eq!(Query::from_button_id("confirm:1"), None);
+        assert!(id.len() <= 100);
+    }
+
+    #[test]
+    fn pages_of_players() {
+        // Given
+        let mut query = Query::new(Sort::Server, "");
+        query.page = 4;
+
+        // When
+        let (page, matched, pages) = select(players(120), &query, |_| None);
+
+        // Then
+        assert_eq!(matched, 120);
+        assert_eq!(pages, 5);
+        assert_eq!(page.len(), 120 - 4 * PAGE_SIZE);
+        assert_eq!(page[0], "Player100");
+    }
+
+    #[test]
+    fn filter_and_sort_players() {
+        // Given
+        let names = vec![
+            String::from("steve"),
+            String::from("Alex"),
+            String::from("Steven"),
+        ];
+        let online_for = |name: &str| match name {
+            "steve" => Some(Duration::from_secs(10)),
+            "Steven" => Some(Duration::from_secs(600)),
+            _ => None,
+        };
+
+        // When
+        let (filtered, matched, _) = select(
+            names.clone(),
+            &Query::new(Sort::Alphabetical, "STEVE"),
+            online_for,
+        );
+        let (by_playtime, _, _) = select(names, &Query::new(Sort::Playtime, ""), online_for);
+
+        // Then
+        assert_eq!(matched, 2);
+        assert_eq!(filtered, vec!["steve", "Steven"]);
+        assert_eq!(by_playtime, vec!["Alex", "Steven", "steve"]);
+    }
+}
@@ -0,0 +1,45 @@
+/// Old-style commands that can be invoked by typing the configured
+/// prefix followed by the command name, e.g. `!list`.
+#[derive(Debug, PartialEq)]
+pub enum PrefixCommand {
+    Help,
+    List,
+}
+
+/// Check if a message's content is a prefix command. If the prefix is
+/// empty, prefix commands are disabled and [None] is always returned.
+pub fn parse_command(prefix: &str, content: &str) -> Option<PrefixCommand> {
+    if prefix.is_empty() {
+        return None;
+    }
+
+    match content.trim().strip_prefix(prefix)? {
+        "help" => Some(PrefixCommand::Help),
+        "list" => Some(PrefixCommand::List),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_command, PrefixCommand};
+
+    #[test]
+    fn parses_known_commands() {
+        assert_eq!(parse_command("!", "!help"), Some(PrefixCommand::Help));
+        assert_eq!(parse_command("!", "!list "), Some(PrefixCommand::List));
+    }
+
+    #[test]
+    fn no_false_positives() {
+        assert_eq!(parse_command("!", "!lists"), None);
+        assert_eq!(parse_command("!", "list"), None);
+        assert_eq!(parse_command("!", "hey !list"), None);
+    }
+
+    #[test]
+    fn disabled_without_prefix() {
+        assert_eq!(parse_command("", "list"), None);
+        assert_eq!(parse_command("", "!list"), None);
+    }
+}
@@ -0,0 +1,135 @@
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, Permissions},
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, EditInteractionResponse},
+    prelude::*,
+};
+use thiserror::Error;
+use tracing::warn;
+
+use super::{SlashCommand, Visibility};
+use crate::{
+    config::RootConfig,
+    listener::history::{ChatHistoryContainer, MAX_REMEMBERED},
+    per_server,
+};
+
+/// How many messages to delete if the count isn't given.
+const DEFAULT_COUNT: usize = 10;
+
+pub struct Purge;
+
+#[async_trait]
+impl SlashCommand for Purge {
+    fn name(&self) -> &'static str {
+        "purge"
+    }
+
+    fn description(&self) -> &'static str {
+        "Delete a Minecraft player's recent messages from Discord"
+    }
+
+    fn register(&self, _config: &RootConfig) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description(self.description())
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "player",
+                    "The Minecraft player whose messages to delete",
+                )
+                .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "count",
+                    format!("How many messages to delete, {} by default", DEFAULT_COUNT),
+                )
+                .min_int_value(1)
+                .max_int_value(MAX_REMEMBERED as u64),
+            )
+    }
+
+    fn required_permissions(&self) -> Option<Permissions> {
+        Some(Permissions::MANAGE_MESSAGES)
+    }
+
+    fn default_visibility(&self) -> Visibility {
+        Visibility::Ephemeral
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(purge(ctx, command, visibility).await?)
+    }
+}
+
+/// Deletes the most recent chat messages a player had bridged from
+/// Minecraft, newest first.
+pub async fn purge(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let mut player = "";
+    let mut count = DEFAULT_COUNT;
+    for option in &command.data.options {
+        match option.name.as_str() {
+            "player" => player = option.value.as_str().unwrap_or_default(),
+            "count" => {
+                count = option.value.as_i64().map_or(DEFAULT_COUNT, |count| {
+                    count.clamp(1, MAX_REMEMBERED as i64) as usize
+                })
+            }
+            _ => {}
+        }
+    }
+
+    // Deleting each message can take longer than Discord waits
+    visibility.defer(&ctx, &command).await?;
+
+    let history =
+        per_server::for_channel::<ChatHistoryContainer, _>(&ctx, command.channel_id).await;
+    let messages = match history {
+        Some(history) => history.lock().await.take_recent(player, count),
+        None => Vec::new(),
+    };
+
+    let mut deleted = 0;
+    let mut failed = 0;
+    for sent in &messages {
+        for id in &sent.ids {
+            if let Err(e) = sent.channel_id.delete_message(&ctx.http, *id).await {
+                warn!("purge:purge: unable to delete message {}: {}", id, e);
+                failed += 1;
+            }
+        }
+        deleted += 1;
+    }
+
+    let content = match (messages.len(), failed) {
+        (0, _) => format!("There are no recent messages from {} to delete.", player),
+        (_, 0) => format!("Deleted {} messages from {}.", deleted, player),
+        (_, failed) => format!(
+            "Deleted {} messages from {}, but {} parts couldn't be deleted. They may already be gone.",
+            deleted, player, failed
+        ),
+    };
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        .await?;
+    visibility.finish(&ctx, &command).await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+}
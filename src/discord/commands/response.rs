@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use crate::{config::RootConfig, discord::deletion::DeletionQueueContainer};
+use serenity::{
+    all::CommandInteraction,
+    builder::{CreateInteractionResponse, CreateInteractionResponseMessage},
+    prelude::Context,
+};
+
+/// How long auto-deleted responses stay up if no time is configured.
+pub const DEFAULT_DELETE_AFTER: Duration = Duration::from_secs(30);
+
+/// Who can see a command's response, and for how long.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Visibility {
+    /// Only the user who ran the command can see the response.
+    Ephemeral,
+    /// Everyone in the channel can see the response.
+    Public,
+    /// Everyone can see the response until it is deleted.
+    AutoDelete(Duration),
+}
+
+impl Visibility {
+    /// Get the configured visibility for a command, if there is one.
+    /// Unknown visibilities are treated as `auto_delete`.
+    pub fn from_config(config: &RootConfig, command: &str) -> Option<Self> {
+        let (visibility, delete_after) = config.get_command_response(command)?;
+
+        Some(match visibility.to_lowercase().as_str() {
+            "ephemeral" => Visibility::Ephemeral,
+            "public" => Visibility::Public,
+            _ => Visibility::AutoDelete(Duration::from_secs(delete_after)),
+        })
+    }
+
+    /// Send a response to a command.
+    pub async fn respond(
+        &self,
+        ctx: &Context,
+        command: &CommandInteraction,
+        response: CreateInteractionResponseMessage,
+    ) -> Result<(), serenity::Error> {
+        let response = response.ephemeral(*self == Visibility::Ephemeral);
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+
+        self.finish(ctx, command).await
+    }
+
+    /// Defer the response to a command, for commands that take longer
+    /// than Discord is willing to wait. Call [Visibility::finish] after
+    /// editing in the real response.
+    pub async fn defer(
+        &self,
+        ctx: &Context,
+        command: &CommandInteraction,
+    ) -> Result<(), serenity::Error> {
+        match self {
+            Visibility::Ephemeral => command.defer_ephemeral(&ctx.http).await,
+            _ => command.defer(&ctx.http).await,
+        }
+    }
+
+    /// Clean up after a response has been sent.
+    pub async fn finish(
+        &self,
+        ctx: &Context,
+        command: &CommandInteraction,
+    ) -> Result<(), serenity::Error> {
+        if let Visibility::AutoDelete(delay) = self {
+            ctx.data
+                .read()
+                .await
+                .get::<DeletionQueueContainer>()
+                .expect("expected deletion queue in TypeMap")
+                .delete_response_after(command, *delay);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Visibility;
+    use crate::config::RootConfig;
+
+    #[test]
+    fn parse_configured_visibility() {
+        // Given
+        let config: RootConfig = toml::from_str(
+            r#"
+            [discord_config.command_responses]
+            help = { visibility = "ephemeral" }
+            list = { visibility = "AUTO_DELETE", delete_after_secs = 10 }
+            bridgestats = { visibility = "public" }
+            "#,
+        )
+        .unwrap();
+
+        // Then
+        assert_eq!(
+            Visibility::from_config(&config, "help"),
+            Some(Visibility::Ephemeral)
+        );
+        assert_eq!(
+            Visibility::from_config(&config, "list"),
+            Some(Visibility::AutoDelete(Duration::from_secs(10)))
+        );
+        assert_eq!(
+            Visibility::from_config(&config, "bridgestats"),
+            Some(Visibility::Public)
+        );
+        assert_eq!(Visibility::from_config(&config, "notify"), None);
+    }
+}
@@ -0,0 +1,126 @@
+use serenity::{
+    all::{CommandInteraction, CommandOptionType, Permissions},
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, CreateInteractionResponseMessage},
+    prelude::*,
+};
+use thiserror::Error;
+
+use super::{
+    confirm::{self, confirm},
+    SlashCommand, Visibility,
+};
+use crate::{config::RootConfig, server};
+
+pub struct Server;
+
+#[async_trait]
+impl SlashCommand for Server {
+    fn name(&self) -> &'static str {
+        "server"
+    }
+
+    fn description(&self) -> &'static str {
+        "Start, stop, or restart the Minecraft server"
+    }
+
+    fn register(&self, _config: &RootConfig) -> CreateCommand {
+        let subcommands = [
+            ("start", "Start the Minecraft server"),
+            ("stop", "Stop the Minecraft server"),
+            ("restart", "Restart the Minecraft server"),
+        ];
+
+        subcommands.into_iter().fold(
+            CreateCommand::new(self.name()).description(self.description()),
+            |command, (name, description)| {
+                command.add_option(CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    name,
+                    description,
+                ))
+            },
+        )
+    }
+
+    fn required_permissions(&self) -> Option<Permissions> {
+        Some(Permissions::MANAGE_GUILD)
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(control(ctx, command, visibility).await?)
+    }
+}
+
+/// Controls the Minecraft server process, if Dolphin is running it.
+pub async fn control(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let action = command
+        .data
+        .options
+        .first()
+        .map(|option| option.name.as_str())
+        .unwrap_or_default();
+
+    let Some(server) = server::managed() else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("Dolphin isn't running the Minecraft server, so it can't control it.");
+        visibility.respond(&ctx, &command, response).await?;
+        return Ok(());
+    };
+
+    // Stopping and restarting kick everyone off, so ask first
+    let needs_confirmation = matches!(action, "stop" | "restart");
+
+    let (result, done) = match action {
+        "start" => (server.start_server(), "Starting the Minecraft server."),
+        "stop" | "restart" => {
+            let question = format!(
+                "This will {} the Minecraft server for everyone playing on it.",
+                action
+            );
+            if !confirm(&ctx, &command, visibility, &question).await? {
+                return Ok(());
+            }
+
+            if action == "stop" {
+                (server.stop_server(), "Stopping the Minecraft server.")
+            } else {
+                (server.restart_server(), "Restarting the Minecraft server.")
+            }
+        }
+        _ => (Ok(()), "Unknown action."),
+    };
+
+    let content = match result {
+        Ok(()) => String::from(done),
+        Err(e) => format!("Couldn't control the Minecraft server: {}", e),
+    };
+
+    // The question was the response, so replace it with the outcome
+    if needs_confirmation {
+        command
+            .edit_response(&ctx.http, confirm::resolved(&content))
+            .await?;
+        visibility.finish(&ctx, &command).await?;
+    } else {
+        let response = CreateInteractionResponseMessage::new().content(content);
+        visibility.respond(&ctx, &command, response).await?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+}
@@ -0,0 +1,61 @@
+use serenity::{
+    all::CommandInteraction, async_trait, builder::CreateInteractionResponseMessage, prelude::*,
+};
+use thiserror::Error;
+
+use super::{SlashCommand, Visibility};
+use crate::{config::RootConfig, usage::UsageContainer};
+
+pub struct Status;
+
+#[async_trait]
+impl SlashCommand for Status {
+    fn name(&self) -> &'static str {
+        "status"
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the Minecraft server's TPS, memory, and entity counts"
+    }
+
+    fn enabled(&self, config: &RootConfig) -> bool {
+        config.usage_enabled()
+    }
+
+    async fn execute(
+        &self,
+        ctx: Context,
+        command: CommandInteraction,
+        visibility: Visibility,
+    ) -> Result<(), super::Error> {
+        Ok(status(ctx, command, visibility).await?)
+    }
+}
+
+/// Shows the last resource usage we got from the server.
+pub async fn status(
+    ctx: Context,
+    command: CommandInteraction,
+    visibility: Visibility,
+) -> Result<(), Error> {
+    let latest = ctx.data.read().await.get::<UsageContainer>().cloned();
+    let latest = match latest {
+        Some(latest) => latest.read().await.clone(),
+        None => None,
+    };
+
+    let response = match latest {
+        Some((usage, polled)) => CreateInteractionResponseMessage::new().embed(usage.embed(polled)),
+        None => CreateInteractionResponseMessage::new()
+            .content("The server's usage hasn't been checked yet. Try again in a minute."),
+    };
+    visibility.respond(&ctx, &command, response).await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serenity::{
+    all::{ChannelId, UserId},
+    prelude::TypeMapKey,
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Key {
+    User(UserId),
+    Channel(ChannelId),
+}
+
+/// Keeps track of when each command can next be used by each user and
+/// in each channel.
+#[derive(Debug, Default)]
+pub struct Cooldowns {
+    ready_at: HashMap<(String, Key), Instant>,
+}
+
+impl Cooldowns {
+    /// Check whether a user can run a command in a channel now. If they
+    /// can, the cooldowns start over, and if not, returns how long is
+    /// left. A cooldown of zero is no cooldown.
+    pub fn try_use(
+        &mut self,
+        command: &str,
+        user: UserId,
+        channel: ChannelId,
+        (user_cooldown, channel_cooldown): (Duration, Duration),
+        now: Instant,
+    ) -> Result<(), Duration> {
+        self.ready_at.retain(|_, ready_at| *ready_at > now);
+
+        let keys = [
+            (Key::User(user), user_cooldown),
+            (Key::Channel(channel), channel_cooldown),
+        ];
+
+        let left = keys
+            .iter()
+            .filter_map(|(key, _)| self.ready_at.get(&(command.to_string(), *key)))
+            .map(|ready_at| *ready_at - now)
+            .max();
+        if let Some(left) = left {
+            return Err(left);
+        }
+
+        for (key, cooldown) in keys {
+            if !cooldown.is_zero() {
+                self.ready_at
+                    .insert((command.to_string(), key), now + cooldown);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct CooldownsContainer;
+
+impl TypeMapKey for CooldownsContainer {
+    type Value = Arc<Mutex<Cooldowns>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serenity::all::{ChannelId, UserId};
+
+    use super::Cooldowns;
+
+    const STEVE: UserId = UserId::new(1);
+    const ALEX: UserId = UserId::new(2);
+    const GENERAL: ChannelId = ChannelId::new(10);
+    const OFF_TOPIC: ChannelId = ChannelId::new(11);
+
+    #[test]
+    fn users_wait_between_uses() {
+        // Given
+        let mut cooldowns = Cooldowns::default();
+        let cooldown = (Duration::from_secs(10), Duration::ZERO);
+        let now = Instant::now();
+
+        // When
+        let first = cooldowns.try_use("list", STEVE, GENERAL, cooldown, now);
+        let again = cooldowns.try_use(
+            "list",
+            STEVE,
+            OFF_TOPIC,
+            cooldown,
+            now + Duration::from_secs(4),
+        );
+        let other = cooldowns.try_use(
+            "list",
+            ALEX,
+            GENERAL,
+            cooldown,
+            now + Duration::from_secs(4),
+        );
+        let later = cooldowns.try_use(
+            "list",
+            STEVE,
+            GENERAL,
+            cooldown,
+            now + Duration::from_secs(10),
+        );
+
+        // Then
+        assert_eq!(first, Ok(()));
+        assert_eq!(again, Err(Duration::from_secs(6)));
+        assert_eq!(other, Ok(()));
+        assert_eq!(later, Ok(()));
+    }
+
+    #[test]
+    fn channels_wait_between_uses() {
+        // Given
+        let mut cooldowns = Cooldowns::default();
+        let cooldown = (Duration::ZERO, Duration::from_secs(30));
+        let now = Instant::now();
+
+        // When
+        let first = cooldowns.try_use("list", STEVE, GENERAL, cooldown, now);
+        let same_channel = cooldowns.try_use("list", ALEX, GENERAL, cooldown, now);
+        let other_channel = cooldowns.try_use("list", ALEX, OFF_TOPIC, cooldown, now);
+        let other_command = cooldowns.try_use("help", ALEX, GENERAL, cooldown, now);
+
+        // Then
+        assert_eq!(first, Ok(()));
+        assert_eq!(same_channel, Err(Duration::from_secs(30)));
+        assert_eq!(other_channel, Ok(()));
+        assert_eq!(other_command, Ok(()));
+    }
+
+    #[test]
+    fn no_cooldown_by_default() {
+        // Given
+        let mut cooldowns = Cooldowns::default();
+        let now = Instant::now();
+
+        // Then
+        for _ in 0..3 {
+            assert_eq!(
+                cooldowns.try_use(
+                    "list",
+                    STEVE,
+                    GENERAL,
+                    (Duration::ZERO, Duration::ZERO),
+                    now
+                ),
+                Ok(())
+            );
+        }
+    }
+}
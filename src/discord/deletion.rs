@@ -0,0 +1,225 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serenity::{
+    all::{ChannelId, CommandInteraction, Message, MessageId},
+    http::Http,
+    prelude::TypeMapKey,
+};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    time::{timeout_at, Instant},
+};
+use tracing::{debug, error};
+
+/// Something to delete from Discord.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    /// The original response to an interaction, identified by its token.
+    Interaction(String),
+    Message(ChannelId, MessageId),
+}
+
+#[derive(Debug)]
+struct Entry {
+    at: Instant,
+    target: Target,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.at == other.at
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    // Reversed so the heap gives us the earliest entry first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.at.cmp(&self.at)
+    }
+}
+
+/// Pending deletions, ordered by when they are due.
+#[derive(Debug, Default)]
+struct Schedule {
+    entries: BinaryHeap<Entry>,
+}
+
+impl Schedule {
+    fn push(&mut self, at: Instant, target: Target) {
+        self.entries.push(Entry { at, target });
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        self.entries.peek().map(|entry| entry.at)
+    }
+
+    /// Remove and return every target that is due at the given time.
+    fn take_due(&mut self, now: Instant) -> Vec<Target> {
+        let mut due = Vec::new();
+        while self.entries.peek().is_some_and(|entry| entry.at <= now) {
+            due.push(self.entries.pop().unwrap().target);
+        }
+
+        due
+    }
+}
+
+/// Deletes messages after a delay, so that commands don't have to hold
+/// a task open while they wait. A single task works through every
+/// pending deletion in the order they are due.
+#[derive(Clone)]
+pub struct DeletionQueue {
+    sender: UnboundedSender<Entry>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl DeletionQueue {
+    /// Start the task that performs deletions.
+    pub fn start(http: Arc<Http>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let pending = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(run(http, receiver, pending.clone()));
+
+        Self { sender, pending }
+    }
+
+    /// How many deletions are waiting to happen.
+    pub fn pending(&self) -> usize {
+        self.pending.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Delete the original response to a command after a delay.
+    pub fn delete_response_after(&self, command: &CommandInteraction, delay: Duration) {
+        self.schedule(Target::Interaction(command.token.clone()), delay);
+    }
+
+    /// Delete a message after a delay.
+    pub fn delete_message_after(&self, message: &Message, delay: Duration) {
+        self.schedule(Target::Message(message.channel_id, message.id), delay);
+    }
+
+    fn schedule(&self, target: Target, delay: Duration) {
+        let entry = Entry {
+            at: Instant::now() + delay,
+            target,
+        };
+
+        match self.sender.send(entry) {
+            Ok(()) => {
+                self.pending.fetch_add(1, AtomicOrdering::Relaxed);
+            }
+            Err(_) => error!("Deletion queue has stopped, message will not be deleted"),
+        }
+    }
+}
+
+async fn run(http: Arc<Http>, mut receiver: UnboundedReceiver<Entry>, pending: Arc<AtomicUsize>) {
+    let mut schedule = Schedule::default();
+
+    loop {
+        let received = match schedule.next_due() {
+            Some(at) => match timeout_at(at, receiver.recv()).await {
+                Ok(received) => received,
+                Err(_) => {
+                    for target in schedule.take_due(Instant::now()) {
+                        delete(&http, target).await;
+                        pending.fetch_sub(1, AtomicOrdering::Relaxed);
+                    }
+                    continue;
+                }
+            },
+            None => receiver.recv().await,
+        };
+
+        match received {
+            Some(entry) => schedule.push(entry.at, entry.target),
+            // Every sender is gone, so we're shutting down
+            None => {
+                debug!(
+                    "Deletion queue stopped with {} pending deletions",
+                    schedule.entries.len()
+                );
+                return;
+            }
+        }
+    }
+}
+
+async fn delete(http: &Http, target: Target) {
+    let result = match &target {
+        Target::Interaction(token) => http.delete_original_interaction_response(token).await,
+        Target::Message(channel_id, message_id) => {
+            channel_id.delete_message(http, *message_id).await
+        }
+    };
+
+    if let Err(e) = result {
+        error!("Unable to delete {:?}: {e}", target);
+    }
+}
+
+pub struct DeletionQueueContainer;
+
+impl TypeMapKey for DeletionQueueContainer {
+    type Value = DeletionQueue;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serenity::all::{ChannelId, MessageId};
+    use tokio::time::Instant;
+
+    use super::{Schedule, Target};
+
+    fn message(id: u64) -> Target {
+        Target::Message(ChannelId::new(1), MessageId::new(id))
+    }
+
+    #[test]
+    fn takes_due_entries_in_order() {
+        // Given
+        let now = Instant::now();
+        let mut schedule = Schedule::default();
+        schedule.push(now + Duration::from_secs(30), message(3));
+        schedule.push(now + Duration::from_secs(10), message(1));
+        schedule.push(now + Duration::from_secs(60), message(4));
+        schedule.push(now + Duration::from_secs(20), message(2));
+
+        // When
+        let due = schedule.take_due(now + Duration::from_secs(30));
+
+        // Then
+        assert_eq!(due, vec![message(1), message(2), message(3)]);
+        assert_eq!(schedule.next_due(), Some(now + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn nothing_due_yet() {
+        // Given
+        let now = Instant::now();
+        let mut schedule = Schedule::default();
+        schedule.push(now + Duration::from_secs(10), message(1));
+
+        // Then
+        assert!(schedule.take_due(now).is_empty());
+        assert!(Schedule::default().next_due().is_none());
+    }
+}
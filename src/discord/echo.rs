@@ -0,0 +1,217 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serenity::prelude::TypeMapKey;
+
+use crate::per_server::PerServer;
+
+/// Remembered messages shorter than this only count as an echo if the
+/// whole message matches, so short replies like "lol" aren't dropped
+/// just for showing up inside another message.
+const MIN_CONTAINED_LENGTH: usize = 12;
+
+/// How many bridged messages each guard holds on to, so a flood of chat
+/// can't grow it without bound.
+const MAX_REMEMBERED: usize = 256;
+
+/// A message we bridged, and who it was from.
+#[derive(Debug)]
+struct Remembered {
+    at: Instant,
+    author: String,
+    content: String,
+}
+
+/// Remembers what was recently bridged in one direction, so that
+/// messages another bridge or plugin echoes back to us can be dropped
+/// instead of going around in a loop.
+#[derive(Debug)]
+pub struct EchoGuard {
+    window: Duration,
+    recent: Mutex<VecDeque<Remembered>>,
+}
+
+/// A guard for each direction. Echoes are only ever looked for in what
+/// was bridged the other way, so two players saying the same thing are
+/// never mistaken for one another.
+#[derive(Debug)]
+pub struct Echoes {
+    /// What was sent from Discord to Minecraft.
+    pub to_minecraft: EchoGuard,
+    /// What was sent from Minecraft to Discord.
+    pub to_discord: EchoGuard,
+}
+
+impl Echoes {
+    pub fn new(window: Duration) -> Self {
+        Echoes {
+            to_minecraft: EchoGuard::new(window),
+            to_discord: EchoGuard::new(window),
+        }
+    }
+}
+
+impl EchoGuard {
+    /// Create a guard that remembers messages for the given window. A
+    /// window of zero turns echo detection off.
+    pub fn new(window: Duration) -> Self {
+        EchoGuard {
+            window,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Remember a message we just bridged, and the name it was sent as.
+    pub fn remember(&self, author: &str, content: &str) {
+        let content = normalize(content);
+        if self.window.is_zero() || content.is_empty() {
+            return;
+        }
+
+        let mut recent = self.recent.lock().unwrap();
+        expire(&mut recent, self.window);
+        if recent.len() == MAX_REMEMBERED {
+            recent.pop_front();
+        }
+        recent.push_back(Remembered {
+            at: Instant::now(),
+            author: normalize(author),
+            content,
+        });
+    }
+
+    /// Check if chat is a message we recently bridged, sent back to us
+    /// by a plugin. It has to carry the name we bridged it as, either as
+    /// the sender or in front of the text like `[Discord] Bob: hi`, so
+    /// someone repeating what was said isn't dropped.
+    pub fn is_relayed(&self, name: &str, content: &str) -> bool {
+        let name = normalize(name);
+        let content = normalize(content);
+        self.any_recent(|remembered| {
+            (remembered.author == name && remembered.content == content)
+                || (!remembered.author.is_empty()
+                    && content.ends_with(&remembered.content)
+                    && content[..content.len() - remembered.content.len()]
+                        .contains(&remembered.author))
+        })
+    }
+
+    /// Check if a message is, or contains, something we recently
+    /// bridged, e.g. another bot reposting it with a name in front.
+    pub fn is_echo(&self, content: &str) -> bool {
+        let content = normalize(content);
+        self.any_recent(|remembered| {
+            let remembered = &remembered.content;
+            *remembered == content
+                || (remembered.chars().count() >= MIN_CONTAINED_LENGTH
+                    && content.contains(remembered.as_str()))
+        })
+    }
+
+    fn any_recent(&self, matches: impl Fn(&Remembered) -> bool) -> bool {
+        if self.window.is_zero() {
+            return false;
+        }
+
+        let mut recent = self.recent.lock().unwrap();
+        expire(&mut recent, self.window);
+        recent.iter().any(matches)
+    }
+}
+
+fn expire(recent: &mut VecDeque<Remembered>, window: Duration) {
+    while recent
+        .front()
+        .is_some_and(|remembered| remembered.at.elapsed() > window)
+    {
+        recent.pop_front();
+    }
+}
+
+/// Lowercase and collapse whitespace, so small formatting differences
+/// between bridges don't hide an echo.
+fn normalize(content: &str) -> String {
+    content
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The echo guards for each server.
+pub struct EchoGuardContainer;
+
+impl TypeMapKey for EchoGuardContainer {
+    type Value = Arc<PerServer<Echoes>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{EchoGuard, Echoes};
+
+    #[test]
+    fn detects_reposted_messages() {
+        // Given
+        let guard = EchoGuard::new(Duration::from_secs(30));
+
+        // When
+        guard.remember("Steve", "anyone want to go to the nether?");
+        guard.remember("Steve", "lol");
+
+        // Then
+        assert!(guard.is_echo("**Steve**: Anyone want to go  to the Nether?"));
+        assert!(guard.is_echo("LOL"));
+        assert!(!guard.is_echo("lol that's great"));
+        assert!(!guard.is_echo("something else entirely"));
+    }
+
+    #[test]
+    fn relayed_chat_carries_our_name() {
+        // Given
+        let guard = EchoGuard::new(Duration::from_secs(30));
+
+        // When
+        guard.remember("Bob", "anyone want to go to the nether?");
+
+        // Then
+        assert!(guard.is_relayed("bob", "Anyone want to go to the nether?"));
+        assert!(guard.is_relayed(
+            "ChatPlugin",
+            "[Discord] Bob: anyone want to go to the nether?"
+        ));
+        assert!(!guard.is_relayed("Steve", "anyone want to go to the nether?"));
+    }
+
+    #[test]
+    fn players_can_say_the_same_thing() {
+        // Given
+        let echoes = Echoes::new(Duration::from_secs(30));
+
+        // When
+        let steve = !echoes.to_minecraft.is_relayed("Steve", "gg");
+        echoes.to_discord.remember("Steve", "gg");
+        let alex = !echoes.to_minecraft.is_relayed("Alex", "gg");
+        echoes.to_discord.remember("Alex", "gg");
+
+        // Then
+        assert!(steve);
+        assert!(alex);
+    }
+
+    #[test]
+    fn disabled_with_zero_window() {
+        // Given
+        let guard = EchoGuard::new(Duration::ZERO);
+
+        // When
+        guard.remember("Steve", "anyone want to go to the nether?");
+
+        // Then
+        assert!(!guard.is_echo("anyone want to go to the nether?"));
+    }
+}
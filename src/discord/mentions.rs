@@ -0,0 +1,467 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use serenity::{
+    all::{ChannelId, GuildId, RoleId, UserId},
+    cache::Cache,
+    model::guild::Guild,
+    prelude::{Mentionable, TypeMapKey},
+};
+
+/// Partial names shorter than this never match, so a stray `@a` doesn't
+/// ping someone.
+const MIN_PREFIX_LENGTH: usize = 3;
+
+/// How closely a name from Minecraft has to match to be a mention.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Matching {
+    /// Names have to match exactly.
+    Exact,
+    /// Names can be written in any case.
+    IgnoreCase,
+    /// Names can be written in any case, and the start of a member's
+    /// name is enough if only one member's name starts with it.
+    Prefix,
+}
+
+impl Matching {
+    /// Unknown values are treated as `ignore_case`.
+    pub fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "exact" => Matching::Exact,
+            "prefix" => Matching::Prefix,
+            _ => Matching::IgnoreCase,
+        }
+    }
+}
+
+/// Something in the guild that can be mentioned by name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    User(UserId),
+    Role(RoleId),
+    Channel(ChannelId),
+}
+
+impl Target {
+    /// The text Discord turns into a mention.
+    pub fn mention(&self) -> String {
+        match self {
+            Target::User(id) => id.mention().to_string(),
+            Target::Role(id) => id.mention().to_string(),
+            Target::Channel(id) => id.mention().to_string(),
+        }
+    }
+}
+
+/// Names of one kind, e.g. usernames, looked up as written or in any
+/// case. A name that's the same for different IDs when case is ignored
+/// only matches when written exactly.
+#[derive(Debug)]
+struct Names<T> {
+    exact: HashMap<String, T>,
+    folded: BTreeMap<String, Option<T>>,
+}
+
+impl<T> Default for Names<T> {
+    fn default() -> Self {
+        Names {
+            exact: HashMap::new(),
+            folded: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> Names<T> {
+    fn insert(&mut self, name: &str, id: T) {
+        self.exact.entry(name.to_string()).or_insert(id);
+        self.folded
+            .entry(name.to_lowercase())
+            .and_modify(|found| {
+                if *found != Some(id) {
+                    *found = None;
+                }
+            })
+            .or_insert(Some(id));
+    }
+
+    fn get(&self, name: &str) -> Option<T> {
+        self.exact.get(name).copied()
+    }
+
+    fn get_folded(&self, name: &str) -> Option<T> {
+        self.folded.get(&name.to_lowercase()).copied().flatten()
+    }
+
+    /// Add every ID with a name starting with the prefix, or `None` for
+    /// names that are ambiguous.
+    fn starting_with(&self, prefix: &str, found: &mut Vec<Option<T>>) {
+        let prefix = prefix.to_lowercase();
+        for (_, id) in self
+            .folded
+            .range(prefix.clone()..)
+            .take_while(|(name, _)| name.starts_with(&prefix))
+        {
+            if !found.contains(id) {
+                found.push(*id);
+            }
+        }
+    }
+}
+
+/// Lookup tables from names to the members, roles, and channels of a
+/// guild, so mentions don't need a scan over the whole guild.
+///
+/// Members are looked up by `username#discriminator`, then username,
+/// then global display name, then nickname. Roles are tried after
+/// members, and channels last. Exact matches always win over ones that
+/// ignore case, and those over partial names.
+#[derive(Debug, Default)]
+pub struct MentionIndex {
+    everyone: RoleId,
+    tags: Names<UserId>,
+    usernames: Names<UserId>,
+    global_names: Names<UserId>,
+    nicks: Names<UserId>,
+    roles: Names<RoleId>,
+    channels: Names<ChannelId>,
+}
+
+impl MentionIndex {
+    pub fn from_guild(guild: &Guild) -> Self {
+        let mut index = MentionIndex {
+            everyone: RoleId::new(guild.id.get()),
+            ..Default::default()
+        };
+
+        for member in guild.members.values() {
+            let user = &member.user;
+            let tag = user
+                .discriminator
+                .map(|discriminator| format!("{}#{:04}", user.name, discriminator));
+            index.add_member(
+                user.id,
+                &user.name,
+                tag,
+                user.global_name.as_deref(),
+                member.nick.as_deref(),
+            );
+        }
+
+        for role in guild.roles.values() {
+            index.roles.insert(&role.name, role.id);
+        }
+
+        for channel in guild.channels.values() {
+            index.channels.insert(&channel.name, channel.id);
+        }
+
+        index
+    }
+
+    fn add_member(
+        &mut self,
+        id: UserId,
+        username: &str,
+        tag: Option<String>,
+        global_name: Option<&str>,
+        nick: Option<&str>,
+    ) {
+        if let Some(tag) = tag {
+            self.tags.insert(&tag, id);
+        }
+        self.usernames.insert(username, id);
+        if let Some(global_name) = global_name {
+            self.global_names.insert(global_name, id);
+        }
+        if let Some(nick) = nick {
+            self.nicks.insert(nick, id);
+        }
+    }
+
+    fn members(&self) -> [&Names<UserId>; 4] {
+        [&self.tags, &self.usernames, &self.global_names, &self.nicks]
+    }
+
+    /// Find what a name refers to. The `@everyone` role is only found if
+    /// `allow_everyone` is set.
+    pub fn find(&self, name: &str, allow_everyone: bool, matching: Matching) -> Option<Target> {
+        let role_allowed = |id: &RoleId| allow_everyone || *id != self.everyone;
+        let exact = || {
+            let member = self.members().into_iter().find_map(|names| names.get(name));
+            member.map(Target::User).or_else(|| {
+                let role = self.roles.get(name).filter(role_allowed).map(Target::Role);
+                role.or_else(|| self.channels.get(name).map(Target::Channel))
+            })
+        };
+        let folded = || {
+            let member = self
+                .members()
+                .into_iter()
+                .find_map(|names| names.get_folded(name));
+            member.map(Target::User).or_else(|| {
+                let role = self
+                    .roles
+                    .get_folded(name)
+                    .filter(role_allowed)
+                    .map(Target::Role);
+                role.or_else(|| self.channels.get_folded(name).map(Target::Channel))
+            })
+        };
+
+        match matching {
+            Matching::Exact => exact(),
+            Matching::IgnoreCase => exact().or_else(folded),
+            Matching::Prefix => exact()
+                .or_else(folded)
+                .or_else(|| self.member_starting_with(name).map(Target::User)),
+        }
+    }
+
+    /// The first of the names that a mention from Minecraft would find
+    /// the member by, so a mention filled in from it pings them. Names
+    /// have to be typeable as one word, and a `#` only works in a tag.
+    pub fn mention_name<'a>(
+        &self,
+        id: UserId,
+        names: &[&'a str],
+        matching: Matching,
+    ) -> Option<&'a str> {
+        let typeable = |name: &str| {
+            !name.is_empty()
+                && !name.contains(char::is_whitespace)
+                && !name.contains('@')
+                && name.split_once('#').is_none_or(|(_, discriminator)| {
+                    discriminator.len() == 4 && discriminator.chars().all(|c| c.is_ascii_digit())
+                })
+        };
+
+        names.iter().copied().find(|name| {
+            typeable(name) && self.find(name, false, matching) == Some(Target::User(id))
+        })
+    }
+
+    /// Find the only member with a name starting with the prefix.
+    fn member_starting_with(&self, prefix: &str) -> Option<UserId> {
+        if prefix.chars().count() < MIN_PREFIX_LENGTH {
+            return None;
+        }
+
+        let mut found = Vec::new();
+        for names in self.members() {
+            names.starting_with(prefix, &mut found);
+        }
+
+        match found.as_slice() {
+            [Some(id)] => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps the mention index for the bridged guild, building it from the
+/// cache when it's first needed and again after anything in it changes.
+#[derive(Debug, Default)]
+pub struct MentionCache {
+    index: Mutex<Option<Arc<MentionIndex>>>,
+}
+
+impl MentionCache {
+    /// Get the index for a guild, building it if the guild changed since
+    /// it was last built. Returns `None` if the guild isn't cached.
+    pub fn get(&self, cache: &Cache, guild_id: GuildId) -> Option<Arc<MentionIndex>> {
+        let mut index = self.index.lock().unwrap();
+        if let Some(index) = index.as_ref() {
+            if index.everyone.get() == guild_id.get() {
+                return Some(index.clone());
+            }
+        }
+
+        let built = Arc::new(MentionIndex::from_guild(&*cache.guild(guild_id)?));
+        *index = Some(built.clone());
+        Some(built)
+    }
+
+    /// Forget the index, so it's built again with the latest members,
+    /// roles, and channels.
+    pub fn invalidate(&self) {
+        *self.index.lock().unwrap() = None;
+    }
+}
+
+pub struct MentionCacheContainer;
+
+impl TypeMapKey for MentionCacheContainer {
+    type Value = Arc<MentionCache>;
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::{ChannelId, RoleId, UserId};
+
+    use super::{Matching, MentionIndex, Target};
+
+    fn index() -> MentionIndex {
+        let mut index = MentionIndex {
+            everyone: RoleId::new(1),
+            ..Default::default()
+        };
+        index.add_member(
+            UserId::new(10),
+            "steve",
+            None,
+            Some("Steve"),
+            Some("Steve the Builder"),
+        );
+        index.add_member(
+            UserId::new(11),
+            "alex",
+            Some(String::from("alex#1234")),
+            None,
+            None,
+        );
+        index.add_member(UserId::new(12), "builder", None, None, Some("alex"));
+        index.add_member(UserId::new(13), "notch", None, Some("Markus"), None);
+        index.add_member(UserId::new(14), "jeb_", None, Some("Jens"), None);
+        index.add_member(UserId::new(15), "jebediah", None, None, None);
+        index.roles.insert("@everyone", RoleId::new(1));
+        index.roles.insert("Moderators", RoleId::new(20));
+        index.channels.insert("general", ChannelId::new(30));
+        index
+    }
+
+    #[test]
+    fn find_members_by_tag_username_and_nick() {
+        // Given
+        let index = index();
+
+        // Then
+        assert_eq!(
+            index.find("alex#1234", false, Matching::Exact),
+            Some(Target::User(UserId::new(11)))
+        );
+        assert_eq!(
+            index.find("steve", false, Matching::Exact),
+            Some(Target::User(UserId::new(10)))
+        );
+        assert_eq!(
+            index.find("Steve the Builder", false, Matching::Exact),
+            Some(Target::User(UserId::new(10)))
+        );
+    }
+
+    #[test]
+    fn usernames_win_over_nicks() {
+        // Given
+        let index = index();
+
+        // When
+        let found = index.find("alex", false, Matching::Exact);
+
+        // Then
+        assert_eq!(found, Some(Target::User(UserId::new(11))));
+    }
+
+    #[test]
+    fn find_roles_and_channels() {
+        // Given
+        let index = index();
+
+        // Then
+        assert_eq!(
+            index.find("Moderators", false, Matching::Exact),
+            Some(Target::Role(RoleId::new(20)))
+        );
+        assert_eq!(
+            index.find("general", false, Matching::Exact),
+            Some(Target::Channel(ChannelId::new(30)))
+        );
+        assert_eq!(index.find("nobody", false, Matching::Prefix), None);
+    }
+
+    #[test]
+    fn everyone_only_when_allowed() {
+        // Given
+        let index = index();
+
+        // Then
+        assert_eq!(index.find("@everyone", false, Matching::IgnoreCase), None);
+        assert_eq!(
+            index.find("@everyone", true, Matching::IgnoreCase),
+            Some(Target::Role(RoleId::new(1)))
+        );
+    }
+
+    #[test]
+    fn ignore_case_unless_exact() {
+        // Given
+        let index = index();
+
+        // Then
+        assert_eq!(index.find("MARKUS", false, Matching::Exact), None);
+        assert_eq!(
+            index.find("MARKUS", false, Matching::IgnoreCase),
+            Some(Target::User(UserId::new(13)))
+        );
+        assert_eq!(
+            index.find("moderators", false, Matching::IgnoreCase),
+            Some(Target::Role(RoleId::new(20)))
+        );
+    }
+
+    #[test]
+    fn match_unique_prefixes() {
+        // Given
+        let index = index();
+
+        // Then
+        assert_eq!(index.find("mark", false, Matching::IgnoreCase), None);
+        assert_eq!(
+            index.find("mark", false, Matching::Prefix),
+            Some(Target::User(UserId::new(13)))
+        );
+        // Both the username and global name of one member start with it
+        assert_eq!(
+            index.find("Stev", false, Matching::Prefix),
+            Some(Target::User(UserId::new(10)))
+        );
+        // `jeb_` and `jebediah` both start with it
+        assert_eq!(index.find("jeb", false, Matching::Prefix), None);
+        // Too short to guess
+        assert_eq!(index.find("no", false, Matching::Prefix), None);
+    }
+
+    #[test]
+    fn pick_a_name_that_mentions_the_member() {
+        // Given
+        let index = index();
+
+        // Then
+        assert_eq!(
+            index.mention_name(
+                UserId::new(10),
+                &["Steve the Builder", "steve"],
+                Matching::Exact
+            ),
+            Some("steve")
+        );
+        assert_eq!(
+            index.mention_name(UserId::new(12), &["alex", "builder"], Matching::Exact),
+            Some("builder")
+        );
+        assert_eq!(
+            index.mention_name(UserId::new(11), &["alex#1234"], Matching::Exact),
+            Some("alex#1234")
+        );
+        assert_eq!(
+            index.mention_name(UserId::new(13), &["Markus"], Matching::Exact),
+            Some("Markus")
+        );
+        assert_eq!(
+            index.mention_name(UserId::new(99), &["steve"], Matching::Exact),
+            None
+        );
+    }
+}
@@ -1,44 +1,244 @@
-use std::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
-    Arc,
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use crate::config::RootConfig;
-use crate::listener::{split_webhook_url, Listener, LogTailer, Webserver};
+use crate::emoji;
+use crate::listener::{
+    digest, relay, split_webhook_url, Listener, LogTailer, ServerOutput, Webserver,
+};
+use crate::metrics::{Direction, Metrics};
+use crate::pause;
+use crate::per_server::PerServer;
+use crate::template;
+use crate::translate;
+use crate::transport::Transport;
+use crate::unicode;
+use crate::update;
+use crate::usage;
+
+use self::bridged::{Bridged, BridgedMessages};
+use self::echo::Echoes;
+use self::mentions::{Matching, MentionCache};
+use self::pending::PendingDeliveries;
+use self::retry::{Delivery, RetryQueue};
 
-use rcon::Connection;
-use serenity::builder::{CreateCommand, CreateInteractionResponseMessage};
-use serenity::gateway::ActivityData;
+use serde_json::json;
+use serenity::all::{
+    Guild, GuildChannel, GuildMemberUpdateEvent, GuildMembersChunkEvent, Member,
+    MessageUpdateEvent, Role, RoleId, ShardStageUpdateEvent, User,
+};
+use serenity::builder::CreateInteractionResponseMessage;
+use serenity::gateway::{ActivityData, ConnectionStage};
 use serenity::utils::parse_channel_mention;
 use serenity::{
-    all::{ChannelId, Interaction},
+    all::{ChannelId, Interaction, MessageId},
     builder::CreateInteractionResponse,
 };
 use serenity::{
     async_trait,
-    model::{channel::Message, gateway::Ready, id::GuildId},
+    model::{channel::Message, gateway::Ready, id::GuildId, voice::VoiceState},
     prelude::*,
 };
 use thiserror::Error;
+use tokio::time::sleep;
 use tracing::{debug, error, info};
 
+pub mod banner;
+pub mod bridged;
 mod commands;
+pub mod cooldown;
+pub mod deletion;
+pub mod echo;
 mod markdown;
+pub mod mentions;
+mod pending;
+pub mod preflight;
+pub mod retry;
+mod sounds;
+mod voice;
 
-const MAX_LINE_LENGTH: usize = 100;
+use self::commands::prefix::PrefixCommand;
+
+/// One of the extra servers from `[[servers]]`, with its own channel.
+pub struct BridgedServer {
+    pub name: String,
+    pub webserver_token: String,
+    pub config_lock: Arc<RwLock<RootConfig>>,
+}
 
 pub struct Handler {
     config_lock: Arc<RwLock<RootConfig>>,
+    servers: Vec<BridgedServer>,
+    bridged: BridgedMessages,
+    discord_down: AtomicBool,
     guild_id: AtomicU64,
+    echoes: Arc<PerServer<Echoes>>,
     is_watching: AtomicBool,
+    mentions: Arc<MentionCache>,
+    metrics: Arc<PerServer<Metrics>>,
+    pending: PendingDeliveries,
+    retries: Arc<RetryQueue>,
 }
 
 impl Handler {
-    pub fn new(config_lock: Arc<RwLock<RootConfig>>) -> Self {
+    pub fn new(
+        config_lock: Arc<RwLock<RootConfig>>,
+        servers: Vec<BridgedServer>,
+        metrics: Arc<PerServer<Metrics>>,
+        echoes: Arc<PerServer<Echoes>>,
+        mentions: Arc<MentionCache>,
+        bridged: BridgedMessages,
+        retries: RetryQueue,
+    ) -> Self {
         Self {
             config_lock,
+            servers,
+            bridged,
+            discord_down: AtomicBool::new(false),
+            echoes,
             guild_id: AtomicU64::new(0),
             is_watching: AtomicBool::new(false),
+            mentions,
+            metrics,
+            pending: PendingDeliveries::default(),
+            retries: Arc::new(retries),
+        }
+    }
+
+    /// The config of the server bridged with a channel. Channels that
+    /// aren't one of the extra servers' belong to the main server.
+    async fn config_for(&self, channel_id: ChannelId) -> Arc<RwLock<RootConfig>> {
+        for server in &self.servers {
+            if server.config_lock.read().await.get_channel_id() == channel_id.get() {
+                return server.config_lock.clone();
+            }
+        }
+
+        self.config_lock.clone()
+    }
+
+    /// The name to fill in as `%mention%` when players click the author's
+    /// name in game. It's one a mention from Minecraft finds the author
+    /// by, so replying pings them. Empty if no mention from Minecraft
+    /// would find them, so templates can leave the click event out.
+    fn click_mention(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        shown: &str,
+        matching: Matching,
+    ) -> String {
+        let tag = msg.author.tag();
+        let index = msg
+            .guild_id
+            .and_then(|guild_id| self.mentions.get(&ctx.cache, guild_id));
+
+        index
+            .and_then(|index| {
+                index
+                    .mention_name(msg.author.id, &[shown, &msg.author.name, &tag], matching)
+                    .map(str::to_string)
+            })
+            .unwrap_or_default()
+    }
+
+    /// Check how we can post to the channels of every server.
+    async fn check_all_channels(&self, ctx: &Context) {
+        preflight::check_all(ctx, &*self.config_lock.read().await).await;
+        for server in &self.servers {
+            preflight::check_all(ctx, &*server.config_lock.read().await).await;
+        }
+    }
+
+    /// Turn an edited message into tellraw lines for Minecraft. Unlike
+    /// new messages, edits aren't translated or given reply context.
+    async fn edited_lines(&self, ctx: &Context, config: &RootConfig, msg: &Message) -> Vec<String> {
+        let mut content = sanitize_message(ctx, msg).await;
+        if config.normalize_unicode() {
+            content = unicode::normalize(&content);
+        }
+        if config.ascii_emoji_enabled() {
+            content = emoji::to_ascii(&content);
+        }
+        if config.transliterate_enabled() {
+            content = unicode::transliterate(&content, &config.get_transliterate_exceptions());
+        }
+        let content = escape_text(&content);
+
+        let marked = content
+            .split('\n')
+            .map(|line| markdown::to_minecraft_format(&markdown::parse(line)))
+            .collect();
+        let lines = truncate_lines(marked, config.get_max_line_length());
+        apply_line_template(
+            &config.get_message_template_for(msg.channel_id.get()),
+            lines,
+            "",
+        )
+    }
+
+    /// Tell players that a message they were shown was deleted, if it
+    /// was bridged recently.
+    async fn retract(&self, ctx: &Context, channel_id: ChannelId, id: MessageId) {
+        let Some(bridged) = self.bridged.forget(id) else {
+            return;
+        };
+
+        let config_lock = self.config_for(channel_id).await;
+        let config = config_lock.read().await;
+        let template = config.get_deleted_template();
+        if template.is_empty() || pause::is_paused(ctx).await {
+            return;
+        }
+
+        debug!("event_handler:message_delete: retracting a bridged message");
+        let command = build_tellraw_command(
+            bridged.name,
+            &bridged.mention,
+            &config.get_username_template(),
+            bridged.prefix.as_ref(),
+            &template,
+            &config.get_name_replacements(),
+        );
+        if let Err(e) = send_to_minecraft(command, Transport::from_config(&config)).await {
+            error!("Error sending a message deletion to Minecraft: {}", e);
+            self.metrics
+                .get(config.get_channel_id())
+                .record_rcon_error(&e.to_string());
+        }
+    }
+
+    /// Play sounds in game for players mentioned in a message, or for
+    /// everyone if the message was sent by an announcer.
+    async fn play_sound_cues(&self, config: &RootConfig, msg: &Message) {
+        let announcement_roles = config.get_announcement_roles();
+        let is_announcement = msg.member.as_ref().is_some_and(|member| {
+            member
+                .roles
+                .iter()
+                .any(|role| announcement_roles.contains(&role.get()))
+        });
+
+        let commands = sounds::build_sound_commands(
+            &msg.content,
+            is_announcement,
+            &config.get_mention_sound(),
+            &config.get_announcement_sound(),
+        );
+
+        for command in commands {
+            if let Err(e) = send_to_minecraft(command, Transport::from_config(config)).await {
+                error!("Error sending a sound cue to Minecraft: {}", e);
+                self.metrics
+                    .get(config.get_channel_id())
+                    .record_rcon_error(&e.to_string());
+            }
         }
     }
 }
@@ -47,18 +247,13 @@ impl Handler {
 impl EventHandler for Handler {
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
         if let Interaction::Command(command) = interaction {
-            match command.data.name.as_str() {
-                "help" => {
-                    if let Err(e) = commands::minecraft::help(ctx, command).await {
-                        error!("Error performing 'help' command: {e}");
+            match commands::find(&command.data.name) {
+                Some(handler) => {
+                    if let Err(e) = commands::run(handler, ctx, command).await {
+                        error!("Error performing '{}' command: {e}", handler.name());
                     }
                 }
-                "list" => {
-                    if let Err(e) = commands::minecraft::list(ctx, command).await {
-                        error!("Error performing 'list' command: {e}");
-                    }
-                }
-                _ => {
+                None => {
                     let response =
                         CreateInteractionResponseMessage::new().content("Unknown command");
                     if let Err(e) = command
@@ -69,14 +264,36 @@ impl EventHandler for Handler {
                     }
                 }
             };
+        } else if let Interaction::Component(component) = interaction {
+            if let Err(e) = commands::handle_component(ctx, component).await {
+                error!("Error handling a button press: {e}");
+            }
         }
     }
 
     async fn message(&self, ctx: Context, msg: Message) {
-        let configured_id = self.config_lock.read().await.get_channel_id();
+        // Chat in another server's channel goes to that server
+        let config_lock = self.config_for(msg.channel_id).await;
+
+        let (configured_id, main_source, webhook_url, mirrors) = {
+            let config = config_lock.read().await;
+            (
+                config.get_channel_id(),
+                (config.get_guild_tag(), config.get_discord_language()),
+                config.webhook_url(),
+                config.get_mirrors(),
+            )
+        };
 
-        // Ignore messages that aren't from the configured channel
-        if msg.channel_id.get() != configured_id {
+        // Ignore messages that aren't from the configured channel or a mirror
+        let Some((tag, language)) =
+            source_tag(msg.channel_id.get(), configured_id, main_source, &mirrors)
+        else {
+            return;
+        };
+
+        if pause::is_paused(&ctx).await {
+            debug!("event_handler:message: bridging is paused, skipping the message");
             return;
         }
 
@@ -84,17 +301,127 @@ impl EventHandler for Handler {
         let bot = ctx.cache.current_user().clone();
 
         // Ignore messages that are from ourselves
-        let webhook_url = self.config_lock.read().await.webhook_url();
-        let webhook_id = split_webhook_url(&webhook_url).unwrap_or_default().0;
-        if msg.author.id == bot.id
-            || (msg.webhook_id.is_some() && msg.webhook_id.unwrap() == webhook_id)
-        {
+        let is_our_webhook = msg.webhook_id.is_some_and(|id| {
+            std::iter::once(webhook_url.as_str())
+                .chain(mirrors.iter().map(|(_, _, url, _)| url.as_str()))
+                .filter_map(split_webhook_url)
+                .any(|(webhook_id, _)| webhook_id == id.get())
+        });
+        if msg.author.id == bot.id || is_our_webhook {
             debug!("event_handler:message: skipping message from ourselves or our webhook");
             return;
         }
 
+        // Other bridges could send our own messages back to us
+        let ignored_bots = config_lock.read().await.get_ignored_bot_ids();
+        if ignored_bots.contains(&msg.author.id.get()) {
+            debug!("event_handler:message: skipping message from an ignored bot");
+            return;
+        }
+
+        if (msg.author.bot || msg.webhook_id.is_some())
+            && self
+                .echoes
+                .get(configured_id)
+                .to_discord
+                .is_echo(&msg.content)
+        {
+            debug!("event_handler:message: skipping a bot echoing a bridged message");
+            return;
+        }
+
+        // Handle old-style prefix commands instead of sending them to Minecraft
+        let prefix = config_lock.read().await.get_command_prefix();
+        if let Some(command) = commands::prefix::parse_command(&prefix, &msg.content) {
+            let result = match command {
+                PrefixCommand::Help => commands::minecraft::help_message(&ctx, &msg).await,
+                PrefixCommand::List => commands::minecraft::list_message(&ctx, &msg).await,
+            };
+
+            if let Err(e) = result {
+                error!("Error performing '{:?}' prefix command: {e}", command);
+            }
+
+            return;
+        }
+
+        // Give people a moment to delete mistakes before players see them
+        let delay = Duration::from_secs(config_lock.read().await.get_send_delay_secs());
+        if !delay.is_zero() {
+            self.pending.hold(msg.id);
+            sleep(delay).await;
+            if !self.pending.release(msg.id) {
+                return;
+            }
+        }
+
         debug!("event_handler:message: received a message from Discord");
-        let content = sanitize_message(&ctx, &msg).await;
+        let received = Instant::now();
+        let mut content = sanitize_message(&ctx, &msg).await;
+        let normalize = config_lock.read().await.normalize_unicode();
+        if normalize {
+            content = unicode::normalize(&content);
+        }
+
+        // Minecraft shows emoji as boxes
+        if config_lock.read().await.ascii_emoji_enabled() {
+            content = emoji::to_ascii(&content);
+        }
+
+        // Older clients show characters they don't have as `?`
+        let transliterate = {
+            let config = config_lock.read().await;
+            config
+                .transliterate_enabled()
+                .then(|| config.get_transliterate_exceptions())
+        };
+        if let Some(keep) = &transliterate {
+            content = unicode::transliterate(&content, keep);
+        }
+        let plain = content.clone();
+        content = escape_text(&content);
+
+        // Machine translate it for players. The original goes in the
+        // message template's `%original%`, or after the translation if
+        // the template doesn't use it.
+        let template = config_lock
+            .read()
+            .await
+            .get_message_template_for(msg.channel_id.get());
+        let mut original = String::new();
+        {
+            // Don't hold the lock while waiting on the translation API
+            let config = config_lock.read().await.clone();
+            let target = config.get_minecraft_language();
+            if let Some(translated) =
+                translate::translate(&config, &plain, &language, &target).await
+            {
+                let translated = escape_text(&translated);
+                if template.contains("%original%") {
+                    original = content.replace('\n', " ");
+                    content = translated;
+                } else {
+                    content = translate::with_original(&translated, &content);
+                }
+            }
+        }
+
+        // Show players what a reply is replying to
+        let reply_template = config_lock.read().await.get_reply_template();
+        if let Some(replied) = msg
+            .referenced_message
+            .as_ref()
+            .filter(|_| !reply_template.is_empty())
+        {
+            let username = choose_name(replied.author.global_name.as_deref(), &replied.author.name);
+            let max_length = config_lock.read().await.get_max_line_length() / 2;
+            let mut context =
+                reply_context(&reply_template, &username, &replied.content, max_length);
+            if let Some(keep) = &transliterate {
+                context = unicode::transliterate(&context, keep);
+            }
+            content = format!("{} {}", escape_text(&context), content);
+        }
 
         // Send a separate message for each line
         let lines = content.split('\n');
@@ -107,49 +434,373 @@ impl EventHandler for Handler {
             marked.push(markdown::to_minecraft_format(&blocks));
         });
 
-        let lines = truncate_lines(marked);
-        let mut lines =
-            apply_line_template(self.config_lock.read().await.get_message_template(), lines);
+        let line_count = marked.len();
+        let max_line_length = config_lock.read().await.get_max_line_length();
+        let lines = truncate_lines(marked, max_line_length);
+        self.metrics.get(configured_id).record_size(
+            Direction::ToMinecraft,
+            content.chars().count(),
+            lines.len() > line_count,
+        );
+        let mut lines = apply_line_template(&template, lines, &original);
 
         // Add attachement message if an attachment is present
-        if !msg.attachments.is_empty() {
-            let line = self.config_lock.read().await.get_attachment_template();
-            let line = line.replace("%num%", &msg.attachments.len().to_string());
-            let line = line.replace("%url%", &msg.attachments.first().unwrap().url);
+        if let Some(attachment) = msg.attachments.first() {
+            let line = config_lock.read().await.get_attachment_template();
+            let line = template::render(
+                &line,
+                &[
+                    ("num", &msg.attachments.len().to_string()),
+                    ("url", &attachment.url),
+                ],
+            );
             lines.push(line);
         }
 
         // Get the name to use for these messages
-        let name = if self.config_lock.read().await.use_member_nicks() {
-            msg.author
-                .nick_in(&ctx, msg.guild_id.unwrap())
-                .await
-                .unwrap_or_else(|| msg.author.name.clone())
+        let use_nicks = config_lock.read().await.use_member_nicks();
+        let name = author_name(&ctx, &msg, use_nicks).await;
+        let matching = Matching::from_config(&config_lock.read().await.get_mention_matching());
+        let mention = self.click_mention(&ctx, &msg, &name, matching);
+        let name = if normalize {
+            unicode::normalize(&name)
         } else {
-            msg.author.name.clone()
+            name
+        };
+        let name = match &transliterate {
+            Some(keep) => unicode::transliterate(&name, keep),
+            None => name,
+        };
+        let name = match tag.as_str() {
+            "" => name,
+            tag => format!("[{}] {}", tag, name),
         };
 
+        for line in msg.content.lines() {
+            self.echoes
+                .get(configured_id)
+                .to_minecraft
+                .remember(&name, line);
+        }
+
+        // Listeners that talk to plugins get the plain text, without
+        // tellraw escapes or reply context
+        for line in plain.lines() {
+            relay::publish(&name, line);
+        }
+
+        // Show the author's top role in front of their name
+        let prefix = role_prefix(&ctx, &msg, &config_lock.read().await.get_role_prefixes());
+
         // Send each line to Minecraft
+        let mut delivered = true;
         for line in lines {
             let command = build_tellraw_command(
                 name.clone(),
-                &msg.author.tag(),
-                &self.config_lock.read().await.get_username_template(),
+                &mention,
+                &config_lock.read().await.get_username_template(),
+                prefix.as_ref(),
                 &line,
+                &config_lock.read().await.get_name_replacements(),
             );
 
-            if let Err(e) = send_to_minecraft(
-                command,
-                self.config_lock.read().await.get_rcon_addr(),
-                self.config_lock.read().await.get_rcon_password(),
-            )
-            .await
-            {
-                error!("Error sending a chat message to Minecraft: {}", e);
+            let transport = Transport::from_config(&*config_lock.read().await);
+            match self.retries.send(command, transport).await {
+                Ok(Delivery::Sent) => {}
+                Ok(Delivery::Queued(e)) => {
+                    if let Some(e) = e {
+                        self.metrics
+                            .get(configured_id)
+                            .record_rcon_error(&e.to_string());
+                    }
+                    delivered = false;
+                }
+                Err(e) => {
+                    error!("Error sending a chat message to Minecraft: {}", e);
+                    self.metrics
+                        .get(configured_id)
+                        .record_rcon_error(&e.to_string());
+                    delivered = false;
+                }
+            }
+        }
+
+        if delivered {
+            self.metrics
+                .get(configured_id)
+                .record_delivery(Direction::ToMinecraft, received.elapsed());
+        }
+
+        // Remember how it was shown, in case it's edited or deleted
+        self.bridged.remember(Bridged {
+            message: msg.clone(),
+            name,
+            mention,
+            prefix,
+        });
+
+        if config_lock.read().await.sound_cues_enabled() {
+            self.play_sound_cues(&*config_lock.read().await, &msg).await;
+        }
+    }
+
+    async fn message_update(
+        &self,
+        ctx: Context,
+        _old_if_available: Option<Message>,
+        _new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        // Embeds loading in also counts as an update, so only look at
+        // changes to the text
+        let Some(content) = &event.content else {
+            return;
+        };
+        let Some(mut bridged) = self.bridged.get(event.id) else {
+            return;
+        };
+        if *content == bridged.message.content {
+            return;
+        }
+
+        event.apply_to_message(&mut bridged.message);
+        self.bridged.update(bridged.clone());
+
+        let config_lock = self.config_for(event.channel_id).await;
+        let config = config_lock.read().await;
+        let template = config.get_edited_template();
+        if template.is_empty() || pause::is_paused(&ctx).await {
+            return;
+        }
+
+        debug!("event_handler:message_update: sending an edited message");
+        for line in bridged.message.content.lines() {
+            self.echoes
+                .get(config.get_channel_id())
+                .to_minecraft
+                .remember(&bridged.name, line);
+        }
+
+        let lines = self.edited_lines(&ctx, &config, &bridged.message).await;
+        for (i, line) in lines.into_iter().enumerate() {
+            // Only the first line is marked as edited
+            let line = match i {
+                0 => format!("{}, {}", template, line),
+                _ => line,
+            };
+            let command = build_tellraw_command(
+                bridged.name.clone(),
+                &bridged.mention,
+                &config.get_username_template(),
+                bridged.prefix.as_ref(),
+                &line,
+                &config.get_name_replacements(),
+            );
+
+            if let Err(e) = send_to_minecraft(command, Transport::from_config(&config)).await {
+                error!("Error sending an edited message to Minecraft: {}", e);
+                self.metrics
+                    .get(config.get_channel_id())
+                    .record_rcon_error(&e.to_string());
             }
         }
     }
 
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        _guild_id: Option<GuildId>,
+    ) {
+        self.pending.cancel(deleted_message_id);
+        self.retract(&ctx, channel_id, deleted_message_id).await;
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+        _guild_id: Option<GuildId>,
+    ) {
+        for id in multiple_deleted_messages_ids {
+            self.pending.cancel(id);
+            self.retract(&ctx, channel_id, id).await;
+        }
+    }
+
+    async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+        let config = self.config_lock.read().await;
+
+        let voice_channel = config.get_voice_channel_id();
+        if voice_channel == 0 {
+            return;
+        }
+
+        // Only care about people moving in or out of the watched channel
+        let was_in = old
+            .as_ref()
+            .and_then(|state| state.channel_id)
+            .is_some_and(|id| id.get() == voice_channel);
+        let is_in = new.channel_id.is_some_and(|id| id.get() == voice_channel);
+        if was_in == is_in {
+            return;
+        }
+
+        let count = match new.guild_id.and_then(|id| ctx.cache.guild(id)) {
+            Some(guild) => guild
+                .voice_states
+                .values()
+                .filter(|state| state.channel_id.is_some_and(|id| id.get() == voice_channel))
+                .count(),
+            None => return,
+        };
+
+        let name = match &new.member {
+            Some(member) => member.display_name().to_string(),
+            None => String::from("Someone"),
+        };
+
+        let message = voice::status_message(&name, is_in, count);
+        let display = voice::Display::from_config(&config.get_voice_display());
+        let command = voice::build_command(display, &message);
+
+        if let Err(e) = send_to_minecraft(command, Transport::from_config(&config)).await {
+            error!("Error sending a voice status update to Minecraft: {}", e);
+            self.metrics
+                .get(config.get_channel_id())
+                .record_rcon_error(&e.to_string());
+        }
+    }
+
+    // Names used for mentions from Minecraft could have changed, so the
+    // mention index has to be built again
+    async fn guild_create(&self, _ctx: Context, _guild: Guild, _is_new: Option<bool>) {
+        self.mentions.invalidate();
+    }
+
+    async fn guild_member_addition(&self, _ctx: Context, _new_member: Member) {
+        self.mentions.invalidate();
+    }
+
+    async fn guild_member_removal(
+        &self,
+        _ctx: Context,
+        _guild_id: GuildId,
+        _user: User,
+        _member_data_if_available: Option<Member>,
+    ) {
+        self.mentions.invalidate();
+    }
+
+    async fn guild_member_update(
+        &self,
+        ctx: Context,
+        _old_if_available: Option<Member>,
+        _new: Option<Member>,
+        event: GuildMemberUpdateEvent,
+    ) {
+        self.mentions.invalidate();
+
+        // Our roles could have changed what we're allowed to do
+        if event.user.id == ctx.cache.current_user().id {
+            self.check_all_channels(&ctx).await;
+        }
+    }
+
+    async fn guild_members_chunk(&self, _ctx: Context, _chunk: GuildMembersChunkEvent) {
+        self.mentions.invalidate();
+    }
+
+    async fn guild_role_create(&self, _ctx: Context, _new: Role) {
+        self.mentions.invalidate();
+    }
+
+    async fn guild_role_update(&self, ctx: Context, _old: Option<Role>, _new: Role) {
+        self.mentions.invalidate();
+        self.check_all_channels(&ctx).await;
+    }
+
+    async fn guild_role_delete(
+        &self,
+        _ctx: Context,
+        _guild_id: GuildId,
+        _removed_role_id: RoleId,
+        _removed_role_data_if_available: Option<Role>,
+    ) {
+        self.mentions.invalidate();
+    }
+
+    async fn channel_create(&self, _ctx: Context, _channel: GuildChannel) {
+        self.mentions.invalidate();
+    }
+
+    async fn channel_update(&self, ctx: Context, _old: Option<GuildChannel>, new: GuildChannel) {
+        self.mentions.invalidate();
+
+        // Permission overwrites on one of our channels could have changed
+        let ours = {
+            let config = self.config_lock.read().await;
+            new.id.get() == config.get_channel_id()
+                || new.id.get() == config.get_advancement_channel_id()
+                || new.id.get() == config.get_death_channel_id()
+                || config
+                    .get_mirrors()
+                    .iter()
+                    .any(|(_, channel, _, _)| *channel == new.id.get())
+        } || !Arc::ptr_eq(&self.config_for(new.id).await, &self.config_lock);
+        if ours {
+            self.check_all_channels(&ctx).await;
+        }
+    }
+
+    async fn channel_delete(
+        &self,
+        _ctx: Context,
+        _channel: GuildChannel,
+        _messages: Option<Vec<Message>>,
+    ) {
+        self.mentions.invalidate();
+    }
+
+    ///
+    /// Let players know when messages can't reach Discord because the
+    /// gateway connection dropped, and again once it's back.
+    ///
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        let down = match (event.old, event.new) {
+            (ConnectionStage::Connected, new) if new != ConnectionStage::Connected => true,
+            (_, ConnectionStage::Connected) => false,
+            _ => return,
+        };
+
+        // Only send each notice once, no matter how many stages we go through
+        if self.discord_down.swap(down, Ordering::Relaxed) == down {
+            return;
+        }
+
+        if down {
+            info!("Lost connection to Discord");
+        } else {
+            info!("Reconnected to Discord");
+        }
+
+        let config = self.config_lock.read().await;
+        if !config.outage_notices_enabled() {
+            return;
+        }
+
+        if let Err(e) =
+            send_to_minecraft(build_outage_notice(down), Transport::from_config(&config)).await
+        {
+            error!("Error sending a Discord outage notice to Minecraft: {}", e);
+            self.metrics
+                .get(config.get_channel_id())
+                .record_rcon_error(&e.to_string());
+        }
+    }
+
     async fn ready(&self, ctx: Context, _ready: Ready) {
         info!("Connected to Discord");
         let activity_data = ActivityData::playing("Type /help for command list");
@@ -167,8 +818,19 @@ impl EventHandler for Handler {
         let ctx = Arc::new(ctx);
         let config_lock = Arc::clone(&self.config_lock);
 
+        // With mirrors we're in more than one guild, so use the one with
+        // the bridge channel
         if self.guild_id.load(Ordering::Relaxed) == 0 {
-            self.guild_id.store(guilds[0].get(), Ordering::Relaxed);
+            let channel_id = ChannelId::new(config_lock.read().await.get_channel_id());
+            let guild_id = guilds
+                .iter()
+                .find(|id| {
+                    ctx.cache
+                        .guild(**id)
+                        .is_some_and(|guild| guild.channels.contains_key(&channel_id))
+                })
+                .unwrap_or(&guilds[0]);
+            self.guild_id.store(guild_id.get(), Ordering::Relaxed);
         }
 
         let guild_id = self.guild_id.load(Ordering::Relaxed);
@@ -176,25 +838,122 @@ impl EventHandler for Handler {
         let log_path = config_lock.read().await.get_log_path();
 
         // Setup command interactions
-        let commands = vec![
-            CreateCommand::new("help").description("Show the help page"),
-            CreateCommand::new("list")
-                .description("List the current players on the Minecraft server"),
-        ];
+        let commands = commands::create_commands(&*config_lock.read().await);
         match guild_id.set_commands(&ctx.http, commands).await {
             Ok(_) => info!("Command interactions registered"),
             Err(e) => error!("Error registering commands: {}", e),
         };
 
+        // Find out how we can post before anything needs posting
+        self.check_all_channels(&ctx).await;
+
         // Only do stuff if we're not already running
         let loaded = self.is_watching.load(Ordering::Relaxed);
         if !loaded {
+            if config_lock.read().await.startup_banner_enabled() {
+                banner::post(&ctx).await;
+            }
+
+            for config in std::iter::once(&config_lock)
+                .chain(self.servers.iter().map(|server| &server.config_lock))
+            {
+                let config = config.read().await;
+                banner::announce(&ctx.http, &config, &config.get_online_notice()).await;
+            }
+
+            for config in std::iter::once(&config_lock)
+                .chain(self.servers.iter().map(|server| &server.config_lock))
+            {
+                let config = config.read().await;
+                if config.digest_enabled() {
+                    let channel = ChannelId::new(config.get_channel_id());
+                    let period = Duration::from_secs(config.get_digest_interval_mins() * 60);
+                    tokio::spawn(digest::post_periodically(ctx.clone(), channel, period));
+                }
+            }
+
+            if config_lock.read().await.usage_enabled() {
+                tokio::spawn(usage::poll_periodically(ctx.clone()));
+            }
+
+            if config_lock.read().await.update_check_enabled() {
+                let ctx = ctx.clone();
+                tokio::spawn(async move { update::check(&ctx).await });
+            }
+
+            // Each extra server with a log file gets a tailer of its own
+            for server in &self.servers {
+                let log_path = server.config_lock.read().await.get_log_path();
+                if log_path.is_empty() {
+                    continue;
+                }
+
+                info!("Bridging '{}' from its log file", server.name);
+                let log_tailer = LogTailer::new(log_path);
+                let (ctx, config_lock, guild_id) =
+                    (ctx.clone(), server.config_lock.clone(), guild_id.clone());
+                tokio::spawn(async move { log_tailer.listen(ctx, config_lock, guild_id).await });
+            }
+
+            // The rest send to the webserver, and are told apart by token
+            let tokens: HashMap<String, Arc<RwLock<RootConfig>>> = self
+                .servers
+                .iter()
+                .filter(|server| !server.webserver_token.is_empty())
+                .map(|server| (server.webserver_token.clone(), server.config_lock.clone()))
+                .collect();
+
             // Create our listener and start waiting for messages
             let enable_webserver = config_lock.read().await.enable_webserver();
-            if enable_webserver {
+            if !tokens.is_empty() && !enable_webserver {
+                error!("Some servers have a webserver_token, but the webserver isn't enabled, so nothing will get their messages");
+            }
+            if let Some(server) = crate::server::managed() {
+                tokio::spawn(async move {
+                    let listener = ServerOutput::new(server);
+                    listener
+                        .listen(ctx.clone(), config_lock.clone(), guild_id.clone())
+                        .await;
+                });
+            } else if config_lock.read().await.enable_grpc() {
+                let port = config_lock.read().await.get_grpc_port();
+                #[cfg(feature = "grpc")]
+                {
+                    tokio::spawn(async move {
+                        let listener = crate::listener::grpc::GrpcListener::new(port);
+                        listener
+                            .listen(ctx.clone(), config_lock.clone(), guild_id.clone())
+                            .await;
+                    });
+                }
+                #[cfg(not(feature = "grpc"))]
+                error!(
+                    "grpc_config is enabled, but Dolphin was built without the grpc feature, so nothing is listening on port {}",
+                    port
+                );
+            } else if config_lock.read().await.enable_mqtt() {
+                #[cfg(feature = "mqtt")]
+                {
+                    let listener = crate::listener::mqtt::MqttListener::from_config(
+                        &*config_lock.read().await,
+                    );
+                    match listener {
+                        Some(listener) => {
+                            tokio::spawn(async move {
+                                listener
+                                    .listen(ctx.clone(), config_lock.clone(), guild_id.clone())
+                                    .await;
+                            });
+                        }
+                        None => error!("mqtt_config.broker_url isn't a valid MQTT broker URL"),
+                    }
+                }
+                #[cfg(not(feature = "mqtt"))]
+                error!("mqtt_config is enabled, but Dolphin was built without the mqtt feature");
+            } else if enable_webserver {
                 let port = config_lock.read().await.get_webserver_port();
                 tokio::spawn(async move {
-                    let listener = Webserver::new(port);
+                    let listener = Webserver::new(port).with_servers(tokens);
                     listener
                         .listen(ctx.clone(), config_lock.clone(), guild_id.clone())
                         .await;
@@ -213,15 +972,116 @@ impl EventHandler for Handler {
     }
 }
 
+///
+/// Get the name to show in Minecraft for the author of a message. With
+/// `use_nicks`, the member's nickname is used if they have one. Messages
+/// without a guild, or members without a nickname, fall back to the
+/// username.
+///
+async fn author_name(ctx: &Context, msg: &Message, use_nicks: bool) -> String {
+    let nick = match (use_nicks, &msg.member, msg.guild_id) {
+        (false, _, _) => None,
+        // The gateway usually sends the member along with the message
+        (true, Some(member), _) => member.nick.clone(),
+        (true, None, Some(guild_id)) => msg.author.nick_in(ctx, guild_id).await,
+        (true, None, None) => None,
+    };
+
+    choose_name(nick.as_deref(), &msg.author.name)
+}
+
+///
+/// Get the tag to put in front of names and the language for messages
+/// from the given channel, or `None` if messages from it aren't
+/// bridged. The bridge channel uses `main`, and mirrors use their name
+/// and language.
+///
+fn source_tag(
+    channel_id: u64,
+    configured_id: u64,
+    main: (String, String),
+    mirrors: &[(String, u64, String, String)],
+) -> Option<(String, String)> {
+    if channel_id == configured_id {
+        return Some(main);
+    }
+
+    mirrors
+        .iter()
+        .find(|(_, mirror_id, _, _)| *mirror_id == channel_id)
+        .map(|(tag, _, _, language)| (tag.clone(), language.clone()))
+}
+
+///
+/// Get the prefix to show in game for the author of a message, as
+/// `(label, color)`, from their roles in the guild.
+///
+fn role_prefix(
+    ctx: &Context,
+    msg: &Message,
+    prefixes: &HashMap<u64, (String, String)>,
+) -> Option<(String, String)> {
+    if prefixes.is_empty() {
+        return None;
+    }
+
+    let member = msg.member.as_ref()?;
+    let guild = ctx.cache.guild(msg.guild_id?)?;
+    let roles: Vec<_> = member
+        .roles
+        .iter()
+        .filter_map(|id| guild.roles.get(id))
+        .map(|role| (role.id.get(), role.position, role.name.clone()))
+        .collect();
+
+    top_role_prefix(&roles, prefixes)
+}
+
+///
+/// Pick the prefix of the highest of the given roles, as `(id,
+/// position, name)`, that has one. Prefixes without a label use the
+/// role's name.
+///
+fn top_role_prefix(
+    roles: &[(u64, u16, String)],
+    prefixes: &HashMap<u64, (String, String)>,
+) -> Option<(String, String)> {
+    let (_, name, (label, color)) = roles
+        .iter()
+        .filter_map(|(id, position, name)| Some((position, name, prefixes.get(id)?)))
+        .max_by_key(|(position, _, _)| **position)?;
+
+    let label = match label.as_str() {
+        "" => name.clone(),
+        label => label.to_string(),
+    };
+    let color = match color.as_str() {
+        "" => String::from("white"),
+        color => color.to_string(),
+    };
+
+    Some((label, color))
+}
+
+///
+/// Use the nickname if there is a usable one, or the username if not.
+///
+fn choose_name(nick: Option<&str>, username: &str) -> String {
+    match nick.map(str::trim) {
+        Some(nick) if !nick.is_empty() => nick.to_string(),
+        _ => username.to_string(),
+    }
+}
+
 ///
 /// Put each given line into a JSON structure to be passed to the
 /// Minecraft tellraw command.
 ///
-fn apply_line_template(template: String, lines: Vec<String>) -> Vec<String> {
+fn apply_line_template(template: &str, lines: Vec<String>, original: &str) -> Vec<String> {
     let mut formatted_lines: Vec<String> = Vec::new();
 
     for line in lines {
-        let formatted = template.replace("%content%", line.as_str());
+        let formatted = template::render(template, &[("content", &line), ("original", original)]);
         formatted_lines.push(formatted);
     }
 
@@ -237,18 +1097,86 @@ fn build_tellraw_command(
     name: String,
     mention: &str,
     username_template: &str,
+    prefix: Option<&(String, String)>,
     content: &str,
+    replacements: &HashMap<String, String>,
 ) -> String {
-    let command = format!("tellraw @a [{}, {}]", username_template, content);
+    // Fill in our placeholders. Only the template is filled in, so
+    // placeholders typed in Discord are left alone. `%mention%` stays
+    // empty when there's no one to mention, for `%if mention%`.
+    let mention = match mention {
+        "" => String::new(),
+        mention => format!("@{}", sanitize_name(mention, replacements)),
+    };
+    let username = template::render(
+        username_template,
+        &[
+            ("username", &sanitize_name(&name, replacements)),
+            ("mention", &mention),
+        ],
+    );
+
+    match prefix {
+        // The first part's style carries over to the rest, so the
+        // prefix's color has to come after an empty part
+        Some((label, color)) => {
+            let prefix = json!({ "text": format!("[{}] ", label), "color": color });
+            format!("tellraw @a [\"\", {}, {}, {}]", prefix, username, content)
+        }
+        None => format!("tellraw @a [{}, {}]", username, content),
+    }
+}
+
+///
+/// Build the tellraw command telling players that Discord bridging is
+/// down, or that it's working again.
+///
+fn build_outage_notice(down: bool) -> String {
+    let (text, color) = if down {
+        (
+            "[Discord] Bridging is temporarily down, messages won't reach Discord",
+            "yellow",
+        )
+    } else {
+        ("[Discord] Bridging is back up", "green")
+    };
 
-    // Fill in our placeholders
-    let command = command.replace("%username%", &name);
-    command.replace("%mention%", format!("@{}", mention).as_str())
+    format!("tellraw @a {}", json!({ "text": text, "color": color }))
 }
 
 ///
-/// Performs some string replacements for mentions and escapes quotes on
-/// messages that are to be sent to the Minecraft server.
+/// Make a Discord name safe to put in a tellraw JSON string. Characters
+/// in the replacement map are swapped out first. Any quotes or
+/// backslashes that are left are escaped, and formatting codes and
+/// control characters are removed.
+///
+fn sanitize_name(name: &str, replacements: &HashMap<String, String>) -> String {
+    let mut sanitized = String::with_capacity(name.len());
+
+    for c in name.chars() {
+        let mapped = match replacements.get(c.encode_utf8(&mut [0; 4]) as &str) {
+            Some(replacement) => replacement.clone(),
+            None => c.to_string(),
+        };
+
+        for c in mapped.chars() {
+            match c {
+                '"' => sanitized.push_str("\\\""),
+                '\\' => sanitized.push_str("\\\\"),
+                '§' => {}
+                c if c.is_control() => {}
+                c => sanitized.push(c),
+            }
+        }
+    }
+
+    sanitized
+}
+
+///
+/// Performs some string replacements for mentions on messages that are
+/// to be sent to the Minecraft server. The result still needs to go
+/// through [escape_text] before it's put in a tellraw command.
 ///
 async fn sanitize_message(ctx: &Context, msg: &Message) -> String {
     let content = msg.content.clone();
@@ -288,71 +1216,78 @@ async fn sanitize_message(ctx: &Context, msg: &Message) -> String {
         );
     }
 
+    emoji::custom_to_shortcodes(&sanitized)
+}
+
+///
+/// Escape text to go in a tellraw JSON string.
+///
+fn escape_text(text: &str) -> String {
     // Escape double quotes
-    sanitized = sanitized.replace("\"", "\\\"");
+    let escaped = text.replace("\"", "\\\"");
 
     // Escape escape sequences
-    sanitized.replace("\\", "\\\\")
+    escaped.replace("\\", "\\\\")
+}
+
+///
+/// Describe the message a reply is replying to with the template, with
+/// the message cut short so the whole thing is at most `max_length`
+/// characters.
+///
+fn reply_context(template: &str, username: &str, content: &str, max_length: usize) -> String {
+    let render =
+        |snippet: &str| template::render(template, &[("username", username), ("snippet", snippet)]);
+
+    let snippet = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let room = max_length.saturating_sub(render("").chars().count());
+    if snippet.chars().count() <= room {
+        return render(&snippet);
+    }
+
+    let cut: String = snippet.chars().take(room.saturating_sub(1)).collect();
+    render(&format!("{}…", cut.trim_end()))
 }
 
-/// Send a tellraw message to the Minecraft server via RCON. Content
-/// should be a valid JSON Object that the game can parse and display.
+/// Send a tellraw message to the Minecraft server using the configured
+/// transport. Content should be a valid JSON Object that the game can
+/// parse and display.
 ///
-/// If there is an error connecting to RCON or sending the message, the
-/// error will be returned.
+/// If there is an error connecting to the server or sending the
+/// message, the error will be returned.
 ///
 /// # Examples
 ///
 /// ```rust
 /// let command = "say Hello, world!";
-/// let rcon_addr = "localhost:25575";
-/// let rcon_password = "hunter2";
+/// let transport = Transport::from_config(&config);
 ///
-/// send_to_minecraft(command, rcon_addr, rcon_password).await?
+/// send_to_minecraft(command, transport).await?
 /// ```
-async fn send_to_minecraft(
+pub(crate) async fn send_to_minecraft(
     command: String,
-    rcon_addr: String,
-    rcon_password: String,
+    transport: Transport,
 ) -> Result<String, Error> {
     debug!("send_to_minecraft: {}", command);
 
-    // Create RCON connection
-    let mut conn = Connection::builder()
-        .enable_minecraft_quirks(true)
-        .connect(rcon_addr, &rcon_password)
-        .await?;
-
-    // Send the command to Minecraft
-    let resp = conn.cmd(&command).await?;
-    Ok(resp)
+    Ok(transport.send(&command).await?)
 }
 
 ///
-/// Truncates each line if it is longer than the maximum number of characters,
-/// by default 100. If a line is over the limit, it will be split at that
-/// number of chacacters, and a new line inserted into the line Vector.
+/// Truncates each line if it is longer than the maximum number of characters.
+/// If a line is over the limit, it will be split at that number of
+/// chacacters, and a new line inserted into the line Vector.
 ///
-fn truncate_lines(lines: Vec<String>) -> Vec<String> {
+fn truncate_lines(lines: Vec<String>, max_length: usize) -> Vec<String> {
     let mut truncated: Vec<String> = Vec::new();
 
-    for mut line in lines {
-        while !line.is_empty() {
-            // Push 100 characters to our Vector if the line is longer
-            // than 100 characters. If the line is less than that, push
-            // the entire line.
-            let trunk = match line.get(..MAX_LINE_LENGTH) {
-                Some(trunk) => trunk,
-                None => &line,
-            };
-
-            truncated.push(trunk.to_string());
-
-            // Shorten the line for the next iteration
-            line = match line.get(MAX_LINE_LENGTH..) {
-                Some(sub) => sub.to_string(),
-                None => String::new(),
-            };
+    for line in lines {
+        let mut chars = line.chars().peekable();
+        while chars.peek().is_some() {
+            // Push `max_length` characters to our Vector if the line is
+            // longer than that. If the line is shorter, push the rest of
+            // the line.
+            truncated.push(chars.by_ref().take(max_length).collect());
         }
     }
 
@@ -361,13 +1296,18 @@ fn truncate_lines(lines: Vec<String>) -> Vec<String> {
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("rcon error: {0}")]
-    Rcon(#[from] rcon::Error),
+    #[error("{0}")]
+    Transport(#[from] crate::transport::Error),
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::discord::truncate_lines;
+    use std::collections::HashMap;
+
+    use crate::discord::{
+        build_tellraw_command, choose_name, reply_context, sanitize_name, source_tag,
+        top_role_prefix, truncate_lines,
+    };
 
     #[test]
     fn split_long_line() {
@@ -376,12 +1316,24 @@ mod tests {
         let expected = vec!("0123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789", "0123456789");
 
         // When
-        let result = truncate_lines(input);
+        let result = truncate_lines(input, 100);
 
         // Then
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn split_by_characters() {
+        // Given
+        let input = vec![String::from("héllo wörld")];
+
+        // When
+        let result = truncate_lines(input, 5);
+
+        // Then
+        assert_eq!(result, vec!["héllo", " wörl", "d"]);
+    }
+
     #[test]
     fn no_split_line() {
         // Given
@@ -389,9 +1341,170 @@ mod tests {
         let expected = vec!("0123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789");
 
         // When
-        let result = truncate_lines(input);
+        let result = truncate_lines(input, 100);
 
         // Then
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn replace_blocked_name_characters() {
+        // Given
+        let replacements = HashMap::from([
+            (String::from("\""), String::from("'")),
+            (String::from("§"), String::new()),
+        ]);
+
+        // When
+        let result = sanitize_name("\"§4Admin\"", &replacements);
+
+        // Then
+        assert_eq!(result, "'4Admin'");
+    }
+
+    #[test]
+    fn escape_unmapped_name_characters() {
+        // Given
+        let name = "a\"b\\c§l\n";
+
+        // When
+        let result = sanitize_name(name, &HashMap::new());
+
+        // Then
+        assert_eq!(result, "a\\\"b\\\\cl");
+    }
+
+    #[test]
+    fn fall_back_to_username() {
+        assert_eq!(choose_name(Some("Stevie"), "steve"), "Stevie");
+        assert_eq!(choose_name(Some("  "), "steve"), "steve");
+        assert_eq!(choose_name(None, "steve"), "steve");
+    }
+
+    #[test]
+    fn adversarial_name_keeps_valid_json() {
+        // Given
+        let name = String::from("\"}, {\"text\":\"pwned\", \"clickEvent\":{\"action\":\"run_command\",\"value\":\"/op me\"}}, {\"text\":\"");
+
+        // When
+        let command = build_tellraw_command(
+            name,
+            "user",
+            "{\"text\": \"<%username%> \"}",
+            None,
+            "{\"text\": \"hi\"}",
+            &HashMap::new(),
+        );
+
+        // Then
+        let json = command.strip_prefix("tellraw @a ").unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), 2);
+        assert!(!parsed[0].as_object().unwrap().contains_key("clickEvent"));
+    }
+
+    #[test]
+    fn leave_out_the_mention_for_unknown_authors() {
+        // Given
+        let template = "{\"text\": \"<%username%> \"%if mention%, \"clickEvent\": {\"action\": \"suggest_command\", \"value\": \"%mention% \"}%end%}";
+        let tellraw = |mention: &str| {
+            let command = build_tellraw_command(
+                String::from("Steve"),
+                mention,
+                template,
+                None,
+                "{\"text\": \"hi\"}",
+                &HashMap::new(),
+            );
+            let json = command.strip_prefix("tellraw @a ").unwrap().to_string();
+            serde_json::from_str::<serde_json::Value>(&json).unwrap()
+        };
+
+        // When
+        let known = tellraw("steve");
+        let unknown = tellraw("");
+
+        // Then
+        assert_eq!(known[0]["clickEvent"]["value"], "@steve ");
+        assert!(!unknown[0].as_object().unwrap().contains_key("clickEvent"));
+    }
+
+    #[test]
+    fn tag_messages_from_mirrors() {
+        // Given
+        let main = || (String::from("EU"), String::from("de"));
+        let mirrors = vec![(String::from("US"), 2, String::new(), String::from("en"))];
+
+        // Then
+        assert_eq!(source_tag(1, 1, main(), &mirrors), Some(main()));
+        assert_eq!(
+            source_tag(2, 1, main(), &mirrors),
+            Some((String::from("US"), String::from("en")))
+        );
+        assert_eq!(source_tag(3, 1, main(), &mirrors), None);
+    }
+
+    #[test]
+    fn pick_the_top_role_prefix() {
+        // Given
+        let prefixes = HashMap::from([
+            (1, (String::from("Admin"), String::from("red"))),
+            (2, (String::new(), String::new())),
+        ]);
+        let roles = vec![
+            (2, 3, String::from("Helper")),
+            (1, 7, String::from("Administrators")),
+            (3, 9, String::from("Booster")),
+        ];
+
+        // Then
+        assert_eq!(
+            top_role_prefix(&roles, &prefixes),
+            Some((String::from("Admin"), String::from("red")))
+        );
+        assert_eq!(
+            top_role_prefix(&roles[..1], &prefixes),
+            Some((String::from("Helper"), String::from("white")))
+        );
+        assert_eq!(top_role_prefix(&roles[2..], &prefixes), None);
+    }
+
+    #[test]
+    fn role_prefix_keeps_its_own_color() {
+        // Given
+        let prefix = (String::from("Admin"), String::from("red"));
+
+        // When
+        let command = build_tellraw_command(
+            String::from("Steve"),
+            "steve",
+            "{\"text\": \"<%username%> \"}",
+            Some(&prefix),
+            "{\"text\": \"hi\"}",
+            &HashMap::new(),
+        );
+
+        // Then
+        let json = command.strip_prefix("tellraw @a ").unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed[0], "");
+        assert_eq!(parsed[1]["text"], "[Admin] ");
+        assert_eq!(parsed[1]["color"], "red");
+        assert_eq!(parsed[2]["text"], "<Steve> ");
+    }
+
+    #[test]
+    fn show_what_a_reply_replies_to() {
+        // Given
+        let template = "[Reply to %username%: %snippet%]";
+
+        // When
+        let short = reply_context(template, "Steve", "anyone\nhave iron?", 100);
+        let long = reply_context(template, "Steve", "does anyone have spare iron", 30);
+
+        // Then
+        assert_eq!(short, "[Reply to Steve: anyone have iron?]");
+        assert_eq!(long, "[Reply to Steve: does anyone…]");
+        assert_eq!(long.chars().count(), 30);
+    }
 }
@@ -0,0 +1,63 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use serenity::all::MessageId;
+use tracing::debug;
+
+/// Discord messages being held back before they're sent to Minecraft,
+/// so ones deleted in the meantime can be dropped.
+#[derive(Debug, Default)]
+pub struct PendingDeliveries {
+    held: Mutex<HashSet<MessageId>>,
+}
+
+impl PendingDeliveries {
+    /// Start holding back a message.
+    pub fn hold(&self, id: MessageId) {
+        self.held.lock().unwrap().insert(id);
+    }
+
+    /// Drop a held message because it was deleted.
+    pub fn cancel(&self, id: MessageId) {
+        if self.held.lock().unwrap().remove(&id) {
+            debug!("pending:cancel: message {} was deleted before sending", id);
+        }
+    }
+
+    /// Stop holding back a message. Returns `false` if it was deleted
+    /// while it was held, so it shouldn't be sent.
+    pub fn release(&self, id: MessageId) -> bool {
+        self.held.lock().unwrap().remove(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::MessageId;
+
+    use super::PendingDeliveries;
+
+    #[test]
+    fn send_messages_that_are_still_there() {
+        // Given
+        let pending = PendingDeliveries::default();
+        pending.hold(MessageId::new(1));
+
+        // Then
+        assert!(pending.release(MessageId::new(1)));
+    }
+
+    #[test]
+    fn drop_deleted_messages() {
+        // Given
+        let pending = PendingDeliveries::default();
+        pending.hold(MessageId::new(1));
+        pending.hold(MessageId::new(2));
+
+        // When
+        pending.cancel(MessageId::new(1));
+
+        // Then
+        assert!(!pending.release(MessageId::new(1)));
+        assert!(pending.release(MessageId::new(2)));
+    }
+}
@@ -0,0 +1,187 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serenity::{
+    all::{ChannelId, WebhookId},
+    prelude::{Context, RwLock, TypeMapKey},
+};
+use tracing::{error, info, warn};
+
+use crate::{config::RootConfig, listener::split_webhook_url};
+
+/// The ways Dolphin can post to a channel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Paths {
+    pub webhook: bool,
+    pub channel: bool,
+}
+
+/// Until a channel has been checked, every way is worth a try.
+impl Default for Paths {
+    fn default() -> Self {
+        Paths {
+            webhook: true,
+            channel: true,
+        }
+    }
+}
+
+/// How to post a message.
+#[derive(Debug, PartialEq)]
+pub enum Path {
+    Webhook,
+    Channel,
+    Neither,
+}
+
+impl Paths {
+    /// The best way to post, given whether a webhook is set up for the
+    /// channel. The webhook is preferred, and the bot posts as itself if
+    /// the webhook can't be used.
+    pub fn choose(&self, has_webhook: bool) -> Path {
+        match (has_webhook && self.webhook, self.channel) {
+            (true, _) => Path::Webhook,
+            (false, true) => Path::Channel,
+            (false, false) => Path::Neither,
+        }
+    }
+}
+
+/// The result of the last check of each channel we post to.
+pub struct PreflightContainer;
+
+impl TypeMapKey for PreflightContainer {
+    type Value = Arc<RwLock<HashMap<ChannelId, Paths>>>;
+}
+
+/// Decide how to post to a channel, from the last check.
+pub async fn choose(ctx: &Context, channel: ChannelId, webhook_url: &str) -> Path {
+    let paths = match ctx.data.read().await.get::<PreflightContainer>() {
+        Some(checked) => checked.read().await.get(&channel).copied(),
+        None => None,
+    };
+
+    paths.unwrap_or_default().choose(!webhook_url.is_empty())
+}
+
+/// Check whether the webhook and the bot itself can post to the main
+/// channel and every mirror, so that posting doesn't have to fail for
+/// each message to find out.
+pub async fn check_all(ctx: &Context, config: &RootConfig) {
+    let Some(checked) = ctx.data.read().await.get::<PreflightContainer>().cloned() else {
+        return;
+    };
+
+    // Advancement and death channels are always posted to as the bot
+    let targets = std::iter::once((config.get_channel_id(), config.webhook_url()))
+        .chain(
+            config
+                .get_mirrors()
+                .into_iter()
+                .map(|(_, channel, webhook, _)| (channel, webhook)),
+        )
+        .chain([
+            (config.get_advancement_channel_id(), String::new()),
+            (config.get_death_channel_id(), String::new()),
+        ]);
+
+    for (channel, webhook_url) in targets.filter(|(channel, _)| *channel != 0) {
+        let channel = ChannelId::new(channel);
+        let paths = check(ctx, channel, &webhook_url).await;
+
+        match paths.choose(!webhook_url.is_empty()) {
+            Path::Webhook => info!("preflight:check_all: posting to {} with the webhook", channel),
+            Path::Channel if !webhook_url.is_empty() => warn!(
+                "preflight:check_all: the webhook for {} can't be used, so the bot will post as itself",
+                channel
+            ),
+            Path::Channel => info!("preflight:check_all: posting to {} as the bot", channel),
+            Path::Neither => error!(
+                "preflight:check_all: unable to post to {}, check the bot's permissions and the webhook",
+                channel
+            ),
+        }
+
+        checked.write().await.insert(channel, paths);
+    }
+}
+
+async fn check(ctx: &Context, channel: ChannelId, webhook_url: &str) -> Paths {
+    let webhook = match split_webhook_url(webhook_url) {
+        Some((id, token)) => ctx
+            .http
+            .get_webhook_with_token(WebhookId::new(id), token)
+            .await
+            .is_ok(),
+        None => false,
+    };
+
+    Paths {
+        webhook,
+        channel: can_send(ctx, channel),
+    }
+}
+
+/// Whether the bot can send messages to the channel. If the channel
+/// isn't cached we can't tell, so it's assumed that it can.
+fn can_send(ctx: &Context, channel_id: ChannelId) -> bool {
+    let bot = ctx.cache.current_user().id;
+
+    for guild_id in ctx.cache.guilds() {
+        let Some(guild) = ctx.cache.guild(guild_id) else {
+            continue;
+        };
+        let Some(channel) = guild.channels.get(&channel_id) else {
+            continue;
+        };
+
+        return match guild.members.get(&bot) {
+            Some(member) => {
+                let permissions = guild.user_permissions_in(channel, member);
+                permissions.view_channel() && permissions.send_messages()
+            }
+            None => true,
+        };
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Path, Paths};
+
+    #[test]
+    fn prefer_the_webhook() {
+        // Given
+        let paths = Paths::default();
+
+        // Then
+        assert_eq!(paths.choose(true), Path::Webhook);
+        assert_eq!(paths.choose(false), Path::Channel);
+    }
+
+    #[test]
+    fn fall_back_to_the_bot() {
+        // Given
+        let paths = Paths {
+            webhook: false,
+            channel: true,
+        };
+
+        // Then
+        assert_eq!(paths.choose(true), Path::Channel);
+    }
+
+    #[test]
+    fn nowhere_to_post() {
+        // Given
+        let paths = Paths {
+            webhook: false,
+            channel: false,
+        };
+
+        // Then
+        assert_eq!(paths.choose(true), Path::Neither);
+        assert_eq!(paths.choose(false), Path::Neither);
+    }
+}
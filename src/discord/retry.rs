@@ -0,0 +1,363 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+use super::{send_to_minecraft, Error};
+use crate::transport::Transport;
+
+/// How long to wait before the first retry.
+const MIN_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest to wait between retries.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// What happened to a command given to the queue.
+#[derive(Debug)]
+pub enum Delivery {
+    /// It was sent right away.
+    Sent,
+    /// It's waiting for the server to be reachable again. Has the error
+    /// if sending it is what found the server unreachable.
+    Queued(Option<Error>),
+}
+
+/// A command waiting to be retried. The id tells it apart from the rest
+/// of its backlog, since older commands can be dropped while it's sent.
+#[derive(Debug)]
+struct Queued {
+    id: u64,
+    at: Instant,
+    command: String,
+}
+
+/// Commands waiting for one server to be reachable again, oldest first.
+/// A backlog is only around while a retry task is working through it.
+#[derive(Debug)]
+struct Backlog {
+    transport: Transport,
+    commands: VecDeque<Queued>,
+    next_id: u64,
+}
+
+impl Backlog {
+    fn push(&mut self, command: String, now: Instant, capacity: usize) {
+        if self.commands.len() == capacity {
+            debug!("retry:push: the queue is full, dropping the oldest message");
+            self.commands.pop_front();
+        }
+        self.commands.push_back(Queued {
+            id: self.next_id,
+            at: now,
+            command,
+        });
+        self.next_id += 1;
+    }
+}
+
+/// Holds on to chat for Minecraft while the server can't be reached,
+/// e.g. while it restarts, and sends it once it's back.
+#[derive(Debug)]
+pub struct RetryQueue {
+    capacity: usize,
+    max_age: Duration,
+    backlogs: Mutex<Vec<Backlog>>,
+}
+
+impl RetryQueue {
+    /// Create a queue holding at most `capacity` commands for each
+    /// server, each for at most `max_age`. A capacity of zero turns
+    /// retrying off.
+    pub fn new(capacity: usize, max_age: Duration) -> Self {
+        RetryQueue {
+            capacity,
+            max_age,
+            backlogs: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Send a command, or queue it to be retried if the server can't be
+    /// reached. Commands wait behind any already queued for the same
+    /// server, so chat stays in order.
+    pub async fn send(
+        self: &Arc<Self>,
+        command: String,
+        transport: Transport,
+    ) -> Result<Delivery, Error> {
+        if self.capacity == 0 {
+            return send_to_minecraft(command, transport)
+                .await
+                .map(|_| Delivery::Sent);
+        }
+
+        let command = match self.push_if_waiting(command, &transport, Instant::now()) {
+            Ok(()) => return Ok(Delivery::Queued(None)),
+            Err(command) => command,
+        };
+
+        match send_to_minecraft(command.clone(), transport.clone()).await {
+            Ok(_) => Ok(Delivery::Sent),
+            Err(e) => {
+                warn!(
+                    "retry:send: unable to reach the Minecraft server, holding messages until it's back: {}",
+                    e
+                );
+                let first = self.push(command, &transport, Instant::now());
+                if first {
+                    tokio::spawn(self.clone().retry(transport));
+                }
+                Ok(Delivery::Queued(Some(e)))
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn waiting(&self, transport: &Transport) -> bool {
+        self.backlogs
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|backlog| backlog.transport == *transport)
+    }
+
+    /// Queue a command behind the others waiting for a server. Checking
+    /// and queueing happen under one lock, so the retry task can't finish
+    /// in between and leave the command behind. Gives the command back if
+    /// nothing is waiting.
+    fn push_if_waiting(
+        &self,
+        command: String,
+        transport: &Transport,
+        now: Instant,
+    ) -> Result<(), String> {
+        let mut backlogs = self.backlogs.lock().unwrap();
+        match backlogs
+            .iter_mut()
+            .find(|backlog| backlog.transport == *transport)
+        {
+            Some(backlog) => {
+                backlog.push(command, now, self.capacity);
+                Ok(())
+            }
+            None => Err(command),
+        }
+    }
+
+    /// Queue a command, dropping the oldest one if the queue is full.
+    /// Returns `true` if nothing was waiting for this server before, so
+    /// retrying has to be started.
+    fn push(&self, command: String, transport: &Transport, now: Instant) -> bool {
+        let mut backlogs = self.backlogs.lock().unwrap();
+        if let Some(backlog) = backlogs
+            .iter_mut()
+            .find(|backlog| backlog.transport == *transport)
+        {
+            backlog.push(command, now, self.capacity);
+            return false;
+        }
+
+        let mut backlog = Backlog {
+            transport: transport.clone(),
+            commands: VecDeque::new(),
+            next_id: 0,
+        };
+        backlog.push(command, now, self.capacity);
+        backlogs.push(backlog);
+        true
+    }
+
+    /// Get the next command to retry for a server, dropping any that have
+    /// waited too long. Once nothing is left, the server's backlog is
+    /// removed so new commands are sent straight away again.
+    fn next(&self, transport: &Transport, now: Instant) -> Option<(u64, String)> {
+        let mut backlogs = self.backlogs.lock().unwrap();
+        let index = backlogs
+            .iter()
+            .position(|backlog| backlog.transport == *transport)?;
+
+        let commands = &mut backlogs[index].commands;
+        while commands
+            .front()
+            .is_some_and(|queued| now.duration_since(queued.at) > self.max_age)
+        {
+            debug!("retry:next: dropping a message that waited too long");
+            commands.pop_front();
+        }
+
+        match commands.front() {
+            Some(queued) => Some((queued.id, queued.command.clone())),
+            None => {
+                backlogs.remove(index);
+                None
+            }
+        }
+    }
+
+    /// Forget a command once it's been sent. It may already be gone if
+    /// the queue filled up while it was being sent.
+    fn sent(&self, transport: &Transport, id: u64) {
+        let mut backlogs = self.backlogs.lock().unwrap();
+        if let Some(backlog) = backlogs
+            .iter_mut()
+            .find(|backlog| backlog.transport == *transport)
+        {
+            backlog.commands.retain(|queued| queued.id != id);
+        }
+    }
+
+    /// Keep trying to send what's queued for a server, waiting longer
+    /// after each failure, until the queue is empty. The task ends only
+    /// once [`RetryQueue::next`] has removed the backlog, so there's never
+    /// a backlog without a task working on it.
+    async fn retry(self: Arc<Self>, transport: Transport) {
+        let mut delay = MIN_DELAY;
+        let mut sent = 0;
+
+        loop {
+            sleep(delay).await;
+
+            loop {
+                let Some((id, command)) = self.next(&transport, Instant::now()) else {
+                    info!(
+                        "retry:retry: the Minecraft server is back, sent {} held message(s)",
+                        sent
+                    );
+                    return;
+                };
+
+                match send_to_minecraft(command, transport.clone()).await {
+                    Ok(_) => {
+                        self.sent(&transport, id);
+                        sent += 1;
+                        delay = MIN_DELAY;
+                    }
+                    Err(e) => {
+                        debug!("retry:retry: the server still can't be reached: {}", e);
+                        delay = delay.saturating_mul(2).min(MAX_DELAY);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::RetryQueue;
+    use crate::transport::Transport;
+
+    fn transport(program: &str) -> Transport {
+        Transport::Command {
+            program: vec![String::from(program)],
+        }
+    }
+
+    #[test]
+    fn keep_the_newest_commands() {
+        // Given
+        let queue = RetryQueue::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+
+        // When
+        let first = queue.push(String::from("one"), &transport("a"), now);
+        let second = queue.push(String::from("two"), &transport("a"), now);
+        queue.push(String::from("three"), &transport("a"), now);
+
+        // Then
+        assert!(first);
+        assert!(!second);
+        assert_eq!(
+            queue.next(&transport("a"), now),
+            Some((1, String::from("two")))
+        );
+        queue.sent(&transport("a"), 1);
+        assert_eq!(
+            queue.next(&transport("a"), now),
+            Some((2, String::from("three")))
+        );
+        queue.sent(&transport("a"), 2);
+        assert_eq!(queue.next(&transport("a"), now), None);
+        assert!(!queue.waiting(&transport("a")));
+    }
+
+    #[test]
+    fn queue_each_server_separately() {
+        // Given
+        let queue = RetryQueue::new(8, Duration::from_secs(60));
+        let now = Instant::now();
+
+        // When
+        queue.push(String::from("one"), &transport("a"), now);
+
+        // Then
+        assert!(queue.waiting(&transport("a")));
+        assert!(!queue.waiting(&transport("b")));
+        assert!(queue.push(String::from("two"), &transport("b"), now));
+    }
+
+    #[test]
+    fn drop_commands_that_waited_too_long() {
+        // Given
+        let queue = RetryQueue::new(8, Duration::from_secs(60));
+        let now = Instant::now();
+        queue.push(String::from("old"), &transport("a"), now);
+        queue.push(
+            String::from("new"),
+            &transport("a"),
+            now + Duration::from_secs(50),
+        );
+
+        // Then
+        assert_eq!(
+            queue.next(&transport("a"), now + Duration::from_secs(90)),
+            Some((1, String::from("new")))
+        );
+    }
+
+    #[test]
+    fn keep_what_was_queued_while_sending() {
+        // Given
+        let queue = RetryQueue::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+        queue.push(String::from("one"), &transport("a"), now);
+        queue.push(String::from("two"), &transport("a"), now);
+
+        // When
+        let (id, _) = queue.next(&transport("a"), now).unwrap();
+        queue.push(String::from("three"), &transport("a"), now);
+        queue.sent(&transport("a"), id);
+
+        // Then
+        assert_eq!(
+            queue.next(&transport("a"), now),
+            Some((1, String::from("two")))
+        );
+    }
+
+    #[test]
+    fn only_queue_behind_a_backlog() {
+        // Given
+        let queue = RetryQueue::new(8, Duration::from_secs(60));
+        let now = Instant::now();
+
+        // When
+        let idle = queue.push_if_waiting(String::from("one"), &transport("a"), now);
+        queue.push(String::from("two"), &transport("a"), now);
+        let waiting = queue.push_if_waiting(String::from("three"), &transport("a"), now);
+
+        // Then
+        assert_eq!(idle, Err(String::from("one")));
+        assert_eq!(waiting, Ok(()));
+        assert_eq!(
+            queue.next(&transport("a"), now),
+            Some((0, String::from("two")))
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use fancy_regex::Regex;
+
+lazy_static! {
+    // Minecraft names are 3-16 characters. Skip Discord's own `<@id>`
+    // mentions, and things like email addresses.
+    static ref PLAYER_MENTION: Regex = Regex::new(r"(?<![<\w])@(\w{3,16})\b").unwrap();
+}
+
+/// Get the names of all Minecraft players mentioned in a message, e.g.
+/// `@Steve`. Each name is only returned once.
+pub fn mentioned_players(content: &str) -> Vec<String> {
+    let mut players: Vec<String> = Vec::new();
+
+    for captures in PLAYER_MENTION.captures_iter(content).flatten() {
+        let name = captures[1].to_string();
+        if !players.iter().any(|p| p.eq_ignore_ascii_case(&name)) {
+            players.push(name);
+        }
+    }
+
+    players
+}
+
+/// Build the `playsound` commands to send for a Discord message.
+///
+/// Every mentioned player hears the mention sound. If the message is an
+/// announcement, everyone hears the announcement sound instead.
+pub fn build_sound_commands(
+    content: &str,
+    is_announcement: bool,
+    mention_sound: &str,
+    announcement_sound: &str,
+) -> Vec<String> {
+    if is_announcement && !announcement_sound.is_empty() {
+        return vec![playsound(announcement_sound, "@a")];
+    }
+
+    if mention_sound.is_empty() {
+        return Vec::new();
+    }
+
+    mentioned_players(content)
+        .iter()
+        .map(|player| playsound(mention_sound, player))
+        .collect()
+}
+
+fn playsound(sound: &str, target: &str) -> String {
+    format!("playsound {} master {} ~ ~ ~ 1 1 1", sound, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_sound_commands, mentioned_players};
+
+    #[test]
+    fn finds_mentioned_players() {
+        // Given
+        let content = "hey @Steve and @alex_99, @steve again";
+
+        // When
+        let result = mentioned_players(content);
+
+        // Then
+        assert_eq!(result, vec!["Steve", "alex_99"]);
+    }
+
+    #[test]
+    fn ignores_discord_mentions_and_emails() {
+        assert!(mentioned_players("<@123456789> mail me@example.com @ab").is_empty());
+    }
+
+    #[test]
+    fn announcements_play_for_everyone() {
+        // Given
+        let content = "Server restart in 5 minutes @Steve";
+
+        // When
+        let result = build_sound_commands(content, true, "mention", "bell");
+
+        // Then
+        assert_eq!(result, vec!["playsound bell master @a ~ ~ ~ 1 1 1"]);
+    }
+
+    #[test]
+    fn mentions_play_for_player() {
+        // Given
+        let content = "@Steve look at this";
+
+        // When
+        let result = build_sound_commands(content, false, "mention", "bell");
+
+        // Then
+        assert_eq!(result, vec!["playsound mention master Steve ~ ~ ~ 1 1 1"]);
+    }
+}
@@ -0,0 +1,81 @@
+use serde_json::json;
+
+/// Where in game to show voice channel updates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Display {
+    ActionBar,
+    Chat,
+}
+
+impl Display {
+    /// Parse the configured display. Anything that isn't `chat` is
+    /// shown on the action bar.
+    pub fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "chat" => Display::Chat,
+            _ => Display::ActionBar,
+        }
+    }
+}
+
+/// Describe a change in the voice channel, e.g.
+/// `Steve joined voice, 3 people now in voice`.
+pub fn status_message(name: &str, joined: bool, count: usize) -> String {
+    let action = if joined { "joined" } else { "left" };
+
+    let status = match count {
+        0 => String::from("nobody is in voice now"),
+        1 => String::from("1 person now in voice"),
+        n => format!("{} people now in voice", n),
+    };
+
+    format!("{} {} voice, {}", name, action, status)
+}
+
+/// Build the command to show a voice status message to everyone.
+pub fn build_command(display: Display, message: &str) -> String {
+    let text = json!({ "text": message, "color": "aqua" });
+
+    match display {
+        Display::ActionBar => format!("title @a actionbar {}", text),
+        Display::Chat => format!("tellraw @a {}", text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_command, status_message, Display};
+
+    #[test]
+    fn describes_voice_changes() {
+        assert_eq!(
+            status_message("Steve", true, 3),
+            "Steve joined voice, 3 people now in voice"
+        );
+        assert_eq!(
+            status_message("Steve", true, 1),
+            "Steve joined voice, 1 person now in voice"
+        );
+        assert_eq!(
+            status_message("Steve", false, 0),
+            "Steve left voice, nobody is in voice now"
+        );
+    }
+
+    #[test]
+    fn builds_display_commands() {
+        // Given
+        let message = "Steve \"joined\" voice";
+
+        // When
+        let actionbar = build_command(Display::ActionBar, message);
+        let chat = build_command(Display::from_config("Chat"), message);
+
+        // Then
+        assert_eq!(
+            actionbar,
+            r#"title @a actionbar {"color":"aqua","text":"Steve \"joined\" voice"}"#
+        );
+        assert!(chat.starts_with("tellraw @a {"));
+    }
+}
@@ -0,0 +1,177 @@
+use fancy_regex::{Captures, Regex};
+
+lazy_static! {
+    // Discord sends custom emoji as `<:name:id>`, or `<a:name:id>` if
+    // they're animated
+    static ref CUSTOM_EMOJI: Regex = Regex::new(r"<a?:(\w{2,32}):\d+>").unwrap();
+    // Shortcodes can have markdown escapes in them by the time they're
+    // translated, like `:thumbs\_up:`
+    static ref SHORTCODE: Regex = Regex::new(r":((?:\w|\\_|[+-]){1,32}):").unwrap();
+}
+
+/// Common emoji, with their Discord shortcode and an ASCII form for the
+/// ones that have an obvious one.
+const EMOJI: &[(&str, &str, Option<&str>)] = &[
+    ("😀", "grinning", Some(":D")),
+    ("😃", "smiley", Some(":D")),
+    ("😄", "smile", Some(":D")),
+    ("😁", "grin", Some(":D")),
+    ("😆", "laughing", Some("XD")),
+    ("😂", "joy", Some(":'D")),
+    ("🤣", "rofl", Some("XD")),
+    ("🙂", "slight_smile", Some(":)")),
+    ("😊", "blush", Some(":)")),
+    ("😉", "wink", Some(";)")),
+    ("😍", "heart_eyes", Some("<3")),
+    ("😘", "kissing_heart", Some(":*")),
+    ("😛", "stuck_out_tongue", Some(":P")),
+    ("😜", "stuck_out_tongue_winking_eye", Some(";P")),
+    ("😎", "sunglasses", Some("B)")),
+    ("😐", "neutral_face", Some(":|")),
+    ("😕", "confused", Some(":/")),
+    ("🙁", "slight_frown", Some(":(")),
+    ("😢", "cry", Some(":'(")),
+    ("😭", "sob", Some(":'(")),
+    ("😠", "angry", Some(">:(")),
+    ("😡", "rage", Some(">:(")),
+    ("😮", "open_mouth", Some(":O")),
+    ("😱", "scream", Some("D:")),
+    ("😴", "sleeping", Some("zzz")),
+    ("🤔", "thinking", None),
+    ("🙄", "rolling_eyes", None),
+    ("💀", "skull", None),
+    ("❤\u{fe0f}", "heart", Some("<3")),
+    ("❤", "heart", Some("<3")),
+    ("💔", "broken_heart", Some("</3")),
+    ("👍", "thumbsup", Some("+1")),
+    ("👎", "thumbsdown", Some("-1")),
+    ("👋", "wave", Some("o/")),
+    ("👏", "clap", None),
+    ("🙏", "pray", None),
+    ("🎉", "tada", None),
+    ("🔥", "fire", None),
+    ("💯", "100", None),
+    ("⭐", "star", Some("*")),
+    ("✅", "white_check_mark", None),
+    ("❌", "x", None),
+];
+
+/// Other shortcodes Discord knows some of the emoji above by.
+const ALIASES: &[(&str, &str)] = &[
+    ("+1", "thumbsup"),
+    ("-1", "thumbsdown"),
+    ("thumbs_up", "thumbsup"),
+    ("thumbs_down", "thumbsdown"),
+    ("slightly_smiling_face", "slight_smile"),
+    ("red_heart", "heart"),
+];
+
+/// Turn Discord's markup for custom emoji into the `:name:` that members
+/// see it as.
+pub fn custom_to_shortcodes(text: &str) -> String {
+    CUSTOM_EMOJI
+        .replace_all(text, |captures: &Captures| format!(":{}:", &captures[1]))
+        .into_owned()
+}
+
+/// Replace common emoji, which Minecraft shows as boxes, with an ASCII
+/// form like `:)`, or their shortcode like `:fire:` if they don't have
+/// one. Other emoji are left as they are.
+pub fn to_ascii(text: &str) -> String {
+    let mut replaced = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let found = match c.is_ascii() {
+            true => None,
+            false => EMOJI.iter().find(|(emoji, _, _)| rest.starts_with(emoji)),
+        };
+
+        match found {
+            Some((emoji, name, ascii)) => {
+                match ascii {
+                    Some(ascii) => replaced.push_str(ascii),
+                    None => replaced.push_str(&format!(":{}:", name)),
+                }
+                // Drop the variation selector that asks for the emoji form
+                rest = rest[emoji.len()..].trim_start_matches('\u{fe0f}');
+            }
+            None => {
+                replaced.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    replaced
+}
+
+/// Replace `:shortcode:` text from Minecraft with the emoji Discord would
+/// show for it. `custom` is asked first, for the guild's own emoji, and
+/// shortcodes nothing is found for are left as they are.
+pub fn from_shortcodes(text: &str, custom: impl Fn(&str) -> Option<String>) -> String {
+    SHORTCODE
+        .replace_all(text, |captures: &Captures| {
+            let name = captures[1].replace("\\_", "_");
+            custom(&name)
+                .or_else(|| unicode_emoji(&name).map(str::to_string))
+                .unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+fn unicode_emoji(name: &str) -> Option<&'static str> {
+    let name = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, name)| name);
+
+    EMOJI
+        .iter()
+        .find(|(_, shortcode, _)| *shortcode == name)
+        .map(|(emoji, _, _)| *emoji)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{custom_to_shortcodes, from_shortcodes, to_ascii};
+
+    #[test]
+    fn show_custom_emoji_by_name() {
+        // Given
+        let input = "gg <:pepe_happy:123456789012345678> <a:party:987654321098765432>";
+
+        // When
+        let result = custom_to_shortcodes(input);
+
+        // Then
+        assert_eq!(result, "gg :pepe_happy: :party:");
+    }
+
+    #[test]
+    fn replace_emoji_with_ascii() {
+        assert_eq!(to_ascii("hi 🙂 ❤️ ❤"), "hi :) <3 <3");
+        assert_eq!(to_ascii("🔥🔥"), ":fire::fire:");
+        assert_eq!(to_ascii("café 🦀"), "café 🦀");
+    }
+
+    #[test]
+    fn turn_shortcodes_into_emoji() {
+        // Given
+        let custom = |name: &str| {
+            (name == "pepe_happy").then(|| String::from("<:pepe_happy:123456789012345678>"))
+        };
+
+        // When
+        let result = from_shortcodes(
+            "nice :thumbsup: :+1: :heart: :pepe\\_happy: :unknown: at 12:30:45",
+            custom,
+        );
+
+        // Then
+        assert_eq!(
+            result,
+            "nice 👍 👍 ❤\u{fe0f} <:pepe_happy:123456789012345678> :unknown: at 12:30:45"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use serenity::prelude::TypeMapKey;
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+/// Settings saved for a single Discord user.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Link {
+    /// Send the user a DM when they're mentioned in game while they
+    /// aren't online in Discord.
+    pub mention_dms: bool,
+}
+
+/// Keeps track of per-user settings for Discord users, saved as a
+/// JSON file next to the config.
+#[derive(Debug, Default)]
+pub struct LinkStore {
+    path: PathBuf,
+    links: HashMap<u64, Link>,
+}
+
+impl LinkStore {
+    /// Load the link store from the given path. If the file doesn't
+    /// exist yet, an empty store is returned.
+    pub fn load(path: PathBuf) -> Result<Self, Error> {
+        let links = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self { path, links })
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&self.path, serde_json::to_string_pretty(&self.links)?)?;
+        Ok(())
+    }
+
+    pub fn wants_mention_dms(&self, user_id: u64) -> bool {
+        self.links
+            .get(&user_id)
+            .is_some_and(|link| link.mention_dms)
+    }
+
+    /// Turn mention DMs on or off for a user, and save the store.
+    pub fn set_mention_dms(&mut self, user_id: u64, enabled: bool) -> Result<(), Error> {
+        self.links.entry(user_id).or_default().mention_dms = enabled;
+        self.save()
+    }
+}
+
+pub struct LinkStoreContainer;
+
+impl TypeMapKey for LinkStoreContainer {
+    type Value = Arc<RwLock<LinkStore>>;
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::LinkStore;
+
+    #[test]
+    fn saves_and_loads_mention_dms() {
+        // Given
+        let path = env::temp_dir().join(format!("dolphin-links-{}.json", std::process::id()));
+        let mut store = LinkStore::load(path.clone()).unwrap();
+
+        // When
+        store.set_mention_dms(1234, true).unwrap();
+        store.set_mention_dms(5678, false).unwrap();
+        let loaded = LinkStore::load(path.clone()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        // Then
+        assert!(loaded.wants_mention_dms(1234));
+        assert!(!loaded.wants_mention_dms(5678));
+        assert!(!loaded.wants_mention_dms(42));
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use tracing::{debug, warn};
+use warp::{http::StatusCode, Filter, Rejection, Reply};
+
+use crate::config::RootConfig;
+
+/// Who is allowed to send to the webserver: a shared secret senders
+/// have to give as `Authorization: Bearer <token>`, and the addresses
+/// they can send from.
+#[derive(Debug, Default)]
+pub struct Access {
+    token: String,
+    unauthenticated: bool,
+    networks: Vec<(IpAddr, u8)>,
+}
+
+impl Access {
+    pub fn from_config(config: &RootConfig) -> Self {
+        let networks = config
+            .get_webserver_allowed_ips()
+            .iter()
+            .filter_map(|entry| {
+                let network = parse_network(entry);
+                if network.is_none() {
+                    warn!(
+                        "access:from_config: '{}' in allowed_ips isn't an address or network, ignoring it",
+                        entry
+                    );
+                }
+                network
+            })
+            .collect();
+
+        Access {
+            token: config.get_webserver_auth_token(),
+            unauthenticated: config.webserver_allows_unauthenticated(),
+            networks,
+        }
+    }
+
+    /// Check if a request can come from an address. Every address can if
+    /// no addresses are listed.
+    fn allows_address(&self, address: Option<IpAddr>) -> bool {
+        if self.networks.is_empty() {
+            return true;
+        }
+
+        address.is_some_and(|address| {
+            self.networks
+                .iter()
+                .any(|network| in_network(*network, address))
+        })
+    }
+
+    /// Check the `Authorization` header of a request. Without a token,
+    /// requests are only let in if that was asked for.
+    fn allows_authorization(&self, authorization: Option<&str>) -> bool {
+        if self.token.is_empty() {
+            return self.unauthenticated;
+        }
+
+        authorization
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .is_some_and(|token| same_secret(token.trim(), &self.token))
+    }
+}
+
+#[derive(Debug)]
+enum Denied {
+    Address,
+    Token,
+}
+
+impl warp::reject::Reject for Denied {}
+
+/// Turn away requests from addresses that aren't allowed.
+pub fn address(access: Arc<Access>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and_then(move |remote: Option<SocketAddr>| {
+            let allowed = access.allows_address(remote.map(|remote| remote.ip().to_canonical()));
+            async move {
+                match allowed {
+                    true => Ok(()),
+                    false => Err(warp::reject::custom(Denied::Address)),
+                }
+            }
+        })
+        .untuple_one()
+}
+
+/// Turn away requests without the shared secret.
+pub fn bearer(access: Arc<Access>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |authorization: Option<String>| {
+            let allowed = access.allows_authorization(authorization.as_deref());
+            async move {
+                match allowed {
+                    true => Ok(()),
+                    false => Err(warp::reject::custom(Denied::Token)),
+                }
+            }
+        })
+        .untuple_one()
+}
+
+/// Answer turned away requests with `401` for a missing or wrong
+/// secret, or `403` for an address that isn't allowed.
+pub async fn recover(rejection: Rejection) -> Result<warp::reply::Response, Rejection> {
+    match rejection.find::<Denied>() {
+        Some(Denied::Address) => {
+            debug!("access:recover: turning away a request from an address that isn't allowed");
+            Ok(warp::reply::with_status("", StatusCode::FORBIDDEN).into_response())
+        }
+        Some(Denied::Token) => {
+            debug!("access:recover: turning away a request without the right secret");
+            let reply = warp::reply::with_status("", StatusCode::UNAUTHORIZED);
+            Ok(warp::reply::with_header(reply, "WWW-Authenticate", "Bearer").into_response())
+        }
+        None => Err(rejection),
+    }
+}
+
+/// Parse an address like `10.0.0.5`, or a network like `10.0.0.0/24`,
+/// as the network's address and prefix length.
+fn parse_network(entry: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix) = match entry.trim().split_once('/') {
+        Some((address, prefix)) => (address.parse::<IpAddr>().ok()?, prefix.parse().ok()?),
+        None => {
+            let address = entry.trim().parse::<IpAddr>().ok()?;
+            (address, if address.is_ipv4() { 32 } else { 128 })
+        }
+    };
+
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    (prefix <= max).then_some((address.to_canonical(), prefix))
+}
+
+fn in_network((network, prefix): (IpAddr, u8), address: IpAddr) -> bool {
+    // Line both kinds of address up at the top, so the prefix is the
+    // number of bits to compare either way
+    let bits = |address: IpAddr| match address {
+        IpAddr::V4(address) => (u32::from(address) as u128) << 96,
+        IpAddr::V6(address) => u128::from(address),
+    };
+    if network.is_ipv4() != address.is_ipv4() {
+        return false;
+    }
+
+    let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+    bits(network) & mask == bits(address) & mask
+}
+
+/// Compare secrets without stopping at the first difference, so how long
+/// it takes doesn't give away how much of a guess was right.
+pub(super) fn same_secret(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{parse_network, Access};
+
+    #[test]
+    fn need_the_bearer_token() {
+        // Given
+        let access = Access {
+            token: String::from("hunter2"),
+            unauthenticated: false,
+            networks: Vec::new(),
+        };
+
+        // Then
+        assert!(access.allows_authorization(Some("Bearer hunter2")));
+        assert!(!access.allows_authorization(Some("Bearer hunter3")));
+        assert!(!access.allows_authorization(Some("hunter2")));
+        assert!(!access.allows_authorization(None));
+        assert!(!Access::default().allows_authorization(None));
+        assert!(!Access::default().allows_authorization(Some("Bearer ")));
+    }
+
+    #[test]
+    fn only_skip_the_token_when_asked() {
+        // Given
+        let access = Access {
+            token: String::new(),
+            unauthenticated: true,
+            networks: Vec::new(),
+        };
+
+        // Then
+        assert!(access.allows_authorization(None));
+        assert!(access.allows_authorization(Some("Bearer anything")));
+    }
+
+    #[test]
+    fn allow_listed_addresses() {
+        // Given
+        let access = Access {
+            token: String::new(),
+            unauthenticated: true,
+            networks: ["10.0.0.0/24", "192.168.1.5", "::1"]
+                .iter()
+                .filter_map(|entry| parse_network(entry))
+                .collect(),
+        };
+        let address = |address: &str| Some(address.parse::<IpAddr>().unwrap().to_canonical());
+
+        // Then
+        assert!(access.allows_address(address("10.0.0.77")));
+        assert!(access.allows_address(address("192.168.1.5")));
+        assert!(access.allows_address(address("::ffff:10.0.0.1")));
+        assert!(access.allows_address(address("::1")));
+        assert!(!access.allows_address(address("10.0.1.1")));
+        assert!(!access.allows_address(address("192.168.1.6")));
+        assert!(!access.allows_address(None));
+        assert!(Access::default().allows_address(None));
+    }
+
+    #[test]
+    fn reject_bad_networks() {
+        assert!(parse_network("10.0.0.0/33").is_none());
+        assert!(parse_network("localhost").is_none());
+        assert!(parse_network("0.0.0.0/0").is_some());
+    }
+}
@@ -0,0 +1,295 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+/// The display information for an Advancement, used to make the
+/// message sent to Discord a bit nicer.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct AdvancementInfo {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Descriptions for the vanilla Advancements, keyed by the title that
+/// the server prints in the log.
+const VANILLA_ADVANCEMENTS: &[(&str, &str)] = &[
+    ("Minecraft", "The heart and story of the game"),
+    ("Stone Age", "Mine Stone with your new Pickaxe"),
+    ("Getting an Upgrade", "Construct a better Pickaxe"),
+    ("Acquire Hardware", "Smelt an Iron Ingot"),
+    ("Suit Up", "Protect yourself with a piece of iron armor"),
+    ("Hot Stuff", "Fill a Bucket with lava"),
+    ("Isn't It Iron Pick", "Upgrade your Pickaxe"),
+    ("Not Today, Thank You", "Deflect a projectile with a Shield"),
+    ("Ice Bucket Challenge", "Obtain a block of Obsidian"),
+    ("Diamonds!", "Acquire diamonds"),
+    (
+        "We Need to Go Deeper",
+        "Build, light and enter a Nether Portal",
+    ),
+    ("Cover Me with Diamonds", "Diamond armor saves lives"),
+    ("Enchanter", "Enchant an item at an Enchanting Table"),
+    ("Zombie Doctor", "Weaken and then cure a Zombie Villager"),
+    ("Eye Spy", "Follow an Eye of Ender"),
+    ("The End?", "Enter the End Portal"),
+    ("Nether", "Bring summer clothes"),
+    ("Return to Sender", "Destroy a Ghast with a fireball"),
+    ("Those Were the Days", "Enter a Bastion Remnant"),
+    ("Hidden in the Depths", "Obtain Ancient Debris"),
+    (
+        "Subspace Bubble",
+        "Use the Nether to travel 7 km in the Overworld",
+    ),
+    (
+        "A Terrible Fortress",
+        "Break your way into a Nether Fortress",
+    ),
+    ("Who is Cutting Onions?", "Obtain Crying Obsidian"),
+    ("Oh Shiny", "Distract Piglins with gold"),
+    (
+        "This Boat Has Legs",
+        "Ride a Strider with a Warped Fungus on a Stick",
+    ),
+    (
+        "Uneasy Alliance",
+        "Rescue a Ghast from the Nether, bring it safely home to the Overworld... and then kill it",
+    ),
+    ("War Pigs", "Loot a Chest in a Bastion Remnant"),
+    ("Country Lode, Take Me Home", "Use a Compass on a Lodestone"),
+    ("Cover Me in Debris", "Get a full suit of Netherite armor"),
+    ("Spooky Scary Skeleton", "Obtain a Wither Skeleton's skull"),
+    ("Into Fire", "Relieve a Blaze of its rod"),
+    (
+        "Not Quite \"Nine\" Lives",
+        "Charge a Respawn Anchor to the maximum",
+    ),
+    (
+        "Feels Like Home",
+        "Take a Strider for a loooong ride on a lava lake in the Overworld",
+    ),
+    ("Hot Tourist Destinations", "Explore all Nether biomes"),
+    ("Withering Heights", "Summon the Wither"),
+    ("Local Brewery", "Brew a Potion"),
+    ("Bring Home the Beacon", "Construct and place a Beacon"),
+    (
+        "A Furious Cocktail",
+        "Have every potion effect applied at the same time",
+    ),
+    ("Beaconator", "Bring a Beacon to full power"),
+    (
+        "How Did We Get Here?",
+        "Have every effect applied at the same time",
+    ),
+    ("The End", "Or the beginning?"),
+    ("Free the End", "Good luck"),
+    ("The Next Generation", "Hold the Dragon Egg"),
+    ("Remote Getaway", "Escape the island"),
+    ("The End... Again...", "Respawn the Ender Dragon"),
+    (
+        "You Need a Mint",
+        "Collect Dragon's Breath in a Glass Bottle",
+    ),
+    (
+        "The City at the End of the Game",
+        "Go on in, what could happen?",
+    ),
+    ("Sky's the Limit", "Find Elytra"),
+    (
+        "Great View From Up Here",
+        "Levitate up 50 blocks from the attacks of a Shulker",
+    ),
+    ("Adventure", "Adventure, exploration and combat"),
+    ("Voluntary Exile", "Kill a raid captain"),
+    ("Is It a Bird?", "Look at a Parrot through a Spyglass"),
+    ("Monster Hunter", "Kill any hostile monster"),
+    ("What a Deal!", "Successfully trade with a Villager"),
+    (
+        "Sticky Situation",
+        "Jump into a Honey Block to break your fall",
+    ),
+    ("Ol' Betsy", "Shoot a Crossbow"),
+    (
+        "Sweet Dreams",
+        "Sleep in a Bed to change your respawn point",
+    ),
+    (
+        "Hero of the Village",
+        "Successfully defend a village from a raid",
+    ),
+    ("Is It a Balloon?", "Look at a Ghast through a Spyglass"),
+    ("A Throwaway Joke", "Throw a Trident at something"),
+    ("Take Aim", "Shoot something with an Arrow"),
+    ("Monsters Hunted", "Kill one of every hostile monster"),
+    ("Postmortal", "Use a Totem of Undying to cheat death"),
+    (
+        "Hired Help",
+        "Summon an Iron Golem to help defend a village",
+    ),
+    (
+        "Two Birds, One Arrow",
+        "Kill two Phantoms with a piercing Arrow",
+    ),
+    (
+        "Who's the Pillager Now?",
+        "Give a Pillager a taste of their own medicine",
+    ),
+    ("Arbalistic", "Kill five unique mobs with one crossbow shot"),
+    ("Adventuring Time", "Discover every biome"),
+    ("Very Very Frightening", "Strike a Villager with lightning"),
+    (
+        "Sniper Duel",
+        "Kill a Skeleton from at least 50 meters away",
+    ),
+    ("Husbandry", "The world is full of friends and food"),
+    ("The Parrots and the Bats", "Breed two animals together"),
+    ("Best Friends Forever", "Tame an animal"),
+    ("Fishy Business", "Catch a fish"),
+    ("A Seedy Place", "Plant a seed and watch it grow"),
+    ("Two by Two", "Breed all the animals!"),
+    ("A Complete Catalogue", "Tame all cat variants!"),
+    ("Tactical Fishing", "Catch a Fish... without a Fishing Rod!"),
+    (
+        "A Balanced Diet",
+        "Eat everything that is edible, even if it's not good for you",
+    ),
+    (
+        "Serious Dedication",
+        "Use a Netherite Ingot to upgrade a Hoe, and then reevaluate your life choices",
+    ),
+    ("The Cutest Predator", "Catch an Axolotl in a Bucket"),
+];
+
+/// Looks up nicer names and descriptions for Advancements. Custom
+/// entries loaded from a mapping file take priority over the built-in
+/// vanilla table.
+#[derive(Clone, Debug, Default)]
+pub struct AdvancementTable {
+    custom: HashMap<String, AdvancementInfo>,
+}
+
+impl AdvancementTable {
+    pub fn new(custom: HashMap<String, AdvancementInfo>) -> Self {
+        Self { custom }
+    }
+
+    /// Load the custom Advancement mappings from a JSON file. The file
+    /// should contain an object mapping the name that is logged to an
+    /// object with a `title` and optional `description`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)?;
+        let custom = serde_json::from_str(&contents)?;
+        Ok(Self::new(custom))
+    }
+
+    /// Get the display information for the Advancement name found in a
+    /// log line. Unknown names that look like identifiers are turned into
+    /// something a bit more readable.
+    pub fn lookup(&self, name: &str) -> AdvancementInfo {
+        if let Some(info) = self.custom.get(name) {
+            return info.clone();
+        }
+
+        if let Some((title, description)) = VANILLA_ADVANCEMENTS.iter().find(|(t, _)| *t == name) {
+            return AdvancementInfo {
+                title: title.to_string(),
+                description: Some(description.to_string()),
+            };
+        }
+
+        AdvancementInfo {
+            title: prettify(name),
+            description: None,
+        }
+    }
+}
+
+/// Turn an Advancement identifier such as `mymod:story/mine_gem`
+/// or `advancements.mymod.mine_gem.title` into `Mine Gem`. Names that
+/// don't look like identifiers are returned as-is.
+fn prettify(name: &str) -> String {
+    let is_identifier =
+        !name.contains(' ') && (name.contains(':') || name.contains('.') || name.contains('_'));
+    if !is_identifier {
+        return name.to_string();
+    }
+
+    let mut segments = name
+        .split([':', '/', '.'])
+        .filter(|s| !s.is_empty() && *s != "title");
+    let last = segments.next_back().unwrap_or(name);
+
+    last.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{prettify, AdvancementInfo, AdvancementTable};
+
+    #[test]
+    fn finds_vanilla_advancement() {
+        // Given
+        let table = AdvancementTable::default();
+
+        // When
+        let info = table.lookup("Monster Hunter");
+
+        // Then
+        assert_eq!(info.title, "Monster Hunter");
+        assert_eq!(
+            info.description,
+            Some(String::from("Kill any hostile monster"))
+        );
+    }
+
+    #[test]
+    fn custom_entries_take_priority() {
+        // Given
+        let mut custom = HashMap::new();
+        custom.insert(
+            String::from("Monster Hunter"),
+            AdvancementInfo {
+                title: String::from("Hunter"),
+                description: None,
+            },
+        );
+        let table = AdvancementTable::new(custom);
+
+        // When
+        let info = table.lookup("Monster Hunter");
+
+        // Then
+        assert_eq!(info.title, "Hunter");
+        assert_eq!(info.description, None);
+    }
+
+    #[test]
+    fn prettifies_identifiers() {
+        assert_eq!(prettify("mymod:story/mine_gem"), "Mine Gem");
+        assert_eq!(prettify("advancements.mymod.mine_gem.title"), "Mine Gem");
+        assert_eq!(prettify("MonsterHunter"), "MonsterHunter");
+        assert_eq!(prettify("Not Today, Thank You"), "Not Today, Thank You");
+    }
+}
@@ -0,0 +1,235 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use serenity::{
+    all::ChannelId,
+    builder::{CreateAllowedMentions, CreateMessage},
+    http::Http,
+    prelude::{Context, Mutex, TypeMapKey},
+};
+use tokio::time::sleep;
+use tracing::error;
+
+use super::colors::palette_index;
+use crate::config::RootConfig;
+
+/// ANSI colours Discord shows in `ansi` code blocks, for player names.
+/// Grey and white are left out so names stand out from the text.
+const NAME_COLORS: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+/// What the code block adds around the lines: "```ansi\n" and "\n```".
+const FENCE_LENGTH: usize = 12;
+
+/// What happened to a line added to the batch.
+#[derive(Debug, PartialEq)]
+pub enum Push {
+    /// It started a new batch, which should be posted once the window
+    /// is over.
+    Started(u64),
+    /// It was added to the batch that's already waiting.
+    Added,
+    /// The waiting batch was too full for it, so those lines should be
+    /// posted now. The line started a new batch.
+    Full(Vec<String>, u64),
+}
+
+/// Chat lines waiting to be posted together in one code block.
+#[derive(Debug, Default)]
+pub struct AnsiBatch {
+    lines: Vec<String>,
+    length: usize,
+    generation: u64,
+}
+
+impl AnsiBatch {
+    /// Add a line, cutting it down if it can't fit in a message even on
+    /// its own.
+    pub fn push(&mut self, line: String, max_length: usize) -> Push {
+        let room = max_length.saturating_sub(FENCE_LENGTH);
+        let line = match line.char_indices().nth(room) {
+            Some((end, _)) => line[..end].to_string(),
+            None => line,
+        };
+        let line_length = line.chars().count();
+
+        let full = match self.lines.is_empty() {
+            true => None,
+            false if self.length + 1 + line_length <= room => {
+                self.length += 1 + line_length;
+                self.lines.push(line);
+                return Push::Added;
+            }
+            false => Some(self.take_all()),
+        };
+
+        self.length = line_length;
+        self.lines.push(line);
+        match full {
+            Some(lines) => Push::Full(lines, self.generation),
+            None => Push::Started(self.generation),
+        }
+    }
+
+    /// Take the lines of the batch with the given generation, unless it
+    /// was already posted because it filled up.
+    pub fn take(&mut self, generation: u64) -> Option<Vec<String>> {
+        (generation == self.generation && !self.lines.is_empty()).then(|| self.take_all())
+    }
+
+    fn take_all(&mut self) -> Vec<String> {
+        self.generation += 1;
+        self.length = 0;
+        std::mem::take(&mut self.lines)
+    }
+}
+
+/// The waiting batch for each channel.
+pub struct AnsiBatchContainer;
+
+impl TypeMapKey for AnsiBatchContainer {
+    type Value = Arc<Mutex<HashMap<ChannelId, AnsiBatch>>>;
+}
+
+/// A chat line with the player's name in their colour. Players can't
+/// add colours of their own or close the code block.
+pub fn render_line(name: &str, content: &str) -> String {
+    let clean = |text: &str| text.replace('\u{1b}', "").replace('`', "`\u{200b}");
+    let color = NAME_COLORS[palette_index(name, NAME_COLORS.len())];
+
+    format!(
+        "\u{1b}[1;{}m{}\u{1b}[0m: {}",
+        color,
+        clean(name),
+        clean(content)
+    )
+}
+
+fn render(lines: &[String]) -> String {
+    format!("```ansi\n{}\n```", lines.join("\n"))
+}
+
+/// Add a chat line to the batch for the channel, posting the batch after
+/// the batch window, or right away if it fills up.
+pub async fn queue(ctx: &Arc<Context>, config: &RootConfig, channel: ChannelId, line: String) {
+    let Some(batches) = ctx.data.read().await.get::<AnsiBatchContainer>().cloned() else {
+        return;
+    };
+
+    let pushed = batches
+        .lock()
+        .await
+        .entry(channel)
+        .or_default()
+        .push(line, config.get_max_message_length());
+    let generation = match pushed {
+        Push::Added => return,
+        Push::Started(generation) => generation,
+        Push::Full(lines, generation) => {
+            send(&ctx.http, channel, &lines).await;
+            generation
+        }
+    };
+
+    let window = Duration::from_secs(config.get_ansi_batch_window_secs());
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        sleep(window).await;
+        let lines = batches
+            .lock()
+            .await
+            .get_mut(&channel)
+            .and_then(|batch| batch.take(generation));
+        if let Some(lines) = lines {
+            send(&ctx.http, channel, &lines).await;
+        }
+    });
+}
+
+/// Post every waiting batch right away, e.g. because Dolphin is
+/// stopping.
+pub async fn flush(http: &Http, batches: &Mutex<HashMap<ChannelId, AnsiBatch>>) {
+    let waiting: Vec<_> = batches
+        .lock()
+        .await
+        .iter_mut()
+        .filter(|(_, batch)| !batch.lines.is_empty())
+        .map(|(channel, batch)| (*channel, batch.take_all()))
+        .collect();
+
+    for (channel, lines) in waiting {
+        send(http, channel, &lines).await;
+    }
+}
+
+async fn send(http: &Http, channel: ChannelId, lines: &[String]) {
+    let message = CreateMessage::new()
+        .content(render(lines))
+        .allowed_mentions(CreateAllowedMentions::new());
+
+    if let Err(e) = channel.send_message(http, message).await {
+        error!("ansi:send: unable to post a batch of chat: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_line, AnsiBatch, Push};
+
+    #[test]
+    fn batch_lines_until_taken() {
+        // Given
+        let mut batch = AnsiBatch::default();
+
+        // When
+        let first = batch.push(String::from("one"), 2000);
+        let second = batch.push(String::from("two"), 2000);
+
+        // Then
+        assert_eq!(first, Push::Started(0));
+        assert_eq!(second, Push::Added);
+        assert_eq!(
+            batch.take(0),
+            Some(vec![String::from("one"), String::from("two")])
+        );
+        assert_eq!(batch.take(0), None);
+    }
+
+    #[test]
+    fn post_full_batches_early() {
+        // Given
+        let mut batch = AnsiBatch::default();
+        batch.push("a".repeat(10), 30);
+
+        // When
+        let pushed = batch.push("b".repeat(10), 30);
+
+        // Then
+        assert_eq!(pushed, Push::Full(vec!["a".repeat(10)], 1));
+        assert_eq!(batch.take(0), None);
+        assert_eq!(batch.take(1), Some(vec!["b".repeat(10)]));
+    }
+
+    #[test]
+    fn cut_lines_too_long_for_a_message() {
+        // Given
+        let mut batch = AnsiBatch::default();
+
+        // When
+        batch.push("a".repeat(50), 30);
+
+        // Then
+        assert_eq!(batch.take(0), Some(vec!["a".repeat(18)]));
+    }
+
+    #[test]
+    fn players_cant_break_out_of_the_block() {
+        // Given
+        let content = "```\u{1b}[31mred";
+
+        // When
+        let line = render_line("Steve", content);
+
+        // Then
+        assert!(!line.contains("```"));
+        assert!(line.ends_with(": `\u{200b}`\u{200b}`\u{200b}[31mred"));
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use serenity::{client::Context, prelude::RwLock};
+use tracing::{error, info};
+use warp::{http::StatusCode, Filter, Rejection, Reply};
+
+use super::{
+    access::{self, Access},
+    unauthorized,
+};
+use crate::config::RootConfig;
+use crate::discord::{banner, send_to_minecraft};
+use crate::metrics::MetricsContainer;
+use crate::pause::PauseContainer;
+use crate::transport::Transport;
+
+/// The body of a `POST /api/say` request.
+#[derive(Debug, Deserialize)]
+struct Say {
+    message: String,
+}
+
+/// Who an API request is from.
+#[derive(Clone)]
+enum Caller {
+    /// Someone with the `api_token`, who can do anything.
+    Admin(Arc<RwLock<RootConfig>>),
+    /// Someone with another server's `webserver_token`, who can only
+    /// act on that server.
+    Server(Arc<RwLock<RootConfig>>),
+}
+
+impl Caller {
+    fn config(&self) -> &Arc<RwLock<RootConfig>> {
+        match self {
+            Caller::Admin(config) | Caller::Server(config) => config,
+        }
+    }
+}
+
+/// Find who an API request is from. Unlike `/message`, the API always
+/// needs a token: the `api_token` for the main server, or another
+/// server's `webserver_token` for that server. Tokens are compared in
+/// constant time.
+fn authorize(
+    servers: &HashMap<String, Arc<RwLock<RootConfig>>>,
+    main: &Arc<RwLock<RootConfig>>,
+    api_token: &str,
+    token: Option<&str>,
+) -> Option<Caller> {
+    let token = token.filter(|token| !token.is_empty())?;
+    if access::same_secret(token, api_token) {
+        return Some(Caller::Admin(main.clone()));
+    }
+
+    servers
+        .iter()
+        .find(|(server_token, _)| access::same_secret(token, server_token))
+        .map(|(_, config)| Caller::Server(config.clone()))
+}
+
+/// The tellraw command to say something in game for an API request, or
+/// `None` if there's nothing to say.
+fn say_command(message: &str) -> Option<String> {
+    let message: String = message
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let message = message.trim();
+    if message.is_empty() {
+        return None;
+    }
+
+    Some(format!("tellraw @a {}", json!({ "text": message })))
+}
+
+/// The routes for controlling Dolphin over the webserver:
+///
+/// - `GET /api/status` shows how the bridge is doing.
+/// - `POST /api/bridge/pause` and `POST /api/bridge/resume` stop and
+///   start bridging in both directions.
+/// - `POST /api/say` says something in game, with a JSON body like
+///   `{ "message": "Restarting in 5 minutes" }`.
+///
+/// Every request needs a token in the `X-Dolphin-Token` header, and
+/// the same bearer token as `/message`. The API is off unless
+/// `webserver_config.api_token` is set. Pausing affects every server, so
+/// it needs the `api_token`.
+pub fn routes(
+    ctx: Arc<Context>,
+    config_lock: Arc<RwLock<RootConfig>>,
+    servers: Arc<HashMap<String, Arc<RwLock<RootConfig>>>>,
+    api_token: String,
+    access: Arc<Access>,
+) -> impl Filter<Extract = (warp::reply::Response,), Error = Rejection> + Clone {
+    let auth = access::bearer(access)
+        .and(warp::header::optional::<String>("x-dolphin-token"))
+        .and_then(move |token: Option<String>| {
+            let caller = if api_token.is_empty() {
+                None
+            } else {
+                authorize(&servers, &config_lock, &api_token, token.as_deref())
+            };
+
+            async move {
+                match caller {
+                    Some(caller) => Ok(caller),
+                    None => Err(warp::reject::custom(Unauthorized)),
+                }
+            }
+        });
+    let with_ctx = warp::any().map(move || ctx.clone());
+
+    let status = warp::get()
+        .and(warp::path!("api" / "status"))
+        .and(auth.clone())
+        .and(with_ctx.clone())
+        .then(|caller: Caller, ctx: Arc<Context>| async move {
+            warp::reply::json(&status(&ctx, caller.config()).await).into_response()
+        });
+
+    let pause = warp::post()
+        .and(warp::path!("api" / "bridge" / String))
+        .and(auth.clone())
+        .and(with_ctx.clone())
+        .then(
+            |action: String, caller: Caller, ctx: Arc<Context>| async move {
+                if let Caller::Server(_) = caller {
+                    return warp::reply::with_status("", StatusCode::FORBIDDEN).into_response();
+                }
+
+                let paused = match action.as_str() {
+                    "pause" => true,
+                    "resume" => false,
+                    _ => {
+                        return warp::reply::with_status("", StatusCode::NOT_FOUND).into_response()
+                    }
+                };
+
+                if let Some(pause) = ctx.data.read().await.get::<PauseContainer>() {
+                    if pause.set(paused) != paused {
+                        info!(
+                            "api: bridging was {} over the API",
+                            if paused { "paused" } else { "resumed" }
+                        );
+                    }
+                }
+                warp::reply::json(&json!({ "paused": paused })).into_response()
+            },
+        );
+
+    let say = warp::post()
+        .and(warp::path!("api" / "say"))
+        .and(auth)
+        .and(warp::body::content_length_limit(1024 * 4))
+        .and(warp::body::json())
+        .then(|caller: Caller, body: Say| async move {
+            let Some(command) = say_command(&body.message) else {
+                return warp::reply::with_status("", StatusCode::BAD_REQUEST).into_response();
+            };
+
+            let transport = Transport::from_config(&*caller.config().read().await);
+            match send_to_minecraft(command, transport).await {
+                Ok(_) => warp::reply::json(&json!({ "sent": true })).into_response(),
+                Err(e) => {
+                    error!("api: unable to say something in game: {}", e);
+                    warp::reply::with_status("", StatusCode::BAD_GATEWAY).into_response()
+                }
+            }
+        });
+
+    status
+        .or(pause)
+        .unify()
+        .or(say)
+        .unify()
+        .recover(recover_unauthorized)
+        .unify()
+}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+/// Answer requests with a missing or wrong token with `401`, and let
+/// every other rejection through to the other routes.
+async fn recover_unauthorized(rejection: Rejection) -> Result<warp::reply::Response, Rejection> {
+    if rejection.find::<Unauthorized>().is_some() {
+        return Ok(unauthorized());
+    }
+    Err(rejection)
+}
+
+/// Describe how the bridge is doing, for `GET /api/status`.
+async fn status(ctx: &Context, config: &RwLock<RootConfig>) -> Value {
+    let mode = banner::bridge_mode(&*config.read().await);
+    let channel_id = config.read().await.get_channel_id();
+    let minecraft_version = banner::server_version(ctx, channel_id).await;
+    let (paused, snapshot) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<PauseContainer>()
+                .is_some_and(|pause| pause.is_paused()),
+            data.get::<MetricsContainer>()
+                .map(|metrics| metrics.get(channel_id).snapshot()),
+        )
+    };
+
+    let unix = |time: SystemTime| {
+        time.duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    };
+
+    match snapshot {
+        Some(snapshot) => json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "minecraft_version": minecraft_version,
+            "mode": mode,
+            "paused": paused,
+            "uptime_secs": snapshot.uptime.as_secs(),
+            "to_discord_today": snapshot.to_discord.messages_today,
+            "to_minecraft_today": snapshot.to_minecraft.messages_today,
+            "last_heard": snapshot.last_heard.map(unix),
+            "last_rcon_error": snapshot.last_rcon_error.map(|(error, _)| error),
+        }),
+        None => json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "minecraft_version": minecraft_version,
+            "mode": mode,
+            "paused": paused,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use serenity::prelude::RwLock;
+
+    use super::{authorize, say_command, Caller};
+    use crate::config::RootConfig;
+
+    #[test]
+    fn always_need_a_token() {
+        // Given
+        let main = Arc::new(RwLock::new(RootConfig::default()));
+        let creative = Arc::new(RwLock::new(RootConfig::default()));
+        let servers = HashMap::from([(String::from("creative-token"), creative.clone())]);
+
+        // When
+        let none = authorize(&servers, &main, "api-token", None);
+        let api = authorize(&servers, &main, "api-token", Some("api-token"));
+        let server = authorize(&servers, &main, "api-token", Some("creative-token"));
+        let wrong = authorize(&servers, &main, "api-token", Some("nope"));
+
+        // Then
+        assert!(none.is_none());
+        assert!(matches!(api, Some(Caller::Admin(config)) if Arc::ptr_eq(&config, &main)));
+        assert!(matches!(server, Some(Caller::Server(config)) if Arc::ptr_eq(&config, &creative)));
+        assert!(wrong.is_none());
+    }
+
+    #[test]
+    fn say_one_line() {
+        // Then
+        assert_eq!(
+            say_command("Restarting \"soon\"\nop Steve"),
+            Some(String::from(
+                r#"tellraw @a {"text":"Restarting \"soon\" op Steve"}"#
+            ))
+        );
+        assert_eq!(say_command(" \n "), None);
+    }
+}
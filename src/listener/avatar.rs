@@ -0,0 +1,305 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::header::{ETAG, LAST_MODIFIED};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use super::parser::is_player_name;
+use crate::template;
+
+/// How long to wait for an avatar provider to answer.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long to remember whether a provider is up before checking again.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .expect("unable to build the avatar HTTP client");
+    static ref HEALTH: Mutex<HashMap<String, (bool, Instant)>> = Mutex::new(HashMap::new());
+    static ref VERSIONS: Mutex<HashMap<String, (String, Instant)>> = Mutex::new(HashMap::new());
+}
+
+/// Fill in each avatar template in the chain that can be used for a
+/// player, in order. Templates that use a placeholder the player has no value
+/// for, like `%uuid%` when their UUID isn't known, are skipped.
+///
+/// Names and UUIDs can come from the webserver, so ones that couldn't
+/// be real are treated as unknown rather than put in a URL.
+fn candidates(templates: &[String], name: &str, uuid: &str) -> Vec<String> {
+    let name = if is_player_name(name) { name } else { "" };
+    let uuid = if uuid.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        uuid
+    } else {
+        ""
+    };
+    let vars = [("username", name), ("uuid", uuid)];
+
+    templates
+        .iter()
+        .filter(|template| !template.trim().is_empty())
+        .filter(|template| {
+            template::placeholders(template).iter().all(|placeholder| {
+                vars.iter()
+                    .any(|(var, value)| var == placeholder && !value.is_empty())
+            })
+        })
+        .map(|template| template::render(template, &vars))
+        .collect()
+}
+
+/// The scheme and host of a URL, e.g. `https://crafatar.com`.
+fn origin(url: &str) -> &str {
+    let start = url.find("://").map(|i| i + 3).unwrap_or(0);
+    match url[start..].find('/') {
+        Some(end) => &url[..start + end],
+        None => url,
+    }
+}
+
+/// Whether a provider answered recently, checking again if we haven't
+/// asked it in a while.
+async fn is_up(url: &str) -> bool {
+    let origin = origin(url).to_string();
+    if let Some((up, checked)) = HEALTH.lock().await.get(&origin) {
+        if checked.elapsed() < CHECK_INTERVAL {
+            return *up;
+        }
+    }
+
+    let up = match CLIENT.head(url).send().await {
+        Ok(response) => !response.status().is_server_error(),
+        Err(e) => {
+            debug!("avatar:is_up: unable to reach '{}': {}", origin, e);
+            false
+        }
+    };
+    if !up {
+        warn!(
+            "avatar:is_up: avatar provider '{}' looks down, trying the next one",
+            origin
+        );
+    }
+
+    HEALTH.lock().await.insert(origin, (up, Instant::now()));
+    up
+}
+
+/// Ask a provider for a fingerprint of an avatar, from its `ETag` or
+/// `Last-Modified` header.
+async fn fingerprint(url: &str) -> Option<String> {
+    let response = match CLIENT.head(url).send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            debug!(
+                "avatar:fingerprint: '{}' answered with {}",
+                url,
+                response.status()
+            );
+            return None;
+        }
+        Err(e) => {
+            debug!("avatar:fingerprint: unable to reach '{}': {}", url, e);
+            return None;
+        }
+    };
+
+    let headers = response.headers();
+    headers
+        .get(ETAG)
+        .or_else(|| headers.get(LAST_MODIFIED))
+        .and_then(|value| value.to_str().ok())
+        .map(short_hash)
+}
+
+/// The first 8 hex digits of a SHA-256 hash, enough to tell versions of
+/// an avatar apart.
+fn short_hash(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .take(4)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Add a version to a URL as a `v` query parameter.
+fn with_version(url: &str, version: &str) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}v={}", url, separator, version)
+}
+
+/// Add a version to an avatar URL that only changes when the avatar
+/// does. Discord holds on to avatars by URL for a long time, so without
+/// it a player's old skin keeps showing after they change it.
+///
+/// The avatar is checked again every `refresh`. Providers that don't
+/// say when an avatar changed get a new version every `refresh` instead.
+async fn versioned(url: String, refresh: Duration) -> String {
+    if refresh.is_zero() {
+        return url;
+    }
+
+    let cached = VERSIONS.lock().await.get(&url).cloned();
+    let version = match cached {
+        Some((version, checked)) if checked.elapsed() < refresh => version,
+        cached => {
+            let version = match fingerprint(&url).await {
+                Some(version) => version,
+                // Keep what we had rather than change it on a hiccup
+                None => match cached {
+                    Some((version, _)) => version,
+                    None => period(SystemTime::now(), refresh),
+                },
+            };
+            let mut versions = VERSIONS.lock().await;
+            // Forget avatars that haven't been needed in a while, so
+            // every player ever seen isn't kept around
+            versions.retain(|_, (_, checked)| checked.elapsed() < refresh);
+            versions.insert(url.clone(), (version.clone(), Instant::now()));
+            version
+        }
+    };
+
+    with_version(&url, &version)
+}
+
+/// Which `refresh`-long period a time falls in, as a version.
+fn period(now: SystemTime, refresh: Duration) -> String {
+    let seconds = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    format!("t{}", seconds / refresh.as_secs().max(1))
+}
+
+/// Get the avatar for a player from the first provider in the chain
+/// that can be used and is up. If none seem to be up, the last usable
+/// one is used anyway, since Discord might still get through.
+pub async fn player_avatar(
+    templates: &[String],
+    name: &str,
+    uuid: &str,
+    refresh: Duration,
+) -> Option<String> {
+    let candidates = candidates(templates, name, uuid);
+
+    for url in &candidates {
+        if is_up(url).await {
+            return Some(versioned(url.clone(), refresh).await);
+        }
+    }
+
+    match candidates.last() {
+        Some(url) => Some(versioned(url.clone(), refresh).await),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{candidates, origin, period, short_hash, with_version};
+
+    #[test]
+    fn skip_providers_without_a_uuid() {
+        // Given
+        let templates = vec![
+            String::from("https://crafatar.com/avatars/%uuid%?size=256"),
+            String::new(),
+            String::from("https://mc-heads.net/avatar/%username%/256"),
+        ];
+
+        // When
+        let known = candidates(&templates, "Steve", "8667ba71b85a4004af54457a9734eed7");
+        let unknown = candidates(&templates, "Steve", "");
+
+        // Then
+        assert_eq!(
+            known,
+            vec![
+                String::from(
+                    "https://crafatar.com/avatars/8667ba71b85a4004af54457a9734eed7?size=256"
+                ),
+                String::from("https://mc-heads.net/avatar/Steve/256"),
+            ]
+        );
+        assert_eq!(
+            unknown,
+            vec![String::from("https://mc-heads.net/avatar/Steve/256")]
+        );
+    }
+
+    #[test]
+    fn leave_out_names_that_arent_player_names() {
+        // Given
+        let templates = vec![
+            String::from("https://crafatar.com/avatars/%uuid%?size=256"),
+            String::from("https://mc-heads.net/avatar/%username%/256"),
+        ];
+
+        // When
+        let bad_name = candidates(
+            &templates,
+            "../admin?x=",
+            "8667ba71b85a4004af54457a9734eed7",
+        );
+        let bad_uuid = candidates(&templates, "Steve", "8667ba71/../x");
+
+        // Then
+        assert_eq!(
+            bad_name,
+            vec![String::from(
+                "https://crafatar.com/avatars/8667ba71b85a4004af54457a9734eed7?size=256"
+            )]
+        );
+        assert_eq!(
+            bad_uuid,
+            vec![String::from("https://mc-heads.net/avatar/Steve/256")]
+        );
+    }
+
+    #[test]
+    fn find_provider_origins() {
+        // Then
+        assert_eq!(
+            origin("https://mc-heads.net/avatar/Steve/256"),
+            "https://mc-heads.net"
+        );
+        assert_eq!(origin("https://example.com"), "https://example.com");
+    }
+
+    #[test]
+    fn add_avatar_versions() {
+        // Given
+        let version = short_hash("\"abc123\"");
+
+        // Then
+        assert_eq!(version.len(), 8);
+        assert_eq!(version, short_hash("\"abc123\""));
+        assert_ne!(version, short_hash("\"abc124\""));
+        assert_eq!(
+            with_version("https://crafatar.com/avatars/abc?size=256", "1a2b3c4d"),
+            "https://crafatar.com/avatars/abc?size=256&v=1a2b3c4d"
+        );
+        assert_eq!(
+            with_version("https://mc-heads.net/avatar/Steve/256", "1a2b3c4d"),
+            "https://mc-heads.net/avatar/Steve/256?v=1a2b3c4d"
+        );
+    }
+
+    #[test]
+    fn change_fallback_versions_once_a_period() {
+        // Given
+        let refresh = Duration::from_secs(60 * 60);
+        let start = UNIX_EPOCH + Duration::from_secs(10 * 60 * 60);
+
+        // Then
+        assert_eq!(period(start, refresh), "t10");
+        assert_eq!(period(start + Duration::from_secs(59 * 60), refresh), "t10");
+        assert_eq!(period(start + refresh, refresh), "t11");
+    }
+}
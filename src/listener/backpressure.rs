@@ -0,0 +1,91 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Limits how many messages from the webserver can be on their way to
+/// Discord at once, so a burst of posts can't pile up without end.
+#[derive(Clone)]
+pub struct Backpressure {
+    permits: Arc<Semaphore>,
+    max_pending: usize,
+    retry_after: Duration,
+}
+
+impl Backpressure {
+    pub fn new(max_pending: usize, retry_after: Duration) -> Self {
+        let max_pending = max_pending.max(1);
+        Backpressure {
+            permits: Arc::new(Semaphore::new(max_pending)),
+            max_pending,
+            retry_after,
+        }
+    }
+
+    /// Make room for `count` messages. The room is freed when the permit
+    /// is dropped. A batch bigger than the limit has to wait until
+    /// nothing else is. If it's full, returns how long the sender should
+    /// wait before trying again.
+    pub fn try_admit(&self, count: usize) -> Result<OwnedSemaphorePermit, Duration> {
+        let count = count.clamp(1, self.max_pending) as u32;
+        self.permits
+            .clone()
+            .try_acquire_many_owned(count)
+            .map_err(|_| self.retry_after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Backpressure;
+
+    #[test]
+    fn reject_when_full() {
+        // Given
+        let backpressure = Backpressure::new(2, Duration::from_secs(3));
+
+        // When
+        let first = backpressure.try_admit(1);
+        let second = backpressure.try_admit(1);
+        let third = backpressure.try_admit(1);
+
+        // Then
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        assert_eq!(third.err(), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn admit_again_once_delivered() {
+        // Given
+        let backpressure = Backpressure::new(1, Duration::from_secs(1));
+        let permit = backpressure.try_admit(1);
+
+        // When
+        drop(permit);
+
+        // Then
+        assert!(backpressure.try_admit(1).is_ok());
+    }
+
+    #[test]
+    fn batches_take_room_for_each_message() {
+        // Given
+        let backpressure = Backpressure::new(4, Duration::from_secs(1));
+        let _batch = backpressure.try_admit(3);
+
+        // Then
+        assert!(backpressure.try_admit(2).is_err());
+        assert!(backpressure.try_admit(1).is_ok());
+    }
+
+    #[test]
+    fn admit_oversized_batches_when_idle() {
+        // Given
+        let backpressure = Backpressure::new(4, Duration::from_secs(1));
+
+        // Then
+        assert!(backpressure.try_admit(10).is_ok());
+    }
+}
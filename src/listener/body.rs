@@ -0,0 +1,92 @@
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+use warp::hyper::body::Bytes;
+
+/// Content types a MessagePack body can be sent with. Anything else is
+/// read as JSON.
+const MSGPACK_TYPES: &[&str] = &[
+    "application/msgpack",
+    "application/x-msgpack",
+    "application/vnd.msgpack",
+];
+
+/// Decode a request body based on its `Content-Type`. JSON is used if
+/// there is no content type.
+pub fn decode<T: DeserializeOwned>(content_type: Option<&str>, body: &Bytes) -> Result<T, Error> {
+    let mime = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime| mime.trim().to_lowercase())
+        .unwrap_or_default();
+
+    if MSGPACK_TYPES.contains(&mime.as_str()) {
+        Ok(rmp_serde::from_slice(body)?)
+    } else {
+        Ok(serde_json::from_slice(body)?)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid JSON body: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid MessagePack body: {0}")]
+    MessagePack(#[from] rmp_serde::decode::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use warp::hyper::body::Bytes;
+
+    use super::decode;
+    use crate::listener::parser::{MessageKind, MinecraftMessage, Source};
+
+    fn expected() -> MinecraftMessage {
+        MinecraftMessage {
+            name: String::from("Steve"),
+            content: String::from("Hello"),
+            source: Source::Player,
+            uuid: String::from("1234"),
+            kind: MessageKind::Other,
+            rank: String::new(),
+        }
+    }
+
+    fn body() -> serde_json::Value {
+        json!({
+            "name": "Steve",
+            "content": "Hello",
+            "source": "Player",
+            "uuid": "1234",
+        })
+    }
+
+    #[test]
+    fn decode_json_by_default() {
+        // Given
+        let bytes = Bytes::from(body().to_string());
+
+        // When
+        let plain: MinecraftMessage = decode(None, &bytes).unwrap();
+        let json: MinecraftMessage = decode(Some("application/json"), &bytes).unwrap();
+
+        // Then
+        assert_eq!(plain, expected());
+        assert_eq!(json, expected());
+    }
+
+    #[test]
+    fn decode_msgpack() {
+        // Given
+        let bytes = Bytes::from(rmp_serde::to_vec_named(&body()).unwrap());
+
+        // When
+        let message: MinecraftMessage =
+            decode(Some("Application/MsgPack; charset=binary"), &bytes).unwrap();
+
+        // Then
+        assert_eq!(message, expected());
+        assert!(decode::<MinecraftMessage>(None, &bytes).is_err());
+    }
+}
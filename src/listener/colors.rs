@@ -0,0 +1,47 @@
+/// Coloured squares to tell players apart by, when messages are posted
+/// as the bot and all look the same otherwise.
+const SWATCHES: [&str; 8] = ["🟥", "🟧", "🟨", "🟩", "🟦", "🟪", "🟫", "⬜"];
+
+/// Pick one of `count` colours for a player. The same name always gets
+/// the same colour, whatever its case, and across restarts.
+pub fn palette_index(name: &str, count: usize) -> usize {
+    // FNV-1a, since the standard hasher can change between releases
+    let hash = name
+        .to_lowercase()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+    (hash % count as u64) as usize
+}
+
+/// The coloured square for a player.
+pub fn swatch(name: &str) -> &'static str {
+    SWATCHES[palette_index(name, SWATCHES.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{palette_index, swatch};
+
+    #[test]
+    fn same_player_same_colour() {
+        assert_eq!(swatch("Steve"), swatch("Steve"));
+        assert_eq!(swatch("Steve"), swatch("steve"));
+    }
+
+    #[test]
+    fn spread_players_over_the_palette() {
+        // Given
+        let names = ["Steve", "Alex", "Notch", "jeb_", "Dinnerbone", "Grum"];
+
+        // When
+        let mut used: Vec<_> = names.iter().map(|name| palette_index(name, 8)).collect();
+        used.sort();
+        used.dedup();
+
+        // Then
+        assert!(used.len() > 1);
+    }
+}
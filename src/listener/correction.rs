@@ -0,0 +1,103 @@
+/// A `s/old/new/` correction a player sent for their last message.
+#[derive(Debug, PartialEq)]
+pub struct Correction {
+    old: String,
+    new: String,
+    global: bool,
+}
+
+impl Correction {
+    /// Parse a chat message as a correction, like `s/teh/the/`. The last
+    /// slash can be left out, and a trailing `g` replaces every match
+    /// instead of only the first.
+    pub fn parse(content: &str) -> Option<Self> {
+        let rest = content.trim().strip_prefix("s/")?;
+        let (old, rest) = rest.split_once('/')?;
+        let (new, flags) = rest.split_once('/').unwrap_or((rest, ""));
+
+        let global = match flags {
+            "" => false,
+            "g" => true,
+            _ => return None,
+        };
+        if old.is_empty() || new.contains('/') {
+            return None;
+        }
+
+        Some(Correction {
+            old: old.to_string(),
+            new: new.to_string(),
+            global,
+        })
+    }
+
+    /// Escape both sides the same way the message they correct was
+    /// escaped when it was posted.
+    pub fn escape(self, f: impl Fn(&str) -> String) -> Self {
+        Correction {
+            old: f(&self.old),
+            new: f(&self.new),
+            global: self.global,
+        }
+    }
+
+    /// Apply the correction to a message, or `None` if the text to replace
+    /// isn't in it.
+    pub fn apply(&self, text: &str) -> Option<String> {
+        if !text.contains(&self.old) {
+            return None;
+        }
+
+        match self.global {
+            true => Some(text.replace(&self.old, &self.new)),
+            false => Some(text.replacen(&self.old, &self.new, 1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Correction;
+
+    #[test]
+    fn parse_corrections() {
+        assert_eq!(
+            Correction::parse("s/teh/the/"),
+            Some(Correction {
+                old: String::from("teh"),
+                new: String::from("the"),
+                global: false,
+            })
+        );
+        assert_eq!(
+            Correction::parse("s/teh/the"),
+            Correction::parse("s/teh/the/")
+        );
+        assert!(Correction::parse("s/a/b/g").is_some_and(|correction| correction.global));
+        assert_eq!(
+            Correction::parse("s/typo//").map(|correction| correction.new),
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn ignore_normal_chat() {
+        assert_eq!(Correction::parse("so/what/now"), None);
+        assert_eq!(Correction::parse("s//empty/"), None);
+        assert_eq!(Correction::parse("s/a/b/c/"), None);
+        assert_eq!(Correction::parse("s/a/b/x"), None);
+        assert_eq!(Correction::parse("s/missing"), None);
+    }
+
+    #[test]
+    fn apply_first_or_every_match() {
+        // Given
+        let first = Correction::parse("s/o/0/").unwrap();
+        let every = Correction::parse("s/o/0/g").unwrap();
+
+        // Then
+        assert_eq!(first.apply("foo"), Some(String::from("f0o")));
+        assert_eq!(every.apply("foo"), Some(String::from("f00")));
+        assert_eq!(first.apply("bar"), None);
+    }
+}
@@ -0,0 +1,125 @@
+use serenity::{builder::CreateEmbed, model::Colour};
+
+/// A rough grouping of death causes, so each kind of death can get its
+/// own icon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeathCategory {
+    Drowning,
+    Explosion,
+    Fall,
+    Fire,
+    Magic,
+    Mob,
+    Starvation,
+    Other,
+}
+
+impl DeathCategory {
+    /// Work out the category from the death keyword that matched.
+    pub fn from_cause(cause: &str) -> Self {
+        const CATEGORIES: &[(DeathCategory, &[&str])] = &[
+            (DeathCategory::Drowning, &["drowned"]),
+            (DeathCategory::Explosion, &["blew up", "blown up", "bang"]),
+            (
+                DeathCategory::Fall,
+                &["fell", "hit the ground", "doomed", "kinetic"],
+            ),
+            (
+                DeathCategory::Fire,
+                &[
+                    "flames",
+                    "burned",
+                    "burnt",
+                    "fire",
+                    "lava",
+                    "danger zone",
+                    "fireballed",
+                ],
+            ),
+            (DeathCategory::Magic, &["magic", "withered"]),
+            (
+                DeathCategory::Mob,
+                &[
+                    "slain",
+                    "shot",
+                    "killed",
+                    "stung",
+                    "poked",
+                    "imapled",
+                    "impaled",
+                    "pummeled",
+                    "obliterated",
+                ],
+            ),
+            (DeathCategory::Starvation, &["starved"]),
+        ];
+
+        CATEGORIES
+            .iter()
+            .find(|(_, words)| words.iter().any(|word| cause.contains(word)))
+            .map(|(category, _)| *category)
+            .unwrap_or(DeathCategory::Other)
+    }
+
+    pub fn icon(self) -> &'static str {
+        match self {
+            DeathCategory::Drowning => "🌊",
+            DeathCategory::Explosion => "💥",
+            DeathCategory::Fall => "🪂",
+            DeathCategory::Fire => "🔥",
+            DeathCategory::Magic => "🧪",
+            DeathCategory::Mob => "⚔️",
+            DeathCategory::Starvation => "🍗",
+            DeathCategory::Other => "💀",
+        }
+    }
+}
+
+/// Build an embed for a death message, with an icon for the cause and
+/// the victim's head as the thumbnail.
+pub fn death_embed(content: &str, cause: &str, victim: &str) -> CreateEmbed {
+    let category = DeathCategory::from_cause(cause);
+    let line = content.trim_start_matches(":skull: ");
+
+    let embed = CreateEmbed::new()
+        .description(format!("{} {}", category.icon(), line))
+        .color(Colour::DARK_RED);
+
+    if victim.is_empty() {
+        embed
+    } else {
+        embed.thumbnail(format!("https://mc-heads.net/avatar/{}/64", victim))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeathCategory;
+
+    #[test]
+    fn categorizes_causes() {
+        assert_eq!(
+            DeathCategory::from_cause("drowned"),
+            DeathCategory::Drowning
+        );
+        assert_eq!(
+            DeathCategory::from_cause("tried to swim in lava"),
+            DeathCategory::Fire
+        );
+        assert_eq!(
+            DeathCategory::from_cause("hit the ground"),
+            DeathCategory::Fall
+        );
+        assert_eq!(
+            DeathCategory::from_cause("blew up"),
+            DeathCategory::Explosion
+        );
+        assert_eq!(DeathCategory::from_cause("slain"), DeathCategory::Mob);
+        assert_eq!(DeathCategory::from_cause("died"), DeathCategory::Other);
+    }
+
+    #[test]
+    fn fireballs_are_fire() {
+        assert_eq!(DeathCategory::from_cause("fireballed"), DeathCategory::Fire);
+    }
+}
@@ -0,0 +1,182 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::{
+    all::ChannelId,
+    builder::{CreateEmbed, CreateMessage},
+    model::Colour,
+    prelude::{Context, Mutex, TypeMapKey},
+};
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::error;
+
+use super::parser::MessageKind;
+use crate::per_server::PerServer;
+
+/// Discord won't show embed fields longer than this.
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// Events held back to be posted together in a summary.
+#[derive(Debug, Default, PartialEq)]
+pub struct Digest {
+    joins: Vec<String>,
+    leaves: Vec<String>,
+    deaths: Vec<String>,
+}
+
+impl Digest {
+    /// Add an event to the digest. Returns false if this kind of event
+    /// isn't collected in digests and should be posted right away.
+    pub fn add(&mut self, kind: &MessageKind, content: &str) -> bool {
+        let name = || content.split(' ').next().unwrap_or(content).to_string();
+
+        match kind {
+            MessageKind::Join => self.joins.push(name()),
+            MessageKind::Leave => self.leaves.push(name()),
+            MessageKind::Death { .. } => self.deaths.push(content.to_string()),
+            _ => return false,
+        }
+
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.joins.is_empty() && self.leaves.is_empty() && self.deaths.is_empty()
+    }
+
+    /// Build the summary embed for everything in the digest.
+    pub fn embed(&self) -> CreateEmbed {
+        let mut embed = CreateEmbed::new()
+            .title("What Happened")
+            .color(Colour::BLUE);
+
+        if !self.joins.is_empty() {
+            embed = embed.field("Joined", field_value(&unique(&self.joins), ", "), false);
+        }
+        if !self.leaves.is_empty() {
+            embed = embed.field("Left", field_value(&unique(&self.leaves), ", "), false);
+        }
+        if !self.deaths.is_empty() {
+            embed = embed.field(
+                format!("Deaths ({})", self.deaths.len()),
+                field_value(&self.deaths, "\n"),
+                false,
+            );
+        }
+
+        embed
+    }
+}
+
+/// Each name once, in the order they first showed up.
+fn unique(names: &[String]) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for name in names {
+        if !unique.contains(name) {
+            unique.push(name.clone());
+        }
+    }
+
+    unique
+}
+
+/// Join the items for an embed field, leaving out any that don't fit.
+fn field_value(items: &[String], separator: &str) -> String {
+    let mut value = String::new();
+
+    for (i, item) in items.iter().enumerate() {
+        let more = format!("{}…and {} more", separator, items.len() - i);
+        let needed = separator.len() + item.len() + more.len();
+        if !value.is_empty() && value.len() + needed > MAX_FIELD_LENGTH {
+            value.push_str(&more);
+            break;
+        }
+
+        if !value.is_empty() {
+            value.push_str(separator);
+        }
+        value.push_str(item);
+    }
+
+    value
+}
+
+/// Each server's digest, posted to its own channel.
+pub struct DigestContainer;
+
+impl TypeMapKey for DigestContainer {
+    type Value = Arc<PerServer<Mutex<Digest>>>;
+}
+
+/// Take everything out of the digest of the server bridged with the
+/// channel and post it there, if anything happened.
+pub async fn post(ctx: &Context, channel: ChannelId) {
+    let digest = match ctx.data.read().await.get::<DigestContainer>() {
+        Some(digests) => digests.get(channel.get()),
+        None => return,
+    };
+
+    let digest = std::mem::take(&mut *digest.lock().await);
+    if digest.is_empty() {
+        return;
+    }
+
+    if let Err(e) = channel
+        .send_message(&ctx.http, CreateMessage::new().embed(digest.embed()))
+        .await
+    {
+        error!("digest:post: unable to post the digest: {}", e);
+    }
+}
+
+/// Post the digest every `period`.
+pub async fn post_periodically(ctx: Arc<Context>, channel: ChannelId, period: Duration) {
+    let mut ticks = interval(period);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // The first tick happens right away
+    ticks.tick().await;
+
+    loop {
+        ticks.tick().await;
+        post(&ctx, channel).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{field_value, Digest, MAX_FIELD_LENGTH};
+    use crate::listener::parser::MessageKind;
+
+    #[test]
+    fn collects_events() {
+        // Given
+        let mut digest = Digest::default();
+        let death = MessageKind::Death {
+            cause: String::from("drowned"),
+            victim: String::from("Steve"),
+        };
+
+        // When/Then
+        assert!(digest.add(&MessageKind::Join, "Steve joined the game"));
+        assert!(digest.add(&MessageKind::Leave, "Steve left the game"));
+        assert!(digest.add(&death, "Steve drowned"));
+        assert!(!digest.add(&MessageKind::Chat, "hello"));
+        assert_eq!(digest.joins, vec![String::from("Steve")]);
+        assert_eq!(digest.deaths, vec![String::from("Steve drowned")]);
+        assert!(!digest.is_empty());
+    }
+
+    #[test]
+    fn long_fields_are_cut_short() {
+        // Given
+        let names: Vec<String> = (0..500).map(|i| format!("Player{}", i)).collect();
+
+        // When
+        let value = field_value(&names, ", ");
+
+        // Then
+        assert!(value.len() <= MAX_FIELD_LENGTH);
+        assert!(value.starts_with("Player0, Player1"));
+        assert!(value.ends_with("more"));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{pin::Pin, sync::Arc};
+
+use serenity::{async_trait, client::Context, model::id::GuildId, prelude::RwLock};
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use tonic::{transport::Server, Request, Response, Status, Streaming};
+use tracing::{debug, error, info};
+
+use super::{
+    parser::{MessageKind, MinecraftMessage, Source},
+    relay, send_to_discord, Listener,
+};
+use crate::config::RootConfig;
+
+mod proto {
+    tonic::include_proto!("dolphin");
+}
+
+use proto::bridge_server::{Bridge, BridgeServer};
+
+/// Serves the gRPC `Bridge` service from `proto/dolphin.proto`, so
+/// companion plugins can stream messages both ways over one connection.
+pub struct GrpcListener {
+    port: u16,
+}
+
+impl GrpcListener {
+    pub fn new(port: u16) -> Self {
+        GrpcListener { port }
+    }
+}
+
+#[async_trait]
+impl Listener for GrpcListener {
+    async fn listen(
+        &self,
+        ctx: Arc<Context>,
+        config_lock: Arc<RwLock<RootConfig>>,
+        guild_id: Arc<GuildId>,
+    ) {
+        let service = BridgeService {
+            ctx,
+            config_lock,
+            guild_id,
+        };
+
+        info!("grpc:listen: listening for plugins on port {}", self.port);
+        if let Err(e) = Server::builder()
+            .add_service(BridgeServer::new(service))
+            .serve(([0, 0, 0, 0], self.port).into())
+            .await
+        {
+            error!("grpc:listen: the gRPC server stopped: {}", e);
+        }
+    }
+}
+
+struct BridgeService {
+    ctx: Arc<Context>,
+    config_lock: Arc<RwLock<RootConfig>>,
+    guild_id: Arc<GuildId>,
+}
+
+#[async_trait]
+impl Bridge for BridgeService {
+    type StreamStream = Pin<Box<dyn Stream<Item = Result<proto::DiscordMessage, Status>> + Send>>;
+
+    async fn stream(
+        &self,
+        request: Request<Streaming<proto::MinecraftEvent>>,
+    ) -> Result<Response<Self::StreamStream>, Status> {
+        let mut events = request.into_inner();
+        let ctx = self.ctx.clone();
+        let config_lock = self.config_lock.clone();
+        let guild_id = self.guild_id.clone();
+
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(status) => {
+                        debug!("grpc:stream: a plugin disconnected: {}", status);
+                        break;
+                    }
+                };
+
+                let message = MinecraftMessage::from(event);
+                if let Err(e) = send_to_discord(
+                    ctx.clone(),
+                    config_lock.clone(),
+                    guild_id.clone(),
+                    message,
+                    None,
+                )
+                .await
+                {
+                    error!("grpc:stream: unable to send a message to Discord: {}", e);
+                }
+            }
+        });
+
+        // Plugins that fall too far behind miss some messages
+        let messages = BroadcastStream::new(relay::subscribe())
+            .filter_map(|chat| chat.ok())
+            .map(|chat| proto::DiscordMessage {
+                name: chat.name,
+                content: chat.content,
+            })
+            .map(Ok);
+
+        Ok(Response::new(Box::pin(messages)))
+    }
+}
+
+impl From<proto::MinecraftEvent> for MinecraftMessage {
+    fn from(event: proto::MinecraftEvent) -> Self {
+        let source = match event.source() {
+            proto::Source::Player => Source::Player,
+            proto::Source::Server => Source::Server,
+        };
+
+        MinecraftMessage {
+            name: event.name,
+            content: event.content,
+            source,
+            uuid: event.uuid,
+            kind: MessageKind::default(),
+            rank: String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proto;
+    use crate::listener::parser::{MessageKind, MinecraftMessage, Source};
+
+    #[test]
+    fn convert_events_to_messages() {
+        // Given
+        let event = proto::MinecraftEvent {
+            name: String::from("Steve"),
+            content: String::from("Hello"),
+            source: proto::Source::Server as i32,
+            uuid: String::from("1234"),
+        };
+
+        // When
+        let message = MinecraftMessage::from(event);
+
+        // Then
+        assert_eq!(
+            message,
+            MinecraftMessage {
+                name: String::from("Steve"),
+                content: String::from("Hello"),
+                source: Source::Server,
+                uuid: String::from("1234"),
+                kind: MessageKind::Other,
+                rank: String::new(),
+            }
+        );
+    }
+}
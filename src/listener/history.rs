@@ -0,0 +1,144 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use serenity::{
+    all::{ChannelId, MessageId},
+    prelude::{Mutex, TypeMapKey},
+};
+
+use crate::per_server::PerServer;
+
+/// How many chat messages are remembered for each player.
+pub const MAX_REMEMBERED: usize = 50;
+
+/// A chat message we posted for a player. Long messages are posted in
+/// several parts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sent {
+    pub channel_id: ChannelId,
+    pub ids: Vec<MessageId>,
+    pub content: String,
+    /// Whether it was posted in one part to the webhook, so it can be
+    /// corrected.
+    pub editable: bool,
+}
+
+/// Remembers the last few chat messages each player had bridged, so
+/// they can correct them, and moderators can delete them. Names are
+/// matched ignoring case.
+#[derive(Debug, Default)]
+pub struct ChatHistory {
+    players: HashMap<String, VecDeque<Sent>>,
+}
+
+impl ChatHistory {
+    pub fn remember(&mut self, player: &str, sent: Sent) {
+        let messages = self.players.entry(player.to_lowercase()).or_default();
+        if messages.len() == MAX_REMEMBERED {
+            messages.pop_front();
+        }
+        messages.push_back(sent);
+    }
+
+    pub fn last(&self, player: &str) -> Option<&Sent> {
+        self.players.get(&player.to_lowercase())?.back()
+    }
+
+    /// Change what we remember of a message after editing it, if it's
+    /// still the player's last one.
+    pub fn update(&mut self, player: &str, id: MessageId, content: &str) {
+        let last = self
+            .players
+            .get_mut(&player.to_lowercase())
+            .and_then(|messages| messages.back_mut());
+        if let Some(sent) = last.filter(|sent| sent.ids == [id]) {
+            sent.content = content.to_string();
+        }
+    }
+
+    /// Forget and return up to `count` of the player's most recent
+    /// messages, newest first.
+    pub fn take_recent(&mut self, player: &str, count: usize) -> Vec<Sent> {
+        let Some(messages) = self.players.get_mut(&player.to_lowercase()) else {
+            return Vec::new();
+        };
+
+        let keep = messages.len().saturating_sub(count);
+        messages.drain(keep..).rev().collect()
+    }
+}
+
+/// The chat history of each server's players.
+pub struct ChatHistoryContainer;
+
+impl TypeMapKey for ChatHistoryContainer {
+    type Value = Arc<PerServer<Mutex<ChatHistory>>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::{ChannelId, MessageId};
+
+    use super::{ChatHistory, Sent, MAX_REMEMBERED};
+
+    fn sent(id: u64, content: &str) -> Sent {
+        Sent {
+            channel_id: ChannelId::new(1),
+            ids: vec![MessageId::new(id)],
+            content: content.to_string(),
+            editable: true,
+        }
+    }
+
+    #[test]
+    fn only_the_last_message_is_edited() {
+        // Given
+        let mut history = ChatHistory::default();
+
+        // When
+        history.remember("Steve", sent(1, "first"));
+        history.remember("Steve", sent(2, "second"));
+        history.update("Steve", MessageId::new(1), "stale edit");
+        history.update("steve", MessageId::new(2), "edited");
+
+        // Then
+        assert_eq!(history.last("STEVE"), Some(&sent(2, "edited")));
+        assert_eq!(history.last("Alex"), None);
+    }
+
+    #[test]
+    fn take_the_most_recent_messages() {
+        // Given
+        let mut history = ChatHistory::default();
+        for id in 1..=3 {
+            history.remember("Steve", sent(id, "spam"));
+        }
+
+        // When
+        let taken = history.take_recent("steve", 2);
+
+        // Then
+        assert_eq!(taken, vec![sent(3, "spam"), sent(2, "spam")]);
+        assert_eq!(history.last("Steve"), Some(&sent(1, "spam")));
+        assert_eq!(history.take_recent("Steve", 5), vec![sent(1, "spam")]);
+        assert!(history.take_recent("Alex", 5).is_empty());
+    }
+
+    #[test]
+    fn forget_the_oldest_messages() {
+        // Given
+        let mut history = ChatHistory::default();
+
+        // When
+        for id in 1..=(MAX_REMEMBERED as u64 + 1) {
+            history.remember("Steve", sent(id, "spam"));
+        }
+
+        // Then
+        let taken = history.take_recent("Steve", MAX_REMEMBERED + 1);
+        assert_eq!(taken.len(), MAX_REMEMBERED);
+        assert_eq!(taken.last(), Some(&sent(2, "spam")));
+    }
+}
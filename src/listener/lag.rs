@@ -0,0 +1,251 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use fancy_regex::Regex;
+use serenity::{builder::CreateEmbed, model::Colour};
+
+/// A "Can't keep up!" warning from the server log.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LagWarning {
+    pub behind_ms: u64,
+    pub skipped_ticks: u64,
+}
+
+/// Parse a lag warning from a line of server output. Both the older
+/// `Running 2028ms behind, skipping 40 tick(s)` and the newer
+/// `Running 2028ms or 40 ticks behind` forms are understood.
+///
+/// The warning has to come right after the server thread's `WARN` log
+/// prefix, like `[12:00:00] [Server thread/WARN]: ` or Paper's
+/// `[12:00:00 WARN]: `, so players can't fake one in chat.
+pub fn parse_warning(line: &str) -> Option<LagWarning> {
+    lazy_static! {
+        static ref LAG_REGEX: Regex = Regex::new(
+            r"^(?:\[[^\]]+\] \[Server thread/WARN\]|\[[^\]]+ WARN\]): Can't keep up!.*Running (?P<ms>\d+) ?ms (?:or (?P<ticks>\d+) ticks behind|behind, skipping (?P<skipped>\d+) tick)"
+        )
+        .unwrap();
+    }
+
+    let captures = LAG_REGEX.captures(line).ok()??;
+    let ticks = captures
+        .name("ticks")
+        .or_else(|| captures.name("skipped"))?;
+
+    Some(LagWarning {
+        behind_ms: captures["ms"].parse().ok()?,
+        skipped_ticks: ticks.as_str().parse().ok()?,
+    })
+}
+
+/// Stats about the lag warnings seen within the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LagReport {
+    pub warnings: usize,
+    pub total_ms: u64,
+    pub worst_ms: u64,
+    pub skipped_ticks: u64,
+    pub window: Duration,
+}
+
+impl LagReport {
+    pub fn embed(&self) -> CreateEmbed {
+        CreateEmbed::new()
+            .title("Server Lag")
+            .description(format!(
+                "The server fell behind {} times in the last {}",
+                self.warnings,
+                describe_window(self.window)
+            ))
+            .field("Total Behind", format!("{}ms", self.total_ms), true)
+            .field("Worst", format!("{}ms", self.worst_ms), true)
+            .field("Ticks Skipped", self.skipped_ticks.to_string(), true)
+            .color(Colour::ORANGE)
+    }
+}
+
+/// Watches for lag warnings, and says when there have been too many of
+/// them in a short time.
+#[derive(Debug)]
+pub struct LagMonitor {
+    warnings: VecDeque<(Instant, LagWarning)>,
+    threshold: usize,
+    window: Duration,
+    last_alert: Option<Instant>,
+}
+
+impl LagMonitor {
+    pub fn new(threshold: usize, window: Duration) -> Self {
+        LagMonitor {
+            warnings: VecDeque::new(),
+            threshold,
+            window,
+            last_alert: None,
+        }
+    }
+
+    /// Record a lag warning, returning a report if there have now been
+    /// at least `threshold` warnings within the window. Only one report
+    /// is made per window, so a long lag storm doesn't flood the channel.
+    pub fn record(&mut self, warning: LagWarning) -> Option<LagReport> {
+        self.record_at(warning, Instant::now())
+    }
+
+    fn record_at(&mut self, warning: LagWarning, now: Instant) -> Option<LagReport> {
+        // Forget about warnings that are too old to count
+        while let Some((oldest, _)) = self.warnings.front() {
+            if now.duration_since(*oldest) <= self.window {
+                break;
+            }
+            self.warnings.pop_front();
+        }
+
+        self.warnings.push_back((now, warning));
+
+        if self.warnings.len() < self.threshold {
+            return None;
+        }
+
+        if let Some(last_alert) = self.last_alert {
+            if now.duration_since(last_alert) < self.window {
+                return None;
+            }
+        }
+        self.last_alert = Some(now);
+
+        let warnings = self.warnings.iter().map(|(_, warning)| warning);
+        Some(LagReport {
+            warnings: self.warnings.len(),
+            total_ms: warnings.clone().map(|warning| warning.behind_ms).sum(),
+            worst_ms: warnings
+                .clone()
+                .map(|warning| warning.behind_ms)
+                .max()
+                .unwrap_or_default(),
+            skipped_ticks: warnings.map(|warning| warning.skipped_ticks).sum(),
+            window: self.window,
+        })
+    }
+}
+
+fn describe_window(window: Duration) -> String {
+    match window.as_secs() {
+        secs if secs % 60 == 0 && secs >= 60 => match secs / 60 {
+            1 => String::from("minute"),
+            minutes => format!("{} minutes", minutes),
+        },
+        1 => String::from("second"),
+        secs => format!("{} seconds", secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{describe_window, parse_warning, LagMonitor, LagReport, LagWarning};
+
+    #[test]
+    fn parse_both_warning_formats() {
+        // Given
+        let old = "[12:00:00] [Server thread/WARN]: Can't keep up! Did the system time change, or is the server overloaded? Running 2028ms behind, skipping 40 tick(s)";
+        let new = "[12:00:00] [Server thread/WARN]: Can't keep up! Is the server overloaded? Running 5123ms or 102 ticks behind";
+
+        // When
+        let old = parse_warning(old);
+        let new = parse_warning(new);
+
+        // Then
+        assert_eq!(
+            old,
+            Some(LagWarning {
+                behind_ms: 2028,
+                skipped_ticks: 40
+            })
+        );
+        assert_eq!(
+            new,
+            Some(LagWarning {
+                behind_ms: 5123,
+                skipped_ticks: 102
+            })
+        );
+        assert_eq!(
+            parse_warning("[12:00:00] [Server thread/INFO]: Steve: Running 5ms behind"),
+            None
+        );
+        assert!(parse_warning(
+            "[12:00:00 WARN]: Can't keep up! Is the server overloaded? Running 5123ms or 102 ticks behind"
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn ignore_warnings_typed_in_chat() {
+        // Given
+        let chat = "[12:00:00] [Server thread/INFO]: <Steve> Can't keep up! Did the system time change, or is the server overloaded? Running 9999ms behind, skipping 200 tick(s)";
+        let quoted = "[12:00:00] [Server thread/INFO]: <Steve> [Server thread/WARN]: Can't keep up! Running 9999ms behind, skipping 200 tick(s)";
+
+        // Then
+        assert_eq!(parse_warning(chat), None);
+        assert_eq!(parse_warning(quoted), None);
+    }
+
+    #[test]
+    fn report_once_threshold_is_reached() {
+        // Given
+        let mut monitor = LagMonitor::new(3, Duration::from_secs(60));
+        let now = Instant::now();
+        let warning = |behind_ms| LagWarning {
+            behind_ms,
+            skipped_ticks: behind_ms / 50,
+        };
+
+        // When
+        let first = monitor.record_at(warning(2000), now);
+        let second = monitor.record_at(warning(3000), now + Duration::from_secs(10));
+        let third = monitor.record_at(warning(2500), now + Duration::from_secs(20));
+        let fourth = monitor.record_at(warning(2500), now + Duration::from_secs(30));
+
+        // Then
+        assert_eq!(first, None);
+        assert_eq!(second, None);
+        assert_eq!(
+            third,
+            Some(LagReport {
+                warnings: 3,
+                total_ms: 7500,
+                worst_ms: 3000,
+                skipped_ticks: 150,
+                window: Duration::from_secs(60),
+            })
+        );
+        assert_eq!(fourth, None);
+    }
+
+    #[test]
+    fn old_warnings_dont_count() {
+        // Given
+        let mut monitor = LagMonitor::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+        let warning = LagWarning {
+            behind_ms: 2000,
+            skipped_ticks: 40,
+        };
+
+        // When
+        monitor.record_at(warning, now);
+        let report = monitor.record_at(warning, now + Duration::from_secs(61));
+
+        // Then
+        assert_eq!(report, None);
+    }
+
+    #[test]
+    fn describe_windows() {
+        assert_eq!(describe_window(Duration::from_secs(60)), "minute");
+        assert_eq!(describe_window(Duration::from_secs(300)), "5 minutes");
+        assert_eq!(describe_window(Duration::from_secs(90)), "90 seconds");
+    }
+}
@@ -1,24 +1,91 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, sync::mpsc, time::sleep};
 
-use crate::config::RootConfig;
+use crate::config::{container::ConfigPathContainer, RootConfig};
+use crate::discord::{
+    banner,
+    echo::EchoGuardContainer,
+    mentions::{Matching, MentionCacheContainer},
+    preflight::{self, Path},
+    send_to_minecraft,
+};
+use crate::emoji;
+use crate::links::LinkStoreContainer;
+use crate::metrics::{Direction, MetricsContainer};
+use crate::server::ManagedServer;
+use crate::template;
+use crate::translate;
+use crate::transport::Transport;
+use crate::unicode;
 use fancy_regex::Regex;
-use linemux::MuxedLines;
+use serde_json::json;
 use serenity::{
-    all::WebhookId,
+    all::{ChannelType, Message, MessageId, OnlineStatus, ScheduledEventType, UserId, WebhookId},
     async_trait,
-    builder::ExecuteWebhook,
+    builder::{
+        CreateAllowedMentions, CreateEmbed, CreateEmbedFooter, CreateMessage, CreateScheduledEvent,
+        EditWebhookMessage, ExecuteWebhook,
+    },
     client::Context,
     futures::StreamExt,
-    model::id::{ChannelId, GuildId},
-    prelude::RwLock,
+    model::{
+        id::{ChannelId, GuildId},
+        Colour,
+    },
+    prelude::{Mutex, RwLock},
 };
 use thiserror::Error;
-use tracing::{debug, error, info};
-use warp::Filter;
+use tracing::{debug, error, info, warn};
+use warp::{http::StatusCode, Filter, Reply};
 
-use self::parser::{MinecraftMessage, Source};
+use self::access::Access;
+use self::advancement::AdvancementTable;
+use self::backpressure::Backpressure;
+use self::correction::Correction;
+use self::digest::DigestContainer;
+use self::history::{ChatHistoryContainer, Sent};
+use self::lag::LagMonitor;
+pub use self::parser::is_player_name;
+use self::parser::{MessageKind, MinecraftMessage, Source};
+use self::relog::{Relog, RelogFilterContainer};
+use self::scheduled::EventRequest;
+use self::sessions::SessionsContainer;
+use self::spam::{Limits, Spam, SpamFilterContainer};
+use self::tailer::Watched;
 
+mod access;
+mod advancement;
+pub mod ansi;
+mod api;
+mod avatar;
+mod backpressure;
+mod body;
+mod colors;
+mod correction;
+mod death;
+pub mod digest;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod history;
+mod lag;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+mod outbound;
 mod parser;
+pub mod relay;
+pub mod relog;
+mod scheduled;
+pub mod sessions;
+pub mod spam;
+mod startup;
+mod stats;
+mod tailer;
+mod timestamp;
+mod translation;
 
 /// A Listener listens or watches for new messages from a Minecraft instance,
 /// depending on the implementation.
@@ -63,46 +130,288 @@ impl Listener for LogTailer {
         guild_id: Arc<GuildId>,
     ) {
         info!("log_tailer:listen: using log file at '{}'", self.path);
-        let config = config_lock.read().await;
-        let mut parser = parser::MessageParser::new(
+        let mut processor = LineProcessor::new(&*config_lock.read().await);
+
+        // Finish the line we're on before Dolphin exits, then stop
+        let shutdown = crate::shutdown::global();
+        let _hold = shutdown.hold();
+        let mut watched = Watched::new(self.path.clone()).await;
+
+        // Watch a different file if the path in the config file changes
+        let (commands, mut changes) = mpsc::channel(4);
+        let config_path = ctx
+            .data
+            .read()
+            .await
+            .get::<ConfigPathContainer>()
+            .cloned()
+            .expect("expected config path container in TypeMap");
+        tokio::spawn(tailer::follow_config(
+            config_lock.clone(),
+            config_path,
+            self.path.clone(),
+            commands,
+        ));
+
+        loop {
+            let line = match watched.lines() {
+                Some(lines) => tokio::select! {
+                    line = lines.next() => line,
+                    Some(command) = changes.recv() => {
+                        watched.apply(command).await;
+                        continue;
+                    }
+                    _ = shutdown.requested() => return,
+                },
+                // Nothing to read until we're told about a file
+                None => tokio::select! {
+                    command = changes.recv() => match command {
+                        Some(command) => {
+                            watched.apply(command).await;
+                            continue;
+                        }
+                        None => return,
+                    },
+                    _ = shutdown.requested() => return,
+                },
+            };
+
+            let line = match line {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => {
+                    error!("log_tailer:listen: unable to read the log file: {}", e);
+                    return;
+                }
+                None => return,
+            };
+
+            processor
+                .process(
+                    line.line(),
+                    ctx.clone(),
+                    config_lock.clone(),
+                    guild_id.clone(),
+                )
+                .await;
+        }
+    }
+}
+
+/// Reads the output of a Minecraft server that Dolphin is running
+/// itself, so there's no need to tail the log file.
+pub struct ServerOutput {
+    server: &'static ManagedServer,
+}
+
+impl ServerOutput {
+    pub fn new(server: &'static ManagedServer) -> Self {
+        ServerOutput { server }
+    }
+}
+
+#[async_trait]
+impl Listener for ServerOutput {
+    async fn listen(
+        &self,
+        ctx: Arc<Context>,
+        config_lock: Arc<RwLock<RootConfig>>,
+        guild_id: Arc<GuildId>,
+    ) {
+        let mut lines = match self.server.take_lines().await {
+            Some(lines) => lines,
+            None => {
+                error!("server_output:listen: the server output is already being read");
+                return;
+            }
+        };
+
+        let mut processor = LineProcessor::new(&*config_lock.read().await);
+        info!("server_output:listen: started reading the Minecraft server output");
+
+        while let Some(line) = lines.recv().await {
+            processor
+                .process(&line, ctx.clone(), config_lock.clone(), guild_id.clone())
+                .await;
+        }
+    }
+}
+
+/// Turns lines of Minecraft server output into messages, and sends
+/// them to Discord.
+struct LineProcessor {
+    parser: parser::MessageParser,
+    regex: String,
+    started: Instant,
+    suppression_window: Duration,
+    suppression: Suppression,
+    lag: Option<LagMonitor>,
+    unmatched_log: String,
+}
+
+impl LineProcessor {
+    fn new(config: &RootConfig) -> Self {
+        let advancements_file = config.get_advancements_file();
+        let advancements = if advancements_file.is_empty() {
+            AdvancementTable::default()
+        } else {
+            match AdvancementTable::from_file(&advancements_file) {
+                Ok(table) => table,
+                Err(e) => {
+                    warn!(
+                        "line_processor:new: unable to load advancements file '{}': {}",
+                        advancements_file, e
+                    );
+                    AdvancementTable::default()
+                }
+            }
+        };
+
+        let parser = parser::MessageParser::new(
             config.get_death_keywords(),
             config.get_death_ignore_keywords(),
+            config.get_death_translations(),
+            advancements,
+            config.death_stats_enabled(),
+            config.get_server_start_template(),
         );
 
-        // Create our log watcher
-        let mut log_watcher = MuxedLines::new().expect("Unable to create line muxer");
-        log_watcher
-            .add_file(&self.path)
-            .await
-            .expect("Unable to add the Minecraft log file to tail");
+        LineProcessor {
+            parser,
+            regex: config.get_chat_regex(),
+            unmatched_log: config.get_unmatched_log_path(),
+            started: Instant::now(),
+            suppression_window: Duration::from_secs(config.get_startup_suppression_secs()),
+            suppression: Suppression::from_config(&config.get_startup_suppression_mode()),
+            lag: match config.get_lag_alert_threshold() {
+                0 => None,
+                threshold => Some(LagMonitor::new(
+                    threshold,
+                    Duration::from_secs(config.get_lag_alert_window_secs()),
+                )),
+            },
+        }
+    }
 
-        info!("log_tailer:listen: started watching the Minecraft log file");
+    async fn process(
+        &mut self,
+        line: &str,
+        ctx: Arc<Context>,
+        config_lock: Arc<RwLock<RootConfig>>,
+        guild_id: Arc<GuildId>,
+    ) {
+        if let Some(version) = parser::parse_server_version(line) {
+            let channel_id = config_lock.read().await.get_channel_id();
+            banner::set_server_version(&ctx, channel_id, version).await;
+        }
 
-        let regex = config.get_chat_regex();
+        // Let admins know if the server keeps falling behind
+        if let Some(warning) = lag::parse_warning(line) {
+            if let Some(report) = self.lag.as_mut().and_then(|lag| lag.record(warning)) {
+                let channel_id = ChannelId::new(config_lock.read().await.get_ops_channel_id());
+                if let Err(e) = channel_id
+                    .send_message(&ctx.http, CreateMessage::new().embed(report.embed()))
+                    .await
+                {
+                    error!("line_processor:process: unable to post a lag alert: {}", e);
+                }
+            }
+            return;
+        }
 
-        // Wait for the next line
-        while let Some(Ok(line)) = log_watcher.next().await {
-            // Check if the line is something we have to send
-            let message = match parser.parse_line(line.line(), regex.clone()).await {
-                Some(message) => message,
-                None => continue,
-            };
+        // Check if the line is something we have to send
+        let message = match self.parser.parse_line(line, self.regex.clone()).await {
+            Some(message) => message,
+            None => {
+                if let Some(reason) = self.parser.take_ignored() {
+                    if let Some(metrics) = ctx.data.read().await.get::<MetricsContainer>() {
+                        let channel_id = config_lock.read().await.get_channel_id();
+                        metrics.get(channel_id).record_ignored(reason);
+                    }
+                }
+                if let Some(unmatched) = self.parser.take_unmatched() {
+                    self.log_unmatched(&unmatched).await;
+                }
+                return;
+            }
+        };
 
-            // Send the message to the Discord channel
-            if let Err(e) =
-                send_to_discord(ctx.clone(), config_lock.clone(), guild_id.clone(), message).await
-            {
-                error!(
-                    "discord:handler: unable to send a message to Discord: {}",
-                    e
-                );
-            };
+        // Skip the burst of old lines we might see right after starting
+        if self.started.elapsed() < self.suppression_window
+            && self.suppression.suppresses(&message.kind)
+        {
+            debug!(
+                "line_processor:process: suppressing message during startup window: {:?}",
+                message
+            );
+            return;
+        }
+
+        let logged_at = timestamp::parse_log_time(line)
+            .map(|time| timestamp::log_time_to_unix(time, timestamp::now()));
+
+        // Send the message to the Discord channel
+        if let Err(e) = send_to_discord(ctx, config_lock, guild_id, message, logged_at).await {
+            error!(
+                "discord:handler: unable to send a message to Discord: {}",
+                e
+            );
+        };
+    }
+
+    /// Write a line that didn't match anything to the unmatched log, if
+    /// there is one.
+    async fn log_unmatched(&self, line: &str) {
+        if self.unmatched_log.is_empty() {
+            return;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.unmatched_log)
+            .await;
+        let result = match file {
+            Ok(mut file) => file.write_all(format!("{}\n", line).as_bytes()).await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            debug!(
+                "line_processor:log_unmatched: unable to write to '{}': {}",
+                self.unmatched_log, e
+            );
+        }
+    }
+}
+
+/// What to hold back from Discord in the window right after starting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Suppression {
+    /// Only chat messages are bridged.
+    Events,
+    /// Nothing is bridged.
+    All,
+}
+
+impl Suppression {
+    fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "all" => Suppression::All,
+            _ => Suppression::Events,
+        }
+    }
+
+    fn suppresses(self, kind: &MessageKind) -> bool {
+        match self {
+            Suppression::All => true,
+            Suppression::Events => !matches!(kind, MessageKind::Chat | MessageKind::Whisper { .. }),
         }
     }
 }
 
 /// Binds to an IP address and port to listen for messages over a network.
-/// It watches for messages at the `/message` endpoint.
+/// It watches for messages at the `/message` endpoint, and for batches
+/// of them at `/messages`.
 ///
 /// # Examples
 ///
@@ -112,11 +421,22 @@ impl Listener for LogTailer {
 /// ```
 pub struct Webserver {
     port: u16,
+    servers: HashMap<String, Arc<RwLock<RootConfig>>>,
 }
 
 impl Webserver {
     pub fn new(port: u16) -> Self {
-        Webserver { port }
+        Webserver {
+            port,
+            servers: HashMap::new(),
+        }
+    }
+
+    /// Also take messages for other servers, sent with their token in
+    /// the `X-Dolphin-Token` header.
+    pub fn with_servers(mut self, servers: HashMap<String, Arc<RwLock<RootConfig>>>) -> Self {
+        self.servers = servers;
+        self
     }
 }
 
@@ -128,45 +448,237 @@ impl Listener for Webserver {
         config_lock: Arc<RwLock<RootConfig>>,
         guild_id: Arc<GuildId>,
     ) {
-        // POST /message/:msg
-        let messages = warp::post()
-            .and(warp::path("message"))
-            .and(warp::body::content_length_limit(1024 * 16))
-            .and(warp::body::json())
-            .and_then(move |message: MinecraftMessage| {
-                let ctx = ctx.clone();
-                let cfg = config_lock.clone();
-                let guild_id = guild_id.clone();
-
-                // Send the message to the Discord channel
-                async move {
-                    match send_to_discord(ctx, cfg, guild_id, message).await {
-                        Ok(()) => Ok(""),
-                        Err(e) => {
-                            error!(
-                                "discord:handler: unable to send a message to Discord: {}",
-                                e
-                            );
-                            Err(warp::reject::reject())
+        let backpressure = {
+            let config = config_lock.read().await;
+            Backpressure::new(
+                config.get_webserver_max_pending(),
+                Duration::from_secs(config.get_webserver_retry_after_secs()),
+            )
+        };
+        let servers = Arc::new(self.servers.clone());
+        let access = Arc::new(Access::from_config(&*config_lock.read().await));
+
+        // POST /message
+        let single = {
+            let (ctx, config_lock, guild_id, backpressure, servers) = (
+                ctx.clone(),
+                config_lock.clone(),
+                guild_id.clone(),
+                backpressure.clone(),
+                servers.clone(),
+            );
+            warp::post()
+                .and(warp::path("message"))
+                .and(access::bearer(access.clone()))
+                .and(warp::body::content_length_limit(1024 * 16))
+                .and(warp::header::optional::<String>("content-type"))
+                .and(warp::header::optional::<String>("x-dolphin-token"))
+                .and(warp::body::bytes())
+                .and_then(
+                    move |content_type: Option<String>, token: Option<String>, bytes| {
+                        let ctx = ctx.clone();
+                        let cfg = route(&servers, &config_lock, token.as_deref());
+                        let guild_id = guild_id.clone();
+                        let backpressure = backpressure.clone();
+
+                        // Send the message to the Discord channel
+                        async move {
+                            let Some(cfg) = cfg else {
+                                return Ok(unauthorized());
+                            };
+
+                            // Tell the sender to back off if Discord can't keep up
+                            let _permit = match backpressure.try_admit(1) {
+                                Ok(permit) => permit,
+                                Err(retry_after) => return Ok(too_many_requests(retry_after)),
+                            };
+
+                            let message: MinecraftMessage =
+                                match body::decode(content_type.as_deref(), &bytes) {
+                                    Ok(message) => message,
+                                    Err(e) => {
+                                        debug!("discord:handler: {}", e);
+                                        return Err(warp::reject::reject());
+                                    }
+                                };
+
+                            match receive(ctx, cfg, guild_id, message).await {
+                                Ok(()) => Ok("".into_response()),
+                                Err(e) => {
+                                    error!(
+                                        "discord:handler: unable to send a message to Discord: {}",
+                                        e
+                                    );
+                                    Err(warp::reject::reject())
+                                }
+                            }
+                        }
+                    },
+                )
+        };
+
+        // /api/..., for dashboards and hosting panels
+        let api = api::routes(
+            ctx.clone(),
+            config_lock.clone(),
+            servers.clone(),
+            config_lock.read().await.get_webserver_api_token(),
+            access.clone(),
+        );
+
+        // POST /messages, for sending a burst of messages at once
+        let batch = warp::post()
+            .and(warp::path("messages"))
+            .and(access::bearer(access.clone()))
+            .and(warp::body::content_length_limit(1024 * 256))
+            .and(warp::header::optional::<String>("content-type"))
+            .and(warp::header::optional::<String>("x-dolphin-token"))
+            .and(warp::body::bytes())
+            .and_then(
+                move |content_type: Option<String>, token: Option<String>, bytes| {
+                    let ctx = ctx.clone();
+                    let cfg = route(&servers, &config_lock, token.as_deref());
+                    let guild_id = guild_id.clone();
+                    let backpressure = backpressure.clone();
+
+                    async move {
+                        let Some(cfg) = cfg else {
+                            return Ok(unauthorized());
+                        };
+
+                        let messages: Vec<MinecraftMessage> =
+                            match body::decode(content_type.as_deref(), &bytes) {
+                                Ok(messages) => messages,
+                                Err(e) => {
+                                    debug!("discord:handler: {}", e);
+                                    return Err(warp::reject::reject());
+                                }
+                            };
+
+                        let _permit = match backpressure.try_admit(messages.len()) {
+                            Ok(permit) => permit,
+                            Err(retry_after) => return Ok(too_many_requests(retry_after)),
+                        };
+
+                        // Keep them in order, and don't let one bad message
+                        // stop the rest
+                        let (mut delivered, mut failed) = (0, 0);
+                        for message in messages {
+                            match receive(ctx.clone(), cfg.clone(), guild_id.clone(), message).await
+                            {
+                                Ok(()) => delivered += 1,
+                                Err(e) => {
+                                    error!(
+                                        "discord:handler: unable to send a message to Discord: {}",
+                                        e
+                                    );
+                                    failed += 1;
+                                }
+                            }
                         }
+
+                        let counts = json!({ "delivered": delivered, "failed": failed });
+                        Ok(warp::reply::json(&counts).into_response())
                     }
-                }
-            });
+                },
+            );
 
+        // Bind first, so a port that's taken gets a clear error instead
+        // of a panic
+        let shutdown = crate::shutdown::global();
         // TODO: Maybe figure out how to bind to a configurable address?
-        warp::serve(messages).run(([0, 0, 0, 0], self.port)).await
+        let routes = access::address(access)
+            .and(single.or(batch).or(api))
+            .recover(access::recover);
+        let bound = warp::serve(routes)
+            .try_bind_with_graceful_shutdown(([0, 0, 0, 0], self.port), shutdown.requested());
+        let (address, server) = match bound {
+            Ok(bound) => bound,
+            Err(e) => {
+                error!(
+                    "webserver:listen: unable to listen on port {}, it may be in use by another program or Dolphin instance. Change `webserver_config.port` to use another one: {}",
+                    self.port, e
+                );
+                return;
+            }
+        };
+
+        // Finish any requests that are in progress before Dolphin exits
+        let _hold = shutdown.hold();
+        info!("webserver:listen: listening for messages on {}", address);
+        server.await;
+    }
+}
+
+/// Send a message that came in over the webserver to Discord.
+async fn receive(
+    ctx: Arc<Context>,
+    config_lock: Arc<RwLock<RootConfig>>,
+    guild_id: Arc<GuildId>,
+    mut message: MinecraftMessage,
+) -> Result<(), Error> {
+    // Some plugins send their console colors along
+    let legacy = config_lock.read().await.strip_legacy_colors();
+    message.name = strip_colors(&message.name, legacy);
+    message.content = strip_colors(&message.content, legacy);
+
+    send_to_discord(ctx, config_lock, guild_id, message, None).await
+}
+
+/// Find the config for the server a request came from, by its token.
+/// Requests without a token are from the main server. Returns `None`
+/// for a token that no server has. Tokens are compared in constant time.
+fn route(
+    servers: &HashMap<String, Arc<RwLock<RootConfig>>>,
+    main: &Arc<RwLock<RootConfig>>,
+    token: Option<&str>,
+) -> Option<Arc<RwLock<RootConfig>>> {
+    match token {
+        None | Some("") => Some(main.clone()),
+        Some(token) => servers
+            .iter()
+            .find(|(server_token, _)| access::same_secret(token, server_token))
+            .map(|(_, config)| config.clone()),
     }
 }
 
+/// Turn away a request with a token that doesn't belong to any server.
+fn unauthorized() -> warp::reply::Response {
+    debug!("discord:handler: turning away a request with an unknown token");
+    warp::reply::with_status("", StatusCode::UNAUTHORIZED).into_response()
+}
+
+/// Tell a sender to wait before posting again, because too many
+/// messages are already waiting to go to Discord.
+fn too_many_requests(retry_after: Duration) -> warp::reply::Response {
+    debug!("discord:handler: too many pending messages, turning a request away");
+    let reply = warp::reply::with_status("", StatusCode::TOO_MANY_REQUESTS);
+    warp::reply::with_header(
+        reply,
+        "Retry-After",
+        retry_after.as_secs().max(1).to_string(),
+    )
+    .into_response()
+}
+
+/// Held while posting the parts of a message to the webhook.
+static WEBHOOK_ORDER: Mutex<()> = Mutex::const_new(());
+
 /// Post a message to the configured Discord webhook.
 /// If the message is from a player, we will execute the
 /// webhook with that player's head as the avatar and their
 /// in-game name as the username.
+///
+/// If `wait` is true, Discord waits for the message to be posted and
+/// returns every part of it.
 async fn post_to_webhook(
     ctx: Arc<Context>,
+    config: &RootConfig,
     message: MinecraftMessage,
     url: &str,
-) -> Result<(), Error> {
+    wait: bool,
+    allow_everyone: bool,
+) -> Result<Vec<Message>, Error> {
     // Split the url into the webhook id an token
     let parts = match split_webhook_url(url) {
         Some(parts) => parts,
@@ -181,24 +693,88 @@ async fn post_to_webhook(
 
     // Get the avatar URL
     let avatar_url = match message.source {
-        Source::Player => format!(
-            "https://crafatar.com/avatars/{}?size=256",
-            message.uuid.clone()
-        ),
-        // TODO: Do something better than a blind unwrap() here
-        Source::Server => ctx.cache.current_user().avatar_url().unwrap(),
+        Source::Player => {
+            match avatar_override(&config.get_avatar_overrides(), &message.name, &message.uuid) {
+                Some(url) => Some(url),
+                None => {
+                    avatar::player_avatar(
+                        &config.get_avatar_providers(),
+                        &message.name,
+                        &message.uuid,
+                        Duration::from_secs(config.get_avatar_refresh_mins() * 60),
+                    )
+                    .await
+                }
+            }
+        }
+        // Bots without an avatar get Discord's default one
+        Source::Server => Some(configured_or(config.get_server_avatar_url(), || {
+            ctx.cache.current_user().face()
+        })),
+    };
+
+    // Make sure players can't pass themselves off as someone else
+    let username = match message.source {
+        Source::Player => {
+            let name = sanitize_webhook_username(
+                &message.name,
+                &config.get_reserved_names(),
+                &config.get_reserved_name_suffix(),
+            );
+            let display = player_display_name(
+                &config.get_player_name_template(),
+                &name,
+                &strip_name_markdown(&message.rank),
+            );
+            match display.trim() {
+                "" => name,
+                display => display.chars().take(80).collect(),
+            }
+        }
+        Source::Server => message.name.clone(),
     };
 
     // Build the post content
-    let content = ExecuteWebhook::new()
-        .avatar_url(avatar_url)
-        .username(message.name)
-        .content(message.content);
+    let mut content = ExecuteWebhook::new()
+        .username(username)
+        .allowed_mentions(allowed_mentions(allow_everyone));
+    if let Some(avatar_url) = avatar_url {
+        content = content.avatar_url(avatar_url);
+    }
+
+    if let Some(embed) = build_embed(config, &message) {
+        return Ok(webhook
+            .execute(&ctx.http, wait, content.embed(embed))
+            .await?
+            .into_iter()
+            .collect());
+    }
+
+    // Post to the webhook, split up if it's too long for one message
+    let parts = split_message(
+        &message.content,
+        config.get_max_message_length(),
+        &config.get_continuation_marker(),
+    );
+    let count = parts.len();
 
-    // Post to the webhook
-    webhook.execute(&ctx.http, false, content).await?;
+    // Keep other messages from landing between the parts
+    let _order = WEBHOOK_ORDER.lock().await;
 
-    Ok(())
+    let mut sent = Vec::new();
+    for (index, part) in parts.into_iter().enumerate() {
+        sent.extend(
+            webhook
+                .execute(
+                    &ctx.http,
+                    wait_for_part(wait, index, count),
+                    content.clone().content(part),
+                )
+                .await?,
+        );
+    }
+
+    Ok(sent)
 }
 
 /// Send a message from a Minecraft server to a configured Discord channel, either
@@ -212,121 +788,1504 @@ async fn send_to_discord(
     config_lock: Arc<RwLock<RootConfig>>,
     guild_id: Arc<GuildId>,
     mut message: MinecraftMessage,
+    logged_at: Option<i64>,
 ) -> Result<(), Error> {
     debug!(
         "dolphin:send_to_discord: received a message from a Minecraft instance: {:?}",
         message
     );
+    let received = Instant::now();
+
+    if crate::pause::is_paused(&ctx).await {
+        debug!("dolphin:send_to_discord: bridging is paused, dropping the message");
+        return Ok(());
+    }
 
     let config = config_lock.read().await;
 
+    if let Some(metrics) = ctx.data.read().await.get::<MetricsContainer>() {
+        metrics.get(config.get_channel_id()).record_heard();
+    }
+
+    // Some communities don't allow what players say to leave the server
+    if config.events_only() && carries_content(&message.kind) {
+        debug!("dolphin:send_to_discord: only events are bridged, dropping the message");
+        return Ok(());
+    }
+
     // Set the source name to that of the bot if it's a server message
     if message.source == Source::Server {
-        message.name.clone_from(&ctx.cache.current_user().name);
+        message.name = configured_or(config.get_server_display_name(), || {
+            ctx.cache.current_user().name.clone()
+        });
     }
 
-    // Optionally replace mentions in the message
-    if config.mentions_allowed() {
-        if let Err(e) = message.replace_mentions(ctx.clone(), guild_id) {
-            return Err(Error::Parser(e));
-        };
+    // Strip characters that could be used to spoof names
+    if config.normalize_unicode() {
+        message.name = unicode::normalize(&message.name);
+        message.content = unicode::normalize(&message.content);
     }
 
-    // Check if we should use a webhook to post the message
-    let webhook_url = config.webhook_url();
-    if !webhook_url.is_empty() {
-        post_to_webhook(ctx.clone(), message, &webhook_url).await?
-    } else {
-        // Send the message to the channel
-        let final_msg = match message.source {
-            Source::Player => format!("**{}**: {}", message.name, message.content),
-            Source::Server => message.content,
-        };
+    // Whispers go to a single user instead of the channel
+    if let MessageKind::Whisper { recipient } = &message.kind {
+        return send_whisper(&ctx, &config, &guild_id, &message, recipient).await;
+    }
 
-        let id = config.get_channel_id();
-        ChannelId::new(id).say(&ctx, final_msg).await?;
+    // Plugins could echo what we sent from Discord back into chat
+    if let Some(echoes) = ctx.data.read().await.get::<EchoGuardContainer>() {
+        let echoes = echoes.get(config.get_channel_id());
+        if message.kind == MessageKind::Chat
+            && echoes
+                .to_minecraft
+                .is_relayed(&message.name, &message.content)
+        {
+            debug!("dolphin:send_to_discord: skipping an echo of a bridged message");
+            return Ok(());
+        }
+
+        echoes.to_discord.remember(&message.name, &message.content);
     }
 
-    Ok(())
-}
+    // Get the server in sync with the bridge being up
+    let startup_commands = startup::expand(&config.get_startup_commands(), &message.kind);
+    if !startup_commands.is_empty() {
+        tokio::spawn(startup::run(
+            Transport::from_config(&config),
+            startup_commands,
+        ));
+    }
 
-/// Use Regex to split the configured webhook URL into an ID and a token.
-/// If the input url doesn't match the regex, [None] will be returned. No
-/// validation is done to see if the webhook URL is actually a valid and
-/// active Discord webhook.
-///
-/// # Examples
-///
-/// ```rust
-/// let webhook_url = String::from("https://discord.com/api/webhooks/12345/67890");
-/// let webhook_parts = split_webhook_url(&webhook_url);
-///
-/// assert!(webhook_parts.is_some());
-/// assert_eq!(webhook_parts.unwrap().0, 12345);
-/// assert_eq!(webhook_parts.unwrap().1, 67890);
-/// ```
-pub fn split_webhook_url(url: &str) -> Option<(u64, &str)> {
-    // Only compile the regex once, since this is expensive
-    lazy_static! {
-        static ref WEBHOOK_REGEX: Regex =
-            Regex::new(r"^https://discord.com/api/webhooks/(?P<id>.*)/(?P<token>.*)$").unwrap();
+    // Fix up the player's last message instead of posting the correction
+    if config.corrections_enabled()
+        && message.kind == MessageKind::Chat
+        && message.source == Source::Player
+    {
+        if let Some(correction) = Correction::parse(&message.content) {
+            if correct_last_message(&ctx, &config, &message.name, correction).await? {
+                return Ok(());
+            }
+        }
     }
 
-    let mut ret = None;
+    // Let some players schedule Discord events from game
+    if message.kind == MessageKind::Chat && message.source == Source::Player {
+        let allowed = config
+            .get_event_players()
+            .iter()
+            .any(|player| player.eq_ignore_ascii_case(&message.name));
+        if let Some(request) =
+            EventRequest::parse(&message.content, timestamp::now()).filter(|_| allowed)
+        {
+            schedule_event(&ctx, &config, &guild_id, &message.name, request).await;
+            return Ok(());
+        }
+    }
 
-    if let Ok(Some(captures)) = WEBHOOK_REGEX.captures(url) {
-        if captures.len() != 3 {
-            return None;
+    // Fold repeats of the same message into the first post
+    let spam_limits = Limits {
+        window: Duration::from_secs(config.get_spam_window_secs()),
+        mute_threshold: config.get_spam_mute_threshold(),
+        mute_for: Duration::from_secs(config.get_spam_mute_secs()),
+    };
+    if message.kind == MessageKind::Chat
+        && message.source == Source::Player
+        && !spam_limits.window.is_zero()
+    {
+        let spam = ctx.data.read().await.get::<SpamFilterContainer>().cloned();
+        if let Some(spam) = spam {
+            let check = spam.get(config.get_channel_id()).lock().await.check(
+                &message.name,
+                &message.content,
+                &spam_limits,
+                Instant::now(),
+            );
+            match check {
+                Spam::Send => {}
+                Spam::Repeat(count) => {
+                    return collapse_repeat(&ctx, &config, &message.name, count).await;
+                }
+                Spam::Muted => {
+                    debug!(
+                        "dolphin:send_to_discord: dropping a message from muted player {}",
+                        message.name
+                    );
+                    return Ok(());
+                }
+            }
         }
+    }
 
-        let id = captures.name("id").unwrap().as_str();
-        if let Ok(id) = id.parse::<u64>() {
-            ret = Some((id, captures.name("token").unwrap().as_str()));
+    // Keep track of who's online for the player list
+    if let Some(sessions) = ctx.data.read().await.get::<SessionsContainer>() {
+        sessions
+            .get(config.get_channel_id())
+            .lock()
+            .await
+            .update(&message.kind, &message.content);
+    }
+
+    // Let other integrations know about it
+    outbound::fan_out(&config, &message, logged_at.unwrap_or_else(timestamp::now));
+
+    // Some kinds of messages can be turned off for Discord altogether
+    if !announced(&config, &message.kind) {
+        debug!(
+            "dolphin:send_to_discord: not announcing a message of kind {:?}",
+            message.kind
+        );
+        return Ok(());
+    }
+
+    // Hold back leaves for a bit in case the player is just relogging
+    let relog_window = Duration::from_secs(config.get_relog_window_secs());
+    let relogs = ctx
+        .data
+        .read()
+        .await
+        .get::<RelogFilterContainer>()
+        .map(|relogs| relogs.get(config.get_channel_id()));
+    if let Some(relogs) = relogs.filter(|_| !relog_window.is_zero()) {
+        let check = relogs.lock().await.check(&message.kind, &message.content);
+        match check {
+            Relog::Send => {}
+            Relog::Hold(name, id) => {
+                let config_lock = config_lock.clone();
+                tokio::spawn(async move {
+                    sleep(relog_window).await;
+                    if !relogs.lock().await.release(&name, id) {
+                        return;
+                    }
+
+                    let config = config_lock.read().await;
+                    if let Err(e) =
+                        deliver(ctx, &config, guild_id, message, logged_at, Instant::now()).await
+                    {
+                        error!(
+                            "dolphin:send_to_discord: unable to send a leave message: {}",
+                            e
+                        );
+                    }
+                });
+                return Ok(());
+            }
+            Relog::Reconnected(_) if !config.relog_note() => return Ok(()),
+            Relog::Reconnected(name) => message.content = format!("{} reconnected", name),
         }
     }
 
-    ret
+    deliver(ctx, &config, guild_id, message, logged_at, received).await
 }
 
-#[derive(Debug, Error)]
-pub enum Error {
-    #[error("Discord error: {0}")]
-    Discord(#[from] serenity::Error),
+/// Post a message from Minecraft to Discord, once it's been checked and
+/// is ready to go.
+async fn deliver(
+    ctx: Arc<Context>,
+    config: &RootConfig,
+    guild_id: Arc<GuildId>,
+    mut message: MinecraftMessage,
+    logged_at: Option<i64>,
+    received: Instant,
+) -> Result<(), Error> {
+    // Only some players get to ping everyone
+    let allow_everyone = can_mention_everyone(config, &message);
 
-    #[error("parser error: {0}")]
-    Parser(#[from] parser::Error),
+    // Mirrors are in other guilds, so they get the message without
+    // this guild's mentions
+    let mirrors = config.get_mirrors();
+    let original = message.clone();
 
-    #[error("webhook error: {0}")]
-    Webhook(String),
-}
+    translate_chat(config, &mut message, &config.get_discord_language()).await;
 
-#[cfg(test)]
-mod tests {
-    use crate::listener::split_webhook_url;
+    // Code blocks don't show markdown, mentions, or timestamps, so chat
+    // for them is kept as it is
+    let ansi_line = (config.ansi_chat_enabled()
+        && message.kind == MessageKind::Chat
+        && message.source == Source::Player)
+        .then(|| {
+            let name = player_display_name(
+                &config.get_player_name_template(),
+                &message.name,
+                &message.rank,
+            );
+            ansi::render_line(&name, &message.content)
+        });
 
-    #[test]
-    fn parse_parts_from_webhook_url() {
-        // Given
-        let input = String::from("https://discord.com/api/webhooks/12345/67890");
+    // Optionally replace mentions in the message
+    let mut mentioned = Vec::new();
+    if config.mentions_allowed() {
+        let index = ctx
+            .data
+            .read()
+            .await
+            .get::<MentionCacheContainer>()
+            .and_then(|mentions| mentions.get(&ctx.cache, *guild_id));
+        match index {
+            Some(index) => {
+                let matching = Matching::from_config(&config.get_mention_matching());
+                mentioned = message.replace_mentions(&index, allow_everyone, matching);
+            }
+            None => return Err(Error::Parser(parser::Error::NoGuild(*guild_id))),
+        };
+    }
 
-        // When/Then
-        match split_webhook_url(&input) {
-            Some((id, token)) => {
-                assert_eq!(id, 12345);
-                assert_eq!(token, String::from("67890"));
+    // Let mentioned users know if they aren't around to see it
+    if config.mention_dms_enabled() && !mentioned.is_empty() {
+        notify_mentioned(&ctx, &guild_id, &message, mentioned).await;
+    }
+
+    // Keep names and content from Minecraft from turning into markdown
+    let escape = match message.kind {
+        MessageKind::Chat => config.escape_chat_markdown(),
+        _ => config.escape_event_markdown(),
+    };
+    if escape {
+        message.content = escape_markdown(&message.content);
+    }
+
+    // Players can't pick emoji in game, so they type them as `:name:`
+    if config.emoji_shortcodes_enabled() && message.kind == MessageKind::Chat {
+        let custom: Vec<(String, String)> = guild_id
+            .to_guild_cached(&ctx.cache)
+            .map(|guild| {
+                guild
+                    .emojis
+                    .values()
+                    .filter(|emoji| emoji.available)
+                    .map(|emoji| (emoji.name.clone(), emoji.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        message.content = emoji::from_shortcodes(&message.content, |name| {
+            custom
+                .iter()
+                .find(|(custom, _)| custom == name)
+                .map(|(_, emoji)| emoji.clone())
+        });
+    }
+
+    // Hold back events for the digest
+    if config.digest_enabled() {
+        if let Some(digests) = ctx.data.read().await.get::<DigestContainer>() {
+            let digest = digests.get(config.get_channel_id());
+            if digest.lock().await.add(&message.kind, &message.content) {
+                return Ok(());
             }
-            None => panic!("failed to parse Discord webhook url"),
         }
     }
 
-    #[test]
-    fn parse_non_webhook_url() {
-        // Given
-        let input = String::from("https://example.com");
+    // Show when it happened in each user's own time zone
+    let time = config.timestamps_enabled().then(|| {
+        timestamp::discord_timestamp(
+            logged_at.unwrap_or_else(timestamp::now),
+            &config.get_timestamp_style(),
+        )
+    });
+    if let Some(time) = &time {
+        message.content = format!("{} {}", time, message.content);
+    }
 
-        // When/Then
-        if let Some(_) = split_webhook_url(&input) {
-            panic!("webhook split returned something when it should have returned None");
-        }
+    // Keep track of how long messages are, to help pick a max length
+    let parts = split_message(
+        &message.content,
+        config.get_max_message_length(),
+        &config.get_continuation_marker(),
+    );
+    let split = parts.len() > 1;
+    if let Some(metrics) = ctx.data.read().await.get::<MetricsContainer>() {
+        metrics.get(config.get_channel_id()).record_size(
+            Direction::ToDiscord,
+            message.content.chars().count(),
+            split,
+        );
+    }
+
+    let publish = should_publish(config, &message.kind);
+
+    // Advancements and deaths can have channels of their own. The
+    // webhook belongs to the bridge channel, so they're posted as the bot.
+    let dedicated = event_channel(config, &message.kind);
+    let (channel, webhook_url) = match dedicated {
+        Some(channel) => (ChannelId::new(channel), String::new()),
+        None => (
+            ChannelId::new(config.get_channel_id()),
+            config.webhook_url(),
+        ),
+    };
+
+    // Remember player chat so it can be corrected or purged later. Only
+    // messages posted in one piece to the webhook can be corrected.
+    let remember = (message.kind == MessageKind::Chat && message.source == Source::Player)
+        .then(|| (message.name.clone(), message.content.clone()));
+
+    let sent = match ansi_line {
+        _ if dedicated.is_none() && !routed(&config.get_channel_events(), &message.kind) => {
+            Vec::new()
+        }
+        Some(line) => {
+            ansi::queue(&ctx, config, channel, line).await;
+            Vec::new()
+        }
+        None => {
+            post(
+                &ctx,
+                config,
+                message,
+                channel,
+                &webhook_url,
+                publish || remember.is_some(),
+                allow_everyone,
+            )
+            .await?
+        }
+    };
+
+    if let Some((player, content)) = remember.filter(|_| !sent.is_empty()) {
+        if let Some(history) = ctx.data.read().await.get::<ChatHistoryContainer>() {
+            let sent = Sent {
+                channel_id: sent[0].channel_id,
+                ids: sent.iter().map(|part| part.id).collect(),
+                content,
+                editable: sent.len() == 1 && sent[0].webhook_id.is_some(),
+            };
+            history
+                .get(config.get_channel_id())
+                .lock()
+                .await
+                .remember(&player, sent);
+        }
+    }
+
+    // Copy it to the mirrored communities. A mirror that can't be
+    // reached shouldn't stop the others.
+    for (tag, mirror_channel, mirror_webhook, language) in mirrors {
+        if !routed(&config.get_mirror_events(&tag), &original.kind) {
+            continue;
+        }
+
+        let mut mirrored = original.clone();
+        translate_chat(config, &mut mirrored, &language).await;
+        if escape {
+            mirrored.content = escape_markdown(&mirrored.content);
+        }
+        if let Some(time) = &time {
+            mirrored.content = format!("{} {}", time, mirrored.content);
+        }
+
+        if let Err(e) = post(
+            &ctx,
+            config,
+            mirrored,
+            ChannelId::new(mirror_channel),
+            &mirror_webhook,
+            false,
+            false,
+        )
+        .await
+        {
+            error!(
+                "dolphin:send_to_discord: unable to send a message to the '{}' mirror: {}",
+                tag, e
+            );
+        }
+    }
+
+    // Crosspost to following servers if this is an Announcement channel
+    if let Some(sent) = sent.last().filter(|_| publish) {
+        let is_news = ctx
+            .cache
+            .guild(*guild_id)
+            .and_then(|guild| guild.channels.get(&channel).map(|c| c.kind))
+            == Some(ChannelType::News);
+
+        if is_news {
+            sent.crosspost(&ctx).await?;
+        } else {
+            debug!(
+                "dolphin:send_to_discord: not publishing, channel is not an Announcement channel"
+            );
+        }
+    }
+
+    if let Some(metrics) = ctx.data.read().await.get::<MetricsContainer>() {
+        metrics
+            .get(config.get_channel_id())
+            .record_delivery(Direction::ToDiscord, received.elapsed());
+    }
+
+    Ok(())
+}
+
+/// Whether a channel that wants `events` gets a kind of message. Every
+/// channel gets everything if its events are empty, and messages that
+/// aren't one of the events only go to those channels.
+fn routed(events: &[String], kind: &MessageKind) -> bool {
+    events.is_empty()
+        || outbound::event_name(kind).is_some_and(|name| events.iter().any(|event| event == name))
+}
+
+/// The channel a kind of message is posted in instead of the bridge
+/// channel, if it has one of its own.
+fn event_channel(config: &RootConfig, kind: &MessageKind) -> Option<u64> {
+    let channel = match kind {
+        MessageKind::Advancement { .. } => config.get_advancement_channel_id(),
+        MessageKind::Death { .. } => config.get_death_channel_id(),
+        _ => 0,
+    };
+
+    (channel != 0).then_some(channel)
+}
+
+/// Whether a kind of message should be posted in Discord at all.
+fn announced(config: &RootConfig, kind: &MessageKind) -> bool {
+    match kind {
+        MessageKind::Join => config.announce_joins(),
+        MessageKind::Leave => config.announce_leaves(),
+        MessageKind::Death { .. } => config.announce_deaths(),
+        MessageKind::Advancement { .. } => config.announce_advancements(),
+        MessageKind::ServerStarted { .. } | MessageKind::ServerStart | MessageKind::ServerStop => {
+            config.announce_server_status()
+        }
+        MessageKind::Chat | MessageKind::Whisper { .. } | MessageKind::Other => true,
+    }
+}
+
+/// Whether a kind of message has something a player said in it. Other
+/// messages could be anything, so they count too.
+fn carries_content(kind: &MessageKind) -> bool {
+    matches!(
+        kind,
+        MessageKind::Chat | MessageKind::Whisper { .. } | MessageKind::Other
+    )
+}
+
+/// Machine translate a chat message into the given language, keeping
+/// the original after it.
+async fn translate_chat(config: &RootConfig, message: &mut MinecraftMessage, language: &str) {
+    if message.kind != MessageKind::Chat {
+        return;
+    }
+
+    let source = config.get_minecraft_language();
+    if let Some(translated) =
+        translate::translate(config, &message.content, &source, language).await
+    {
+        message.content = translate::with_original(&translated, &message.content);
+    }
+}
+
+/// Post a message to a channel, or through the webhook if one is set
+/// and it can be used, returning the message if Discord sends it back.
+/// Nothing is posted if neither works.
+async fn post(
+    ctx: &Arc<Context>,
+    config: &RootConfig,
+    message: MinecraftMessage,
+    channel: ChannelId,
+    webhook_url: &str,
+    wait: bool,
+    allow_everyone: bool,
+) -> Result<Vec<Message>, Error> {
+    // Check if we should use a webhook to post the message
+    match preflight::choose(ctx, channel, webhook_url).await {
+        Path::Webhook => {
+            return post_to_webhook(
+                ctx.clone(),
+                config,
+                message,
+                webhook_url,
+                wait,
+                allow_everyone,
+            )
+            .await;
+        }
+        Path::Channel => {}
+        Path::Neither => {
+            debug!(
+                "dolphin:post: dropping a message, there's no way to post to {}",
+                channel
+            );
+            return Ok(Vec::new());
+        }
+    }
+
+    if let Some(embed) = build_embed(config, &message) {
+        return Ok(vec![
+            channel
+                .send_message(ctx, CreateMessage::new().embed(embed))
+                .await?,
+        ]);
+    }
+
+    // Send the message to the channel
+    let swatch = match config.name_swatches() {
+        true => format!("{} ", colors::swatch(&message.name)),
+        false => String::new(),
+    };
+    let final_msg = match message.source {
+        Source::Player if config.escape_event_markdown() => format!(
+            "{}**{}**: {}",
+            swatch,
+            player_display_name(
+                &config.get_player_name_template(),
+                &escape_markdown(&message.name),
+                &escape_markdown(&message.rank),
+            ),
+            message.content
+        ),
+        Source::Player => format!(
+            "{}**{}**: {}",
+            swatch,
+            player_display_name(
+                &config.get_player_name_template(),
+                &message.name,
+                &message.rank,
+            ),
+            message.content
+        ),
+        Source::Server => message.content,
+    };
+
+    // Split it up if it's too long for one message
+    let mut sent = Vec::new();
+    for part in split_message(
+        &final_msg,
+        config.get_max_message_length(),
+        &config.get_continuation_marker(),
+    ) {
+        let part = CreateMessage::new()
+            .content(part)
+            .allowed_mentions(allowed_mentions(allow_everyone));
+        sent.push(channel.send_message(ctx, part).await?);
+    }
+
+    Ok(sent)
+}
+
+/// Apply a player's correction to the last chat message they had
+/// bridged, by editing it through the webhook. Returns `false` if there
+/// was nothing to correct, so the correction is posted as normal chat.
+async fn correct_last_message(
+    ctx: &Context,
+    config: &RootConfig,
+    player: &str,
+    correction: Correction,
+) -> Result<bool, Error> {
+    let Some(history) = ctx.data.read().await.get::<ChatHistoryContainer>().cloned() else {
+        return Ok(false);
+    };
+    let history = history.get(config.get_channel_id());
+    let Some(last) = history.lock().await.last(player).cloned() else {
+        return Ok(false);
+    };
+    let (true, [message_id]) = (last.editable, last.ids.as_slice()) else {
+        return Ok(false);
+    };
+
+    // The posted message was escaped, so the correction has to be too
+    let correction = match config.escape_chat_markdown() {
+        true => correction.escape(escape_markdown),
+        false => correction,
+    };
+    let Some(content) = correction.apply(&last.content) else {
+        return Ok(false);
+    };
+    if content.chars().count() > config.get_max_message_length() {
+        return Ok(false);
+    }
+
+    edit_webhook_message(ctx, config, *message_id, &content).await?;
+
+    history.lock().await.update(player, *message_id, &content);
+    Ok(true)
+}
+
+/// Create a Discord scheduled event a player asked for, and tell them
+/// how it went in game.
+async fn schedule_event(
+    ctx: &Context,
+    config: &RootConfig,
+    guild_id: &GuildId,
+    player: &str,
+    request: Result<EventRequest, scheduled::Error>,
+) {
+    let reply = match request {
+        Ok(request) => {
+            let builder = CreateScheduledEvent::new(
+                ScheduledEventType::External,
+                &request.name,
+                request.start,
+            )
+            .end_time(request.end)
+            .location("Minecraft")
+            .description(format!("Scheduled by {} in game", player));
+            match guild_id.create_scheduled_event(&ctx.http, builder).await {
+                Ok(event) => {
+                    info!("{} scheduled the Discord event '{}'", player, event.name);
+                    let url = format!("https://discord.com/events/{}/{}", guild_id, event.id);
+                    json!({
+                        "text": format!("Scheduled '{}' in Discord: ", event.name),
+                        "color": "green",
+                        "extra": [{
+                            "text": url,
+                            "underlined": true,
+                            "clickEvent": { "action": "open_url", "value": url },
+                        }],
+                    })
+                }
+                Err(e) => {
+                    error!(
+                        "dolphin:schedule_event: unable to create a scheduled event: {}",
+                        e
+                    );
+                    json!({ "text": "Couldn't schedule the event in Discord", "color": "red" })
+                }
+            }
+        }
+        Err(e) => json!({ "text": e.to_string(), "color": "red" }),
+    };
+
+    let command = format!("tellraw {} {}", player, reply);
+    if let Err(e) = send_to_minecraft(command, Transport::from_config(config)).await {
+        error!("dolphin:schedule_event: unable to reply in game: {}", e);
+    }
+}
+
+/// Show how many times a player repeated their last message on its
+/// post, e.g. `buy my shop (x3)`. Repeats are dropped if the post can't
+/// be edited.
+async fn collapse_repeat(
+    ctx: &Context,
+    config: &RootConfig,
+    player: &str,
+    count: u32,
+) -> Result<(), Error> {
+    let Some(history) = ctx.data.read().await.get::<ChatHistoryContainer>().cloned() else {
+        return Ok(());
+    };
+    let history = history.get(config.get_channel_id());
+    let Some(last) = history.lock().await.last(player).cloned() else {
+        return Ok(());
+    };
+    let (true, [message_id]) = (last.editable, last.ids.as_slice()) else {
+        debug!(
+            "dolphin:collapse_repeat: dropping repeat {} from {}",
+            count, player
+        );
+        return Ok(());
+    };
+
+    // The history keeps the original, so the count is replaced each time
+    let content = format!("{} (x{})", last.content, count);
+    if content.chars().count() > config.get_max_message_length() {
+        return Ok(());
+    }
+
+    edit_webhook_message(ctx, config, *message_id, &content).await
+}
+
+/// Replace the content of a message that was posted through the webhook.
+async fn edit_webhook_message(
+    ctx: &Context,
+    config: &RootConfig,
+    message_id: MessageId,
+    content: &str,
+) -> Result<(), Error> {
+    let url = config.webhook_url();
+    let Some((id, token)) = split_webhook_url(&url) else {
+        return Err(Error::Webhook(String::from("invalid webhook url")));
+    };
+    let webhook = ctx
+        .http
+        .get_webhook_with_token(WebhookId::new(id), token)
+        .await?;
+
+    // Don't ping anyone again, or for the first time, with the edit
+    let edit = EditWebhookMessage::new()
+        .content(content)
+        .allowed_mentions(CreateAllowedMentions::new());
+    webhook.edit_message(&ctx.http, message_id, edit).await?;
+
+    Ok(())
+}
+
+/// Send a whisper from a Minecraft player as a DM to the named Discord
+/// user, and let the player know how it went.
+async fn send_whisper(
+    ctx: &Context,
+    config: &RootConfig,
+    guild_id: &GuildId,
+    message: &MinecraftMessage,
+    recipient: &str,
+) -> Result<(), Error> {
+    // The name could come from the webserver, and goes in a command
+    if !is_player_name(&message.name) {
+        warn!(
+            "dolphin:send_whisper: dropping a whisper from '{}', which isn't a player name",
+            message.name
+        );
+        return Ok(());
+    }
+
+    let allowed = can_whisper(config, &message.name);
+    let user_id = if allowed {
+        ctx.cache
+            .guild(*guild_id)
+            .and_then(|guild| guild.member_named(recipient).map(|member| member.user.id))
+    } else {
+        None
+    };
+
+    let notice = match user_id {
+        Some(user_id) => {
+            let dm = CreateMessage::new().content(format!(
+                "**{}** whispered from Minecraft: {}",
+                message.name, message.content
+            ));
+            match user_id.direct_message(ctx, dm).await {
+                Ok(_) => format!("Message sent to {}", recipient),
+                Err(e) => {
+                    warn!(
+                        "dolphin:send_whisper: unable to DM {} a whisper: {}",
+                        recipient, e
+                    );
+                    format!("Couldn't deliver your message to {}", recipient)
+                }
+            }
+        }
+        None if allowed => format!("Couldn't find a Discord user named {}", recipient),
+        None if config.whispers_enabled() => {
+            String::from("You aren't allowed to whisper to Discord")
+        }
+        None => String::from("Whispers to Discord are turned off"),
+    };
+
+    let command = format!(
+        "tellraw {} {}",
+        message.name,
+        json!({ "text": format!("[Discord] {}", notice), "color": "gray" })
+    );
+
+    if let Err(e) = send_to_minecraft(command, Transport::from_config(config)).await {
+        warn!(
+            "dolphin:send_whisper: unable to tell {} about their whisper: {}",
+            message.name, e
+        );
+    }
+
+    Ok(())
+}
+
+/// Check if a player can whisper to Discord: whispers have to be on,
+/// and the player listed in `whisper_players` if anyone is.
+fn can_whisper(config: &RootConfig, name: &str) -> bool {
+    let players = config.get_whisper_players();
+    config.whispers_enabled()
+        && (players.is_empty()
+            || players
+                .iter()
+                .any(|player| player.eq_ignore_ascii_case(name)))
+}
+
+/// Send a DM to each mentioned user who is offline in Discord and has
+/// opted in to mention DMs with the `/notify` command.
+async fn notify_mentioned(
+    ctx: &Context,
+    guild_id: &GuildId,
+    message: &MinecraftMessage,
+    mentioned: Vec<UserId>,
+) {
+    let links = match ctx.data.read().await.get::<LinkStoreContainer>() {
+        Some(links) => links.clone(),
+        None => return,
+    };
+
+    let opted_in: Vec<UserId> = {
+        let links = links.read().await;
+        mentioned
+            .into_iter()
+            .filter(|id| links.wants_mention_dms(id.get()))
+            .collect()
+    };
+
+    let (guild_name, offline) = {
+        let guild = match ctx.cache.guild(*guild_id) {
+            Some(guild) => guild,
+            None => return,
+        };
+
+        let offline: Vec<UserId> = opted_in
+            .into_iter()
+            .filter(|id| {
+                guild.presences.get(id).is_none_or(|presence| {
+                    matches!(
+                        presence.status,
+                        OnlineStatus::Offline | OnlineStatus::Invisible
+                    )
+                })
+            })
+            .collect();
+
+        (guild.name.clone(), offline)
+    };
+
+    for user_id in offline {
+        let embed = CreateEmbed::new()
+            .title(format!("{} mentioned you in Minecraft", message.name))
+            .description(&message.content)
+            .footer(CreateEmbedFooter::new(&guild_name))
+            .color(Colour::BLUE);
+
+        if let Err(e) = user_id
+            .direct_message(ctx, CreateMessage::new().embed(embed))
+            .await
+        {
+            warn!(
+                "dolphin:notify_mentioned: unable to send a DM to {}: {}",
+                user_id, e
+            );
+        }
+    }
+}
+
+/// Check if the sender of a message is allowed to ping `@everyone` and
+/// `@here`. This is blocked unless it's turned on for everyone, or the
+/// player is on the allowlist.
+fn can_mention_everyone(config: &RootConfig, message: &MinecraftMessage) -> bool {
+    if message.source != Source::Player {
+        return false;
+    }
+
+    config.everyone_mentions_allowed()
+        || config
+            .get_everyone_mention_players()
+            .iter()
+            .any(|player| player.eq_ignore_ascii_case(&message.name))
+}
+
+/// Find a custom avatar for a player, by their name or UUID. Names and
+/// UUIDs are matched ignoring case, and UUIDs with or without dashes.
+fn avatar_override(overrides: &HashMap<String, String>, name: &str, uuid: &str) -> Option<String> {
+    let uuid = uuid.replace('-', "");
+
+    overrides
+        .iter()
+        .find(|(key, _)| {
+            key.eq_ignore_ascii_case(name)
+                || (!uuid.is_empty() && key.replace('-', "").eq_ignore_ascii_case(&uuid))
+        })
+        .map(|(_, url)| url.clone())
+}
+
+/// Users and roles can always be mentioned, but `@everyone` and `@here`
+/// only work if they're allowed.
+fn allowed_mentions(everyone: bool) -> CreateAllowedMentions {
+    CreateAllowedMentions::new()
+        .all_users(true)
+        .all_roles(true)
+        .everyone(everyone)
+}
+
+/// Check if a message should be published to servers following the
+/// channel, if it's an Announcement channel.
+fn should_publish(config: &RootConfig, kind: &MessageKind) -> bool {
+    match kind {
+        MessageKind::ServerStart | MessageKind::ServerStarted { .. } => {
+            config.publish_server_start()
+        }
+        MessageKind::ServerStop => config.publish_server_stop(),
+        _ => false,
+    }
+}
+
+/// Some messages carry extra information that looks better as an embed
+/// than as a plain message. If this message is one of them, build the
+/// embed for it.
+fn build_embed(config: &RootConfig, message: &MinecraftMessage) -> Option<CreateEmbed> {
+    match &message.kind {
+        MessageKind::Death { cause, victim } if config.death_embeds_enabled() => {
+            Some(death::death_embed(&message.content, cause, victim))
+        }
+        MessageKind::Advancement {
+            description: Some(description),
+            ..
+        } => Some(
+            CreateEmbed::new()
+                .title(&message.content)
+                .description(description)
+                .color(Colour::GOLD),
+        ),
+        _ => None,
+    }
+}
+
+/// Whether to wait for Discord to create a part of a split message. Every
+/// part except the last is waited on, so that Discord can't post them out
+/// of order.
+fn wait_for_part(wait: bool, index: usize, count: usize) -> bool {
+    wait || index + 1 < count
+}
+
+/// Split a message into parts that are at most `max` characters long,
+/// so Discord doesn't reject it. Messages are split at whitespace where
+/// possible, and every part but the last ends with the marker.
+fn split_message(text: &str, max: usize, marker: &str) -> Vec<String> {
+    let limit = max.saturating_sub(marker.chars().count()).max(1);
+    let mut parts = Vec::new();
+    let mut rest = text;
+
+    while rest.chars().count() > max {
+        let end = rest
+            .char_indices()
+            .nth(limit)
+            .map_or(rest.len(), |(i, _)| i);
+        let split = if rest[end..].starts_with(char::is_whitespace) {
+            end
+        } else {
+            rest[..end]
+                .rfind(char::is_whitespace)
+                .filter(|&i| i > 0)
+                .unwrap_or(end)
+        };
+
+        parts.push(format!("{}{}", rest[..split].trim_end(), marker));
+        rest = rest[split..].trim_start();
+    }
+
+    parts.push(rest.to_string());
+    parts
+}
+
+/// Remove ANSI escape sequences, like console colors, from text. If
+/// `legacy` is set, Minecraft's `§` formatting codes are removed too.
+pub(crate) fn strip_colors(text: &str, legacy: bool) -> String {
+    lazy_static! {
+        static ref ANSI_REGEX: Regex =
+            Regex::new(r"\x1b(\[[0-9;?]*[ -/]*[@-~]|\][^\x07\x1b]*(\x07|\x1b\\)|[@-Z\\-_])")
+                .unwrap();
+        static ref LEGACY_REGEX: Regex = Regex::new(r"(?i)§[0-9a-fk-orx]").unwrap();
+    }
+
+    let stripped = ANSI_REGEX.replace_all(text, "");
+    if legacy {
+        LEGACY_REGEX.replace_all(&stripped, "").into_owned()
+    } else {
+        stripped.into_owned()
+    }
+}
+
+/// Escape characters that Discord would render as markdown, so text
+/// from Minecraft shows up the way it was written. Block quotes and
+/// headings are only escaped at the start of a line, so mentions like
+/// `<@1234>` are left alone. Emoji like `:partying_face:` are left alone
+/// too, or Discord would show their names instead.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut line_start = true;
+    let mut chars = text.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if let Some(shortcode) = shortcode_at(&text[i..]) {
+            escaped.push_str(shortcode);
+            // Skip the rest of the shortcode
+            chars.nth(shortcode.chars().count() - 2);
+            line_start = false;
+            continue;
+        }
+
+        match c {
+            '\\' | '*' | '_' | '~' | '`' | '|' => escaped.push('\\'),
+            '>' | '#' if line_start => escaped.push('\\'),
+            _ => {}
+        }
+
+        escaped.push(c);
+        line_start = c == '\n';
+    }
+
+    escaped
+}
+
+/// The `:shortcode:` at the start of some text, if there is one.
+fn shortcode_at(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix(':')?;
+    let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    (end > 0 && rest[end..].starts_with(':')).then(|| &text[..end + 2])
+}
+
+/// Use a configured value, or the fallback if it's left empty.
+fn configured_or(value: String, fallback: impl FnOnce() -> String) -> String {
+    if value.trim().is_empty() {
+        fallback()
+    } else {
+        value
+    }
+}
+
+/// Show a player's name on Discord the way `player_name_template` says,
+/// with their rank if the chat regex captured one.
+fn player_display_name(template: &str, name: &str, rank: &str) -> String {
+    template::render(template, &[("name", name), ("rank", rank)])
+}
+
+/// Remove markdown and invisible characters from a name.
+fn strip_name_markdown(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '*' | '_' | '~' | '`' | '|' | '>' | '#' | '@' | ':'))
+        .filter(|c| !unicode::is_disallowed(*c))
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Clean up a player name before using it as a webhook username.
+///
+/// Markdown and invisible characters are removed, and names that
+/// contain one of the reserved names (e.g. `AdminSteve`) get the
+/// suffix added so they can't be mistaken for a Discord user.
+fn sanitize_webhook_username(name: &str, reserved: &[String], suffix: &str) -> String {
+    let mut username = strip_name_markdown(name);
+
+    if username.is_empty() {
+        username = String::from("Player");
+    }
+
+    let lowercase = username.to_lowercase();
+    if !suffix.is_empty()
+        && reserved
+            .iter()
+            .any(|word| !word.is_empty() && lowercase.contains(&word.to_lowercase()))
+    {
+        username = format!("{} {}", username, suffix);
+    }
+
+    // Discord doesn't allow webhook usernames longer than 80 characters
+    username.chars().take(80).collect()
+}
+
+/// Use Regex to split the configured webhook URL into an ID and a token.
+/// If the input url doesn't match the regex, [None] will be returned. No
+/// validation is done to see if the webhook URL is actually a valid and
+/// active Discord webhook.
+///
+/// # Examples
+///
+/// ```rust
+/// let webhook_url = String::from("https://discord.com/api/webhooks/12345/67890");
+/// let webhook_parts = split_webhook_url(&webhook_url);
+///
+/// assert!(webhook_parts.is_some());
+/// assert_eq!(webhook_parts.unwrap().0, 12345);
+/// assert_eq!(webhook_parts.unwrap().1, 67890);
+/// ```
+pub fn split_webhook_url(url: &str) -> Option<(u64, &str)> {
+    // Only compile the regex once, since this is expensive
+    lazy_static! {
+        static ref WEBHOOK_REGEX: Regex =
+            Regex::new(r"^https://discord.com/api/webhooks/(?P<id>.*)/(?P<token>.*)$").unwrap();
+    }
+
+    let mut ret = None;
+
+    if let Ok(Some(captures)) = WEBHOOK_REGEX.captures(url) {
+        if captures.len() != 3 {
+            return None;
+        }
+
+        let id = captures.name("id").unwrap().as_str();
+        if let Ok(id) = id.parse::<u64>() {
+            ret = Some((id, captures.name("token").unwrap().as_str()));
+        }
+    }
+
+    ret
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+
+    #[error("parser error: {0}")]
+    Parser(#[from] parser::Error),
+
+    #[error("webhook error: {0}")]
+    Webhook(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use serenity::prelude::RwLock;
+
+    use crate::config::RootConfig;
+    use crate::listener::{
+        announced, avatar_override, can_mention_everyone, can_whisper, carries_content,
+        configured_or, escape_markdown, event_channel,
+        parser::{MessageKind, MinecraftMessage, Source},
+        player_display_name, route, routed, sanitize_webhook_username, should_publish,
+        split_message, split_webhook_url, strip_colors, wait_for_part, Suppression,
+    };
+
+    #[test]
+    fn parse_parts_from_webhook_url() {
+        // Given
+        let input = String::from("https://discord.com/api/webhooks/12345/67890");
+
+        // When/Then
+        match split_webhook_url(&input) {
+            Some((id, token)) => {
+                assert_eq!(id, 12345);
+                assert_eq!(token, String::from("67890"));
+            }
+            None => panic!("failed to parse Discord webhook url"),
+        }
+    }
+
+    #[test]
+    #[allow(clippy::redundant_pattern_matching)]
+    fn parse_non_webhook_url() {
+        // Given
+        let input = String::from("https://example.com");
+
+        // When/Then
+        if let Some(_) = split_webhook_url(&input) {
+            panic!("webhook split returned something when it should have returned None");
+        }
+    }
+
+    #[test]
+    fn strip_markdown_from_username() {
+        // Given
+        let input = "**Ste\u{200B}ve**";
+
+        // When
+        let result = sanitize_webhook_username(input, &[], "[MC]");
+
+        // Then
+        assert_eq!(result, "Steve");
+    }
+
+    #[test]
+    fn suffix_reserved_username() {
+        // Given
+        let reserved = vec![String::from("admin")];
+
+        // When/Then
+        assert_eq!(
+            sanitize_webhook_username("AdminSteve", &reserved, "[MC]"),
+            "AdminSteve [MC]"
+        );
+        assert_eq!(
+            sanitize_webhook_username("Steve", &reserved, "[MC]"),
+            "Steve"
+        );
+        assert_eq!(sanitize_webhook_username("__", &reserved, "[MC]"), "Player");
+    }
+
+    #[test]
+    fn show_ranks_before_names() {
+        // Given
+        let template = "%if rank%[%rank%] %end%%name%";
+
+        // Then
+        assert_eq!(
+            player_display_name(template, "Steve", "Admin"),
+            "[Admin] Steve"
+        );
+        assert_eq!(player_display_name(template, "Steve", ""), "Steve");
+    }
+
+    #[test]
+    fn publish_nothing_by_default() {
+        // Given
+        let config = RootConfig::default();
+
+        // When/Then
+        assert!(!should_publish(&config, &MessageKind::ServerStart));
+        assert!(!should_publish(&config, &MessageKind::ServerStop));
+        assert!(!should_publish(&config, &MessageKind::Chat));
+    }
+
+    #[test]
+    fn suppress_events_during_startup() {
+        // Given
+        let suppression = Suppression::from_config("events");
+
+        // When/Then
+        assert!(!suppression.suppresses(&MessageKind::Chat));
+        assert!(suppression.suppresses(&MessageKind::Join));
+        assert!(suppression.suppresses(&MessageKind::Death {
+            cause: String::from("drowned"),
+            victim: String::from("Steve"),
+        }));
+        assert!(Suppression::from_config("all").suppresses(&MessageKind::Chat));
+    }
+
+    #[test]
+    fn escape_markdown_characters() {
+        // Given
+        let input = "xX_Steve_Xx was slain by **Boss** using `Sword`";
+
+        // When
+        let result = escape_markdown(input);
+
+        // Then
+        assert_eq!(
+            result,
+            "xX\\_Steve\\_Xx was slain by \\*\\*Boss\\*\\* using \\`Sword\\`"
+        );
+    }
+
+    #[test]
+    fn only_listed_players_whisper() {
+        // Given
+        let everyone: RootConfig = toml::from_str("[discord_config]\nwhispers = true\n").unwrap();
+        let listed: RootConfig =
+            toml::from_str("[discord_config]\nwhispers = true\nwhisper_players = [\"Steve\"]\n")
+                .unwrap();
+
+        // Then
+        assert!(can_whisper(&everyone, "Alex"));
+        assert!(can_whisper(&listed, "steve"));
+        assert!(!can_whisper(&listed, "Alex"));
+        assert!(!can_whisper(&RootConfig::default(), "Steve"));
+    }
+
+    #[test]
+    fn escape_markdown_keeps_emoji() {
+        assert_eq!(
+            escape_markdown(":partying_face: xX_Steve_Xx has made the advancement [Stone Age]"),
+            ":partying_face: xX\\_Steve\\_Xx has made the advancement [Stone Age]"
+        );
+        assert_eq!(
+            escape_markdown(":white_check_mark: Server has started"),
+            ":white_check_mark: Server has started"
+        );
+        assert_eq!(escape_markdown("at 12:30_ish"), "at 12:30\\_ish");
+    }
+
+    #[test]
+    fn escape_markdown_keeps_mentions() {
+        assert_eq!(escape_markdown("> hi <@1234>"), "\\> hi <@1234>");
+        assert_eq!(escape_markdown("# <#5678>"), "\\# <#5678>");
+    }
+
+    #[test]
+    fn split_long_message() {
+        // Given
+        let input = "aaaa bbbb cccc dddd";
+
+        // When
+        let result = split_message(input, 10, "…");
+
+        // Then
+        assert_eq!(result, vec!["aaaa bbbb…", "cccc dddd"]);
+    }
+
+    #[test]
+    fn split_message_without_whitespace() {
+        // Given
+        let input = "é".repeat(25);
+
+        // When
+        let result = split_message(&input, 10, "...");
+
+        // Then
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[0], format!("{}...", "é".repeat(7)));
+        assert!(result.iter().all(|part| part.chars().count() <= 10));
+    }
+
+    #[test]
+    fn strip_console_colors() {
+        // Given
+        let input = "\x1b[32mSteve\x1b[0m joined \x1b[1;33mthe game\x1b[m\x1b]0;title\x07";
+
+        // When
+        let result = strip_colors(input, false);
+
+        // Then
+        assert_eq!(result, "Steve joined the game");
+    }
+
+    #[test]
+    fn strip_legacy_colors_only_when_asked() {
+        assert_eq!(strip_colors("§aGreen §lbold§r", false), "§aGreen §lbold§r");
+        assert_eq!(strip_colors("§aGreen §Lbold§r", true), "Green bold");
+    }
+
+    #[test]
+    fn fall_back_when_not_configured() {
+        let fallback = || String::from("Dolphin");
+
+        assert_eq!(configured_or(String::from("Server"), fallback), "Server");
+        assert_eq!(configured_or(String::from(" "), fallback), "Dolphin");
+        assert_eq!(configured_or(String::new(), fallback), "Dolphin");
+    }
+
+    #[test]
+    fn wait_for_all_but_the_last_part() {
+        assert!(wait_for_part(false, 0, 3));
+        assert!(wait_for_part(false, 1, 3));
+        assert!(!wait_for_part(false, 2, 3));
+        assert!(!wait_for_part(false, 0, 1));
+        assert!(wait_for_part(true, 0, 1));
+    }
+
+    #[test]
+    fn no_split_short_message() {
+        assert_eq!(split_message("hello", 2000, "…"), vec!["hello"]);
+    }
+
+    #[test]
+    fn block_everyone_mentions_by_default() {
+        // Given
+        let mut config: RootConfig =
+            toml::from_str("[discord_config]\neveryone_mention_players = [\"EbonJaeger\"]\n")
+                .unwrap();
+        let mut message = MinecraftMessage {
+            name: String::from("Steve"),
+            content: String::from("@everyone look"),
+            source: Source::Player,
+            uuid: String::new(),
+            kind: MessageKind::Chat,
+            rank: String::new(),
+        };
+
+        // When/Then
+        assert!(!can_mention_everyone(&config, &message));
+
+        message.name = String::from("ebonjaeger");
+        assert!(can_mention_everyone(&config, &message));
+
+        message.source = Source::Server;
+        assert!(!can_mention_everyone(&config, &message));
+
+        config = toml::from_str("[discord_config]\nallow_everyone_mentions = true\n").unwrap();
+        message.source = Source::Player;
+        message.name = String::from("Steve");
+        assert!(can_mention_everyone(&config, &message));
+    }
+
+    #[test]
+    fn find_avatar_overrides() {
+        // Given
+        let overrides = HashMap::from([
+            (
+                String::from("Steve"),
+                String::from("https://example.com/steve.png"),
+            ),
+            (
+                String::from("7F7C909B-24F1-49A4-817F-BAA4F4973980"),
+                String::from("https://example.com/ebon.png"),
+            ),
+        ]);
+
+        // Then
+        assert_eq!(
+            avatar_override(&overrides, "steve", "").as_deref(),
+            Some("https://example.com/steve.png")
+        );
+        assert_eq!(
+            avatar_override(&overrides, "EbonJaeger", "7f7c909b24f149a4817fbaa4f4973980")
+                .as_deref(),
+            Some("https://example.com/ebon.png")
+        );
+        assert_eq!(avatar_override(&overrides, "Alex", ""), None);
+    }
+
+    #[test]
+    fn route_requests_by_token() {
+        // Given
+        let main = Arc::new(RwLock::new(RootConfig::default()));
+        let creative = Arc::new(RwLock::new(RootConfig::default()));
+        let servers = HashMap::from([(String::from("hunter2"), creative.clone())]);
+
+        // Then
+        assert!(Arc::ptr_eq(&route(&servers, &main, None).unwrap(), &main));
+        assert!(Arc::ptr_eq(
+            &route(&servers, &main, Some("hunter2")).unwrap(),
+            &creative
+        ));
+        assert!(route(&servers, &main, Some("wrong")).is_none());
+    }
+
+    #[test]
+    fn route_events_to_channels() {
+        // Given
+        let everything = Vec::new();
+        let deaths = vec![String::from("death")];
+        let death = MessageKind::Death {
+            cause: String::from("fell from a high place"),
+            victim: String::from("Steve"),
+        };
+
+        // Then
+        assert!(routed(&everything, &MessageKind::Chat));
+        assert!(routed(&everything, &MessageKind::Other));
+        assert!(routed(&deaths, &death));
+        assert!(!routed(&deaths, &MessageKind::Chat));
+        assert!(!routed(&deaths, &MessageKind::Other));
+    }
+
+    #[test]
+    fn route_events_to_their_own_channels() {
+        // Given
+        let config: RootConfig =
+            toml::from_str("[discord_config]\nchannel_id = 1\ndeath_channel_id = 2\n").unwrap();
+        let death = MessageKind::Death {
+            cause: String::from("fell from a high place"),
+            victim: String::from("Steve"),
+        };
+        let advancement = MessageKind::Advancement {
+            title: String::from("Stone Age"),
+            description: None,
+        };
+
+        // Then
+        assert_eq!(event_channel(&config, &death), Some(2));
+        assert_eq!(event_channel(&config, &advancement), None);
+        assert_eq!(event_channel(&config, &MessageKind::Chat), None);
+    }
+
+    #[test]
+    fn silence_turned_off_kinds() {
+        // Given
+        let config: RootConfig = toml::from_str(
+            "[minecraft_config]\nannounce_deaths = false\nannounce_server_status = false\n",
+        )
+        .unwrap();
+        let death = MessageKind::Death {
+            cause: String::from("fell from a high place"),
+            victim: String::from("Steve"),
+        };
+
+        // Then
+        assert!(!announced(&config, &death));
+        assert!(!announced(&config, &MessageKind::ServerStop));
+        assert!(announced(&config, &MessageKind::Join));
+        assert!(announced(&config, &MessageKind::Chat));
+    }
+
+    #[test]
+    fn keep_player_content_out_of_events_only_mode() {
+        // Given
+        let whisper = MessageKind::Whisper {
+            recipient: String::from("Alex"),
+        };
+        let advancement = MessageKind::Advancement {
+            title: String::from("Stone Age"),
+            description: None,
+        };
+
+        // Then
+        assert!(carries_content(&MessageKind::Chat));
+        assert!(carries_content(&whisper));
+        assert!(carries_content(&MessageKind::Other));
+        assert!(!carries_content(&MessageKind::Join));
+        assert!(!carries_content(&advancement));
+        assert!(!carries_content(&MessageKind::ServerStop));
     }
 }
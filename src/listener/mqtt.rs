@@ -0,0 +1,193 @@
+use std::{sync::Arc, time::Duration};
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use serde_json::json;
+use serenity::{async_trait, client::Context, model::id::GuildId, prelude::RwLock};
+use tokio::{sync::broadcast::error::RecvError, time::sleep};
+use tracing::{debug, error, info, warn};
+
+use super::{body, parser::MinecraftMessage, relay, send_to_discord, Listener};
+use crate::config::RootConfig;
+
+/// How long to wait before trying the broker again after losing it.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// The port MQTT brokers listen on if the URL doesn't say.
+const DEFAULT_PORT: u16 = 1883;
+
+/// Subscribes to a topic on an MQTT broker for messages from Minecraft,
+/// and publishes chat from Discord to another topic.
+///
+/// Messages from Minecraft use the same JSON body as the webserver.
+/// Messages from Discord are JSON objects with `name` and `content`.
+pub struct MqttListener {
+    settings: Settings,
+}
+
+#[derive(Clone, Debug)]
+struct Settings {
+    host: String,
+    port: u16,
+    client_id: String,
+    credentials: Option<(String, String)>,
+    inbound_topic: String,
+    outbound_topic: String,
+}
+
+impl MqttListener {
+    /// Read the broker settings from the config. Returns [None] if the
+    /// broker URL can't be understood.
+    pub fn from_config(config: &RootConfig) -> Option<Self> {
+        let (host, port) = broker_address(&config.get_mqtt_broker_url())?;
+        let username = config.get_mqtt_username();
+
+        Some(MqttListener {
+            settings: Settings {
+                host,
+                port,
+                client_id: config.get_mqtt_client_id(),
+                credentials: (!username.is_empty()).then(|| (username, config.get_mqtt_password())),
+                inbound_topic: config.get_mqtt_inbound_topic(),
+                outbound_topic: config.get_mqtt_outbound_topic(),
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl Listener for MqttListener {
+    async fn listen(
+        &self,
+        ctx: Arc<Context>,
+        config_lock: Arc<RwLock<RootConfig>>,
+        guild_id: Arc<GuildId>,
+    ) {
+        let settings = &self.settings;
+        let mut options = MqttOptions::new(&settings.client_id, &settings.host, settings.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some((username, password)) = &settings.credentials {
+            options.set_credentials(username, password);
+        }
+
+        let (client, mut event_loop) = AsyncClient::new(options, 64);
+        tokio::spawn(publish_discord_chat(
+            client.clone(),
+            settings.outbound_topic.clone(),
+        ));
+
+        info!(
+            "mqtt:listen: connecting to the MQTT broker at {}:{}",
+            settings.host, settings.port
+        );
+
+        loop {
+            let packet = match event_loop.poll().await {
+                Ok(Event::Incoming(packet)) => packet,
+                Ok(Event::Outgoing(_)) => continue,
+                Err(e) => {
+                    warn!("mqtt:listen: lost the MQTT broker: {}", e);
+                    sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+            };
+
+            match packet {
+                // Subscriptions don't outlive the session
+                Packet::ConnAck(_) => {
+                    info!("mqtt:listen: connected to the MQTT broker");
+                    if let Err(e) = client
+                        .subscribe(&settings.inbound_topic, QoS::AtLeastOnce)
+                        .await
+                    {
+                        error!("mqtt:listen: unable to subscribe to the topic: {}", e);
+                    }
+                }
+                Packet::Publish(publish) => {
+                    let message: MinecraftMessage = match body::decode(None, &publish.payload) {
+                        Ok(message) => message,
+                        Err(e) => {
+                            debug!("mqtt:listen: skipping a message: {}", e);
+                            continue;
+                        }
+                    };
+
+                    if let Err(e) = send_to_discord(
+                        ctx.clone(),
+                        config_lock.clone(),
+                        guild_id.clone(),
+                        message,
+                        None,
+                    )
+                    .await
+                    {
+                        error!("mqtt:listen: unable to send a message to Discord: {}", e);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Publish chat from Discord to the outbound topic.
+async fn publish_discord_chat(client: AsyncClient, topic: String) {
+    let mut messages = relay::subscribe();
+
+    loop {
+        let chat = match messages.recv().await {
+            Ok(chat) => chat,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("mqtt:publish: fell behind and skipped {} messages", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        let payload = json!({ "name": chat.name, "content": chat.content }).to_string();
+        if let Err(e) = client
+            .publish(&topic, QoS::AtLeastOnce, false, payload)
+            .await
+        {
+            error!("mqtt:publish: unable to publish a message: {}", e);
+        }
+    }
+}
+
+/// Get the host and port from a broker URL like `mqtt://localhost:1883`.
+fn broker_address(url: &str) -> Option<(String, u16)> {
+    let address = url
+        .strip_prefix("mqtt://")
+        .or_else(|| url.strip_prefix("tcp://"))
+        .unwrap_or(url)
+        .trim_end_matches('/');
+
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().ok()?),
+        None => (address, DEFAULT_PORT),
+    };
+
+    (!host.is_empty() && !host.contains('/')).then(|| (host.to_string(), port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::broker_address;
+
+    #[test]
+    fn parse_broker_urls() {
+        assert_eq!(
+            broker_address("mqtt://broker.local:1884"),
+            Some((String::from("broker.local"), 1884))
+        );
+        assert_eq!(
+            broker_address("tcp://localhost"),
+            Some((String::from("localhost"), 1883))
+        );
+        assert_eq!(
+            broker_address("10.0.0.2:1883/"),
+            Some((String::from("10.0.0.2"), 1883))
+        );
+        assert_eq!(broker_address("mqtts://broker.local"), None);
+        assert_eq!(broker_address("mqtt://:1883"), None);
+    }
+}
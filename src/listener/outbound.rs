@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tracing::{debug, warn};
+
+use super::parser::{MessageKind, MinecraftMessage, Source};
+use crate::config::RootConfig;
+
+/// The header the payload's signature is sent in, if a secret is set.
+const SIGNATURE_HEADER: &str = "X-Dolphin-Signature";
+
+/// The header with the name of the event, so receivers can route
+/// events without reading the body.
+const EVENT_HEADER: &str = "X-Dolphin-Event";
+
+/// How long to wait for an event webhook to answer, so a slow receiver
+/// can't hold up the events behind it.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .expect("unable to build the event webhook HTTP client");
+}
+
+/// Post a message to every configured event webhook that wants it. The
+/// posts happen in the background, so slow endpoints don't hold up
+/// the bridge.
+pub fn fan_out(config: &RootConfig, message: &MinecraftMessage, timestamp: i64) {
+    let Some(event) = event_name(&message.kind) else {
+        return;
+    };
+
+    let webhooks = config.get_event_webhooks();
+    if webhooks.is_empty() {
+        return;
+    }
+
+    let body = payload(event, message, timestamp).to_string();
+    for (url, secret, events) in webhooks {
+        if !events.is_empty() && !events.iter().any(|wanted| wanted == event) {
+            continue;
+        }
+
+        let body = body.clone();
+        tokio::spawn(async move {
+            let mut request = CLIENT
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, event);
+            if !secret.is_empty() {
+                request = request.header(SIGNATURE_HEADER, sign(&secret, &body));
+            }
+
+            match request.body(body).send().await {
+                Ok(response) if !response.status().is_success() => {
+                    warn!(
+                        "outbound:fan_out: '{}' answered with {}",
+                        url,
+                        response.status()
+                    );
+                }
+                Ok(_) => debug!("outbound:fan_out: posted a '{}' event to '{}'", event, url),
+                Err(e) => warn!("outbound:fan_out: unable to post to '{}': {}", url, e),
+            }
+        });
+    }
+}
+
+/// The name to use for a kind of message, or [None] if it shouldn't be
+/// sent to event webhooks.
+pub fn event_name(kind: &MessageKind) -> Option<&'static str> {
+    match kind {
+        MessageKind::Chat => Some("chat"),
+        MessageKind::Join => Some("join"),
+        MessageKind::Leave => Some("leave"),
+        MessageKind::Death { .. } => Some("death"),
+        MessageKind::Advancement { .. } => Some("advancement"),
+        MessageKind::ServerStarted { .. } | MessageKind::ServerStart => Some("server_start"),
+        MessageKind::ServerStop => Some("server_stop"),
+        // Whispers are private
+        MessageKind::Whisper { .. } | MessageKind::Other => None,
+    }
+}
+
+/// Build the JSON body for an event.
+fn payload(event: &str, message: &MinecraftMessage, timestamp: i64) -> Value {
+    let mut payload = json!({
+        "event": event,
+        "name": message.name,
+        "content": message.content,
+        "source": match message.source {
+            Source::Player => "player",
+            Source::Server => "server",
+        },
+        "uuid": message.uuid,
+        "timestamp": timestamp,
+    });
+
+    let details = match &message.kind {
+        MessageKind::Death { cause, victim } => json!({ "cause": cause, "victim": victim }),
+        MessageKind::Advancement { title, description } => {
+            json!({ "title": title, "description": description })
+        }
+        MessageKind::ServerStarted {
+            version,
+            startup_time,
+        } => json!({ "version": version, "startup_time": startup_time }),
+        _ => return payload,
+    };
+    payload["details"] = details;
+
+    payload
+}
+
+/// Sign a body with HMAC-SHA256, formatted like `sha256=<hex digest>`.
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body.as_bytes());
+
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("sha256={}", digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{event_name, payload, sign};
+    use crate::listener::parser::{MessageKind, MinecraftMessage, Source};
+
+    #[test]
+    fn sign_payloads() {
+        assert_eq!(
+            sign("key", "The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn skip_private_messages() {
+        let whisper = MessageKind::Whisper {
+            recipient: String::from("Steve"),
+        };
+
+        assert_eq!(event_name(&whisper), None);
+        assert_eq!(event_name(&MessageKind::Other), None);
+        assert_eq!(event_name(&MessageKind::ServerStart), Some("server_start"));
+    }
+
+    #[test]
+    fn build_death_payload() {
+        // Given
+        let message = MinecraftMessage {
+            name: String::new(),
+            content: String::from("Steve fell from a high place"),
+            source: Source::Server,
+            uuid: String::new(),
+            kind: MessageKind::Death {
+                cause: String::from("fall"),
+                victim: String::from("Steve"),
+            },
+            rank: String::new(),
+        };
+
+        // When
+        let result = payload("death", &message, 1700000000);
+
+        // Then
+        assert_eq!(
+            result,
+            json!({
+                "event": "death",
+                "name": "",
+                "content": "Steve fell from a high place",
+                "source": "server",
+                "uuid": "",
+                "timestamp": 1700000000,
+                "details": { "cause": "fall", "victim": "Steve" },
+            })
+        );
+    }
+}
@@ -1,24 +1,41 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
 
 use fancy_regex::Regex;
 use serde::Deserialize;
-use serenity::{
-    model::prelude::GuildId,
-    prelude::{Context, Mentionable},
-};
+use serenity::model::prelude::{GuildId, UserId};
 use thiserror::Error;
-use tracing::error;
+
+use super::advancement::AdvancementTable;
+use super::stats::{self, DeathStats};
+use super::translation::{compile_translations, Translation};
+use crate::discord::mentions::{Matching, MentionIndex, Target};
+use crate::metrics::Ignored;
+use crate::template;
 
 #[derive(Clone)]
 pub struct MessageParser {
+    advancements: AdvancementTable,
     cached_uuids: HashMap<String, String>,
     death_keywords: Vec<String>,
+    death_stats: Option<DeathStats>,
+    death_translations: Vec<Translation>,
     ignore_phrases: Vec<String>,
+    ignored: Option<Ignored>,
+    server_start_template: String,
+    server_version: Option<String>,
+    unmatched: Option<String>,
 }
 
 impl MessageParser {
     /// Create a new MessageParser to parse Minecraft log lines.
-    pub fn new(mut custom_keywords: Vec<String>, mut ignore_keywords: Vec<String>) -> Self {
+    pub fn new(
+        mut custom_keywords: Vec<String>,
+        mut ignore_keywords: Vec<String>,
+        death_translations: HashMap<String, String>,
+        advancements: AdvancementTable,
+        track_death_stats: bool,
+        server_start_template: String,
+    ) -> Self {
         let mut death_keywords = vec![
             String::from(" shot"),
             String::from(" pricked"),
@@ -68,9 +85,16 @@ impl MessageParser {
         ignore_phrases.append(&mut ignore_keywords);
 
         Self {
+            advancements,
             cached_uuids: HashMap::new(),
             death_keywords,
+            death_stats: track_death_stats.then(DeathStats::default),
+            death_translations: compile_translations(death_translations),
             ignore_phrases,
+            ignored: None,
+            server_start_template,
+            server_version: None,
+            unmatched: None,
         }
     }
 
@@ -128,9 +152,16 @@ impl MessageParser {
         );
 
         Self {
+            advancements: AdvancementTable::default(),
             cached_uuids,
             death_keywords,
+            death_stats: None,
+            death_translations: Vec::new(),
             ignore_phrases,
+            ignored: None,
+            server_start_template: String::from(":white_check_mark: Server has started"),
+            server_version: None,
+            unmatched: None,
         }
     }
 
@@ -148,11 +179,15 @@ impl MessageParser {
     pub async fn parse_line(&mut self, line: &str, regex: String) -> Option<MinecraftMessage> {
         let line = match trim_prefix(line) {
             Some(line) => line.trim(),
-            None => return None,
+            None => {
+                self.ignored = Some(Ignored::NoPrefix);
+                return None;
+            }
         };
 
         // Ignore villager death messages
         if line.starts_with("Villager") && line.contains("died, message:") {
+            self.ignored = Some(Ignored::VillagerDeath);
             return None;
         }
 
@@ -167,6 +202,12 @@ impl MessageParser {
             return None;
         }
 
+        // Remember the version for when the server is done starting
+        if let Some(version) = server_version(line) {
+            self.server_version = Some(version);
+            return None;
+        }
+
         let chat_regex = Regex::new(&regex).unwrap();
 
         // Check if the line is a chat message
@@ -182,29 +223,34 @@ impl MessageParser {
                 }
             }
 
+            let kind = if line.contains("joined the game") {
+                MessageKind::Join
+            } else {
+                MessageKind::Leave
+            };
+
             // Join/leave message
             Some(MinecraftMessage {
                 name: String::new(),
                 content: String::from(line),
                 source: Source::Server,
                 uuid: String::new(),
+                kind,
+                rank: String::new(),
             })
-        } else if is_advancement(line) {
-            // Player Advancement message
+        } else if let Some((name, recipient, content)) = parse_whisper_command(line) {
             Some(MinecraftMessage {
-                name: String::new(),
-                content: format!(":partying_face: {}", line),
-                source: Source::Server,
+                name,
+                content,
+                source: Source::Player,
                 uuid: String::new(),
+                kind: MessageKind::Whisper { recipient },
+                rank: String::new(),
             })
+        } else if is_advancement(line) {
+            Some(self.parse_advancement(line))
         } else if line.starts_with("Done (") {
-            // Server started message
-            Some(MinecraftMessage {
-                name: String::new(),
-                content: String::from(":white_check_mark: Server has started"),
-                source: Source::Server,
-                uuid: String::new(),
-            })
+            Some(self.parse_server_started(line))
         } else if line.starts_with("Stopping the server") {
             // Server stopping message
             Some(MinecraftMessage {
@@ -212,16 +258,66 @@ impl MessageParser {
                 content: String::from(":x: Server is shutting down"),
                 source: Source::Server,
                 uuid: String::new(),
+                kind: MessageKind::ServerStop,
+                rank: String::new(),
             })
         } else {
-            self.try_parse_death(line)
+            let death = self.try_parse_death(line);
+            if death.is_none() && self.ignored.is_none() {
+                self.ignored = Some(Ignored::NoMatch);
+                self.unmatched = Some(line.to_string());
+            }
+            death
+        }
+    }
+
+    /// Take the last line that had a log prefix but didn't match any
+    /// kind of message, to help with writing chat regexes.
+    pub fn take_unmatched(&mut self) -> Option<String> {
+        self.unmatched.take()
+    }
+
+    /// Take the reason the last line was ignored, if it was ignored
+    /// instead of just not being a message, like a UUID line.
+    pub fn take_ignored(&mut self) -> Option<Ignored> {
+        self.ignored.take()
+    }
+
+    /// Build the message for the server being done starting, e.g.
+    /// `Done (21.3242s)! For help, type "help"`.
+    fn parse_server_started(&self, line: &str) -> MinecraftMessage {
+        let startup_time = line
+            .strip_prefix("Done (")
+            .and_then(|rest| rest.split_once("s)"))
+            .and_then(|(seconds, _)| seconds.parse::<f64>().ok())
+            .map(|seconds| format!("{:.1}s", seconds))
+            .unwrap_or_else(|| String::from("unknown"));
+        let version = self
+            .server_version
+            .clone()
+            .unwrap_or_else(|| String::from("unknown"));
+
+        MinecraftMessage {
+            name: String::new(),
+            content: template::render(
+                &self.server_start_template,
+                &[("version", &version), ("startup_time", &startup_time)],
+            ),
+            source: Source::Server,
+            uuid: String::new(),
+            kind: MessageKind::ServerStarted {
+                version,
+                startup_time,
+            },
+            rank: String::new(),
         }
     }
 
     /// Try to parse a line as a chat message.
     ///
     /// The line will be split into two parts: the username and
-    /// the message itself.
+    /// the message itself. A `rank` or `prefix` group is kept as the
+    /// player's rank.
     async fn try_parse_chat(&mut self, chat_regex: Regex, line: &str) -> Option<MinecraftMessage> {
         let captures = chat_regex
             .captures(line)
@@ -238,19 +334,75 @@ impl MessageParser {
             .expect("log message matched chat regex, but there's no content")
             .as_str();
 
-        let uuid = match self.get_player_uuid(name).await {
-            Ok(uuid) => uuid,
-            Err(_e) => String::from("c06f8906-4c8a-4911-9c29-ea1dbd1aab82"),
+        let rank = captures
+            .name("rank")
+            .or_else(|| captures.name("prefix"))
+            .map(|rank| rank.as_str().trim().to_string())
+            .unwrap_or_default();
+
+        // Players who joined before Dolphin started aren't in the cache
+        let uuid = self.get_player_uuid(name).await.unwrap_or_default();
+
+        // Chat messages can also be whispers to a Discord user
+        let (content, kind) = match parse_whisper(content) {
+            Some((recipient, content)) => (content, MessageKind::Whisper { recipient }),
+            None => (content.to_string(), MessageKind::Chat),
         };
 
         Some(MinecraftMessage {
             name: name.to_string(),
-            content: content.to_string(),
+            content,
             source: Source::Player,
             uuid,
+            kind,
+            rank,
         })
     }
 
+    /// Build a message for a player earning an Advancement. The
+    /// Advancement name is looked up so it can be shown with a nicer
+    /// title and a description, if we have one.
+    fn parse_advancement(&self, line: &str) -> MinecraftMessage {
+        lazy_static! {
+            static ref ADVANCEMENT_REGEX: Regex =
+                Regex::new(r"^(?P<prefix>.+ \[)(?P<name>.+)\]$").unwrap();
+        }
+
+        let captures = match ADVANCEMENT_REGEX.captures(line) {
+            Ok(Some(captures)) => captures,
+            _ => {
+                return MinecraftMessage {
+                    name: String::new(),
+                    content: format!(":partying_face: {}", line),
+                    source: Source::Server,
+                    uuid: String::new(),
+                    kind: MessageKind::Advancement {
+                        title: String::new(),
+                        description: None,
+                    },
+                    rank: String::new(),
+                }
+            }
+        };
+
+        let prefix = captures.name("prefix").unwrap().as_str();
+        let info = self
+            .advancements
+            .lookup(captures.name("name").unwrap().as_str());
+
+        MinecraftMessage {
+            name: String::new(),
+            content: format!(":partying_face: {}{}]", prefix, info.title),
+            source: Source::Server,
+            uuid: String::new(),
+            kind: MessageKind::Advancement {
+                title: info.title,
+                description: info.description,
+            },
+            rank: String::new(),
+        }
+    }
+
     /// Get the player's UUID so we can get their skin later
     /// If the player isn't in our cache, try to get their UUID
     /// from the Mojang API using their username. If that fails,
@@ -281,9 +433,19 @@ impl MessageParser {
     ///
     /// If we get past that, check if the message contains keywords
     /// that are a part of death messages.
+    ///
+    /// Lines matching a configured death translation are normalized
+    /// before any of this happens.
     fn try_parse_death(&mut self, line: &str) -> Option<MinecraftMessage> {
+        let translated = self
+            .death_translations
+            .iter()
+            .find_map(|translation| translation.apply(line));
+        let line = translated.as_deref().unwrap_or(line);
+
         for ignore_phrase in &self.ignore_phrases {
             if line.contains(ignore_phrase.as_str()) {
+                self.ignored = Some(Ignored::IgnorePhrase);
                 return None;
             }
         }
@@ -298,23 +460,83 @@ impl MessageParser {
             return self.try_parse_death(actual_line);
         }
 
-        let mut message: Option<MinecraftMessage> = None;
+        // The last keyword that matches is the cause of death
+        let cause = self
+            .death_keywords
+            .iter()
+            .rev()
+            .find(|word| line.contains(word.as_str()))?
+            .trim()
+            .to_string();
 
-        for word in &self.death_keywords {
-            if !line.contains(word.as_str()) {
-                continue;
-            }
+        // Player names can't have spaces, so the victim is the first word
+        let victim = line.split(' ').next().unwrap_or_default().to_string();
 
-            message = Some(MinecraftMessage {
-                name: String::new(),
-                content: format!(":skull: {line}"),
-                source: Source::Server,
-                uuid: String::new(),
-            });
+        let mut content = format!(":skull: {line}");
+
+        if let Some(stats) = &mut self.death_stats {
+            if let Some(flavor) = stats::flavor_text(stats.record(&cause)) {
+                content = format!("{content} {flavor}");
+            }
         }
 
-        message
+        Some(MinecraftMessage {
+            name: String::new(),
+            content,
+            source: Source::Server,
+            uuid: String::new(),
+            kind: MessageKind::Death { cause, victim },
+            rank: String::new(),
+        })
+    }
+}
+
+/// Check if a chat message is a whisper to a Discord user, like
+/// `@discord Name: message`. Returns the recipient and the message.
+fn parse_whisper(content: &str) -> Option<(String, String)> {
+    let rest = content.strip_prefix("@discord ")?;
+    let (recipient, message) = rest.split_once(':')?;
+    let (recipient, message) = (recipient.trim(), message.trim());
+
+    if recipient.is_empty() || message.is_empty() {
+        return None;
+    }
+
+    Some((recipient.to_string(), message.to_string()))
+}
+
+/// Check if the line is a player running `/dolphin msg Name message`,
+/// for servers that log commands. Returns the sender, the recipient,
+/// and the message.
+fn parse_whisper_command(line: &str) -> Option<(String, String, String)> {
+    lazy_static! {
+        static ref WHISPER_COMMAND_REGEX: Regex = Regex::new(
+            r"^(?P<sender>\w+) issued server command: /dolphin msg (?P<recipient>\S+) (?P<content>.+)$"
+        )
+        .unwrap();
     }
+
+    let captures = WHISPER_COMMAND_REGEX.captures(line).ok()??;
+
+    Some((
+        captures["sender"].to_string(),
+        captures["recipient"].to_string(),
+        captures["content"].trim().to_string(),
+    ))
+}
+
+/// Check if the line is the server announcing which version of
+/// Minecraft it's starting, and return the version.
+pub fn parse_server_version(line: &str) -> Option<String> {
+    server_version(trim_prefix(line)?.trim())
+}
+
+fn server_version(line: &str) -> Option<String> {
+    let version = line
+        .strip_prefix("Starting minecraft server version ")?
+        .trim();
+
+    (!version.is_empty()).then(|| version.to_string())
 }
 
 /// Check if the line is the server logging a player earning
@@ -348,6 +570,45 @@ pub enum Source {
     Server,
 }
 
+/// The kind of event a message came from. Messages received over the
+/// network that don't specify a kind are treated as [MessageKind::Other].
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub enum MessageKind {
+    Chat,
+    Join,
+    Leave,
+    Death {
+        cause: String,
+        #[serde(default)]
+        victim: String,
+    },
+    Advancement {
+        title: String,
+        description: Option<String>,
+    },
+    Whisper {
+        recipient: String,
+    },
+    /// The server started. Parsed from the log, with the server version
+    /// and how long it took to start.
+    ServerStarted {
+        version: String,
+        startup_time: String,
+    },
+    /// The server started, as reported by a remote server.
+    ServerStart,
+    ServerStop,
+    #[default]
+    Other,
+}
+
+/// Check if a name could be a Minecraft player's: 1 to 16 letters,
+/// digits, and underscores. Names from outside, like the webserver, have
+/// to pass this before they're put in a command.
+pub fn is_player_name(name: &str) -> bool {
+    (1..=16).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 /// Represents a message from a Minecraft server, with any metadata that may be
 /// associated with it.
 ///
@@ -359,6 +620,12 @@ pub struct MinecraftMessage {
     pub content: String,
     pub source: Source,
     pub uuid: String,
+    #[serde(default)]
+    pub kind: MessageKind,
+    /// The player's rank or prefix from a chat plugin, if the chat regex
+    /// captured one.
+    #[serde(default)]
+    pub rank: String,
 }
 
 impl MinecraftMessage {
@@ -370,20 +637,21 @@ impl MinecraftMessage {
     /// case of users, optionally their  descriptor. This works
     /// for names that have spaces in them, and really probably
     /// anything else.
+    ///
+    /// How closely names have to match is set by `matching`. The
+    /// `@everyone` role is only matched if `allow_everyone` is set.
+    /// The IDs of any users that were mentioned are returned.
     pub fn replace_mentions(
         &mut self,
-        ctx: Arc<Context>,
-        guild_id: Arc<GuildId>,
-    ) -> Result<(), Error> {
-        let guild = match ctx.cache.guild(*guild_id) {
-            Some(guild) => guild,
-            None => return Err(Error::NoGuild(*guild_id)),
-        };
-
+        index: &MentionIndex,
+        allow_everyone: bool,
+        matching: Matching,
+    ) -> Vec<UserId> {
         let mut found_start = false;
         let mut start = 0;
         let mut end = 0;
         let mut replaced = self.content.clone();
+        let mut mentioned = Vec::new();
 
         for (i, c) in self.content.char_indices() {
             if !found_start && (c == '@' || c == '#') {
@@ -401,22 +669,16 @@ impl MinecraftMessage {
             if found_start && end > 0 {
                 if let Some(mention) = replaced.get(start..end) {
                     let name = &mention[1..];
-                    if let Some(member) = guild.member_named(name) {
-                        replaced = replaced.replace(mention, &member.mention().to_string());
-                    } else if let Some(role) = guild.role_by_name(name) {
-                        replaced = replaced.replace(mention, &role.mention().to_string());
-                    } else if let Some(id) = guild
-                        .channels
-                        .iter()
-                        .find(|&(_, v)| v.name == name)
-                        .map(|(k, _)| k)
-                    {
-                        if let Some(channel) = guild.channels.get(id) {
-                            replaced = replaced.replace(mention, &channel.mention().to_string());
-                        }
-                    } else {
+                    let Some(target) = index.find(name, allow_everyone, matching) else {
                         continue;
+                    };
+
+                    if let Target::User(id) = target {
+                        if !mentioned.contains(&id) {
+                            mentioned.push(id);
+                        }
                     }
+                    replaced = replaced.replace(mention, &target.mention());
 
                     // If we got here, we found a mention, so reset everything
                     start = 0;
@@ -427,7 +689,7 @@ impl MinecraftMessage {
         }
 
         self.content = replaced;
-        Ok(())
+        mentioned
     }
 }
 
@@ -457,9 +719,14 @@ pub enum Error {
 
 #[cfg(test)]
 mod tests {
+    use super::parse_server_version;
+    use super::DeathStats;
+    use super::Ignored;
+    use super::MessageKind;
     use super::MessageParser;
     use super::MinecraftMessage;
     use super::Source;
+    use super::Translation;
 
     #[tokio::test]
     async fn parse_vanilla_chat_line() {
@@ -472,6 +739,8 @@ mod tests {
             content: String::from("Sending a chat message"),
             source: Source::Player,
             uuid: String::from("7f7c909b-24f1-49a4-817f-baa4f4973980"),
+            kind: MessageKind::Chat,
+            rank: String::new(),
         };
 
         // When/Then
@@ -487,6 +756,90 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn record_why_lines_were_ignored() {
+        // Given
+        let mut parser = MessageParser::new_for_test();
+        let regex = String::from(r"^<(?P<username>\w+)> (?P<content>.+)");
+        let lines = [
+            "Starting minecraft server version 1.20.4",
+            "[12:32:45] [Server thread/INFO]: Villager axw['Villager'/85, l='world', x=-147.30, y=57.00, z=-190.70] died, message: 'Villager was squished too much'",
+            "[12:32:45] [Server thread/INFO]: Found that the dragon has been killed in this world already.",
+            "[12:32:45] [Server thread/INFO]: [VIP] Steve » hi",
+            "[12:32:45] [Server thread/INFO]: <Steve> hi",
+        ];
+
+        // When
+        let mut ignored = Vec::new();
+        for line in lines {
+            parser.parse_line(line, regex.clone()).await;
+            ignored.push(parser.take_ignored());
+        }
+
+        // Then
+        assert_eq!(
+            ignored,
+            vec![
+                Some(Ignored::NoPrefix),
+                Some(Ignored::VillagerDeath),
+                Some(Ignored::IgnorePhrase),
+                Some(Ignored::NoMatch),
+                None,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn keep_unmatched_lines() {
+        // Given
+        let mut parser = MessageParser::new_for_test();
+        let regex = String::from(r"^<(?P<username>\w+)> (?P<content>.+)");
+
+        // When
+        let chat = parser
+            .parse_line("[12:32:45] [Server thread/INFO]: <Steve> hi", regex.clone())
+            .await;
+        let after_chat = parser.take_unmatched();
+        parser
+            .parse_line(
+                "[12:32:45] [Server thread/INFO]: [VIP] Steve » hi",
+                regex.clone(),
+            )
+            .await;
+
+        // Then
+        assert!(chat.is_some());
+        assert_eq!(after_chat, None);
+        assert_eq!(
+            parser.take_unmatched(),
+            Some(String::from("[VIP] Steve » hi"))
+        );
+        assert_eq!(parser.take_unmatched(), None);
+    }
+
+    #[tokio::test]
+    async fn parse_chat_rank() {
+        // Given
+        let input = String::from(
+            "[12:32:45] [Server thread/INFO]: [Admin] EbonJaeger: Sending a chat message",
+        );
+        let mut parser = MessageParser::new_for_test();
+
+        // When
+        let message = parser
+            .parse_line(
+                &input,
+                String::from(r"^\[(?P<rank>[^\]]+)\] (?P<username>\w+): (?P<content>.+)"),
+            )
+            .await;
+
+        // Then
+        let message = message.expect("failed to parse chat message");
+        assert_eq!(message.name, "EbonJaeger");
+        assert_eq!(message.rank, "Admin");
+        assert_eq!(message.content, "Sending a chat message");
+    }
+
     #[tokio::test]
     async fn parse_non_vanilla_chat_line() {
         // Given
@@ -498,6 +851,8 @@ mod tests {
             content: String::from("Sending a chat message"),
             source: Source::Player,
             uuid: String::from("7f7c909b-24f1-49a4-817f-baa4f4973980"),
+            kind: MessageKind::Chat,
+            rank: String::new(),
         };
 
         // When/Then
@@ -525,6 +880,8 @@ mod tests {
             content: String::from("Sending a chat message"),
             source: Source::Player,
             uuid: String::from("7f7c909b-24f1-49a4-817f-baa4f4973980"),
+            kind: MessageKind::Chat,
+            rank: String::new(),
         };
 
         // When/Then
@@ -547,6 +904,8 @@ mod tests {
             content: String::from("TestUser joined the game"),
             source: Source::Server,
             uuid: String::new(),
+            kind: MessageKind::Join,
+            rank: String::new(),
         };
 
         // When/Then
@@ -572,6 +931,8 @@ mod tests {
             content: String::from("EbonJaeger left the game"),
             source: Source::Server,
             uuid: String::new(),
+            kind: MessageKind::Leave,
+            rank: String::new(),
         };
 
         // When/Then
@@ -605,6 +966,11 @@ mod tests {
             ),
             source: Source::Server,
             uuid: String::new(),
+            kind: MessageKind::Advancement {
+                title: String::from("MonsterHunter"),
+                description: None,
+            },
+            rank: String::new(),
         };
 
         // When/Then
@@ -634,6 +1000,11 @@ mod tests {
             ),
             source: Source::Server,
             uuid: String::new(),
+            kind: MessageKind::Advancement {
+                title: String::from("MonsterHunter"),
+                description: None,
+            },
+            rank: String::new(),
         };
 
         // When/Then
@@ -649,6 +1020,40 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn parse_advancement_with_description() {
+        // Given
+        let input = String::from(
+            "[12:32:45] [Server thread/INFO]: TestUser has made the advancement [Monster Hunter]",
+        );
+        let mut parser = MessageParser::new_for_test();
+        let expected = MinecraftMessage {
+            name: String::new(),
+            content: String::from(
+                ":partying_face: TestUser has made the advancement [Monster Hunter]",
+            ),
+            source: Source::Server,
+            uuid: String::new(),
+            kind: MessageKind::Advancement {
+                title: String::from("Monster Hunter"),
+                description: Some(String::from("Kill any hostile monster")),
+            },
+            rank: String::new(),
+        };
+
+        // When/Then
+        match parser
+            .parse_line(
+                &input,
+                String::from(r"^<(?P<username>\w+)> (?P<content>.+)"),
+            )
+            .await
+        {
+            Some(msg) => assert_eq!(msg, expected),
+            None => panic!("failed to parse advancement message"),
+        }
+    }
+
     #[tokio::test]
     async fn parse_server_start_line() {
         // Given
@@ -661,6 +1066,11 @@ mod tests {
             content: String::from(":white_check_mark: Server has started"),
             source: Source::Server,
             uuid: String::new(),
+            kind: MessageKind::ServerStarted {
+                version: String::from("unknown"),
+                startup_time: String::from("21.3s"),
+            },
+            rank: String::new(),
         };
 
         // When/Then
@@ -686,6 +1096,8 @@ mod tests {
             content: String::from(":x: Server is shutting down"),
             source: Source::Server,
             uuid: String::new(),
+            kind: MessageKind::ServerStop,
+            rank: String::new(),
         };
 
         // When/Then
@@ -702,6 +1114,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(clippy::redundant_pattern_matching)]
     async fn parser_ignore_villager_death_message() {
         // Given
         let input = String::from("[12:32:45] [Server thread/INFO]: Villager axw['Villager'/85, l='world', x=-147.30, y=57.00, z=-190.70] died, message: 'Villager was squished too much'");
@@ -741,6 +1154,56 @@ mod tests {
     }
 
     #[tokio::test]
+    async fn parser_translates_death_message() {
+        // Given
+        let input = String::from("[12:32:45] [Server thread/INFO]: EbonJaeger ertrank");
+        let mut parser = MessageParser::new_for_test();
+        parser.death_translations = vec![Translation::new("%1$s ertrank", "%1$s drowned").unwrap()];
+
+        // When
+        let message = parser
+            .parse_line(
+                &input,
+                String::from(r"^<(?P<username>\w+)> (?P<content>.+)"),
+            )
+            .await
+            .expect("A message should have been generated.");
+
+        // Then
+        assert_eq!(message.content, ":skull: EbonJaeger drowned");
+    }
+
+    #[tokio::test]
+    async fn parser_adds_death_stats() {
+        // Given
+        let input = String::from("[12:32:45] [Server thread/INFO]: EbonJaeger drowned");
+        let mut parser = MessageParser::new_for_test();
+        parser.death_stats = Some(DeathStats::default());
+        let regex = String::from(r"^<(?P<username>\w+)> (?P<content>.+)");
+
+        // When
+        parser.parse_line(&input, regex.clone()).await;
+        let message = parser
+            .parse_line(&input, regex)
+            .await
+            .expect("A message should have been generated.");
+
+        // Then
+        assert_eq!(
+            message.content,
+            ":skull: EbonJaeger drowned (2nd time this week)"
+        );
+        assert_eq!(
+            message.kind,
+            MessageKind::Death {
+                cause: String::from("drowned"),
+                victim: String::from("EbonJaeger"),
+            }
+        );
+    }
+
+    #[tokio::test]
+    #[allow(clippy::redundant_pattern_matching)]
     async fn parser_cache_uuid_on_join() {
         // Given
         let input = String::from(
@@ -766,4 +1229,105 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn parse_whisper_chat_line() {
+        // Given
+        let input = String::from(
+            "[12:32:45] [Server thread/INFO]: <EbonJaeger> @discord Some User: are you on?",
+        );
+        let mut parser = MessageParser::new_for_test();
+        let regex = String::from(r"^<(?P<username>\w+)> (?P<content>.+)");
+
+        // When
+        let message = parser
+            .parse_line(&input, regex)
+            .await
+            .expect("A message should have been generated.");
+
+        // Then
+        assert_eq!(message.content, "are you on?");
+        assert_eq!(
+            message.kind,
+            MessageKind::Whisper {
+                recipient: String::from("Some User")
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn parse_whisper_command_line() {
+        // Given
+        let input = String::from(
+            "[12:32:45] [Server thread/INFO]: EbonJaeger issued server command: /dolphin msg Evan hello there",
+        );
+        let mut parser = MessageParser::new_for_test();
+        let regex = String::from(r"^<(?P<username>\w+)> (?P<content>.+)");
+
+        // When
+        let message = parser
+            .parse_line(&input, regex)
+            .await
+            .expect("A message should have been generated.");
+
+        // Then
+        assert_eq!(message.name, "EbonJaeger");
+        assert_eq!(message.content, "hello there");
+        assert_eq!(
+            message.kind,
+            MessageKind::Whisper {
+                recipient: String::from("Evan")
+            }
+        );
+    }
+
+    #[test]
+    fn parse_starting_server_version() {
+        assert_eq!(
+            parse_server_version(
+                "[10:01:12] [Server thread/INFO]: Starting minecraft server version 1.21.1"
+            ),
+            Some(String::from("1.21.1"))
+        );
+        assert_eq!(
+            parse_server_version(
+                "[10:01:12] [Server thread/INFO]: <Steve> Starting minecraft server version 1.0"
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn server_start_template_placeholders() {
+        // Given
+        let regex = String::from(r"^<(?P<username>\w+)> (?P<content>.+)");
+        let mut parser = MessageParser::new_for_test();
+        parser.server_start_template = String::from("Started %version% in %startup_time%");
+
+        // When
+        let version = parser
+            .parse_line(
+                "[12:32:20] [Server thread/INFO]: Starting minecraft server version 1.21.1",
+                regex.clone(),
+            )
+            .await;
+        let started = parser
+            .parse_line(
+                "[12:32:45] [Server thread/INFO]: Done (5.012s)! For help, type \"help\"",
+                regex,
+            )
+            .await
+            .expect("A message should have been generated.");
+
+        // Then
+        assert!(version.is_none());
+        assert_eq!(started.content, "Started 1.21.1 in 5.0s");
+        assert_eq!(
+            started.kind,
+            MessageKind::ServerStarted {
+                version: String::from("1.21.1"),
+                startup_time: String::from("5.0s"),
+            }
+        );
+    }
 }
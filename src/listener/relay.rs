@@ -0,0 +1,58 @@
+use std::sync::OnceLock;
+
+use tokio::sync::broadcast;
+
+/// How many Discord messages to hold for listeners that fall behind.
+const BUFFER: usize = 256;
+
+static RELAY: OnceLock<broadcast::Sender<DiscordChat>> = OnceLock::new();
+
+/// A line of chat sent in the bridged Discord channel, for listeners
+/// that pass messages back to Minecraft themselves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiscordChat {
+    pub name: String,
+    pub content: String,
+}
+
+/// Pass a chat message from Discord on to every listener that wants it.
+pub fn publish(name: &str, content: &str) {
+    if let Some(relay) = RELAY.get() {
+        // This only fails if nothing is listening
+        let _ = relay.send(DiscordChat {
+            name: name.to_string(),
+            content: content.to_string(),
+        });
+    }
+}
+
+/// Start receiving chat messages from Discord.
+#[cfg_attr(not(any(feature = "grpc", feature = "mqtt")), allow(dead_code))]
+pub fn subscribe() -> broadcast::Receiver<DiscordChat> {
+    RELAY
+        .get_or_init(|| broadcast::channel(BUFFER).0)
+        .subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{publish, subscribe, DiscordChat};
+
+    #[test]
+    fn subscribers_get_published_messages() {
+        // Given
+        let mut messages = subscribe();
+
+        // When
+        publish("Steve", "Hello");
+
+        // Then
+        assert_eq!(
+            messages.try_recv().unwrap(),
+            DiscordChat {
+                name: String::from("Steve"),
+                content: String::from("Hello"),
+            }
+        );
+    }
+}
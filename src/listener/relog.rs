@@ -0,0 +1,124 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serenity::prelude::{Mutex, TypeMapKey};
+
+use super::{parser::MessageKind, sessions::player_name};
+use crate::per_server::PerServer;
+
+/// What to do with a join or leave message.
+#[derive(Debug, PartialEq)]
+pub enum Relog {
+    /// Post the message now.
+    Send,
+    /// Hold on to the leave message, and post it later if
+    /// [RelogFilter::release] says the player is still gone.
+    Hold(String, u64),
+    /// The player left and came right back, so the leave was never
+    /// posted and the join shouldn't be either.
+    Reconnected(String),
+}
+
+/// Holds back leave messages for a while, so players that drop and
+/// rejoin right away don't flood the channel.
+#[derive(Debug, Default)]
+pub struct RelogFilter {
+    pending: HashMap<String, u64>,
+    next_id: u64,
+}
+
+impl RelogFilter {
+    pub fn check(&mut self, kind: &MessageKind, content: &str) -> Relog {
+        let Some(name) = player_name(content) else {
+            return Relog::Send;
+        };
+
+        match kind {
+            MessageKind::Leave => {
+                self.next_id += 1;
+                self.pending.insert(name.to_string(), self.next_id);
+                Relog::Hold(name.to_string(), self.next_id)
+            }
+            MessageKind::Join if self.pending.remove(name).is_some() => {
+                Relog::Reconnected(name.to_string())
+            }
+            _ => Relog::Send,
+        }
+    }
+
+    /// Returns `true` if a held leave message should be posted, because
+    /// the player hasn't come back since.
+    pub fn release(&mut self, name: &str, id: u64) -> bool {
+        if self.pending.get(name) == Some(&id) {
+            self.pending.remove(name);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How many leave messages are being held back.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// The relog filter for each server, so leaving one server and joining
+/// another isn't taken for a reconnect.
+pub struct RelogFilterContainer;
+
+impl TypeMapKey for RelogFilterContainer {
+    type Value = Arc<PerServer<Mutex<RelogFilter>>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Relog, RelogFilter};
+    use crate::listener::parser::MessageKind;
+
+    #[test]
+    fn quick_rejoin_is_a_reconnect() {
+        // Given
+        let mut filter = RelogFilter::default();
+
+        // When
+        let leave = filter.check(&MessageKind::Leave, "Steve left the game");
+        let join = filter.check(&MessageKind::Join, "Steve joined the game");
+
+        // Then
+        assert_eq!(leave, Relog::Hold(String::from("Steve"), 1));
+        assert_eq!(join, Relog::Reconnected(String::from("Steve")));
+        assert!(!filter.release("Steve", 1));
+    }
+
+    #[test]
+    fn release_leaves_for_players_still_gone() {
+        // Given
+        let mut filter = RelogFilter::default();
+
+        // When
+        filter.check(&MessageKind::Leave, "Steve left the game");
+        filter.check(&MessageKind::Leave, "Alex left the game");
+        let rejoined = filter.check(&MessageKind::Join, "Alex joined the game");
+
+        // Then
+        assert!(filter.release("Steve", 1));
+        assert!(!filter.release("Alex", 2));
+        assert_eq!(rejoined, Relog::Reconnected(String::from("Alex")));
+        assert_eq!(
+            filter.check(&MessageKind::Join, "Notch joined the game"),
+            Relog::Send
+        );
+    }
+
+    #[test]
+    fn only_the_latest_leave_is_released() {
+        let mut filter = RelogFilter::default();
+
+        filter.check(&MessageKind::Leave, "Steve left the game");
+        filter.check(&MessageKind::Join, "Steve joined the game");
+        filter.check(&MessageKind::Leave, "Steve left the game");
+
+        assert!(!filter.release("Steve", 1));
+        assert!(filter.release("Steve", 2));
+    }
+}
@@ -0,0 +1,172 @@
+use serenity::model::Timestamp;
+use thiserror::Error;
+
+/// A player asking to schedule a Discord event from game, like
+/// `!event "Build contest" 2024-07-01T18:00 2h`.
+#[derive(Debug, PartialEq)]
+pub struct EventRequest {
+    pub name: String,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl EventRequest {
+    /// Parse a chat message as an event request. Returns `None` if it
+    /// isn't one at all, so it can be posted like any other chat.
+    ///
+    /// Start times without an offset are in UTC, and lengths are made of
+    /// days, hours, and minutes, like `2h` or `1h30m`.
+    pub fn parse(content: &str, now: i64) -> Option<Result<Self, Error>> {
+        let rest = content.trim().strip_prefix("!event")?;
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        Some(parse_args(rest.trim(), now))
+    }
+}
+
+fn parse_args(args: &str, now: i64) -> Result<EventRequest, Error> {
+    let (name, rest) = match args.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').ok_or(Error::Usage)?,
+        None => args.split_once(char::is_whitespace).ok_or(Error::Usage)?,
+    };
+    let mut rest = rest.split_whitespace();
+    let (Some(start), Some(length), None) = (rest.next(), rest.next(), rest.next()) else {
+        return Err(Error::Usage);
+    };
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::Usage);
+    }
+
+    let start = parse_time(start).ok_or_else(|| Error::Time(start.to_string()))?;
+    if start.unix_timestamp() <= now {
+        return Err(Error::Past);
+    }
+    let length = parse_length(length).ok_or_else(|| Error::Length(length.to_string()))?;
+    let end = Timestamp::from_unix_timestamp(start.unix_timestamp() + length)
+        .map_err(|_| Error::Time(start.to_string()))?;
+
+    Ok(EventRequest {
+        name: name.to_string(),
+        start,
+        end,
+    })
+}
+
+/// Parse a time like `2024-07-01T18:00`, with optional seconds and an
+/// optional offset like `Z` or `+02:00`.
+fn parse_time(time: &str) -> Option<Timestamp> {
+    let (date_time, offset) = match time.get(16..) {
+        Some(rest) if rest.starts_with(':') => return Timestamp::parse(time).ok(),
+        Some("") => (time, "Z"),
+        Some(offset) => (time.get(..16)?, offset),
+        None => return None,
+    };
+
+    Timestamp::parse(&format!("{}:00{}", date_time, offset)).ok()
+}
+
+/// Parse a length like `2h`, `90m`, or `1d2h30m` into seconds.
+fn parse_length(length: &str) -> Option<i64> {
+    let mut seconds = 0;
+    let mut number = String::new();
+
+    for c in length.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'd' => 24 * 60 * 60,
+            'h' => 60 * 60,
+            'm' => 60,
+            _ => return None,
+        };
+        seconds += number.parse::<i64>().ok()? * unit;
+        number.clear();
+    }
+
+    (number.is_empty() && seconds > 0).then_some(seconds)
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Usage: !event \"name\" <start, like 2024-07-01T18:00> <length, like 2h>")]
+    Usage,
+
+    #[error("'{0}' isn't a time like 2024-07-01T18:00")]
+    Time(String),
+
+    #[error("'{0}' isn't a length like 2h or 1h30m")]
+    Length(String),
+
+    #[error("Events have to start in the future")]
+    Past,
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::Timestamp;
+
+    use super::{parse_length, parse_time, Error, EventRequest};
+
+    #[test]
+    fn parse_event_requests() {
+        // Given
+        let now = Timestamp::parse("2024-06-01T00:00:00Z")
+            .unwrap()
+            .unix_timestamp();
+
+        // When
+        let quoted = EventRequest::parse("!event \"Build contest\" 2024-07-01T18:00 2h", now);
+        let single = EventRequest::parse("!event Party 2024-07-01T18:00+02:00 30m", now);
+
+        // Then
+        assert_eq!(
+            quoted,
+            Some(Ok(EventRequest {
+                name: String::from("Build contest"),
+                start: Timestamp::parse("2024-07-01T18:00:00Z").unwrap(),
+                end: Timestamp::parse("2024-07-01T20:00:00Z").unwrap(),
+            }))
+        );
+        assert!(single.is_some_and(|request| request.is_ok_and(
+            |request| request.start == Timestamp::parse("2024-07-01T16:00:00Z").unwrap()
+        )));
+    }
+
+    #[test]
+    fn explain_bad_requests() {
+        // Given
+        let now = Timestamp::parse("2024-06-01T00:00:00Z")
+            .unwrap()
+            .unix_timestamp();
+
+        // Then
+        assert_eq!(EventRequest::parse("!event", now), Some(Err(Error::Usage)));
+        assert_eq!(
+            EventRequest::parse("!event Party tomorrow 2h", now),
+            Some(Err(Error::Time(String::from("tomorrow"))))
+        );
+        assert_eq!(
+            EventRequest::parse("!event Party 2024-05-01T18:00 2h", now),
+            Some(Err(Error::Past))
+        );
+        assert_eq!(EventRequest::parse("!events are fun", now), None);
+        assert_eq!(EventRequest::parse("is there an !event", now), None);
+    }
+
+    #[test]
+    fn parse_times_and_lengths() {
+        assert!(parse_time("2024-07-01T18:00:30Z").is_some());
+        assert!(parse_time("2024-07-01").is_none());
+        assert_eq!(parse_length("1h30m"), Some(90 * 60));
+        assert_eq!(parse_length("1d"), Some(24 * 60 * 60));
+        assert_eq!(parse_length("2"), None);
+        assert_eq!(parse_length("0m"), None);
+    }
+}
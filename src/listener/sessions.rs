@@ -0,0 +1,76 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serenity::prelude::{Mutex, TypeMapKey};
+
+use super::parser::MessageKind;
+use crate::per_server::PerServer;
+
+/// Keeps track of when each online player joined.
+#[derive(Debug, Default)]
+pub struct Sessions {
+    joined: HashMap<String, Instant>,
+}
+
+impl Sessions {
+    /// Update the sessions from a join or leave message.
+    pub fn update(&mut self, kind: &MessageKind, content: &str) {
+        let Some(name) = player_name(content).map(String::from) else {
+            return;
+        };
+
+        match kind {
+            MessageKind::Join => {
+                self.joined.insert(name, Instant::now());
+            }
+            MessageKind::Leave => {
+                self.joined.remove(&name);
+            }
+            _ => {}
+        }
+    }
+
+    /// How long a player has been online, if we saw them join.
+    pub fn online_for(&self, name: &str) -> Option<Duration> {
+        self.joined.get(name).map(|joined| joined.elapsed())
+    }
+}
+
+/// Get the player's name from a join or leave message, which starts
+/// with it.
+pub fn player_name(content: &str) -> Option<&str> {
+    content.split(' ').next().filter(|name| !name.is_empty())
+}
+
+/// Who's online on each server.
+pub struct SessionsContainer;
+
+impl TypeMapKey for SessionsContainer {
+    type Value = Arc<PerServer<Mutex<Sessions>>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sessions;
+    use crate::listener::parser::MessageKind;
+
+    #[test]
+    fn tracks_joins_and_leaves() {
+        // Given
+        let mut sessions = Sessions::default();
+
+        // When
+        sessions.update(&MessageKind::Join, "Steve joined the game");
+        sessions.update(&MessageKind::Join, "Alex joined the game");
+        sessions.update(&MessageKind::Leave, "Alex left the game");
+        sessions.update(&MessageKind::Chat, "Notch");
+
+        // Then
+        assert!(sessions.online_for("Steve").is_some());
+        assert!(sessions.online_for("Alex").is_none());
+        assert!(sessions.online_for("Notch").is_none());
+    }
+}
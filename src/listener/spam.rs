@@ -0,0 +1,191 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serenity::prelude::{Mutex, TypeMapKey};
+
+use crate::per_server::PerServer;
+
+/// What to do with a chat message from a player.
+#[derive(Debug, PartialEq)]
+pub enum Spam {
+    /// Post it as usual.
+    Send,
+    /// The player sent the same thing again, this many times in a row.
+    Repeat(u32),
+    /// The player kept repeating themselves and is muted on Discord for
+    /// a while.
+    Muted,
+}
+
+#[derive(Debug)]
+struct Repeat {
+    content: String,
+    count: u32,
+    last_at: Instant,
+}
+
+/// Spots players sending the same message over and over, so the
+/// repeats can be collapsed into one post.
+#[derive(Debug, Default)]
+pub struct SpamFilter {
+    last: HashMap<String, Repeat>,
+    muted: HashMap<String, Instant>,
+}
+
+/// When a message counts as spam.
+#[derive(Debug)]
+pub struct Limits {
+    /// Messages repeated within this long of the one before are repeats.
+    pub window: Duration,
+    /// Players who repeat a message this many times are muted, unless
+    /// it's zero.
+    pub mute_threshold: u32,
+    /// How long players are muted for.
+    pub mute_for: Duration,
+}
+
+impl SpamFilter {
+    pub fn check(&mut self, player: &str, content: &str, limits: &Limits, now: Instant) -> Spam {
+        self.muted.retain(|_, until| *until > now);
+        if self.muted.contains_key(player) {
+            return Spam::Muted;
+        }
+
+        let repeat = self
+            .last
+            .get_mut(player)
+            .filter(|last| last.content == content && now - last.last_at <= limits.window);
+
+        let Some(repeat) = repeat else {
+            self.last.insert(
+                player.to_string(),
+                Repeat {
+                    content: content.to_string(),
+                    count: 1,
+                    last_at: now,
+                },
+            );
+            return Spam::Send;
+        };
+
+        repeat.count += 1;
+        repeat.last_at = now;
+        let count = repeat.count;
+
+        if limits.mute_threshold > 0 && count >= limits.mute_threshold {
+            self.last.remove(player);
+            self.muted.insert(player.to_string(), now + limits.mute_for);
+            return Spam::Muted;
+        }
+
+        Spam::Repeat(count)
+    }
+}
+
+/// The spam filter for each server's players.
+pub struct SpamFilterContainer;
+
+impl TypeMapKey for SpamFilterContainer {
+    type Value = Arc<PerServer<Mutex<SpamFilter>>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Limits, Spam, SpamFilter};
+
+    #[test]
+    fn count_repeats_in_the_window() {
+        // Given
+        let mut filter = SpamFilter::default();
+        let limits = Limits {
+            window: Duration::from_secs(10),
+            mute_threshold: 0,
+            mute_for: Duration::ZERO,
+        };
+        let now = Instant::now();
+
+        // When
+        let first = filter.check("Steve", "buy my shop", &limits, now);
+        let second = filter.check(
+            "Steve",
+            "buy my shop",
+            &limits,
+            now + Duration::from_secs(5),
+        );
+        let third = filter.check(
+            "Steve",
+            "buy my shop",
+            &limits,
+            now + Duration::from_secs(12),
+        );
+        let other = filter.check(
+            "Alex",
+            "buy my shop",
+            &limits,
+            now + Duration::from_secs(12),
+        );
+
+        // Then
+        assert_eq!(first, Spam::Send);
+        assert_eq!(second, Spam::Repeat(2));
+        assert_eq!(third, Spam::Repeat(3));
+        assert_eq!(other, Spam::Send);
+    }
+
+    #[test]
+    fn start_over_after_the_window_or_new_messages() {
+        // Given
+        let mut filter = SpamFilter::default();
+        let limits = Limits {
+            window: Duration::from_secs(10),
+            mute_threshold: 0,
+            mute_for: Duration::ZERO,
+        };
+        let now = Instant::now();
+        filter.check("Steve", "hi", &limits, now);
+
+        // Then
+        assert_eq!(
+            filter.check("Steve", "hi", &limits, now + Duration::from_secs(11)),
+            Spam::Send
+        );
+        assert_eq!(
+            filter.check("Steve", "hello", &limits, now + Duration::from_secs(12)),
+            Spam::Send
+        );
+    }
+
+    #[test]
+    fn mute_players_that_keep_going() {
+        // Given
+        let mut filter = SpamFilter::default();
+        let limits = Limits {
+            window: Duration::from_secs(10),
+            mute_threshold: 3,
+            mute_for: Duration::from_secs(60),
+        };
+        let now = Instant::now();
+
+        // When
+        filter.check("Steve", "spam", &limits, now);
+        filter.check("Steve", "spam", &limits, now);
+        let muted = filter.check("Steve", "spam", &limits, now);
+        let still_muted = filter.check(
+            "Steve",
+            "something else",
+            &limits,
+            now + Duration::from_secs(30),
+        );
+        let unmuted = filter.check("Steve", "sorry", &limits, now + Duration::from_secs(61));
+
+        // Then
+        assert_eq!(muted, Spam::Muted);
+        assert_eq!(still_muted, Spam::Muted);
+        assert_eq!(unmuted, Spam::Send);
+    }
+}
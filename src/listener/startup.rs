@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use tokio::time::timeout;
+use tracing::{error, info};
+
+use super::parser::MessageKind;
+use crate::{template, transport::Transport};
+
+/// How long to wait for the server to answer each startup command.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Fill in the startup commands for a server start. Only the `Done` line
+/// read from the log or console counts; a start reported by a plugin over
+/// the webserver gets no commands, since anyone with the token could send one.
+pub fn expand(commands: &[String], kind: &MessageKind) -> Vec<String> {
+    let (version, startup_time) = match kind {
+        MessageKind::ServerStarted {
+            version,
+            startup_time,
+        } => (version.as_str(), startup_time.as_str()),
+        _ => return Vec::new(),
+    };
+
+    commands
+        .iter()
+        .map(|command| {
+            template::render(
+                command,
+                &[("version", version), ("startup_time", startup_time)],
+            )
+        })
+        .filter(|command| !command.trim().is_empty())
+        .collect()
+}
+
+/// Send each command to the server in order. A command that fails is
+/// logged, and the rest are still sent.
+pub async fn run(transport: Transport, commands: Vec<String>) {
+    info!("Running {} startup commands", commands.len());
+
+    for command in commands {
+        match timeout(COMMAND_TIMEOUT, transport.send(&command)).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => error!("startup:run: unable to run '{}': {}", command, e),
+            Err(_) => error!("startup:run: timed out running '{}'", command),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand;
+    use crate::listener::parser::MessageKind;
+
+    #[test]
+    fn fill_in_server_start_details() {
+        // Given
+        let commands = vec![
+            String::from("say Discord bridge is up on %version%"),
+            String::from("scoreboard objectives setdisplay sidebar bridge"),
+        ];
+        let kind = MessageKind::ServerStarted {
+            version: String::from("1.21"),
+            startup_time: String::from("5.0s"),
+        };
+
+        // When
+        let expanded = expand(&commands, &kind);
+
+        // Then
+        assert_eq!(
+            expanded,
+            vec![
+                "say Discord bridge is up on 1.21",
+                "scoreboard objectives setdisplay sidebar bridge"
+            ]
+        );
+    }
+
+    #[test]
+    fn only_run_on_done_line() {
+        // Given
+        let commands = vec![String::from("say hi")];
+
+        // Then
+        assert!(expand(&commands, &MessageKind::ServerStart).is_empty());
+        assert!(expand(&commands, &MessageKind::Chat).is_empty());
+        assert!(expand(&commands, &MessageKind::ServerStop).is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// How far back deaths are counted.
+const WINDOW: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+/// Keeps track of how often each kind of death has happened recently.
+///
+/// Stats are only kept in memory, so they start over whenever Dolphin
+/// is restarted.
+#[derive(Clone, Debug, Default)]
+pub struct DeathStats {
+    deaths: HashMap<String, VecDeque<Instant>>,
+}
+
+impl DeathStats {
+    /// Record a death with the given cause, returning how many times a
+    /// death with this cause has happened in the past week, including
+    /// this one.
+    pub fn record(&mut self, cause: &str) -> usize {
+        self.record_at(cause, Instant::now())
+    }
+
+    fn record_at(&mut self, cause: &str, now: Instant) -> usize {
+        let times = self.deaths.entry(cause.to_string()).or_default();
+
+        // Forget about deaths that are too old to count
+        while let Some(oldest) = times.front() {
+            if now.duration_since(*oldest) <= WINDOW {
+                break;
+            }
+            times.pop_front();
+        }
+
+        times.push_back(now);
+        times.len()
+    }
+}
+
+/// Build the flavor text to append to a death message, e.g.
+/// `(3rd time this week)`. Nothing is added for first-time deaths.
+pub fn flavor_text(count: usize) -> Option<String> {
+    if count < 2 {
+        return None;
+    }
+
+    Some(format!("({} time this week)", ordinal(count)))
+}
+
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{}{}", n, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{flavor_text, ordinal, DeathStats, WINDOW};
+
+    #[test]
+    fn counts_deaths_per_cause() {
+        // Given
+        let mut stats = DeathStats::default();
+        let now = Instant::now();
+
+        // When
+        stats.record_at("drowned", now);
+        stats.record_at("fell", now);
+        let count = stats.record_at("drowned", now);
+
+        // Then
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn forgets_old_deaths() {
+        // Given
+        let mut stats = DeathStats::default();
+        let start = Instant::now();
+
+        // When
+        stats.record_at("drowned", start);
+        let count = stats.record_at("drowned", start + WINDOW + Duration::from_secs(1));
+
+        // Then
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn formats_flavor_text() {
+        assert_eq!(flavor_text(1), None);
+        assert_eq!(flavor_text(3), Some(String::from("(3rd time this week)")));
+    }
+
+    #[test]
+    fn formats_ordinals() {
+        assert_eq!(ordinal(1), "1st");
+        assert_eq!(ordinal(2), "2nd");
+        assert_eq!(ordinal(3), "3rd");
+        assert_eq!(ordinal(4), "4th");
+        assert_eq!(ordinal(11), "11th");
+        assert_eq!(ordinal(12), "12th");
+        assert_eq!(ordinal(13), "13th");
+        assert_eq!(ordinal(21), "21st");
+        assert_eq!(ordinal(112), "112th");
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use linemux::MuxedLines;
+use serenity::prelude::RwLock;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info};
+
+use crate::config::RootConfig;
+
+/// How often to check the config file for a new log file path.
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A change to the files a [super::LogTailer] is watching.
+#[derive(Debug, PartialEq)]
+pub enum TailerCommand {
+    Watch(String),
+    Unwatch(String),
+}
+
+/// The files being tailed, and the line muxer reading them.
+pub struct Watched {
+    paths: Vec<String>,
+    lines: Option<MuxedLines>,
+}
+
+impl Watched {
+    pub async fn new(path: String) -> Self {
+        let mut watched = Watched {
+            paths: Vec::new(),
+            lines: None,
+        };
+        if !path.is_empty() {
+            watched.apply(TailerCommand::Watch(path)).await;
+        }
+        watched
+    }
+
+    pub fn lines(&mut self) -> Option<&mut MuxedLines> {
+        self.lines.as_mut()
+    }
+
+    /// Start or stop watching a file. The line muxer can't forget
+    /// about a file, so it's started over with the ones that are left.
+    pub async fn apply(&mut self, command: TailerCommand) {
+        match command {
+            TailerCommand::Watch(path) => {
+                if !self.paths.contains(&path) {
+                    self.watch(path).await;
+                }
+            }
+            TailerCommand::Unwatch(path) => {
+                if !self.paths.contains(&path) {
+                    return;
+                }
+
+                info!("log_tailer:apply: stopped watching '{}'", path);
+                let paths = std::mem::take(&mut self.paths);
+                self.lines = None;
+                for path in paths.into_iter().filter(|watched| *watched != path) {
+                    self.watch(path).await;
+                }
+            }
+        }
+    }
+
+    async fn watch(&mut self, path: String) {
+        if self.lines.is_none() {
+            self.lines = new_muxer();
+        }
+        let Some(lines) = self.lines.as_mut() else {
+            return;
+        };
+
+        match lines.add_file(&path).await {
+            Ok(_) => {
+                info!("log_tailer:watch: started watching '{}'", path);
+                self.paths.push(path);
+            }
+            Err(e) => error!("log_tailer:watch: unable to watch '{}': {}", path, e),
+        }
+    }
+}
+
+fn new_muxer() -> Option<MuxedLines> {
+    match MuxedLines::new() {
+        Ok(lines) => Some(lines),
+        Err(e) => {
+            error!("log_tailer:new_muxer: unable to create a line muxer: {}", e);
+            None
+        }
+    }
+}
+
+/// What to tell a tailer when the log file path changes.
+fn path_changes(old: &str, new: &str) -> Vec<TailerCommand> {
+    let mut commands = Vec::new();
+    if old == new {
+        return commands;
+    }
+
+    if !old.is_empty() {
+        commands.push(TailerCommand::Unwatch(old.to_string()));
+    }
+    if !new.is_empty() {
+        commands.push(TailerCommand::Watch(new.to_string()));
+    }
+    commands
+}
+
+/// Read the log file path for this server from the config file on disk.
+async fn read_log_path(config_path: &Path, channel_id: u64) -> Option<String> {
+    let text = match tokio::fs::read_to_string(config_path).await {
+        Ok(text) => text,
+        Err(e) => {
+            debug!("log_tailer:read_log_path: unable to read the config: {}", e);
+            return None;
+        }
+    };
+
+    match toml::from_str::<RootConfig>(&text) {
+        Ok(config) => Some(config.get_log_path_for(channel_id)),
+        Err(e) => {
+            debug!(
+                "log_tailer:read_log_path: unable to parse the config: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Keep a tailer on the log file path in the config file, for as long as
+/// it's running. Only a change in the file moves the tailer, so a path
+/// given on the command line is kept until the file is edited. The new
+/// path is saved to the loaded config too.
+pub async fn follow_config(
+    config_lock: Arc<RwLock<RootConfig>>,
+    config_path: Arc<PathBuf>,
+    mut path: String,
+    commands: mpsc::Sender<TailerCommand>,
+) {
+    let channel_id = config_lock.read().await.get_channel_id();
+    let mut saved = read_log_path(&config_path, channel_id).await;
+    let mut interval = tokio::time::interval(CONFIG_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let Some(new_path) = read_log_path(&config_path, channel_id).await else {
+            continue;
+        };
+        if saved.as_ref() == Some(&new_path) {
+            continue;
+        }
+        saved = Some(new_path.clone());
+
+        for command in path_changes(&path, &new_path) {
+            if commands.send(command).await.is_err() {
+                return;
+            }
+        }
+        config_lock.write().await.set_log_file(new_path.clone());
+        path = new_path;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{path_changes, TailerCommand};
+
+    #[test]
+    fn swap_files_when_the_path_changes() {
+        // Then
+        assert_eq!(path_changes("a.log", "a.log"), Vec::new());
+        assert_eq!(
+            path_changes("a.log", "b.log"),
+            vec![
+                TailerCommand::Unwatch(String::from("a.log")),
+                TailerCommand::Watch(String::from("b.log"))
+            ]
+        );
+        assert_eq!(
+            path_changes("", "b.log"),
+            vec![TailerCommand::Watch(String::from("b.log"))]
+        );
+        assert_eq!(
+            path_changes("a.log", ""),
+            vec![TailerCommand::Unwatch(String::from("a.log"))]
+        );
+    }
+}
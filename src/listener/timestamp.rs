@@ -0,0 +1,101 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Time zone offsets are always a multiple of this many seconds.
+const OFFSET_GRANULARITY: i64 = 15 * 60;
+
+/// Get the time of day from the start of a log line, e.g. `[12:32:45]`
+/// or `[12:32:45 INFO]`, in seconds since midnight.
+pub fn parse_log_time(line: &str) -> Option<i64> {
+    let time = line.strip_prefix('[')?.get(..8)?;
+    let mut parts = time.split(':').map(|part| part.parse::<i64>().ok());
+
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next()??);
+    if hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Turn the time of day a line was logged into a Unix timestamp.
+///
+/// Log times are in the server's time zone, which we don't know, but
+/// lines are read right after they're written. The difference between
+/// the log time and the current UTC time is the server's offset from
+/// UTC plus however long ago the line was written, so rounding it to the
+/// nearest possible offset tells us both. This works as long as lines
+/// are less than 7.5 minutes old.
+pub fn log_time_to_unix(log_time: i64, now: i64) -> i64 {
+    let utc_time = now.rem_euclid(SECONDS_PER_DAY);
+
+    // Keep the difference within half a day either way of UTC
+    let mut difference = (log_time - utc_time).rem_euclid(SECONDS_PER_DAY);
+    if difference > SECONDS_PER_DAY / 2 {
+        difference -= SECONDS_PER_DAY;
+    }
+
+    let offset =
+        (difference as f64 / OFFSET_GRANULARITY as f64).round() as i64 * OFFSET_GRANULARITY;
+    let age = (offset - difference).max(0);
+
+    now - age
+}
+
+/// The current Unix timestamp.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Format a timestamp so that Discord shows it in each user's own time
+/// zone. Unknown styles are shown as a short time.
+pub fn discord_timestamp(timestamp: i64, style: &str) -> String {
+    let style = match style {
+        "t" | "T" | "d" | "D" | "f" | "F" | "R" => style,
+        _ => "t",
+    };
+
+    format!("<t:{}:{}>", timestamp, style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{discord_timestamp, log_time_to_unix, parse_log_time};
+
+    // 2024-07-23 10:00:00 UTC
+    const NOW: i64 = 1_721_728_800;
+
+    #[test]
+    fn parse_log_line_times() {
+        assert_eq!(
+            parse_log_time("[12:32:45] [Server thread/INFO]: <Steve> hi"),
+            Some(45165)
+        );
+        assert_eq!(parse_log_time("[12:32:45 INFO]: <Steve> hi"), Some(45165));
+        assert_eq!(parse_log_time("<Steve> hi"), None);
+        assert_eq!(parse_log_time("[Server thread/INFO]: hi"), None);
+    }
+
+    #[test]
+    fn log_times_in_other_time_zones() {
+        // UTC+2, logged just now
+        assert_eq!(log_time_to_unix(12 * 3600, NOW), NOW);
+        // UTC-5, logged 10 seconds ago
+        assert_eq!(log_time_to_unix(5 * 3600 - 10, NOW), NOW - 10);
+        // UTC+5:30, logged 2 minutes ago
+        assert_eq!(log_time_to_unix(15 * 3600 + 28 * 60, NOW), NOW - 120);
+        // UTC-10, on the day before UTC
+        assert_eq!(log_time_to_unix(0, NOW), NOW);
+    }
+
+    #[test]
+    fn format_discord_timestamps() {
+        assert_eq!(discord_timestamp(NOW, "t"), "<t:1721728800:t>");
+        assert_eq!(discord_timestamp(NOW, "R"), "<t:1721728800:R>");
+        assert_eq!(discord_timestamp(NOW, "x"), "<t:1721728800:t>");
+    }
+}
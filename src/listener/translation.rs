@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use fancy_regex::Regex;
+use tracing::warn;
+
+lazy_static! {
+    /// Matches `%s` and positional `%1$s` placeholders.
+    static ref PLACEHOLDER: Regex = Regex::new(r"%(?:(\d+)\$)?s").unwrap();
+}
+
+/// A single mapping from a (usually non-English) death message pattern
+/// to the message it should be normalized to.
+///
+/// Patterns use the same placeholders as the Minecraft language files,
+/// e.g. `%1$s wurde von %2$s erschossen`, so they can be copied straight
+/// out of a server's language file.
+#[derive(Clone, Debug)]
+pub struct Translation {
+    regex: Regex,
+    replacement: String,
+}
+
+impl Translation {
+    pub fn new(pattern: &str, replacement: &str) -> Result<Self, Box<fancy_regex::Error>> {
+        let mut regex = String::from("^");
+        let mut next_arg = 1;
+
+        let mut last = 0;
+        for captures in PLACEHOLDER.captures_iter(pattern).flatten() {
+            let whole = captures.get(0).unwrap();
+            regex.push_str(&fancy_regex::escape(&pattern[last..whole.start()]));
+
+            let arg = match captures.get(1) {
+                Some(n) => n.as_str().parse().unwrap_or(next_arg),
+                None => next_arg,
+            };
+            next_arg = arg + 1;
+
+            regex.push_str(&format!("(?P<arg{}>.+?)", arg));
+            last = whole.end();
+        }
+
+        regex.push_str(&fancy_regex::escape(&pattern[last..]));
+        regex.push('$');
+
+        Ok(Self {
+            regex: Regex::new(&regex).map_err(Box::new)?,
+            replacement: replacement.to_string(),
+        })
+    }
+
+    /// Try to apply this translation to a line. If the line doesn't match
+    /// the pattern, [None] is returned.
+    pub fn apply(&self, line: &str) -> Option<String> {
+        let captures = self.regex.captures(line).ok()??;
+        let mut next_arg = 1;
+
+        let translated =
+            PLACEHOLDER.replace_all(&self.replacement, |caps: &fancy_regex::Captures| {
+                let arg = match caps.get(1) {
+                    Some(n) => n.as_str().parse().unwrap_or(next_arg),
+                    None => next_arg,
+                };
+                next_arg = arg + 1;
+
+                captures
+                    .name(&format!("arg{}", arg))
+                    .map(|m| m.as_str().to_string())
+                    .unwrap_or_default()
+            });
+
+        Some(translated.to_string())
+    }
+}
+
+/// Compile the configured death message translations. Invalid patterns
+/// are logged and skipped. Longer patterns are tried first so that more
+/// specific messages win over generic ones.
+pub fn compile_translations(translations: HashMap<String, String>) -> Vec<Translation> {
+    let mut compiled: Vec<(usize, Translation)> = translations
+        .iter()
+        .filter_map(
+            |(pattern, replacement)| match Translation::new(pattern, replacement) {
+                Ok(translation) => Some((pattern.len(), translation)),
+                Err(e) => {
+                    warn!("Ignoring invalid death translation '{}': {}", pattern, e);
+                    None
+                }
+            },
+        )
+        .collect();
+
+    compiled.sort_by_key(|(len, _)| std::cmp::Reverse(*len));
+    compiled.into_iter().map(|(_, t)| t).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{compile_translations, Translation};
+
+    #[test]
+    fn translates_positional_placeholders() {
+        // Given
+        let translation =
+            Translation::new("%1$s wurde von %2$s erschossen", "%1$s was shot by %2$s").unwrap();
+
+        // When
+        let result = translation.apply("EbonJaeger wurde von Skelett erschossen");
+
+        // Then
+        assert_eq!(result, Some(String::from("EbonJaeger was shot by Skelett")));
+    }
+
+    #[test]
+    fn translates_reordered_placeholders() {
+        // Given
+        let translation = Translation::new("%2$s tötete %1$s", "%1$s was slain by %2$s").unwrap();
+
+        // When
+        let result = translation.apply("Zombie tötete EbonJaeger");
+
+        // Then
+        assert_eq!(result, Some(String::from("EbonJaeger was slain by Zombie")));
+    }
+
+    #[test]
+    fn escapes_pattern_literals() {
+        // Given
+        let translation = Translation::new("%s ertrank.", "%s drowned").unwrap();
+
+        // When/Then
+        assert_eq!(
+            translation.apply("EbonJaeger ertrank."),
+            Some(String::from("EbonJaeger drowned"))
+        );
+        assert_eq!(translation.apply("EbonJaeger ertrankX"), None);
+    }
+
+    #[test]
+    fn prefers_longer_patterns() {
+        // Given
+        let mut input = HashMap::new();
+        input.insert(String::from("%s starb"), String::from("%s died"));
+        input.insert(
+            String::from("%1$s starb durch %2$s"),
+            String::from("%1$s was killed by %2$s"),
+        );
+
+        // When
+        let translations = compile_translations(input);
+        let result = translations
+            .iter()
+            .find_map(|t| t.apply("EbonJaeger starb durch Magie"));
+
+        // Then
+        assert_eq!(result, Some(String::from("EbonJaeger was killed by Magie")));
+    }
+}
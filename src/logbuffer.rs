@@ -0,0 +1,210 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use serenity::prelude::TypeMapKey;
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{layer::Context, Layer};
+
+/// How many warnings and errors to keep.
+const CAPACITY: usize = 100;
+
+/// A warning or error that was logged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    pub at: SystemTime,
+}
+
+/// The most recent warnings and errors Dolphin logged, so they can be
+/// checked from Discord without reading through the logs. Once it's
+/// full, the oldest records are dropped.
+#[derive(Clone, Default)]
+pub struct LogBuffer {
+    records: Arc<Mutex<VecDeque<Record>>>,
+}
+
+impl LogBuffer {
+    /// A tracing layer that adds logged warnings and errors to this
+    /// buffer.
+    pub fn layer(&self) -> CaptureLayer {
+        CaptureLayer {
+            buffer: self.clone(),
+        }
+    }
+
+    /// Up to `limit` of the latest records at `level` or worse, newest
+    /// first. `Level::WARN` gets both warnings and errors.
+    pub fn recent(&self, level: Level, limit: usize) -> Vec<Record> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|record| record.level <= level)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// How many records at `level` or worse were logged at or after
+    /// `since`.
+    pub fn count_since(&self, level: Level, since: SystemTime) -> usize {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .take_while(|record| record.at >= since)
+            .filter(|record| record.level <= level)
+            .count()
+    }
+
+    fn push(&self, record: Record) {
+        let mut records = self.records.lock().unwrap();
+        if records.len() == CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+pub struct CaptureLayer {
+    buffer: LogBuffer,
+}
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() > Level::WARN {
+            return;
+        }
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        self.buffer.push(Record {
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            at: SystemTime::now(),
+        });
+    }
+}
+
+/// Puts an event's message and any other fields into one line.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.message.is_empty() {
+            self.message.push(' ');
+        }
+
+        let _ = match field.name() {
+            "message" => write!(self.message, "{:?}", value),
+            name => write!(self.message, "{}={:?}", name, value),
+        };
+    }
+}
+
+pub struct LogBufferContainer;
+
+impl TypeMapKey for LogBufferContainer {
+    type Value = LogBuffer;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use tracing::{error, info, warn, Level};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::{LogBuffer, Record, CAPACITY};
+
+    fn record(level: Level, message: &str, at: SystemTime) -> Record {
+        Record {
+            level,
+            target: String::from("dolphin_rs"),
+            message: message.to_string(),
+            at,
+        }
+    }
+
+    #[test]
+    fn capture_warnings_and_errors() {
+        // Given
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::registry().with(buffer.layer());
+
+        // When
+        tracing::subscriber::with_default(subscriber, || {
+            info!("all good");
+            warn!(target: "dolphin_rs::usage", "server is slow");
+            error!(code = 50013, "unable to post: {}", "Missing Permissions");
+        });
+
+        // Then
+        let recent = buffer.recent(Level::WARN, 10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].level, Level::ERROR);
+        assert_eq!(
+            recent[0].message,
+            "unable to post: Missing Permissions code=50013"
+        );
+        assert_eq!(recent[1].level, Level::WARN);
+        assert_eq!(recent[1].target, "dolphin_rs::usage");
+        assert_eq!(buffer.recent(Level::ERROR, 10).len(), 1);
+    }
+
+    #[test]
+    fn keep_only_the_newest() {
+        // Given
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::registry().with(buffer.layer());
+
+        // When
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..CAPACITY + 2 {
+                error!("error {}", i);
+            }
+        });
+
+        // Then
+        let recent = buffer.recent(Level::ERROR, CAPACITY + 2);
+        assert_eq!(recent.len(), CAPACITY);
+        assert_eq!(recent[0].message, format!("error {}", CAPACITY + 1));
+        assert_eq!(recent[CAPACITY - 1].message, "error 2");
+    }
+
+    #[test]
+    fn count_by_time() {
+        // Given
+        let buffer = LogBuffer::default();
+        let now = SystemTime::now();
+        let hour_ago = now - Duration::from_secs(3600);
+        buffer.push(record(Level::ERROR, "old", hour_ago));
+        buffer.push(record(Level::WARN, "slow", now));
+        buffer.push(record(Level::ERROR, "broken", now));
+
+        // When
+        let since = now - Duration::from_secs(60);
+        let errors = buffer.count_since(Level::ERROR, since);
+
+        // Then
+        assert_eq!(errors, 1);
+        assert_eq!(buffer.count_since(Level::WARN, since), 2);
+        assert_eq!(buffer.count_since(Level::WARN, hour_ago), 3);
+    }
+}
@@ -3,7 +3,21 @@ use std::error::Error;
 mod cli;
 mod config;
 mod discord;
+mod emoji;
+mod links;
 mod listener;
+mod logbuffer;
+mod metrics;
+mod pause;
+mod per_server;
+mod server;
+mod shutdown;
+mod template;
+mod translate;
+mod transport;
+mod unicode;
+mod update;
+mod usage;
 
 #[macro_use]
 extern crate lazy_static;
@@ -0,0 +1,462 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serenity::prelude::TypeMapKey;
+use tracing::info;
+
+use crate::per_server::PerServer;
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// Upper bounds of the message size buckets, in characters. Longer
+/// messages go in one more bucket after these.
+const SIZE_BUCKETS: [usize; 6] = [50, 100, 256, 500, 1000, 2000];
+
+/// The direction a message was bridged in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    ToDiscord,
+    ToMinecraft,
+}
+
+/// Why a line from the Minecraft log wasn't bridged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ignored {
+    /// The line didn't start with a log prefix like `[12:00:00] [Server thread/INFO]:`.
+    NoPrefix,
+    VillagerDeath,
+    /// The line contained one of the death ignore phrases.
+    IgnorePhrase,
+    /// The line didn't match the chat regex or any other kind of message.
+    NoMatch,
+}
+
+/// How many log lines were ignored for each reason, since Dolphin
+/// started.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IgnoredStats {
+    pub no_prefix: u64,
+    pub villager_deaths: u64,
+    pub ignore_phrases: u64,
+    pub no_match: u64,
+}
+
+impl IgnoredStats {
+    fn record(&mut self, reason: Ignored) {
+        let count = match reason {
+            Ignored::NoPrefix => &mut self.no_prefix,
+            Ignored::VillagerDeath => &mut self.villager_deaths,
+            Ignored::IgnorePhrase => &mut self.ignore_phrases,
+            Ignored::NoMatch => &mut self.no_match,
+        };
+        *count += 1;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.no_prefix + self.villager_deaths + self.ignore_phrases + self.no_match
+    }
+}
+
+/// Counters for a single direction of the bridge.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DirectionStats {
+    pub messages_today: u64,
+    total_latency: Duration,
+    latency_samples: u32,
+}
+
+impl DirectionStats {
+    /// The average time it took to deliver a message, if any messages
+    /// have been delivered.
+    pub fn average_latency(&self) -> Option<Duration> {
+        if self.latency_samples == 0 {
+            return None;
+        }
+
+        Some(self.total_latency / self.latency_samples)
+    }
+}
+
+/// How long the messages bridged in one direction were, since Dolphin
+/// started, and how many had to be split up to fit.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SizeStats {
+    pub messages: u64,
+    pub split: u64,
+    pub longest: usize,
+    total_chars: u64,
+    buckets: [u64; SIZE_BUCKETS.len() + 1],
+}
+
+impl SizeStats {
+    fn record(&mut self, chars: usize, split: bool) {
+        self.messages += 1;
+        self.total_chars += chars as u64;
+        self.longest = self.longest.max(chars);
+        if split {
+            self.split += 1;
+        }
+
+        let bucket = SIZE_BUCKETS
+            .iter()
+            .position(|bound| chars <= *bound)
+            .unwrap_or(SIZE_BUCKETS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    /// The average length of a message in characters, if any messages
+    /// have been bridged.
+    pub fn average(&self) -> Option<u64> {
+        if self.messages == 0 {
+            return None;
+        }
+
+        Some(self.total_chars / self.messages)
+    }
+
+    /// How many messages were in each size bucket, with a label like
+    /// `≤100` or `>2000`. Empty buckets are left out.
+    pub fn buckets(&self) -> Vec<(String, u64)> {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| {
+                let label = match SIZE_BUCKETS.get(bucket) {
+                    Some(bound) => format!("≤{}", bound),
+                    None => format!(">{}", SIZE_BUCKETS[SIZE_BUCKETS.len() - 1]),
+                };
+                (label, *count)
+            })
+            .collect()
+    }
+
+    /// A one line summary for the logs, e.g. `10 messages, 1 split,
+    /// 48 chars average, 300 longest (≤50: 8, ≤500: 2)`.
+    pub fn summary(&self) -> String {
+        let buckets = self
+            .buckets()
+            .into_iter()
+            .map(|(label, count)| format!("{}: {}", label, count))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        format!(
+            "{} messages, {} split, {} chars average, {} longest ({})",
+            self.messages,
+            self.split,
+            self.average().unwrap_or_default(),
+            self.longest,
+            buckets
+        )
+    }
+}
+
+/// A point-in-time copy of the bridge metrics.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub to_discord: DirectionStats,
+    pub to_minecraft: DirectionStats,
+    pub sizes_to_discord: SizeStats,
+    pub sizes_to_minecraft: SizeStats,
+    pub ignored: IgnoredStats,
+    pub last_rcon_error: Option<(String, SystemTime)>,
+    pub last_heard: Option<SystemTime>,
+    pub uptime: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    day: u64,
+    to_discord: DirectionStats,
+    to_minecraft: DirectionStats,
+    sizes_to_discord: SizeStats,
+    sizes_to_minecraft: SizeStats,
+    ignored: IgnoredStats,
+    last_rcon_error: Option<(String, SystemTime)>,
+    last_heard: Option<SystemTime>,
+}
+
+impl Inner {
+    /// Reset the daily counters if the (UTC) day has changed.
+    fn roll_over(&mut self, day: u64) {
+        if self.day != day {
+            self.day = day;
+            self.to_discord = DirectionStats::default();
+            self.to_minecraft = DirectionStats::default();
+        }
+    }
+
+    fn record(&mut self, direction: Direction, latency: Duration, day: u64) {
+        self.roll_over(day);
+
+        let stats = match direction {
+            Direction::ToDiscord => &mut self.to_discord,
+            Direction::ToMinecraft => &mut self.to_minecraft,
+        };
+
+        stats.messages_today += 1;
+        stats.total_latency += latency;
+        stats.latency_samples += 1;
+    }
+
+    fn record_size(&mut self, direction: Direction, chars: usize, split: bool) {
+        match direction {
+            Direction::ToDiscord => self.sizes_to_discord.record(chars, split),
+            Direction::ToMinecraft => self.sizes_to_minecraft.record(chars, split),
+        }
+    }
+}
+
+/// Keeps track of how the bridge is doing, so it can be shown with
+/// the `/bridgestats` command.
+#[derive(Debug)]
+pub struct Metrics {
+    started: Instant,
+    inner: Mutex<Inner>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            inner: Mutex::new(Inner {
+                day: today(),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+impl Metrics {
+    /// Record that a message was delivered, and how long it took
+    /// from when we received it.
+    pub fn record_delivery(&self, direction: Direction, latency: Duration) {
+        self.inner
+            .lock()
+            .unwrap()
+            .record(direction, latency, today());
+    }
+
+    /// Record how long a bridged message was, in characters, and
+    /// whether it had to be split up to fit.
+    pub fn record_size(&self, direction: Direction, chars: usize, split: bool) {
+        self.inner
+            .lock()
+            .unwrap()
+            .record_size(direction, chars, split);
+    }
+
+    /// Record that a line from the Minecraft log was ignored, and why.
+    pub fn record_ignored(&self, reason: Ignored) {
+        self.inner.lock().unwrap().ignored.record(reason);
+    }
+
+    /// Record an error talking to the Minecraft server over RCON.
+    pub fn record_rcon_error(&self, error: &str) {
+        self.inner.lock().unwrap().last_rcon_error = Some((error.to_string(), SystemTime::now()));
+    }
+
+    /// Record that the listener got a message from Minecraft, so we can
+    /// tell whether it's still working.
+    pub fn record_heard(&self) {
+        self.inner.lock().unwrap().last_heard = Some(SystemTime::now());
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let mut inner = self.inner.lock().unwrap();
+        inner.roll_over(today());
+
+        Snapshot {
+            to_discord: inner.to_discord.clone(),
+            to_minecraft: inner.to_minecraft.clone(),
+            sizes_to_discord: inner.sizes_to_discord.clone(),
+            sizes_to_minecraft: inner.sizes_to_minecraft.clone(),
+            ignored: inner.ignored.clone(),
+            last_rcon_error: inner.last_rcon_error.clone(),
+            last_heard: inner.last_heard,
+            uptime: self.started.elapsed(),
+        }
+    }
+}
+
+/// Log a summary of message sizes in each direction for each server
+/// every period, so the max lengths can be tuned from how people
+/// actually chat. Nothing is logged until something has been bridged.
+pub async fn log_sizes_periodically(metrics: Arc<PerServer<Metrics>>, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    // The first tick completes right away
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        for (channel_id, metrics) in metrics.all() {
+            let snapshot = metrics.snapshot();
+            for (direction, sizes) in [
+                ("Minecraft → Discord", &snapshot.sizes_to_discord),
+                ("Discord → Minecraft", &snapshot.sizes_to_minecraft),
+            ] {
+                if sizes.messages > 0 {
+                    info!(
+                        "Message sizes in channel {}, {}: {}",
+                        channel_id,
+                        direction,
+                        sizes.summary()
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// How each server's bridge is doing.
+pub struct MetricsContainer;
+
+impl TypeMapKey for MetricsContainer {
+    type Value = Arc<PerServer<Metrics>>;
+}
+
+/// Get the number of days since the Unix epoch, in UTC.
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / SECONDS_PER_DAY
+}
+
+/// Format a duration in a short, human-friendly way, e.g. `3d 4h 12m`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes) = (secs / 86400, (secs % 86400) / 3600, (secs % 3600) / 60);
+
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format_duration, Direction, Ignored, IgnoredStats, Inner, SizeStats};
+
+    #[test]
+    fn records_deliveries_per_direction() {
+        // Given
+        let mut inner = Inner::default();
+
+        // When
+        inner.record(Direction::ToDiscord, Duration::from_millis(100), 1);
+        inner.record(Direction::ToDiscord, Duration::from_millis(300), 1);
+        inner.record(Direction::ToMinecraft, Duration::from_millis(50), 1);
+
+        // Then
+        assert_eq!(inner.to_discord.messages_today, 2);
+        assert_eq!(
+            inner.to_discord.average_latency(),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(inner.to_minecraft.messages_today, 1);
+    }
+
+    #[test]
+    fn resets_counts_on_new_day() {
+        // Given
+        let mut inner = Inner::default();
+        inner.record(Direction::ToDiscord, Duration::from_millis(100), 1);
+
+        // When
+        inner.record(Direction::ToMinecraft, Duration::from_millis(100), 2);
+
+        // Then
+        assert_eq!(inner.to_discord.messages_today, 0);
+        assert_eq!(inner.to_discord.average_latency(), None);
+        assert_eq!(inner.to_minecraft.messages_today, 1);
+    }
+
+    #[test]
+    fn records_sizes_in_buckets() {
+        // Given
+        let mut inner = Inner::default();
+
+        // When
+        inner.record_size(Direction::ToMinecraft, 20, false);
+        inner.record_size(Direction::ToMinecraft, 40, false);
+        inner.record_size(Direction::ToMinecraft, 300, true);
+        inner.record_size(Direction::ToMinecraft, 5000, true);
+
+        // Then
+        let sizes = &inner.sizes_to_minecraft;
+        assert_eq!(sizes.messages, 4);
+        assert_eq!(sizes.split, 2);
+        assert_eq!(sizes.longest, 5000);
+        assert_eq!(sizes.average(), Some(1340));
+        assert_eq!(
+            sizes.buckets(),
+            vec![
+                (String::from("≤50"), 2),
+                (String::from("≤500"), 1),
+                (String::from(">2000"), 1)
+            ]
+        );
+        assert_eq!(inner.sizes_to_discord, SizeStats::default());
+    }
+
+    #[test]
+    fn sizes_survive_a_new_day() {
+        // Given
+        let mut inner = Inner::default();
+        inner.record_size(Direction::ToDiscord, 10, false);
+
+        // When
+        inner.record(Direction::ToDiscord, Duration::from_millis(100), 2);
+
+        // Then
+        assert_eq!(inner.sizes_to_discord.messages, 1);
+    }
+
+    #[test]
+    fn counts_ignored_lines_per_reason() {
+        // Given
+        let mut inner = Inner::default();
+
+        // When
+        inner.ignored.record(Ignored::NoPrefix);
+        inner.ignored.record(Ignored::NoMatch);
+        inner.ignored.record(Ignored::NoMatch);
+        inner.ignored.record(Ignored::IgnorePhrase);
+
+        // Then
+        assert_eq!(
+            inner.ignored,
+            IgnoredStats {
+                no_prefix: 1,
+                villager_deaths: 0,
+                ignore_phrases: 1,
+                no_match: 2,
+            }
+        );
+        assert_eq!(inner.ignored.total(), 4);
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_duration(Duration::from_secs(3 * 3600 + 60)), "3h 1m");
+        assert_eq!(
+            format_duration(Duration::from_secs(2 * 86400 + 3600)),
+            "2d 1h 0m"
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use serenity::prelude::{Context, TypeMapKey};
+
+/// Whether bridging has been paused, e.g. by a hosting panel while the
+/// server is down for maintenance. Nothing is bridged in either
+/// direction while it's paused.
+#[derive(Debug, Default)]
+pub struct Pause {
+    paused: AtomicBool,
+}
+
+impl Pause {
+    /// Pause or resume bridging. Returns whether it was paused before.
+    pub fn set(&self, paused: bool) -> bool {
+        self.paused.swap(paused, Ordering::Relaxed)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+}
+
+pub struct PauseContainer;
+
+impl TypeMapKey for PauseContainer {
+    type Value = Arc<Pause>;
+}
+
+/// Whether bridging is paused right now.
+pub async fn is_paused(ctx: &Context) -> bool {
+    ctx.data
+        .read()
+        .await
+        .get::<PauseContainer>()
+        .is_some_and(|pause| pause.is_paused())
+}
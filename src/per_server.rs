@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use serenity::{
+    all::ChannelId,
+    prelude::{Context, TypeMapKey},
+};
+
+use crate::config::container;
+
+/// State kept apart for each bridged server, by the ID of the channel
+/// the server is bridged to. Players on different servers can share a
+/// name, so things like chat history and spam counts can't be shared.
+pub struct PerServer<T> {
+    make: Box<dyn Fn() -> T + Send + Sync>,
+    servers: Mutex<HashMap<u64, Arc<T>>>,
+}
+
+impl<T> PerServer<T> {
+    /// Start each server's state with `make` the first time it's needed.
+    pub fn new(make: impl Fn() -> T + Send + Sync + 'static) -> Self {
+        PerServer {
+            make: Box::new(make),
+            servers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the state of the server bridged to `channel_id`.
+    pub fn get(&self, channel_id: u64) -> Arc<T> {
+        self.servers
+            .lock()
+            .unwrap()
+            .entry(channel_id)
+            .or_insert_with(|| Arc::new((self.make)()))
+            .clone()
+    }
+
+    /// Get the state of every server that has any, by channel ID.
+    pub fn all(&self) -> Vec<(u64, Arc<T>)> {
+        let mut all: Vec<_> = self
+            .servers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(channel_id, state)| (*channel_id, state.clone()))
+            .collect();
+        all.sort_by_key(|(channel_id, _)| *channel_id);
+        all
+    }
+}
+
+impl<T: Default + 'static> Default for PerServer<T> {
+    fn default() -> Self {
+        PerServer::new(T::default)
+    }
+}
+
+/// Get the state from a container of the server a command was run for,
+/// going by the channel it was run in.
+pub async fn for_channel<K, T>(ctx: &Context, channel_id: ChannelId) -> Option<Arc<T>>
+where
+    K: TypeMapKey<Value = Arc<PerServer<T>>>,
+    T: Send + Sync + 'static,
+{
+    let config = container::config_for(ctx, channel_id).await;
+    let channel_id = config.read().await.get_channel_id();
+    let data = ctx.data.read().await;
+    Some(data.get::<K>()?.get(channel_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::PerServer;
+
+    #[test]
+    fn keep_servers_apart() {
+        // Given
+        let servers: PerServer<std::sync::Mutex<Vec<&str>>> = PerServer::default();
+
+        // When
+        servers.get(1).lock().unwrap().push("survival");
+        servers.get(2).lock().unwrap().push("creative");
+
+        // Then
+        assert_eq!(*servers.get(1).lock().unwrap(), vec!["survival"]);
+        assert_eq!(*servers.get(2).lock().unwrap(), vec!["creative"]);
+        assert!(Arc::ptr_eq(&servers.get(1), &servers.get(1)));
+        assert_eq!(
+            servers
+                .all()
+                .iter()
+                .map(|(channel_id, _)| *channel_id)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+}
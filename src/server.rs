@@ -0,0 +1,418 @@
+use std::{
+    fmt,
+    path::PathBuf,
+    process::{ExitStatus, Stdio},
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStdin, Command},
+    sync::{
+        mpsc::{self, error::TrySendError},
+        Mutex,
+    },
+    time::{sleep, timeout},
+};
+use tracing::{error, info, warn};
+
+use crate::config::RootConfig;
+
+/// How many lines of server output to hold on to while nothing is
+/// reading them, e.g. before Discord has connected.
+const LINE_BUFFER: usize = 1024;
+
+static MANAGED_SERVER: OnceLock<ManagedServer> = OnceLock::new();
+
+/// Get the Minecraft server Dolphin is running, if it's running one.
+pub fn managed() -> Option<&'static ManagedServer> {
+    MANAGED_SERVER.get()
+}
+
+/// How the Minecraft server process should be run.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub command: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+    pub restart_on_crash: bool,
+    pub restart_delay: Duration,
+    pub stop_timeout: Duration,
+}
+
+impl Settings {
+    pub fn from_config(config: &RootConfig) -> Self {
+        let working_dir = config.get_server_working_dir();
+
+        Settings {
+            command: config.get_server_command(),
+            working_dir: (!working_dir.is_empty()).then(|| PathBuf::from(working_dir)),
+            restart_on_crash: config.restart_server_on_crash(),
+            restart_delay: Duration::from_secs(config.get_server_restart_delay_secs()),
+            stop_timeout: Duration::from_secs(config.get_server_stop_timeout_secs()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Control {
+    Start,
+    Stop,
+    Restart,
+}
+
+/// What happened to a running server.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// The server exited on its own.
+    Exited { crashed: bool },
+    /// We stopped the server, and it should stay stopped.
+    Stopped,
+    /// We stopped the server so that it can be started again.
+    Restarting,
+}
+
+/// A Minecraft server process started and watched over by Dolphin.
+///
+/// Output from the server is passed on for parsing, and commands are
+/// written to its console. If the server crashes, it's started again.
+#[derive(Clone)]
+pub struct ManagedServer {
+    control: mpsc::UnboundedSender<Control>,
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
+    lines: Arc<Mutex<Option<mpsc::Receiver<String>>>>,
+}
+
+impl ManagedServer {
+    /// Start the Minecraft server and the task that watches over it.
+    /// This can only be done once.
+    pub fn start(settings: Settings) -> Result<&'static ManagedServer, Error> {
+        if settings.command.is_empty() {
+            return Err(Error::NoCommand);
+        }
+
+        let (control, control_rx) = mpsc::unbounded_channel();
+        let (lines_tx, lines_rx) = mpsc::channel(LINE_BUFFER);
+        let server = ManagedServer {
+            control,
+            stdin: Arc::new(Mutex::new(None)),
+            lines: Arc::new(Mutex::new(Some(lines_rx))),
+        };
+
+        MANAGED_SERVER
+            .set(server.clone())
+            .map_err(|_| Error::AlreadyStarted)?;
+
+        let supervisor = Supervisor {
+            settings,
+            stdin: server.stdin.clone(),
+            lines: lines_tx,
+        };
+        tokio::spawn(supervisor.run(control_rx));
+
+        Ok(managed().expect("managed server was just set"))
+    }
+
+    /// Take the stream of lines the server prints. There is only one
+    /// stream, so this returns [None] if it has already been taken.
+    pub async fn take_lines(&self) -> Option<mpsc::Receiver<String>> {
+        self.lines.lock().await.take()
+    }
+
+    /// Write a command to the server's console.
+    pub async fn send_command(&self, command: &str) -> Result<(), Error> {
+        let mut stdin = self.stdin.lock().await;
+        let stdin = stdin.as_mut().ok_or(Error::NotRunning)?;
+
+        // A line break would let one message become more than one command
+        let command = command.replace(['\r', '\n'], " ");
+        stdin.write_all(format!("{}\n", command).as_bytes()).await?;
+        stdin.flush().await?;
+
+        Ok(())
+    }
+
+    pub fn start_server(&self) -> Result<(), Error> {
+        self.send_control(Control::Start)
+    }
+
+    pub fn stop_server(&self) -> Result<(), Error> {
+        self.send_control(Control::Stop)
+    }
+
+    pub fn restart_server(&self) -> Result<(), Error> {
+        self.send_control(Control::Restart)
+    }
+
+    fn send_control(&self, control: Control) -> Result<(), Error> {
+        self.control
+            .send(control)
+            .map_err(|_| Error::SupervisorStopped)
+    }
+}
+
+impl fmt::Debug for ManagedServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ManagedServer")
+    }
+}
+
+impl PartialEq for ManagedServer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.stdin, &other.stdin)
+    }
+}
+
+struct Supervisor {
+    settings: Settings,
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
+    lines: mpsc::Sender<String>,
+}
+
+impl Supervisor {
+    async fn run(self, mut control: mpsc::UnboundedReceiver<Control>) {
+        let mut running = true;
+
+        loop {
+            if !running {
+                match control.recv().await {
+                    Some(Control::Start) | Some(Control::Restart) => running = true,
+                    Some(Control::Stop) => continue,
+                    None => return,
+                }
+            }
+
+            let mut child = match self.spawn().await {
+                Ok(child) => child,
+                Err(e) => {
+                    error!("server:run: unable to start the Minecraft server: {}", e);
+                    running = false;
+                    continue;
+                }
+            };
+
+            info!("server:run: started the Minecraft server");
+
+            let outcome = self.watch(&mut child, &mut control).await;
+            *self.stdin.lock().await = None;
+
+            match outcome {
+                Some(Outcome::Exited { crashed: true }) if self.settings.restart_on_crash => {
+                    warn!(
+                        "server:run: the Minecraft server crashed, restarting in {}s",
+                        self.settings.restart_delay.as_secs()
+                    );
+                    sleep(self.settings.restart_delay).await;
+                }
+                Some(Outcome::Exited { .. }) | Some(Outcome::Stopped) => {
+                    info!("server:run: the Minecraft server has stopped");
+                    running = false;
+                }
+                Some(Outcome::Restarting) => info!("server:run: restarting the Minecraft server"),
+                None => return,
+            }
+        }
+    }
+
+    async fn spawn(&self) -> Result<Child, Error> {
+        let (program, args) = self
+            .settings
+            .command
+            .split_first()
+            .ok_or(Error::NoCommand)?;
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(dir) = &self.settings.working_dir {
+            command.current_dir(dir);
+        }
+
+        let mut child = command.spawn()?;
+        *self.stdin.lock().await = child.stdin.take();
+
+        if let Some(stdout) = child.stdout.take() {
+            let lines = self.lines.clone();
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stdout).lines();
+                let mut dropped: u64 = 0;
+                while let Ok(Some(line)) = reader.next_line().await {
+                    // Keep the server console visible to whoever runs Dolphin
+                    println!("{}", line);
+
+                    // Waiting for room would stop us reading stdout, and
+                    // the server would stall writing to it, so lines are
+                    // dropped instead while the buffer is full
+                    match lines.try_send(line) {
+                        Ok(()) if dropped > 0 => {
+                            warn!(
+                                "server:spawn: dropped {} lines of server output while the buffer was full",
+                                dropped
+                            );
+                            dropped = 0;
+                        }
+                        Ok(()) => {}
+                        Err(TrySendError::Full(_)) => {
+                            if dropped == 0 {
+                                warn!("server:spawn: server output is backing up, dropping lines until it's read");
+                            }
+                            dropped += 1;
+                        }
+                        Err(TrySendError::Closed(_)) => {}
+                    }
+                }
+            });
+        }
+
+        Ok(child)
+    }
+
+    /// Wait until the server exits or is told to stop. Returns [None] if
+    /// every handle to the server is gone.
+    async fn watch(
+        &self,
+        child: &mut Child,
+        control: &mut mpsc::UnboundedReceiver<Control>,
+    ) -> Option<Outcome> {
+        loop {
+            tokio::select! {
+                status = child.wait() => {
+                    return Some(Outcome::Exited { crashed: crashed(status) });
+                }
+                received = control.recv() => match received {
+                    Some(Control::Start) => info!("server:watch: the Minecraft server is already running"),
+                    Some(Control::Stop) => {
+                        self.stop(child).await;
+                        return Some(Outcome::Stopped);
+                    }
+                    Some(Control::Restart) => {
+                        self.stop(child).await;
+                        return Some(Outcome::Restarting);
+                    }
+                    None => {
+                        self.stop(child).await;
+                        return None;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Ask the server to stop, and kill it if it takes too long.
+    async fn stop(&self, child: &mut Child) {
+        if let Some(stdin) = self.stdin.lock().await.as_mut() {
+            if let Err(e) = stdin.write_all(b"stop\n").await {
+                warn!("server:stop: unable to send the stop command: {}", e);
+            }
+            let _ = stdin.flush().await;
+        }
+
+        if timeout(self.settings.stop_timeout, child.wait())
+            .await
+            .is_err()
+        {
+            warn!("server:stop: the Minecraft server didn't stop in time, killing it");
+            if let Err(e) = child.kill().await {
+                error!("server:stop: unable to kill the Minecraft server: {}", e);
+            }
+        }
+    }
+}
+
+/// Whether the server exited because something went wrong.
+fn crashed(status: std::io::Result<ExitStatus>) -> bool {
+    !status.is_ok_and(|status| status.success())
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("no server command is set")]
+    NoCommand,
+
+    #[error("the Minecraft server is already being managed")]
+    AlreadyStarted,
+
+    #[error("the Minecraft server isn't running")]
+    NotRunning,
+
+    #[error("the server supervisor has stopped")]
+    SupervisorStopped,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{process::Command, time::Duration};
+
+    use tokio::sync::mpsc;
+
+    use super::{crashed, Outcome, Settings, Supervisor};
+
+    fn supervisor(command: &[&str], lines: mpsc::Sender<String>) -> Supervisor {
+        Supervisor {
+            settings: Settings {
+                command: command.iter().map(|part| part.to_string()).collect(),
+                working_dir: None,
+                restart_on_crash: false,
+                restart_delay: Duration::from_secs(0),
+                stop_timeout: Duration::from_secs(5),
+            },
+            stdin: Default::default(),
+            lines,
+        }
+    }
+
+    #[test]
+    fn detects_crashes() {
+        let ok = Command::new("true").status();
+        let failed = Command::new("false").status();
+
+        assert!(!crashed(ok));
+        assert!(crashed(failed));
+    }
+
+    #[tokio::test]
+    async fn reads_output_and_notices_exit() {
+        // Given
+        let (lines_tx, mut lines_rx) = mpsc::channel(8);
+        let (_control_tx, mut control_rx) = mpsc::unbounded_channel();
+        let supervisor = supervisor(&["sh", "-c", "echo Done; exit 1"], lines_tx);
+
+        // When
+        let mut child = supervisor.spawn().await.unwrap();
+        let outcome = supervisor.watch(&mut child, &mut control_rx).await;
+
+        // Then
+        assert_eq!(outcome, Some(Outcome::Exited { crashed: true }));
+        assert_eq!(lines_rx.recv().await.as_deref(), Some("Done"));
+    }
+
+    #[tokio::test]
+    async fn stops_with_the_stop_command() {
+        // Given
+        let (lines_tx, _lines_rx) = mpsc::channel(8);
+        let (control_tx, mut control_rx) = mpsc::unbounded_channel();
+        let supervisor = supervisor(
+            &["sh", "-c", "read line; [ \"$line\" = stop ] && exit 0"],
+            lines_tx,
+        );
+
+        // When
+        let mut child = supervisor.spawn().await.unwrap();
+        control_tx.send(super::Control::Stop).unwrap();
+        let outcome = supervisor.watch(&mut child, &mut control_rx).await;
+
+        // Then
+        assert_eq!(outcome, Some(Outcome::Stopped));
+        assert!(child
+            .try_wait()
+            .unwrap()
+            .is_some_and(|status| status.success()));
+    }
+}
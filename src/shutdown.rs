@@ -0,0 +1,101 @@
+use std::{sync::OnceLock, time::Duration};
+
+use tokio::{sync::watch, time::timeout};
+
+static SHUTDOWN: OnceLock<Shutdown> = OnceLock::new();
+
+/// Lets long-running servers find out that Dolphin is stopping, and
+/// lets Dolphin wait for them to close before it exits.
+pub struct Shutdown {
+    requested: watch::Sender<bool>,
+    active: watch::Sender<usize>,
+}
+
+/// Held by a server while it runs. Dolphin waits for every one of these
+/// to be dropped before exiting.
+pub struct Hold<'a> {
+    shutdown: &'a Shutdown,
+}
+
+impl Drop for Hold<'_> {
+    fn drop(&mut self) {
+        self.shutdown.active.send_modify(|active| *active -= 1);
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Shutdown {
+            requested: watch::channel(false).0,
+            active: watch::channel(0).0,
+        }
+    }
+}
+
+impl Shutdown {
+    /// Tell everything that's running to stop.
+    pub fn begin(&self) {
+        self.requested.send_replace(true);
+    }
+
+    /// Wait until shutting down has begun.
+    pub async fn requested(&self) {
+        let mut requested = self.requested.subscribe();
+        let _ = requested.wait_for(|requested| *requested).await;
+    }
+
+    pub fn hold(&self) -> Hold<'_> {
+        self.active.send_modify(|active| *active += 1);
+        Hold { shutdown: self }
+    }
+
+    /// Wait for everything holding on to finish, for at most `limit`.
+    /// Returns `false` if something was still running.
+    pub async fn drained(&self, limit: Duration) -> bool {
+        let mut active = self.active.subscribe();
+        let drained = timeout(limit, active.wait_for(|active| *active == 0)).await;
+        drained.is_ok()
+    }
+}
+
+/// The shutdown for this process.
+pub fn global() -> &'static Shutdown {
+    SHUTDOWN.get_or_init(Shutdown::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Shutdown;
+
+    #[tokio::test]
+    async fn wait_for_holds_to_drop() {
+        // Given
+        let shutdown = Shutdown::default();
+        let hold = shutdown.hold();
+
+        // When
+        let before = shutdown.drained(Duration::from_millis(10)).await;
+        drop(hold);
+        let after = shutdown.drained(Duration::from_millis(10)).await;
+
+        // Then
+        assert!(!before);
+        assert!(after);
+    }
+
+    #[tokio::test]
+    async fn notify_once_requested() {
+        // Given
+        let shutdown = Shutdown::default();
+
+        // When
+        shutdown.begin();
+
+        // Then
+        tokio::time::timeout(Duration::from_millis(10), shutdown.requested())
+            .await
+            .expect("shutdown should have been requested");
+    }
+}
@@ -0,0 +1,248 @@
+#[derive(Debug, PartialEq)]
+enum Node<'a> {
+    Text(&'a str),
+    Var(&'a str),
+    If {
+        name: &'a str,
+        then: Vec<Node<'a>>,
+        otherwise: Vec<Node<'a>>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+enum Tag<'a> {
+    Var(&'a str),
+    If(&'a str),
+    Else,
+    End,
+}
+
+/// Fill in a template with the given values.
+///
+/// `%name%` is replaced with the value of `name`. Parts of a template
+/// can be left out with `%if name%...%end%`, which is only kept when
+/// `name` is set and isn't empty, and an `%else%` part can be added
+/// for when it is. Anything that doesn't look like a placeholder, like
+/// `100%`, is left as it is, and so are placeholders with no value.
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut rest = template;
+    let nodes = parse(&mut rest, false).0;
+
+    let mut output = String::with_capacity(template.len());
+    write(&nodes, vars, &mut output);
+    output
+}
+
+/// Get the names used in a template's placeholders and conditionals,
+/// in the order they first appear.
+pub fn placeholders(template: &str) -> Vec<&str> {
+    fn collect<'a>(nodes: &[Node<'a>], names: &mut Vec<&'a str>) {
+        for node in nodes {
+            match node {
+                Node::Text(_) => {}
+                Node::Var(name) if !names.contains(name) => names.push(name),
+                Node::Var(_) => {}
+                Node::If {
+                    name,
+                    then,
+                    otherwise,
+                } => {
+                    if !names.contains(name) {
+                        names.push(name);
+                    }
+                    collect(then, names);
+                    collect(otherwise, names);
+                }
+            }
+        }
+    }
+
+    let mut rest = template;
+    let nodes = parse(&mut rest, false).0;
+
+    let mut names = Vec::new();
+    collect(&nodes, &mut names);
+    names
+}
+
+/// Parse nodes until the end of the template, or until the `%else%` or
+/// `%end%` closing the current `%if%` when `nested` is set. Returns the
+/// nodes and the tag that stopped parsing.
+fn parse<'a>(rest: &mut &'a str, nested: bool) -> (Vec<Node<'a>>, Option<Tag<'a>>) {
+    let mut nodes = Vec::new();
+
+    while !rest.is_empty() {
+        let Some((before, tag, raw, after)) = next_tag(rest) else {
+            nodes.push(Node::Text(rest));
+            *rest = "";
+            break;
+        };
+
+        if !before.is_empty() {
+            nodes.push(Node::Text(before));
+        }
+        *rest = after;
+
+        match tag {
+            Tag::Var(name) => nodes.push(Node::Var(name)),
+            Tag::If(name) => {
+                let (then, closed_by) = parse(rest, true);
+                let otherwise = match closed_by {
+                    Some(Tag::Else) => parse(rest, true).0,
+                    _ => Vec::new(),
+                };
+                nodes.push(Node::If {
+                    name,
+                    then,
+                    otherwise,
+                });
+            }
+            Tag::Else | Tag::End if nested => return (nodes, Some(tag)),
+            // A stray `%else%` or `%end%` is just text
+            Tag::Else | Tag::End => nodes.push(Node::Text(raw)),
+        }
+    }
+
+    (nodes, None)
+}
+
+/// Find the next tag, returning the text before it, the tag, the tag as
+/// written, and the text after it.
+fn next_tag(text: &str) -> Option<(&str, Tag<'_>, &str, &str)> {
+    let mut offset = 0;
+
+    while let Some(start) = text[offset..].find('%').map(|i| i + offset) {
+        let end = start + 1 + text[start + 1..].find('%')?;
+
+        if let Some(tag) = parse_tag(&text[start + 1..end]) {
+            return Some((&text[..start], tag, &text[start..=end], &text[end + 1..]));
+        }
+
+        // Not a tag, so the closing `%` might start one
+        offset = end;
+    }
+
+    None
+}
+
+fn parse_tag(inner: &str) -> Option<Tag<'_>> {
+    let is_name = |name: &str| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+
+    match inner {
+        "else" => Some(Tag::Else),
+        "end" => Some(Tag::End),
+        _ => match inner.strip_prefix("if ") {
+            Some(name) if is_name(name.trim()) => Some(Tag::If(name.trim())),
+            Some(_) => None,
+            None => is_name(inner).then_some(Tag::Var(inner)),
+        },
+    }
+}
+
+fn write(nodes: &[Node], vars: &[(&str, &str)], output: &mut String) {
+    let lookup = |name: &str| {
+        vars.iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+    };
+
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Var(name) => match lookup(name) {
+                Some(value) => output.push_str(value),
+                None => {
+                    output.push('%');
+                    output.push_str(name);
+                    output.push('%');
+                }
+            },
+            Node::If {
+                name,
+                then,
+                otherwise,
+            } => {
+                let set = lookup(name).is_some_and(|value| !value.is_empty());
+                write(if set { then } else { otherwise }, vars, output);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{placeholders, render};
+
+    #[test]
+    fn replace_placeholders() {
+        assert_eq!(
+            render(
+                "<%username%> %content%",
+                &[("username", "Steve"), ("content", "hi")]
+            ),
+            "<Steve> hi"
+        );
+    }
+
+    #[test]
+    fn leave_other_text_alone() {
+        // Given
+        let template = "100% of %unknown% and 50%%num%";
+
+        // When
+        let result = render(template, &[("num", "2")]);
+
+        // Then
+        assert_eq!(result, "100% of %unknown% and 50%2");
+    }
+
+    #[test]
+    fn values_are_not_parsed() {
+        assert_eq!(
+            render(
+                "%content%",
+                &[("content", "%if content%x%end%"), ("name", "y")]
+            ),
+            "%if content%x%end%"
+        );
+    }
+
+    #[test]
+    fn conditional_parts() {
+        // Given
+        let template = r#"{"text": "<%username%>"%if mention%, "clickEvent": "%mention%"%end%}"#;
+
+        // When
+        let with = render(template, &[("username", "Steve"), ("mention", "@Steve")]);
+        let without = render(template, &[("username", "Steve"), ("mention", "")]);
+
+        // Then
+        assert_eq!(with, r#"{"text": "<Steve>", "clickEvent": "@Steve"}"#);
+        assert_eq!(without, r#"{"text": "<Steve>"}"#);
+    }
+
+    #[test]
+    fn conditional_with_else_and_nesting() {
+        let template = "%if a%A%if b%B%else%-%end%%else%none%end%";
+
+        assert_eq!(render(template, &[("a", "1"), ("b", "1")]), "AB");
+        assert_eq!(render(template, &[("a", "1")]), "A-");
+        assert_eq!(render(template, &[]), "none");
+    }
+
+    #[test]
+    fn stray_tags_are_text() {
+        assert_eq!(render("a%end%b%else%", &[]), "a%end%b%else%");
+        assert_eq!(render("%if a%unclosed", &[("a", "x")]), "unclosed");
+    }
+
+    #[test]
+    fn find_placeholders() {
+        assert_eq!(
+            placeholders("lp user %player% parent set %group%%if note% (%note%)%end% %player%"),
+            vec!["player", "group", "note"]
+        );
+    }
+}
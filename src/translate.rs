@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::config::RootConfig;
+
+/// How long to wait for the translation service before giving up and
+/// bridging the message as it was written.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .expect("unable to build the translation HTTP client");
+}
+
+#[derive(Deserialize)]
+struct TranslateResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: String,
+}
+
+/// Whether text should be translated from one language to the other.
+/// Both languages have to be set, and different.
+pub fn needed(source: &str, target: &str) -> bool {
+    !source.is_empty() && !target.is_empty() && !source.eq_ignore_ascii_case(target)
+}
+
+/// Machine translate text with the configured service, which has to
+/// speak the LibreTranslate API. Returns `None` if no translation is
+/// needed, no service is set, or translating fails, so the text can be
+/// bridged as it was written.
+pub async fn translate(
+    config: &RootConfig,
+    text: &str,
+    source: &str,
+    target: &str,
+) -> Option<String> {
+    let endpoint = config.get_translation_endpoint();
+    if endpoint.is_empty() || !needed(source, target) || text.trim().is_empty() {
+        return None;
+    }
+
+    match request(
+        &endpoint,
+        &config.get_translation_api_key(),
+        text,
+        source,
+        target,
+    )
+    .await
+    {
+        Ok(translated) if translated.trim() != text.trim() => Some(translated),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("translate:translate: unable to translate a message: {}", e);
+            None
+        }
+    }
+}
+
+async fn request(
+    endpoint: &str,
+    api_key: &str,
+    text: &str,
+    source: &str,
+    target: &str,
+) -> Result<String, Error> {
+    let mut body = json!({
+        "q": text,
+        "source": source,
+        "target": target,
+        "format": "text",
+    });
+    if !api_key.is_empty() {
+        body["api_key"] = json!(api_key);
+    }
+
+    let response: TranslateResponse = CLIENT
+        .post(endpoint)
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(response.translated_text)
+}
+
+/// Show a translation with the original text after it.
+pub fn with_original(translated: &str, original: &str) -> String {
+    format!("{} ({})", translated, original)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{needed, with_original};
+
+    #[test]
+    fn only_translate_between_different_languages() {
+        assert!(needed("de", "en"));
+        assert!(!needed("en", "EN"));
+        assert!(!needed("", "en"));
+        assert!(!needed("de", ""));
+    }
+
+    #[test]
+    fn keep_the_original() {
+        assert_eq!(with_original("Hello", "Hallo"), "Hello (Hallo)");
+    }
+}
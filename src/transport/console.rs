@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, process::Command};
+
+use super::Error;
+
+/// Where to write commands so they reach the server console.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Console {
+    /// A named pipe that the server reads its console input from.
+    Pipe(PathBuf),
+
+    /// A tmux session or pane that the server is running in.
+    Tmux(String),
+
+    /// A GNU Screen session that the server is running in.
+    Screen(String),
+}
+
+impl Console {
+    /// Parse the configured console. Anything that isn't `tmux` or
+    /// `screen` is treated as a named pipe.
+    pub fn from_config(kind: &str, target: String) -> Self {
+        match kind.to_lowercase().as_str() {
+            "tmux" => Console::Tmux(target),
+            "screen" => Console::Screen(target),
+            _ => Console::Pipe(PathBuf::from(target)),
+        }
+    }
+
+    /// Write a command to the console. The console doesn't tell us what
+    /// the server answered, so nothing is returned.
+    pub async fn send(&self, command: &str) -> Result<(), Error> {
+        let command = single_line(command);
+
+        match self {
+            Console::Pipe(path) => {
+                let mut pipe = OpenOptions::new().append(true).open(path).await?;
+                pipe.write_all(format!("{}\n", command).as_bytes()).await?;
+                pipe.flush().await?;
+                Ok(())
+            }
+            Console::Tmux(target) => {
+                run("tmux", &["send-keys", "-t", target, "-l", &command]).await?;
+                run("tmux", &["send-keys", "-t", target, "Enter"]).await
+            }
+            Console::Screen(session) => {
+                let keys = format!("{}\r", escape_screen(&command));
+                run("screen", &["-S", session, "-p", "0", "-X", "stuff", &keys]).await
+            }
+        }
+    }
+}
+
+/// Replace line breaks so a single message can't be turned into more
+/// than one console command.
+fn single_line(command: &str) -> String {
+    command.replace(['\r', '\n'], " ")
+}
+
+/// Screen's `stuff` command treats backslashes and carets as escapes.
+fn escape_screen(command: &str) -> String {
+    command.replace('\\', "\\\\").replace('^', "\\^")
+}
+
+async fn run(program: &str, args: &[&str]) -> Result<(), Error> {
+    let output = Command::new(program).args(args).output().await?;
+
+    if !output.status.success() {
+        return Err(Error::CommandFailed(
+            format!("{} {}", program, output.status),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::{escape_screen, single_line, Console};
+
+    #[test]
+    fn commands_stay_on_one_line() {
+        assert_eq!(single_line("say hi\nop Steve\r\n"), "say hi op Steve  ");
+    }
+
+    #[test]
+    fn escape_screen_stuff() {
+        assert_eq!(
+            escape_screen(r#"tellraw @a {"text":"a\"b ^C"}"#),
+            r#"tellraw @a {"text":"a\\"b \^C"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_commands_to_pipe() {
+        // Given
+        let path = env::temp_dir().join(format!("dolphin-console-{}", std::process::id()));
+        fs::write(&path, "").unwrap();
+        let console = Console::from_config("pipe", path.to_string_lossy().to_string());
+
+        // When
+        console.send("say one").await.unwrap();
+        console.send("say two\nstop").await.unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        // Then
+        assert_eq!(written, "say one\nsay two stop\n");
+    }
+}
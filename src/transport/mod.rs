@@ -0,0 +1,219 @@
+#[cfg(unix)]
+use std::path::PathBuf;
+
+#[cfg(unix)]
+use rcon::Connection;
+use thiserror::Error;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::{config::RootConfig, server::ManagedServer};
+pub use console::Console;
+
+mod console;
+mod pool;
+
+/// How commands are sent to the Minecraft server.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transport {
+    /// RCON over TCP, the way Minecraft exposes it.
+    Tcp { addr: String, password: String },
+
+    /// RCON over a Unix domain socket, e.g. from a local RCON proxy.
+    #[cfg(unix)]
+    Unix { path: PathBuf, password: String },
+
+    /// Run a program with the Minecraft command as its last argument,
+    /// and use whatever it prints as the response.
+    Command { program: Vec<String> },
+
+    /// Write commands straight to the server console. The server's
+    /// answers can't be read back this way.
+    Console(Console),
+
+    /// Write commands to the console of the server Dolphin is running.
+    Process(ManagedServer),
+}
+
+impl Transport {
+    /// Get the configured transport. If Dolphin is running the server,
+    /// commands always go to its console. Otherwise, anything that isn't
+    /// `unix`, `command`, or `console` uses RCON over TCP. Startup is
+    /// refused if `unix` is set on a platform without Unix sockets, see
+    /// [RootConfig::check_transport].
+    pub fn from_config(config: &RootConfig) -> Self {
+        if config.managed_server_enabled() {
+            if let Some(server) = crate::server::managed() {
+                return Transport::Process(server.clone());
+            }
+        }
+
+        match config.get_rcon_transport().to_lowercase().as_str() {
+            #[cfg(unix)]
+            "unix" => Transport::Unix {
+                path: PathBuf::from(config.get_rcon_socket_path()),
+                password: config.get_rcon_password(),
+            },
+            "command" => Transport::Command {
+                program: config.get_rcon_command(),
+            },
+            "console" => Transport::Console(Console::from_config(
+                &config.get_console_type(),
+                config.get_console_target(),
+            )),
+            _ => Transport::Tcp {
+                addr: config.get_rcon_addr(),
+                password: config.get_rcon_password(),
+            },
+        }
+    }
+
+    /// Whether the server's response to a command can be read back.
+    pub fn has_responses(&self) -> bool {
+        !matches!(self, Transport::Console(_) | Transport::Process(_))
+    }
+
+    /// Send a command to the Minecraft server, returning its response.
+    /// When writing to a console, the response is always empty.
+    pub async fn send(&self, command: &str) -> Result<String, Error> {
+        debug!("transport:send: {}", command);
+
+        match self {
+            // Connections are kept open, since busy chat would otherwise
+            // open one for every message
+            Transport::Tcp { addr, password } => pool::send(addr, password, command).await,
+            #[cfg(unix)]
+            Transport::Unix { path, password } => {
+                let stream = UnixStream::connect(path).await?;
+                let mut conn = Connection::builder()
+                    .enable_minecraft_quirks(true)
+                    .handshake(stream, password)
+                    .await?;
+
+                Ok(conn.cmd(command).await?)
+            }
+            Transport::Command { program } => {
+                let (program, args) = program.split_first().ok_or(Error::NoCommand)?;
+                let output = Command::new(program)
+                    .args(args)
+                    .arg(command)
+                    .output()
+                    .await?;
+
+                if !output.status.success() {
+                    return Err(Error::CommandFailed(
+                        format!("rcon_command {}", output.status),
+                        String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                    ));
+                }
+
+                Ok(String::from_utf8_lossy(&output.stdout)
+                    .trim_end()
+                    .to_string())
+            }
+            Transport::Console(console) => {
+                console.send(command).await?;
+                Ok(String::new())
+            }
+            Transport::Process(server) => {
+                server.send_command(command).await?;
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("rcon error: {0}")]
+    Rcon(#[from] rcon::Error),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Server(#[from] crate::server::Error),
+
+    #[error("unable to reach the server over RCON, trying again in {0}s")]
+    Unreachable(u64),
+
+    #[error("no rcon_command is set")]
+    NoCommand,
+
+    #[error("{0}: {1}")]
+    CommandFailed(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Transport};
+    use crate::config::RootConfig;
+
+    #[test]
+    fn parse_configured_transport() {
+        // Given
+        let tcp = RootConfig::default();
+
+        // Then
+        assert_eq!(
+            Transport::from_config(&tcp),
+            Transport::Tcp {
+                addr: String::from("localhost:25575"),
+                password: String::new(),
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parse_unix_transport() {
+        // Given
+        let unix: RootConfig = toml::from_str(
+            r#"
+            [minecraft_config]
+            rcon_transport = "unix"
+            rcon_socket_path = "/run/minecraft/rcon.sock"
+            rcon_password = "hunter2"
+            "#,
+        )
+        .unwrap();
+
+        // Then
+        assert!(unix.check_transport().is_ok());
+        assert_eq!(
+            Transport::from_config(&unix),
+            Transport::Unix {
+                path: std::path::PathBuf::from("/run/minecraft/rcon.sock"),
+                password: String::from("hunter2"),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn command_transport_returns_output() {
+        // Given
+        let transport = Transport::Command {
+            program: vec![String::from("echo"), String::from("ran:")],
+        };
+
+        // When
+        let response = transport.send("list").await.unwrap();
+
+        // Then
+        assert_eq!(response, "ran: list");
+    }
+
+    #[tokio::test]
+    async fn command_transport_needs_a_program() {
+        let transport = Transport::Command {
+            program: Vec::new(),
+        };
+
+        assert!(matches!(
+            transport.send("list").await,
+            Err(Error::NoCommand)
+        ));
+    }
+}
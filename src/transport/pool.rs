@@ -0,0 +1,284 @@
+use std::{
+    collections::HashMap,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use rcon::Connection;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+    sync::Mutex,
+};
+use tracing::debug;
+
+use super::Error;
+
+/// How long to wait before connecting again after the first failure.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest to wait between attempts to connect.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    static ref POOL: Mutex<HashMap<String, Arc<Mutex<Slot>>>> = Mutex::new(HashMap::new());
+}
+
+/// How long to wait before trying to connect again, doubling with each
+/// failure in a row.
+#[derive(Debug, Default)]
+struct Backoff {
+    failures: u32,
+    until: Option<Instant>,
+}
+
+impl Backoff {
+    /// How much longer to wait before connecting, if at all.
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.until
+            .map(|until| until.saturating_duration_since(now))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    fn failed(&mut self, now: Instant) {
+        self.failures = self.failures.saturating_add(1);
+        let delay = MIN_BACKOFF
+            .saturating_mul(2u32.saturating_pow(self.failures - 1))
+            .min(MAX_BACKOFF);
+        self.until = Some(now + delay);
+    }
+
+    fn reset(&mut self) {
+        *self = Backoff::default();
+    }
+}
+
+/// A connection to the server that notes once anything has been written
+/// to it. Before the first write it checks that the server hasn't hung
+/// up, so a stale connection fails without sending anything.
+struct Tracked {
+    stream: TcpStream,
+    written: Arc<AtomicBool>,
+}
+
+impl AsyncRead for Tracked {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Tracked {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if !this.written.load(Ordering::Relaxed) {
+            // Nothing should be waiting to be read between commands, and
+            // reading nothing at all means the server hung up
+            match this.stream.try_read(&mut [0; 1]) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into())),
+                Ok(_) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "unexpected data from the server",
+                    )))
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+
+        let written = Pin::new(&mut this.stream).poll_write(cx, buf);
+        if matches!(written, Poll::Ready(Ok(n)) if n > 0) {
+            this.written.store(true, Ordering::Relaxed);
+        }
+        written
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
+
+/// The open connection to a server, if there is one.
+#[derive(Default)]
+struct Slot {
+    connection: Option<Connection<Tracked>>,
+    written: Arc<AtomicBool>,
+    backoff: Backoff,
+}
+
+/// Send a command over the RCON connection to `addr`, opening one if
+/// there isn't one yet. If the open connection fails before any of the
+/// command was written, it's sent once more on a new connection; once the
+/// server may have seen it, it isn't, so a command never runs twice.
+/// Commands to the same server are sent one at a time.
+pub async fn send(addr: &str, password: &str, command: &str) -> Result<String, Error> {
+    let slot = POOL
+        .lock()
+        .await
+        .entry(format!("{}\n{}", addr, password))
+        .or_default()
+        .clone();
+    let mut slot = slot.lock().await;
+
+    // Taken out while in use, so a command that's given up on doesn't
+    // leave a half-read response for the next one
+    if let Some(mut connection) = slot.connection.take() {
+        slot.written.store(false, Ordering::Relaxed);
+        match connection.cmd(command).await {
+            Ok(response) => {
+                slot.connection = Some(connection);
+                return Ok(response);
+            }
+            Err(rcon::Error::CommandTooLong) => {
+                slot.connection = Some(connection);
+                return Err(rcon::Error::CommandTooLong.into());
+            }
+            Err(e) if !slot.written.load(Ordering::Relaxed) => {
+                debug!("transport:pool: reconnecting to {}: {}", addr, e)
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if let Some(remaining) = slot.backoff.remaining(Instant::now()) {
+        return Err(Error::Unreachable(remaining.as_secs().max(1)));
+    }
+
+    let connected = async {
+        let stream = Tracked {
+            stream: TcpStream::connect(addr).await?,
+            written: slot.written.clone(),
+        };
+        Connection::builder()
+            .enable_minecraft_quirks(true)
+            .handshake(stream, password)
+            .await
+            .map_err(Error::from)
+    };
+    let mut connection = match connected.await {
+        Ok(connection) => connection,
+        Err(e) => {
+            slot.backoff.failed(Instant::now());
+            return Err(e);
+        }
+    };
+    slot.backoff.reset();
+
+    let response = connection.cmd(command).await?;
+    slot.connection = Some(connection);
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    use tokio::{
+        io::AsyncWriteExt,
+        net::{TcpListener, TcpStream},
+        time::sleep,
+    };
+
+    use super::{Backoff, Tracked, MAX_BACKOFF};
+
+    async fn connect(listener: &TcpListener) -> (Tracked, TcpStream) {
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        let tracked = Tracked {
+            stream,
+            written: Arc::new(AtomicBool::new(false)),
+        };
+        (tracked, server)
+    }
+
+    #[test]
+    fn back_off_longer_after_each_failure() {
+        // Given
+        let now = Instant::now();
+        let mut backoff = Backoff::default();
+
+        // When
+        backoff.failed(now);
+        let first = backoff.remaining(now);
+        backoff.failed(now);
+        let second = backoff.remaining(now);
+
+        // Then
+        assert_eq!(first, Some(Duration::from_secs(1)));
+        assert_eq!(second, Some(Duration::from_secs(2)));
+        assert_eq!(backoff.remaining(now + Duration::from_secs(2)), None);
+    }
+
+    #[test]
+    fn cap_and_reset_the_backoff() {
+        // Given
+        let now = Instant::now();
+        let mut backoff = Backoff::default();
+        for _ in 0..40 {
+            backoff.failed(now);
+        }
+
+        // When
+        let capped = backoff.remaining(now);
+        backoff.reset();
+
+        // Then
+        assert_eq!(capped, Some(MAX_BACKOFF));
+        assert_eq!(backoff.remaining(now), None);
+    }
+
+    #[tokio::test]
+    async fn note_when_a_command_was_written() {
+        // Given
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (mut tracked, _server) = connect(&listener).await;
+
+        // When
+        tracked.write_all(b"list").await.unwrap();
+
+        // Then
+        assert!(tracked.written.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn write_nothing_once_the_server_hung_up() {
+        // Given
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (mut tracked, server) = connect(&listener).await;
+        drop(server);
+        sleep(Duration::from_millis(50)).await;
+
+        // When
+        let result = tracked.write_all(b"list").await;
+
+        // Then
+        assert!(result.is_err());
+        assert!(!tracked.written.load(Ordering::Relaxed));
+    }
+}
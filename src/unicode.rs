@@ -0,0 +1,156 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Normalize text passing through the bridge. Text is put in NFC form,
+/// and characters that can be used to spoof names or break rendering
+/// are removed: direction overrides, zero-width characters, and control
+/// characters other than tabs and line breaks.
+pub fn normalize(text: &str) -> String {
+    text.nfc()
+        .filter(|c| !is_disallowed(*c) && (!c.is_control() || matches!(c, '\t' | '\n')))
+        .collect()
+}
+
+/// Replace characters that older Minecraft clients can't show with
+/// ASCII lookalikes, like `é` with `e` and `“` with `"`. Characters in
+/// `keep`, and ones without an ASCII form, like emoji, are left as they
+/// are.
+pub fn transliterate(text: &str, keep: &[char]) -> String {
+    let mut transliterated = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if c.is_ascii() || keep.contains(&c) {
+            transliterated.push(c);
+            continue;
+        }
+
+        if let Some(ascii) = lookalike(c) {
+            transliterated.push_str(ascii);
+            continue;
+        }
+
+        // Accents come apart from their letters, and compatibility forms
+        // like `ﬁ` and full width letters turn into plain ones
+        let decomposed: String = c.nfkd().filter(|c| !is_combining_mark(*c)).collect();
+        match decomposed.is_ascii() && !decomposed.is_empty() {
+            true => transliterated.push_str(&decomposed),
+            false => transliterated.push(c),
+        }
+    }
+
+    transliterated
+}
+
+/// ASCII for characters that don't decompose into it.
+fn lookalike(c: char) -> Option<&'static str> {
+    let ascii = match c {
+        '‘' | '’' | '‚' | '′' => "'",
+        '“' | '”' | '„' | '″' | '«' | '»' => "\"",
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => "-",
+        '…' => "...",
+        '•' | '·' => "*",
+        '\u{a0}' => " ",
+        'ß' => "ss",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ø' => "o",
+        'Ø' => "O",
+        'đ' | 'ð' => "d",
+        'Đ' | 'Ð' => "D",
+        'ł' => "l",
+        'Ł' => "L",
+        'þ' => "th",
+        'Þ' => "Th",
+        'ı' => "i",
+        _ => return None,
+    };
+
+    Some(ascii)
+}
+
+/// Whether a character is invisible or changes how the text around it
+/// is displayed.
+pub fn is_disallowed(c: char) -> bool {
+    matches!(
+        c,
+        // Zero-width spaces and direction marks. Zero-width joiners and
+        // non-joiners are kept, since emoji like 👨‍👩‍👧 and scripts like
+        // Persian need them.
+        '\u{200B}'
+            | '\u{200E}'
+            | '\u{200F}'
+            // Direction embeddings and overrides
+            | '\u{202A}'..='\u{202E}'
+            // Word joiner, invisible operators, and direction isolates
+            | '\u{2060}'..='\u{206F}'
+            | '\u{061C}'
+            | '\u{180E}'
+            | '\u{FEFF}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize, transliterate};
+
+    #[test]
+    fn compose_characters() {
+        // Given
+        let input = "Cafe\u{301}";
+
+        // When
+        let result = normalize(input);
+
+        // Then
+        assert_eq!(result, "Café");
+        assert_eq!(result.chars().count(), 4);
+    }
+
+    #[test]
+    fn strip_direction_overrides() {
+        // A name that shows up as "Admin" but is something else
+        assert_eq!(normalize("\u{202E}nimdA"), "nimdA");
+        assert_eq!(normalize("Steve\u{2066}\u{2069}"), "Steve");
+        assert_eq!(normalize("\u{200F}abc\u{061C}"), "abc");
+    }
+
+    #[test]
+    fn strip_zero_width_characters() {
+        assert_eq!(normalize("Ste\u{200B}ve"), "Steve");
+        assert_eq!(normalize("Ste\u{200B}ve\u{FEFF}"), "Steve");
+        assert_eq!(normalize("Not\u{2060}ch"), "Notch");
+    }
+
+    #[test]
+    fn keep_joiners() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(normalize(family), family);
+        assert_eq!(normalize("می\u{200C}خواهم"), "می\u{200C}خواهم");
+    }
+
+    #[test]
+    fn strip_control_characters() {
+        assert_eq!(normalize("hi\u{7}\u{1b}[31m\r"), "hi[31m");
+        assert_eq!(normalize("one\ntwo\tthree"), "one\ntwo\tthree");
+    }
+
+    #[test]
+    fn transliterate_to_ascii() {
+        assert_eq!(transliterate("Café déjà vu", &[]), "Cafe deja vu");
+        assert_eq!(
+            transliterate("“Straße” – ﬁne…", &[]),
+            "\"Strasse\" - fine..."
+        );
+        assert_eq!(transliterate("ＡＢＣ", &[]), "ABC");
+    }
+
+    #[test]
+    fn transliterate_keeps_exceptions_and_unknown_characters() {
+        assert_eq!(
+            transliterate("¿Qué tal, señor?", &['ñ']),
+            "¿Que tal, señor?"
+        );
+        assert_eq!(transliterate("gg 🎉", &[]), "gg 🎉");
+    }
+}
@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use serenity::{
+    all::ChannelId,
+    builder::{CreateEmbed, CreateMessage},
+    model::Colour,
+    prelude::Context,
+};
+use tracing::{debug, error, warn};
+
+use crate::config::container::ConfigContainer;
+
+/// Where GitHub lists the newest release of Dolphin.
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/EbonJaeger/dolphin-rs/releases/latest";
+
+/// How long to wait for GitHub before giving up on the check.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        // GitHub turns away requests without a user agent
+        .user_agent(concat!("dolphin-rs/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .expect("unable to build the update HTTP client");
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+}
+
+/// Parse a version like `2.8.0` or `v2.8.0`. Anything after the patch
+/// number, like `-beta.1`, is ignored.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let mut parts = version.splitn(3, '.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch: String = parts
+        .next()?
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+
+    Some((major, minor, patch.parse().ok()?))
+}
+
+/// Whether the `latest` version is newer than the `current` one.
+/// Versions that can't be read are never newer.
+fn is_newer(current: &str, latest: &str) -> bool {
+    match (parse_version(current), parse_version(latest)) {
+        (Some(current), Some(latest)) => latest > current,
+        _ => false,
+    }
+}
+
+fn notice_embed(version: &str, url: &str) -> CreateEmbed {
+    CreateEmbed::new()
+        .title("Dolphin Update Available")
+        .description(format!(
+            "Dolphin {} is out, and this is {}. See [what's changed]({}).",
+            version,
+            env!("CARGO_PKG_VERSION"),
+            url
+        ))
+        .color(Colour::ORANGE)
+}
+
+/// Check GitHub for a newer release, and let the admins know about it.
+/// Dolphin keeps running as it is either way, so failures are only
+/// logged.
+pub async fn check(ctx: &Context) {
+    let Some(config) = ctx.data.read().await.get::<ConfigContainer>().cloned() else {
+        return;
+    };
+
+    let release: Release = match fetch().await {
+        Ok(release) => release,
+        Err(e) => {
+            warn!("update:check: unable to check for a newer version: {}", e);
+            return;
+        }
+    };
+
+    if !is_newer(env!("CARGO_PKG_VERSION"), &release.tag_name) {
+        debug!("update:check: Dolphin is up to date");
+        return;
+    }
+
+    warn!(
+        "Dolphin {} is available, this is {}. See what's changed: {}",
+        release.tag_name,
+        env!("CARGO_PKG_VERSION"),
+        release.html_url
+    );
+
+    let (notify_ops, channel_id) = {
+        let config = config.read().await;
+        (config.update_notify_ops(), config.get_ops_channel_id())
+    };
+    if !notify_ops || channel_id == 0 {
+        return;
+    }
+
+    let embed = notice_embed(&release.tag_name, &release.html_url);
+    if let Err(e) = ChannelId::new(channel_id)
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await
+    {
+        error!("update:check: unable to post the update notice: {}", e);
+    }
+}
+
+async fn fetch() -> Result<Release, reqwest::Error> {
+    CLIENT
+        .get(LATEST_RELEASE_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_newer, parse_version};
+
+    #[test]
+    fn parse_release_tags() {
+        // Then
+        assert_eq!(parse_version("2.8.0"), Some((2, 8, 0)));
+        assert_eq!(parse_version("v2.10.1"), Some((2, 10, 1)));
+        assert_eq!(parse_version("v3.0.0-beta.1"), Some((3, 0, 0)));
+        assert_eq!(parse_version("nightly"), None);
+    }
+
+    #[test]
+    fn compare_versions() {
+        // Then
+        assert!(is_newer("2.8.0", "v2.9.0"));
+        assert!(is_newer("2.8.0", "v2.8.1"));
+        assert!(is_newer("2.8.0", "v10.0.0"));
+        assert!(!is_newer("2.8.0", "v2.8.0"));
+        assert!(!is_newer("2.8.0", "v2.7.9"));
+        assert!(!is_newer("2.8.0", "latest"));
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use serde::Deserialize;
+use serenity::{
+    all::ChannelId,
+    builder::{CreateEmbed, CreateMessage},
+    model::Colour,
+    prelude::{Context, RwLock, TypeMapKey},
+};
+use thiserror::Error;
+use tokio::time::{interval, timeout, MissedTickBehavior};
+use tracing::{debug, error, warn};
+
+use crate::{
+    config::{container::ConfigContainer, RootConfig},
+    listener::strip_colors,
+    metrics::format_duration,
+    transport::Transport,
+};
+
+/// How long to wait for the companion endpoint or the server to answer.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .expect("unable to build the usage HTTP client");
+}
+
+/// What the Minecraft server is using, as far as we could find out.
+/// Anything the source doesn't report is left out.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Usage {
+    pub tps: Option<f64>,
+    pub mspt: Option<f64>,
+    pub memory_used_mb: Option<u64>,
+    pub memory_max_mb: Option<u64>,
+    pub entities: Option<u64>,
+    pub chunks: Option<u64>,
+}
+
+impl Usage {
+    /// Get the usage from the configured source: the companion endpoint
+    /// if one is set, or else the TPS command over RCON.
+    pub async fn poll(config: &RootConfig) -> Result<Self, Error> {
+        let endpoint = config.get_usage_endpoint();
+        if !endpoint.is_empty() {
+            let usage = CLIENT
+                .get(&endpoint)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            return Ok(usage);
+        }
+
+        let transport = Transport::from_config(config);
+        if !transport.has_responses() {
+            return Err(Error::NoResponses);
+        }
+
+        let output = timeout(TIMEOUT, transport.send(&config.get_usage_tps_command()))
+            .await
+            .map_err(|_| Error::Timeout)??;
+
+        match parse_tps(&output) {
+            Some(tps) => Ok(Usage {
+                tps: Some(tps),
+                ..Default::default()
+            }),
+            None => Err(Error::Unrecognized(output)),
+        }
+    }
+
+    pub fn embed(&self, polled: SystemTime) -> CreateEmbed {
+        let mut embed = CreateEmbed::new().title("Server Status");
+
+        if let Some(tps) = self.tps {
+            embed = embed.field("TPS", format!("{:.1}", tps), true);
+        }
+        if let Some(mspt) = self.mspt {
+            embed = embed.field("MSPT", format!("{:.1}ms", mspt), true);
+        }
+        match (self.memory_used_mb, self.memory_max_mb) {
+            (Some(used), Some(max)) => {
+                embed = embed.field("Memory", format!("{} / {} MB", used, max), true)
+            }
+            (Some(used), None) => embed = embed.field("Memory", format!("{} MB", used), true),
+            _ => {}
+        }
+        if let Some(entities) = self.entities {
+            embed = embed.field("Entities", entities.to_string(), true);
+        }
+        if let Some(chunks) = self.chunks {
+            embed = embed.field("Loaded Chunks", chunks.to_string(), true);
+        }
+
+        let ago = SystemTime::now().duration_since(polled).unwrap_or_default();
+        embed
+            .description(format!("Checked {} ago.", format_duration(ago)))
+            .color(self.colour())
+    }
+
+    /// Green while the server keeps up, orange when it's a bit behind,
+    /// and red when it's struggling.
+    fn colour(&self) -> Colour {
+        match self.tps {
+            Some(tps) if tps < 15.0 => Colour::RED,
+            Some(tps) if tps < 19.0 => Colour::ORANGE,
+            Some(_) => Colour::DARK_GREEN,
+            None => Colour::BLUE,
+        }
+    }
+}
+
+/// Get the most recent TPS from the output of a TPS command, e.g.
+/// Paper's `tps` or `spark tps`. The first number after `TPS from last
+/// ...:` is used.
+pub fn parse_tps(output: &str) -> Option<f64> {
+    let output = strip_colors(output, true);
+    let start = output.find("TPS from last")?;
+    let (_, values) = output[start..].split_once(':')?;
+
+    values
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .find(|value| !value.is_empty())?
+        .parse()
+        .ok()
+}
+
+/// The last usage we got, and when.
+pub struct UsageContainer;
+
+impl TypeMapKey for UsageContainer {
+    type Value = Arc<RwLock<Option<(Usage, SystemTime)>>>;
+}
+
+/// Poll the server's usage every `poll_interval_secs`, keeping the
+/// latest for `/status`, and post it to the ops channel every
+/// `report_interval_mins` if that's set.
+pub async fn poll_periodically(ctx: Arc<Context>) {
+    let config = ctx
+        .data
+        .read()
+        .await
+        .get::<ConfigContainer>()
+        .cloned()
+        .expect("expected config container in TypeMap");
+    let latest = ctx
+        .data
+        .read()
+        .await
+        .get::<UsageContainer>()
+        .cloned()
+        .expect("expected usage container in TypeMap");
+
+    let (poll_interval, report_interval) = {
+        let config = config.read().await;
+        (
+            Duration::from_secs(config.get_usage_poll_interval_secs().max(1)),
+            Duration::from_secs(config.get_usage_report_interval_mins() * 60),
+        )
+    };
+
+    let mut ticks = interval(poll_interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_report = SystemTime::now();
+
+    loop {
+        ticks.tick().await;
+
+        // Don't hold the config lock while we wait on the network
+        let snapshot = config.read().await.clone();
+        let usage = match Usage::poll(&snapshot).await {
+            Ok(usage) => usage,
+            Err(e) => {
+                warn!(
+                    "usage:poll_periodically: unable to get the server's usage: {}",
+                    e
+                );
+                continue;
+            }
+        };
+        debug!("usage:poll_periodically: {:?}", usage);
+
+        let now = SystemTime::now();
+        *latest.write().await = Some((usage.clone(), now));
+
+        let due = now.duration_since(last_report).unwrap_or_default() >= report_interval;
+        if report_interval.is_zero() || !due {
+            continue;
+        }
+        last_report = now;
+
+        let channel_id = ChannelId::new(config.read().await.get_ops_channel_id());
+        let message = CreateMessage::new().embed(usage.embed(now));
+        if let Err(e) = channel_id.send_message(&ctx.http, message).await {
+            error!(
+                "usage:poll_periodically: unable to post a usage report: {}",
+                e
+            );
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("{0}")]
+    Transport(#[from] crate::transport::Error),
+
+    #[error("the server didn't respond in time")]
+    Timeout,
+
+    #[error("the configured transport can't read the server's responses")]
+    NoResponses,
+
+    #[error("unrecognized tps output: {0}")]
+    Unrecognized(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_tps, Usage};
+
+    #[test]
+    fn parse_paper_tps() {
+        // Given
+        let output = "§6TPS from last 1m, 5m, 15m: §a*20.0, §a19.8, §a19.9";
+
+        // When
+        let tps = parse_tps(output);
+
+        // Then
+        assert_eq!(tps, Some(20.0));
+    }
+
+    #[test]
+    fn parse_spark_tps() {
+        // Given
+        let output = "TPS from last 5s, 10s, 1m, 5m, 15m:\n ▶ 18.5, 19.2, 19.9, 20.0, 20.0\n\nTick durations (min/med/95%ile/max ms) from last 10s, 1m:\n ▶ 1.2/2.3/4.5/6.0";
+
+        // When
+        let tps = parse_tps(output);
+
+        // Then
+        assert_eq!(tps, Some(18.5));
+    }
+
+    #[test]
+    fn unknown_output_has_no_tps() {
+        assert_eq!(parse_tps("Unknown command"), None);
+    }
+
+    #[test]
+    fn endpoint_fields_are_optional() {
+        // Given
+        let body = r#"{"tps": 19.5, "memory_used_mb": 2048}"#;
+
+        // When
+        let usage: Usage = serde_json::from_str(body).unwrap();
+
+        // Then
+        assert_eq!(
+            usage,
+            Usage {
+                tps: Some(19.5),
+                memory_used_mb: Some(2048),
+                ..Default::default()
+            }
+        );
+    }
+}